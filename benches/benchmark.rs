@@ -14,6 +14,7 @@ use tlspuffin::term;
 use tlspuffin::term::dynamic_function::make_dynamic;
 use tlspuffin::tls::error::FnError;
 use tlspuffin::tls::fn_impl::*;
+use tlspuffin::trace::Action;
 use tlspuffin::trace::Trace;
 use tlspuffin::trace::TraceContext;
 
@@ -50,6 +51,7 @@ fn benchmark_mutations(c: &mut Criterion) {
         let mut mutator = ReplaceReuseMutator::new(TermConstraints {
             min_term_size: 0,
             max_term_size: 200,
+            ..TermConstraints::default()
         });
         let mut trace = seed_client_attacker12(client);
 
@@ -100,6 +102,20 @@ fn benchmark_trace(c: &mut Criterion) {
 
         b.iter(|| client_hello.clone())
     });
+
+    group.bench_function("term deep clone", |b| {
+        let client_hello = seed_client_attacker12(AgentName::first()).steps[0]
+            .action
+            .clone();
+        let client_hello = match client_hello {
+            Action::Input(input) => input.recipe,
+            Action::Output(_) | Action::Delay(_) => panic!("expected the ClientHello"),
+        };
+
+        // Rewriting without rules copies every subterm, see tests/term_allocations.rs for the
+        // allocations of a copy
+        b.iter(|| client_hello.rewrite(&[]))
+    });
 }
 
 fn benchmark_seeds(c: &mut Criterion) {
@@ -183,11 +199,45 @@ fn benchmark_seeds(c: &mut Criterion) {
     group.finish()
 }
 
+fn benchmark_context_reuse(c: &mut Criterion) {
+    const EXECUTIONS: usize = 1000;
+
+    let mut group = c.benchmark_group("context_reuse");
+    group.sample_size(10);
+
+    let client = AgentName::first();
+    let server = client.next();
+    let trace = seed_successful12(client, server);
+
+    group.bench_function("seed_successful12 fresh context", |b| {
+        b.iter(|| {
+            for _ in 0..EXECUTIONS {
+                let mut ctx = TraceContext::new();
+                trace.execute(&mut ctx).unwrap();
+            }
+        })
+    });
+
+    group.bench_function("seed_successful12 reused context", |b| {
+        let mut ctx = TraceContext::new();
+
+        b.iter(|| {
+            for _ in 0..EXECUTIONS {
+                ctx.reset();
+                trace.execute(&mut ctx).unwrap();
+            }
+        })
+    });
+
+    group.finish()
+}
+
 criterion_group!(
     benches,
     benchmark_dynamic,
     benchmark_trace,
     benchmark_mutations,
     benchmark_seeds,
+    benchmark_context_reuse,
 );
 criterion_main!(benches);
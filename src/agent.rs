@@ -6,11 +6,15 @@
 
 use crate::error::Error;
 use crate::io::OpenSSLStream;
+use crate::openssl_binding::CertParams;
+use openssl::ssl::SslOptions;
 use core::fmt;
+use std::convert::TryFrom;
 use serde::{Deserialize, Serialize};
 
 use crate::trace::VecClaimer;
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Rc;
 use rustls::msgs::enums::ProtocolVersion::TLSv1_2;
 use crate::agent::TLSVersion::Unknown;
@@ -32,6 +36,17 @@ impl AgentName {
     pub fn first() -> AgentName {
         AgentName::new()
     }
+
+    /// Returns the `n`-th agent name, which equals calling [`AgentName::next`] `n` times on
+    /// [`AgentName::first`].
+    pub fn nth(n: usize) -> AgentName {
+        AgentName(u8::try_from(n).expect("there are at most 256 agent names"))
+    }
+
+    /// Iterates over all agent names, starting at [`AgentName::first`]
+    pub fn iter() -> impl Iterator<Item = AgentName> {
+        (0..=u8::MAX).map(AgentName)
+    }
 }
 
 impl fmt::Display for AgentName {
@@ -42,7 +57,7 @@ impl fmt::Display for AgentName {
 
 /// AgentDescriptors act like a blueprint to spawn [`Agent`]s with a corresponding server or
 /// client role and a specific TLs version. Essentially they are an [`Agent`] without a stream.
-#[derive(Debug, Copy, Clone, Deserialize, Serialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
 pub struct AgentDescriptor {
     pub name: AgentName,
     pub tls_version: TLSVersion,
@@ -50,13 +65,110 @@ pub struct AgentDescriptor {
     pub server: bool,
     /// Whether we want to try to reuse a previous agent. This is needed for TLS session resumption
     /// as openssl agents rotate ticket keys if they are recreated.
-    pub try_reuse: bool
+    pub try_reuse: bool,
+    /// Application protocols (ALPN) which this agent supports, ordered by preference. A client
+    /// offers them in its ClientHello, a server selects the first one it shares with the client.
+    pub alpn_protocols: Option<Vec<Vec<u8>>>,
+    /// Maximum amount of 0-RTT data a server accepts. Early data is rejected if this is zero.
+    pub max_early_data: u32,
+    /// Supported groups in the format of OpenSSL, e.g. `P-256:P-384`. A client sends a key share
+    /// for the first group only. If `None`, then the default of the agent is used.
+    pub groups: Option<String>,
+    /// TLS 1.2 cipher suites in the format of OpenSSL, e.g. `ECDHE-RSA-AES128-GCM-SHA256`. If
+    /// `None`, then the default list of the agent is used.
+    pub cipher_list: Option<String>,
+    /// A server presents a certificate generated for these parameters. A client verifies the
+    /// server and trusts the certificate generated for these parameters. If `None`, then a server
+    /// presents a static RSA certificate and a client does not verify the server.
+    pub cert: Option<CertParams>,
+    /// Whether a TLS 1.3 server answers each ClientHello without a valid cookie with a stateless
+    /// HelloRetryRequest which contains a cookie, see `SSL_stateless`.
+    pub stateless_retry: bool,
+    /// Secrets of the connections of this agent are appended to this file in the NSS key log
+    /// format, like with `SSLKEYLOGFILE`. This allows decrypting captured traffic, e.g. in
+    /// Wireshark.
+    pub key_log: Option<PathBuf>,
+    /// A server staples this OCSP response if the client requests the status of the server
+    /// certificate through the status_request extension.
+    pub ocsp_response: Option<Vec<u8>>,
+    /// Whether a client requests the status of the server certificate. The handshake fails if the
+    /// server does not staple a well-formed OCSP response.
+    pub request_ocsp: bool,
+    /// A TLS 1.3 client offers this PSK, a server accepts it for the identity of the PSK. The
+    /// handshake is authenticated through the PSK instead of certificates.
+    pub external_psk: Option<ExternalPsk>,
+    /// Whether the agent hands the record layer to the kernel (KTLS) once the keys are
+    /// established. The agents are connected through memory buffers, which never support KTLS.
+    /// Therefore, the option is ignored with a warning.
+    pub ktls: bool,
+    /// Whether a server requests a certificate from the client. The handshake fails if the client
+    /// does not authenticate with the static RSA certificate, see
+    /// [`static_rsa_cert`](crate::openssl_binding::static_rsa_cert).
+    pub client_auth: bool,
+    /// OpenSSL options which are set on the context of the agent in addition to the ones the
+    /// agent sets itself, e.g. `SslOptions::NO_TICKET`.
+    #[serde(with = "crate::openssl_binding::serde_ssl_options")]
+    pub options: SslOptions,
+    /// A server requires the client to send exactly this host name in the server_name extension.
+    /// If the name is absent or differs, the server aborts with an unrecognized_name alert.
+    pub server_name: Option<String>,
+}
+
+/// PSK which is provisioned outside of TLS, in contrast to PSKs of resumed sessions
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
+pub struct ExternalPsk {
+    pub identity: Vec<u8>,
+    pub key: Vec<u8>,
+}
+
+impl Default for AgentDescriptor {
+    fn default() -> Self {
+        Self {
+            name: AgentName::first(),
+            tls_version: TLSVersion::V1_3,
+            server: false,
+            try_reuse: false,
+            alpn_protocols: None,
+            max_early_data: 0,
+            groups: None,
+            cipher_list: None,
+            cert: None,
+            stateless_retry: false,
+            key_log: None,
+            ocsp_response: None,
+            request_ocsp: false,
+            external_psk: None,
+            ktls: false,
+            client_auth: false,
+            options: SslOptions::empty(),
+            server_name: None,
+        }
+    }
 }
 
 impl AgentDescriptor {
     /// checks whether a agent with this descriptor is reusable with the other descriptor
     pub fn is_reusable_with(&self, other: &AgentDescriptor) -> bool {
-        return self.server == other.server && self.tls_version == other.tls_version;
+        return self.server == other.server
+            && self.tls_version == other.tls_version
+            && self.alpn_protocols == other.alpn_protocols
+            && self.max_early_data == other.max_early_data
+            && self.groups == other.groups
+            && self.cipher_list == other.cipher_list
+            && self.cert == other.cert
+            && self.stateless_retry == other.stateless_retry
+            && self.key_log == other.key_log
+            && self.ocsp_response == other.ocsp_response
+            && self.request_ocsp == other.request_ocsp
+            && self.external_psk == other.external_psk
+            && self.ktls == other.ktls
+            && self.client_auth == other.client_auth
+            && self.options == other.options
+            && self.server_name == other.server_name;
+    }
+
+    pub fn builder(name: AgentName) -> AgentDescriptorBuilder {
+        AgentDescriptorBuilder::new(name)
     }
 
     pub fn new_reusable_server(name: AgentName, tls_version: TLSVersion) -> Self {
@@ -64,7 +176,8 @@ impl AgentDescriptor {
             name,
             tls_version,
             server: true,
-            try_reuse: true
+            try_reuse: true,
+            ..AgentDescriptor::default()
         }
     }
 
@@ -73,7 +186,8 @@ impl AgentDescriptor {
             name,
             tls_version,
             server: true,
-            try_reuse: true
+            try_reuse: true,
+            ..AgentDescriptor::default()
         }
     }
 
@@ -82,7 +196,8 @@ impl AgentDescriptor {
             name,
             tls_version,
             server: true,
-            try_reuse: false
+            try_reuse: false,
+            ..AgentDescriptor::default()
         }
     }
 
@@ -91,8 +206,168 @@ impl AgentDescriptor {
             name,
             tls_version,
             server: false,
-            try_reuse: false
+            try_reuse: false,
+            ..AgentDescriptor::default()
+        }
+    }
+}
+
+/// Builds an [`AgentDescriptor`] with fluent methods. Fields which are not set keep the value of
+/// [`AgentDescriptor::default`]. Contradictory settings are rejected by
+/// [`AgentDescriptorBuilder::build`].
+#[derive(Debug, Clone)]
+pub struct AgentDescriptorBuilder {
+    descriptor: AgentDescriptor,
+}
+
+impl AgentDescriptorBuilder {
+    pub fn new(name: AgentName) -> Self {
+        Self {
+            descriptor: AgentDescriptor {
+                name,
+                ..AgentDescriptor::default()
+            },
+        }
+    }
+
+    pub fn server(mut self, server: bool) -> Self {
+        self.descriptor.server = server;
+        self
+    }
+
+    pub fn tls_version(mut self, tls_version: TLSVersion) -> Self {
+        self.descriptor.tls_version = tls_version;
+        self
+    }
+
+    pub fn try_reuse(mut self, try_reuse: bool) -> Self {
+        self.descriptor.try_reuse = try_reuse;
+        self
+    }
+
+    pub fn alpn_protocols(mut self, alpn_protocols: Vec<Vec<u8>>) -> Self {
+        self.descriptor.alpn_protocols = Some(alpn_protocols);
+        self
+    }
+
+    pub fn max_early_data(mut self, max_early_data: u32) -> Self {
+        self.descriptor.max_early_data = max_early_data;
+        self
+    }
+
+    pub fn groups(mut self, groups: &str) -> Self {
+        self.descriptor.groups = Some(groups.to_string());
+        self
+    }
+
+    pub fn cipher_list(mut self, cipher_list: &str) -> Self {
+        self.descriptor.cipher_list = Some(cipher_list.to_string());
+        self
+    }
+
+    pub fn cert(mut self, cert: CertParams) -> Self {
+        self.descriptor.cert = Some(cert);
+        self
+    }
+
+    pub fn stateless_retry(mut self, stateless_retry: bool) -> Self {
+        self.descriptor.stateless_retry = stateless_retry;
+        self
+    }
+
+    pub fn key_log(mut self, key_log: PathBuf) -> Self {
+        self.descriptor.key_log = Some(key_log);
+        self
+    }
+
+    pub fn ocsp_response(mut self, ocsp_response: Vec<u8>) -> Self {
+        self.descriptor.ocsp_response = Some(ocsp_response);
+        self
+    }
+
+    pub fn request_ocsp(mut self, request_ocsp: bool) -> Self {
+        self.descriptor.request_ocsp = request_ocsp;
+        self
+    }
+
+    pub fn external_psk(mut self, external_psk: ExternalPsk) -> Self {
+        self.descriptor.external_psk = Some(external_psk);
+        self
+    }
+
+    pub fn ktls(mut self, ktls: bool) -> Self {
+        self.descriptor.ktls = ktls;
+        self
+    }
+
+    pub fn client_auth(mut self, client_auth: bool) -> Self {
+        self.descriptor.client_auth = client_auth;
+        self
+    }
+
+    pub fn options(mut self, options: SslOptions) -> Self {
+        self.descriptor.options = options;
+        self
+    }
+
+    pub fn server_name(mut self, server_name: &str) -> Self {
+        self.descriptor.server_name = Some(server_name.to_string());
+        self
+    }
+
+    /// Returns the descriptor, or an error if options only apply to the other role or to another
+    /// TLS version
+    pub fn build(self) -> Result<AgentDescriptor, Error> {
+        let descriptor = self.descriptor;
+        let role = if descriptor.server {
+            "server"
+        } else {
+            "client"
+        };
+        let contradiction = |option: &str, reason: &str| {
+            Err(Error::Agent(format!(
+                "Option {} of {} {} {}",
+                option, role, descriptor.name, reason
+            )))
+        };
+
+        if descriptor.tls_version == TLSVersion::Unknown {
+            return contradiction("tls_version", "is no supported TLS version");
+        }
+
+        if !descriptor.server {
+            if descriptor.max_early_data > 0 {
+                return contradiction("max_early_data", "only applies to servers");
+            }
+            if descriptor.stateless_retry {
+                return contradiction("stateless_retry", "only applies to servers");
+            }
+            if descriptor.ocsp_response.is_some() {
+                return contradiction("ocsp_response", "only applies to servers");
+            }
+            if descriptor.client_auth {
+                return contradiction("client_auth", "only applies to servers");
+            }
+            if descriptor.server_name.is_some() {
+                return contradiction("server_name", "only applies to servers");
+            }
+        } else if descriptor.request_ocsp {
+            return contradiction("request_ocsp", "only applies to clients");
+        }
+
+        if descriptor.tls_version == TLSVersion::V1_2 {
+            if descriptor.max_early_data > 0 {
+                return contradiction("max_early_data", "requires TLS 1.3");
+            }
+            if descriptor.stateless_retry {
+                return contradiction("stateless_retry", "requires TLS 1.3");
+            }
+            if descriptor.external_psk.is_some() {
+                return contradiction("external_psk", "requires TLS 1.3");
+            }
         }
+
+        Ok(descriptor)
     }
 }
 
@@ -124,12 +399,7 @@ impl Agent {
         descriptor: &AgentDescriptor,
         claimer: Rc<RefCell<VecClaimer>>,
     ) -> Result<Self, Error> {
-        let openssl_stream = OpenSSLStream::new(
-            descriptor.server,
-            &descriptor.tls_version,
-            descriptor.name,
-            claimer,
-        )?;
+        let openssl_stream = OpenSSLStream::new(descriptor, claimer)?;
 
         let agent = Self::from_stream(descriptor, openssl_stream);
 
@@ -147,7 +417,7 @@ impl Agent {
 
     fn from_stream(descriptor: &AgentDescriptor, stream: OpenSSLStream) -> Agent {
         Agent {
-            descriptor: *descriptor,
+            descriptor: descriptor.clone(),
             stream,
         }
     }
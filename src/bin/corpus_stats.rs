@@ -0,0 +1,56 @@
+//! Prints aggregate statistics of a corpus directory of serialized traces, see
+//! [`corpus_stats`](tlspuffin::fuzzer::corpus_stats::corpus_stats).
+//!
+//! Usage: `corpus_stats <corpus-dir>`
+//!
+//! Files which can not be loaded as trace are skipped with a warning. The exit code is `1` if the
+//! directory can not be read.
+
+use std::path::PathBuf;
+use std::{env, fs, process};
+
+use tlspuffin::{fuzzer::corpus_stats::corpus_stats, trace::Trace};
+
+const EXIT_LOAD_FAILURE: i32 = 1;
+
+fn main() {
+    let corpus_dir = match env::args().nth(1) {
+        Some(corpus_dir) => PathBuf::from(corpus_dir),
+        None => {
+            eprintln!("usage: corpus_stats <corpus-dir>");
+            process::exit(EXIT_LOAD_FAILURE);
+        }
+    };
+
+    let mut files = match fs::read_dir(&corpus_dir).and_then(|entries| {
+        entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<PathBuf>, _>>()
+    }) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!(
+                "failed to read corpus directory {}: {}",
+                corpus_dir.display(),
+                err
+            );
+            process::exit(EXIT_LOAD_FAILURE);
+        }
+    };
+    files.retain(|path| path.is_file());
+    files.sort();
+
+    let mut traces = Vec::with_capacity(files.len());
+    for path in files {
+        match fs::read(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|buffer| {
+                postcard::from_bytes::<Trace>(&buffer).map_err(|err| err.to_string())
+            }) {
+            Ok(trace) => traces.push(trace),
+            Err(err) => eprintln!("skipping {}: {}", path.display(), err),
+        }
+    }
+
+    print!("{}", corpus_stats(&traces));
+}
@@ -0,0 +1,66 @@
+//! Lightweight replay of a single serialized trace without setting up the fuzzer.
+//!
+//! Usage: `replay <trace-file>`
+//!
+//! The exit code is `0` if the trace executed without violating a security claim, `2` if a
+//! security claim was violated (objective) and `1` if the trace could not be loaded. A crash
+//! inside the PUT aborts the process and therefore also yields a nonzero exit code.
+
+use std::{env, fs, process};
+
+use tlspuffin::{
+    error::Error,
+    make_deterministic, openssl_version,
+    trace::{Trace, TraceContext},
+};
+
+const EXIT_LOAD_FAILURE: i32 = 1;
+const EXIT_OBJECTIVE: i32 = 2;
+
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: replay <trace-file>");
+            process::exit(EXIT_LOAD_FAILURE);
+        }
+    };
+
+    let trace = match fs::read(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|buffer| postcard::from_bytes::<Trace>(&buffer).map_err(|err| err.to_string()))
+    {
+        Ok(trace) => trace,
+        Err(err) => {
+            eprintln!("failed to load trace {}: {}", path, err);
+            process::exit(EXIT_LOAD_FAILURE);
+        }
+    };
+
+    println!("{}", openssl_version());
+    make_deterministic();
+
+    let mut ctx = TraceContext::new();
+    let result = trace.execute(&mut ctx);
+
+    for descriptor in &trace.descriptors {
+        if let (Ok(sent), Ok(received)) = (
+            ctx.sent_alerts(descriptor.name),
+            ctx.received_alerts(descriptor.name),
+        ) {
+            println!(
+                "agent {}: sent alerts {:?}, received alerts {:?}",
+                descriptor.name, sent, received
+            );
+        }
+    }
+
+    match result {
+        Ok(_) => println!("trace executed successfully"),
+        Err(err @ Error::SecurityClaim(_, _)) => {
+            println!("objective: {}", err);
+            process::exit(EXIT_OBJECTIVE);
+        }
+        Err(err) => println!("trace execution stopped: {}", err),
+    }
+}
@@ -0,0 +1,187 @@
+//! Differential testing executes the same [`Trace`] with two [`Backend`]s and compares what the
+//! agents sent. A divergence hints at a bug in one of the implementations.
+//!
+//! The fuzzer only runs differential executions if they are enabled and more than one backend is
+//! available, see [`available_backends`]. Comparing a backend with itself would never diverge.
+
+use std::any::TypeId;
+
+use rustls::msgs::message::Message;
+
+use crate::agent::AgentName;
+use crate::error::Error;
+use crate::io::ConnectionInfo;
+use crate::trace::{TlsMessageType, Trace, TraceContext};
+
+/// A backend is able to execute traces. Each execution starts with a fresh [`TraceContext`].
+pub trait Backend {
+    fn name(&self) -> &str;
+
+    /// Executes the `trace` and returns the context after the execution along with its result
+    fn execute(&self, trace: &Trace) -> (TraceContext, Result<(), Error>);
+}
+
+/// Executes traces with OpenSSL agents
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OpenSSLBackend;
+
+impl Backend for OpenSSLBackend {
+    fn name(&self) -> &str {
+        "openssl"
+    }
+
+    fn execute(&self, trace: &Trace) -> (TraceContext, Result<(), Error>) {
+        let mut ctx = TraceContext::new();
+        let result = trace.execute(&mut ctx);
+        (ctx, result)
+    }
+}
+
+/// Returns the backends which are linked into tlspuffin
+pub fn available_backends() -> Vec<Box<dyn Backend>> {
+    vec![Box::new(OpenSSLBackend)]
+}
+
+/// A message which an agent sent during the execution of a trace
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ObservedMessage {
+    pub agent: AgentName,
+    pub message_type: Option<TlsMessageType>,
+}
+
+/// Everything we compare between two executions
+#[derive(Clone, Debug)]
+struct Observation {
+    /// Messages which could be decoded, in the order they were sent
+    messages: Vec<ObservedMessage>,
+    /// Parameters which each agent of the trace negotiated
+    connections: Vec<(AgentName, Option<ConnectionInfo>)>,
+    error: Option<String>,
+}
+
+impl Observation {
+    fn observe(backend: &dyn Backend, trace: &Trace) -> Self {
+        let (ctx, result) = backend.execute(trace);
+
+        let messages = ctx
+            .knowledge()
+            .iter()
+            .filter(|knowledge| knowledge.data.as_ref().type_id() == TypeId::of::<Message>())
+            .map(|knowledge| ObservedMessage {
+                agent: knowledge.agent_name,
+                message_type: knowledge.tls_message_type,
+            })
+            .collect();
+
+        let connections = trace
+            .descriptors
+            .iter()
+            .map(|descriptor| {
+                let connection = ctx
+                    .find_agent(descriptor.name)
+                    .ok()
+                    .map(|agent| agent.stream.connection_info());
+                (descriptor.name, connection)
+            })
+            .collect();
+
+        Self {
+            messages,
+            connections,
+            error: result.err().map(|err| err.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DiffResult {
+    /// Both backends sent the same messages, negotiated the same parameters and either both or none
+    /// failed
+    Equal,
+    /// The `index`-th message differs. `None` means that the backend sent fewer messages.
+    MessageDiverged {
+        index: usize,
+        a: Option<ObservedMessage>,
+        b: Option<ObservedMessage>,
+    },
+    /// The messages are the same, but the agent negotiated different parameters, e.g. the version
+    /// or the ALPN protocol. `None` means that the agent does not exist.
+    ConnectionDiverged {
+        agent: AgentName,
+        a: Option<ConnectionInfo>,
+        b: Option<ConnectionInfo>,
+    },
+    /// The messages and parameters are the same, but only one of the executions failed
+    OutcomeDiverged {
+        a: Option<String>,
+        b: Option<String>,
+    },
+}
+
+impl DiffResult {
+    pub fn is_divergence(&self) -> bool {
+        *self != DiffResult::Equal
+    }
+}
+
+/// Executes `trace` with both backends and returns the first point at which the executions
+/// diverge. Only messages which could be decoded are compared, because encrypted records differ
+/// between implementations anyway. Afterwards, the parameters negotiated by each agent are
+/// compared, see [`ConnectionInfo`].
+pub fn run_differential(
+    trace: &Trace,
+    backend_a: &dyn Backend,
+    backend_b: &dyn Backend,
+) -> DiffResult {
+    let a = Observation::observe(backend_a, trace);
+    let b = Observation::observe(backend_b, trace);
+
+    let length = a.messages.len().max(b.messages.len());
+    for index in 0..length {
+        let message_a = a.messages.get(index).copied();
+        let message_b = b.messages.get(index).copied();
+
+        if message_a != message_b {
+            debug!(
+                "Backends {} and {} diverge at message #{}: {:?} vs {:?}",
+                backend_a.name(),
+                backend_b.name(),
+                index,
+                message_a,
+                message_b
+            );
+            return DiffResult::MessageDiverged {
+                index,
+                a: message_a,
+                b: message_b,
+            };
+        }
+    }
+
+    for ((agent, connection_a), (_, connection_b)) in a.connections.into_iter().zip(b.connections) {
+        if connection_a != connection_b {
+            debug!(
+                "Backends {} and {} negotiated different parameters for agent {}: {:?} vs {:?}",
+                backend_a.name(),
+                backend_b.name(),
+                agent,
+                connection_a,
+                connection_b
+            );
+            return DiffResult::ConnectionDiverged {
+                agent,
+                a: connection_a,
+                b: connection_b,
+            };
+        }
+    }
+
+    if a.error.is_some() != b.error.is_some() {
+        return DiffResult::OutcomeDiverged {
+            a: a.error,
+            b: b.error,
+        };
+    }
+
+    DiffResult::Equal
+}
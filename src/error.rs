@@ -25,6 +25,8 @@ pub enum Error {
     Stream(String),
     Extraction(ContentType),
     SecurityClaim(&'static str, Vec<(AgentName, Claim)>),
+    /// A [`TraceExpectation`](crate::trace::TraceExpectation) did not hold after executing a trace
+    Expectation(String),
 }
 
 /*fn serialize_openssl_error<S>(error: &ErrorStack, serializer: S) -> Result<S::Ok, S::Error>
@@ -71,6 +73,7 @@ impl fmt::Display for Error {
                 "error because a security violation occurred. msg: {}, claims: {:?}",
                 msg, claims
             ),
+            Error::Expectation(err) => write!(f, "error because an expectation failed: {}", err),
         }
     }
 }
@@ -0,0 +1,113 @@
+//! Bounds a fuzzing campaign by the total amount of executions or by its wallclock time.
+//!
+//! The broker and all clients of a campaign run in their own process group. Once the broker
+//! notices that the budget is used up, it reports a final summary and terminates the whole group.
+//! The supervising process then returns from [`start`](crate::fuzzer::start).
+
+use core::time::Duration;
+use std::process;
+use std::thread;
+use std::time::Instant;
+
+use nix::sys::signal::{killpg, Signal};
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::{fork, getpgrp, setpgid, ForkResult, Pid};
+
+/// Time the broker gets after the wallclock budget ran out to report the final summary. After
+/// that the supervisor kills the campaign itself.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Budget of a fuzzing campaign, summed over all clients. Without any limit a campaign runs
+/// forever.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CampaignBudget {
+    pub max_executions: Option<u64>,
+    pub max_wallclock: Option<Duration>,
+}
+
+impl CampaignBudget {
+    pub fn new(max_executions: Option<u64>, max_wallclock: Option<Duration>) -> Self {
+        Self {
+            max_executions,
+            max_wallclock,
+        }
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.max_executions.is_some() || self.max_wallclock.is_some()
+    }
+
+    /// Whether `executions` which happened within `elapsed` use up the budget
+    pub fn is_exhausted(&self, executions: u64, elapsed: Duration) -> bool {
+        self.max_executions
+            .map_or(false, |max_executions| executions >= max_executions)
+            || self
+                .max_wallclock
+                .map_or(false, |max_wallclock| elapsed >= max_wallclock)
+    }
+}
+
+/// Kills the broker and all clients of the current campaign, which share the process group
+/// created by [`supervise`].
+pub fn terminate_campaign() {
+    if let Err(err) = killpg(getpgrp(), Signal::SIGKILL) {
+        error!("Failed to terminate the campaign: {}", err);
+    }
+}
+
+/// Runs `campaign` in a new process group and blocks until it exited. If the campaign does not
+/// stop on its own within the wallclock budget, then it is killed after a grace period.
+pub fn supervise<F: FnOnce()>(budget: &CampaignBudget, campaign: F) {
+    let started = Instant::now();
+
+    match unsafe { fork() } {
+        Ok(ForkResult::Child) => {
+            // The broker and all clients inherit the process group
+            if let Err(err) = setpgid(Pid::from_raw(0), Pid::from_raw(0)) {
+                error!(
+                    "Failed to create the process group of the campaign: {}",
+                    err
+                );
+                process::exit(1);
+            }
+            campaign();
+            process::exit(0);
+        }
+        Ok(ForkResult::Parent { child }) => {
+            // Also set the process group here, such that killing it never races with the child
+            let _ = setpgid(child, child);
+            let deadline = budget
+                .max_wallclock
+                .map(|max_wallclock| max_wallclock + SHUTDOWN_GRACE_PERIOD);
+
+            loop {
+                match waitpid(child, Some(WaitPidFlag::WNOHANG)) {
+                    Ok(WaitStatus::StillAlive) => {}
+                    Ok(status) => {
+                        info!("Campaign exited with {:?}", status);
+                        break;
+                    }
+                    Err(err) => {
+                        error!("Failed to wait for the campaign: {}", err);
+                        break;
+                    }
+                }
+
+                if deadline.map_or(false, |deadline| started.elapsed() >= deadline) {
+                    warn!("Campaign did not stop within its budget, killing it");
+                    let _ = killpg(child, Signal::SIGKILL);
+                    let _ = waitpid(child, None);
+                    break;
+                }
+
+                thread::sleep(POLL_INTERVAL);
+            }
+
+            // Clients may outlive the broker, e.g. if the launcher failed
+            let _ = killpg(child, Signal::SIGKILL);
+            info!("Campaign finished after {:?}", started.elapsed());
+        }
+        Err(err) => panic!("Failed to fork the campaign: {}", err),
+    }
+}
@@ -0,0 +1,152 @@
+//! Mutations on the byte level. In contrast to the mutations in [`crate::fuzzer::mutations`],
+//! these mutations corrupt the serialized records right before they are delivered to an agent.
+//! This reaches edge cases of record and message parsers which are not expressible as terms.
+
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+
+use libafl::bolts::rands::{Rand, StdRand};
+use libafl::corpus::{Corpus, InMemoryCorpus};
+use libafl::inputs::{BytesInput, HasBytesVec, Input};
+use libafl::mutators::{havoc_mutations, Mutator, StdScheduledMutator};
+use libafl::stages::Stage;
+use libafl::state::{HasClientPerfStats, HasCorpus, HasRand, HasSolutions, StdState};
+use libafl::{Error, Evaluator};
+use serde::{Deserialize, Serialize};
+
+thread_local! {
+    /// Seed for the byte havoc of the current execution of the harness
+    static BYTE_HAVOC_SEED: Cell<Option<u64>> = Cell::new(None);
+    /// Applies the havoc mutations, see [`havoc_bytes`]
+    static BYTE_HAVOC: RefCell<Box<dyn FnMut(&[u8], u64) -> Vec<u8>>> = RefCell::new(byte_havoc());
+}
+
+/// Sets the seed with which the harness mutates the bytes of records. `None` disables byte havoc.
+pub fn set_byte_havoc_seed(seed: Option<u64>) {
+    BYTE_HAVOC_SEED.with(|cell| cell.set(seed));
+}
+
+pub fn byte_havoc_seed() -> Option<u64> {
+    BYTE_HAVOC_SEED.with(|cell| cell.get())
+}
+
+/// Seed of the byte havoc with which a testcase has been executed. The bytes of the testcase are
+/// not mutated, therefore the seed is required to reproduce the execution, see
+/// [`crate::trace::TraceContext::enable_byte_havoc`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ByteHavocMetadata {
+    pub seed: u64,
+}
+
+libafl::impl_serdeany!(ByteHavocMetadata);
+
+/// Builds the state and mutator for the havoc mutations once per thread. The state only provides
+/// the random number generator, which is reseeded for each delivery.
+fn byte_havoc() -> Box<dyn FnMut(&[u8], u64) -> Vec<u8>> {
+    let mut state = StdState::new(
+        StdRand::with_seed(0),
+        InMemoryCorpus::<BytesInput>::new(),
+        InMemoryCorpus::new(),
+        (),
+    );
+    let mut mutator = StdScheduledMutator::new(havoc_mutations());
+
+    Box::new(move |bytes: &[u8], seed: u64| {
+        state.rand_mut().set_seed(seed);
+        let mut input = BytesInput::new(bytes.to_vec());
+
+        // Mutations like crossovers are skipped as there is no corpus. A skipped mutation is fine.
+        if let Err(err) = mutator.mutate(&mut state, &mut input, 0) {
+            debug!("Byte havoc failed: {}", err);
+        }
+
+        input.bytes().to_vec()
+    })
+}
+
+/// Applies libafl's [`havoc_mutations`] to `bytes`. The result only depends on `bytes` and `seed`.
+pub fn havoc_bytes(bytes: &[u8], seed: u64) -> Vec<u8> {
+    BYTE_HAVOC.with(|byte_havoc| (byte_havoc.borrow_mut())(bytes, seed))
+}
+
+/// Executes testcases of the corpus with byte havoc enabled, see [`set_byte_havoc_seed`]. The
+/// testcases themselves are not mutated. Each execution uses a fresh seed which new corpus
+/// entries and solutions carry as [`ByteHavocMetadata`].
+pub struct ByteHavocStage<C, E, EM, I, R, S, SC, Z>
+where
+    C: Corpus<I>,
+    I: Input,
+    R: Rand,
+    S: HasClientPerfStats + HasCorpus<C, I> + HasSolutions<SC, I> + HasRand<R>,
+    SC: Corpus<I>,
+    Z: Evaluator<E, EM, I, S>,
+{
+    max_iterations_per_stage: u64,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(C, E, EM, I, R, S, SC, Z)>,
+}
+
+impl<C, E, EM, I, R, S, SC, Z> Stage<E, EM, S, Z> for ByteHavocStage<C, E, EM, I, R, S, SC, Z>
+where
+    C: Corpus<I>,
+    I: Input,
+    R: Rand,
+    S: HasClientPerfStats + HasCorpus<C, I> + HasSolutions<SC, I> + HasRand<R>,
+    SC: Corpus<I>,
+    Z: Evaluator<E, EM, I, S>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let iterations = 1 + state.rand_mut().below(self.max_iterations_per_stage);
+
+        for _ in 0..iterations {
+            let input = state
+                .corpus()
+                .get(corpus_idx)?
+                .borrow_mut()
+                .load_input()?
+                .clone();
+            let seed = state.rand_mut().next();
+            let solutions = state.solutions().count();
+
+            // Crashes restart the process, therefore log the seed beforehand
+            debug!("Byte havoc with seed {} on testcase {}", seed, corpus_idx);
+            set_byte_havoc_seed(Some(seed));
+            let result = fuzzer.evaluate_input(state, executor, manager, input);
+            set_byte_havoc_seed(None);
+            result?;
+
+            if state.solutions().count() > solutions {
+                info!(
+                    "Byte havoc with seed {} on testcase {} lead to a solution",
+                    seed, corpus_idx
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<C, E, EM, I, R, S, SC, Z> ByteHavocStage<C, E, EM, I, R, S, SC, Z>
+where
+    C: Corpus<I>,
+    I: Input,
+    R: Rand,
+    S: HasClientPerfStats + HasCorpus<C, I> + HasSolutions<SC, I> + HasRand<R>,
+    SC: Corpus<I>,
+    Z: Evaluator<E, EM, I, S>,
+{
+    pub fn new(max_iterations_per_stage: u64) -> Self {
+        Self {
+            max_iterations_per_stage,
+            phantom: PhantomData,
+        }
+    }
+}
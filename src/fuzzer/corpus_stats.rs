@@ -0,0 +1,111 @@
+//! Aggregate statistics of a corpus of traces. They show which functions, recipe shapes and trace
+//! lengths a corpus covers and therefore where the fuzzer is under-exploring.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::term::remove_prefix;
+use crate::tls::SIGNATURE;
+use crate::trace::{Action, Trace};
+
+/// Statistics of a corpus, see [`corpus_stats`]. Each histogram maps a value to the amount of
+/// occurrences of that value.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CorpusStats {
+    /// Amount of traces in the corpus
+    pub traces: usize,
+    /// Histogram of the sizes of the recipes of all input steps, see [`Term::size`]
+    ///
+    /// [`Term::size`]: crate::term::Term::size
+    pub recipe_sizes: BTreeMap<usize, usize>,
+    /// Histogram of the depths of the recipes of all input steps, see [`Term::depth`]
+    ///
+    /// [`Term::depth`]: crate::term::Term::depth
+    pub recipe_depths: BTreeMap<usize, usize>,
+    /// How often each function symbol is applied in the recipes of all input steps, keyed by the
+    /// name of the function without its module path
+    pub function_counts: BTreeMap<String, usize>,
+    /// Histogram of the amount of steps per trace
+    pub step_counts: BTreeMap<usize, usize>,
+}
+
+impl CorpusStats {
+    /// Functions of the [`SIGNATURE`] which no recipe of the corpus uses
+    pub fn unused_functions(&self) -> Vec<String> {
+        let mut unused: Vec<String> = SIGNATURE
+            .functions
+            .iter()
+            .map(|(shape, _)| remove_prefix(shape.name))
+            .filter(|name| !self.function_counts.contains_key(name))
+            .collect();
+        unused.sort_unstable();
+        unused
+    }
+}
+
+/// Computes the [`CorpusStats`] of `traces`. Only the steps of the traces themselves are
+/// considered, not the ones of their prior traces.
+pub fn corpus_stats(traces: &[Trace]) -> CorpusStats {
+    let mut stats = CorpusStats {
+        traces: traces.len(),
+        ..CorpusStats::default()
+    };
+
+    for trace in traces {
+        *stats.step_counts.entry(trace.steps.len()).or_insert(0) += 1;
+
+        for step in &trace.steps {
+            if let Action::Input(input) = &step.action {
+                let recipe = &input.recipe;
+                *stats.recipe_sizes.entry(recipe.size()).or_insert(0) += 1;
+                *stats.recipe_depths.entry(recipe.depth()).or_insert(0) += 1;
+
+                for function in recipe.operators() {
+                    *stats
+                        .function_counts
+                        .entry(remove_prefix(function.name()))
+                        .or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    stats
+}
+
+fn fmt_histogram(
+    f: &mut Formatter<'_>,
+    title: &str,
+    histogram: &BTreeMap<usize, usize>,
+) -> fmt::Result {
+    writeln!(f, "{}:", title)?;
+    for (value, count) in histogram {
+        writeln!(f, "  {:>6}: {}", value, count)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for CorpusStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "traces: {}", self.traces)?;
+        fmt_histogram(f, "steps per trace", &self.step_counts)?;
+        fmt_histogram(f, "recipe sizes", &self.recipe_sizes)?;
+        fmt_histogram(f, "recipe depths", &self.recipe_depths)?;
+
+        // Most frequently used functions first
+        let mut function_counts: Vec<(&String, &usize)> = self.function_counts.iter().collect();
+        function_counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+        writeln!(f, "function usage:")?;
+        for (name, count) in function_counts {
+            writeln!(f, "  {:>6} {}", count, name)?;
+        }
+
+        let unused = self.unused_functions();
+        writeln!(f, "unused functions ({}):", unused.len())?;
+        for name in unused {
+            writeln!(f, "  {}", name)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+//! Classification of crashes. Each solution gets a [`CrashSignature`] which identifies the site of
+//! the crash. Solutions with the same signature are likely duplicates of each other. If the PUT is
+//! built with ASAN, then solutions also get the [`SanitizerReport`] of the crash.
+
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+use libafl::bolts::tuples::Named;
+use libafl::corpus::Testcase;
+use libafl::events::EventFirer;
+use libafl::executors::ExitKind;
+use libafl::feedbacks::Feedback;
+use libafl::inputs::Input;
+use libafl::observers::ObserversTuple;
+use libafl::state::HasClientPerfStats;
+use libafl::Error;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::fuzzer::harness::{collect_fork_report, ForkStatus, FORK_TIMEOUT};
+
+/// How many frames of the backtrace identify a crash site
+pub const CRASH_SIGNATURE_FRAMES: usize = 8;
+
+/// Frames of these modules belong to the crash handling itself and are not part of a signature
+const IGNORED_FRAME_PREFIXES: [&str; 8] = [
+    "backtrace::",
+    "libafl::",
+    "std::",
+    "core::",
+    "alloc::",
+    "tlspuffin::fuzzer::crash::",
+    "tlspuffin::fuzzer::harness::on_child_crash",
+    "__restore_rt",
+];
+
+/// Whether the demangled frame `name` belongs to the crash handling. Methods of trait
+/// implementations are demangled like `<libafl::executors::inprocess::InProcessExecutor<..> as
+/// libafl::executors::Executor<..>>::run_target`, therefore the path of the implementing type
+/// decides.
+pub(crate) fn is_ignored_frame(name: &str) -> bool {
+    let path = name.trim_start_matches('<');
+    IGNORED_FRAME_PREFIXES
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+}
+
+/// Identifies a crash by the symbol names of the topmost frames of its backtrace. Addresses are
+/// not part of the signature as they differ between runs.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CrashSignature {
+    pub hash: u64,
+    pub top_frames: Vec<String>,
+}
+
+libafl::impl_serdeany!(CrashSignature);
+
+impl CrashSignature {
+    pub fn new(top_frames: Vec<String>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        top_frames.hash(&mut hasher);
+
+        Self {
+            hash: hasher.finish(),
+            top_frames,
+        }
+    }
+
+    /// Captures the signature of the current call stack, considering at most `frames` frames
+    pub fn capture(frames: usize) -> Self {
+        let mut top_frames = Vec::new();
+
+        backtrace::trace(|frame| {
+            backtrace::resolve_frame(frame, |symbol| {
+                if let Some(name) = symbol.name() {
+                    let name = format!("{:#}", name);
+                    if !is_ignored_frame(&name) {
+                        top_frames.push(name);
+                    }
+                }
+            });
+            top_frames.len() < frames
+        });
+
+        top_frames.truncate(frames);
+        Self::new(top_frames)
+    }
+}
+
+/// Structured diagnostics of an ASAN or UBSAN report, see [`SanitizerReport::parse`]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SanitizerReport {
+    /// Bug class, e.g. `heap-buffer-overflow`, `heap-use-after-free`, `double-free` or
+    /// `signed-integer-overflow`
+    pub kind: String,
+    /// Address which the faulty access touched, if the report names one
+    pub address: Option<u64>,
+    /// Frames of the first stack trace of the report, e.g.
+    /// `tls1_process_heartbeat ssl/t1_lib.c:2586:3`
+    pub stack: Vec<String>,
+}
+
+libafl::impl_serdeany!(SanitizerReport);
+
+const ASAN_ERROR: &str = "ERROR: AddressSanitizer: ";
+const UBSAN_ERROR: &str = "runtime error: ";
+
+impl SanitizerReport {
+    /// Parses the first error of a sanitizer `report` like it is printed to stderr. Returns `None`
+    /// if the report contains no ASAN or UBSAN error.
+    pub fn parse(report: &str) -> Option<Self> {
+        let mut lines = report.lines();
+
+        let (kind, error) = lines.by_ref().find_map(|line| {
+            if let Some(start) = line.find(ASAN_ERROR) {
+                let error = &line[start + ASAN_ERROR.len()..];
+                let mut words = error.split_whitespace();
+                let kind = match words.next()? {
+                    // "attempting double-free on 0x..."
+                    "attempting" => words.next()?,
+                    kind => kind,
+                };
+                Some((kind.to_string(), error))
+            } else if let Some(start) = line.find(UBSAN_ERROR) {
+                let error = &line[start + UBSAN_ERROR.len()..];
+                // "signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'"
+                let description = error.split(':').next().unwrap_or(error);
+                Some((
+                    description.split_whitespace().collect::<Vec<_>>().join("-"),
+                    error,
+                ))
+            } else {
+                None
+            }
+        })?;
+
+        let address = error
+            .split_whitespace()
+            .find_map(|word| word.strip_prefix("0x"))
+            .and_then(|hex| u64::from_str_radix(hex, 16).ok());
+
+        // Frames look like "    #0 0x55d4c0 in tls1_process_heartbeat ssl/t1_lib.c:2586:3"
+        let stack = lines
+            .map(str::trim)
+            .skip_while(|line| !line.starts_with('#'))
+            .take_while(|line| line.starts_with('#'))
+            .map(|frame| match frame.find(" in ") {
+                Some(start) => frame[start + " in ".len()..].to_string(),
+                None => frame.to_string(),
+            })
+            .collect();
+
+        Some(Self {
+            kind,
+            address,
+            stack,
+        })
+    }
+}
+
+/// The ASAN report of the crash which is currently handled, see [`capture_sanitizer_reports`]
+static SANITIZER_REPORT: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+#[cfg(feature = "asan")]
+extern "C" {
+    fn __asan_set_error_report_callback(
+        callback: Option<unsafe extern "C" fn(*const std::os::raw::c_char)>,
+    );
+}
+
+#[cfg(feature = "asan")]
+unsafe extern "C" fn on_sanitizer_report(report: *const std::os::raw::c_char) {
+    let report = std::ffi::CStr::from_ptr(report)
+        .to_string_lossy()
+        .into_owned();
+    // We are within the crash, never block on a lock which the crashed code may hold
+    if let Ok(mut slot) = SANITIZER_REPORT.try_lock() {
+        *slot = Some(report);
+    }
+}
+
+/// Keeps the report of ASAN before the PUT aborts, such that [`CrashSignatureFeedback`] can
+/// attach it to the solution. UBSAN reports are only printed to stderr, they can be parsed with
+/// [`SanitizerReport::parse`]. Forked children hand the report of their crash to the parent, see
+/// [`crate::fuzzer::harness::ForkStatus`]. Does nothing unless the `asan` feature is enabled.
+pub fn capture_sanitizer_reports() {
+    #[cfg(feature = "asan")]
+    unsafe {
+        __asan_set_error_report_callback(Some(on_sanitizer_report));
+    }
+}
+
+/// Takes the sanitizer report which has been captured since the last call
+pub(crate) fn take_sanitizer_report() -> Option<SanitizerReport> {
+    let report = SANITIZER_REPORT.try_lock().ok()?.take()?;
+    SanitizerReport::parse(&report)
+}
+
+thread_local! {
+    /// How the last forked child ended, see [`record_fork_status`]
+    static FORK_STATUS: RefCell<Option<ForkStatus>> = RefCell::new(None);
+}
+
+/// Records how the forked child of the current execution ended. The crash handler of the parent
+/// does not see the stack of the child, therefore the child captures its signature itself.
+pub(crate) fn record_fork_status(status: ForkStatus) {
+    FORK_STATUS.with(|cell| *cell.borrow_mut() = Some(status));
+}
+
+/// Whether the forked child of the current execution was killed after [`FORK_TIMEOUT`]
+pub(crate) fn fork_timed_out() -> bool {
+    FORK_STATUS.with(|cell| matches!(*cell.borrow(), Some(ForkStatus::TimedOut)))
+}
+
+/// Reports crashes like [`libafl::feedbacks::CrashFeedback`] and additionally attaches a
+/// [`CrashSignature`] and, if available, a [`SanitizerReport`] to the solution. Forked children
+/// which were killed after [`FORK_TIMEOUT`] are not reported as crashes, see
+/// [`crate::fuzzer::harness::ForkTimeoutExecutor`].
+#[derive(Default)]
+pub struct CrashSignatureFeedback {
+    signature: Option<CrashSignature>,
+    sanitizer_report: Option<SanitizerReport>,
+}
+
+impl CrashSignatureFeedback {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Named for CrashSignatureFeedback {
+    fn name(&self) -> &str {
+        "CrashSignatureFeedback"
+    }
+}
+
+impl<I, S> Feedback<I, S> for CrashSignatureFeedback
+where
+    I: Input,
+    S: HasClientPerfStats,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I, S>,
+        OT: ObserversTuple<I, S>,
+    {
+        if let ExitKind::Crash = exit_kind {
+            collect_fork_report();
+            let (signature, sanitizer_report) =
+                match FORK_STATUS.with(|cell| cell.borrow_mut().take()) {
+                    Some(ForkStatus::Crashed(signature, sanitizer_report)) => {
+                        (signature, sanitizer_report)
+                    }
+                    Some(ForkStatus::TimedOut) => {
+                        info!("Forked child timed out after {:?}", FORK_TIMEOUT);
+                        return Ok(false);
+                    }
+                    // We are still within the crash handler, therefore the stack contains the
+                    // crash site
+                    Some(ForkStatus::Finished) | None => (
+                        CrashSignature::capture(CRASH_SIGNATURE_FRAMES),
+                        take_sanitizer_report(),
+                    ),
+                };
+            info!(
+                "Crash signature {:x}: {:?}",
+                signature.hash, signature.top_frames
+            );
+            self.signature = Some(signature);
+
+            self.sanitizer_report = sanitizer_report;
+            if let Some(report) = &self.sanitizer_report {
+                info!("Sanitizer report {} at {:x?}", report.kind, report.address);
+            }
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(signature) = self.signature.take() {
+            testcase.add_metadata(signature);
+        }
+        if let Some(report) = self.sanitizer_report.take() {
+            testcase.add_metadata(report);
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.signature = None;
+        self.sanitizer_report = None;
+        Ok(())
+    }
+}
@@ -0,0 +1,430 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+use libafl::bolts::tuples::Named;
+use libafl::corpus::Testcase;
+use libafl::events::EventFirer;
+use libafl::executors::ExitKind;
+use libafl::feedbacks::Feedback;
+use libafl::observers::ObserversTuple;
+use libafl::state::{HasClientPerfStats, HasMetadata};
+use libafl::Error;
+use rustls::msgs::enums::AlertDescription;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::AgentName;
+use crate::differential::{run_differential, Backend};
+use crate::fuzzer::byte_havoc::{byte_havoc_seed, ByteHavocMetadata};
+use crate::fuzzer::harness::collect_fork_report;
+use crate::fuzzer::stages::MutationLog;
+use crate::trace::Trace;
+
+/// Marks a trace as a solution if two backends diverge while executing it. Each backend is compared
+/// with the first one. With less than two backends, the feedback is disabled and never reports a
+/// trace as interesting.
+pub struct DifferentialFeedback {
+    backends: Vec<Box<dyn Backend>>,
+}
+
+impl DifferentialFeedback {
+    pub fn new(backends: Vec<Box<dyn Backend>>) -> Self {
+        Self { backends }
+    }
+
+    pub fn disabled() -> Self {
+        Self { backends: vec![] }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.backends.len() > 1
+    }
+}
+
+impl Named for DifferentialFeedback {
+    fn name(&self) -> &str {
+        "DifferentialFeedback"
+    }
+}
+
+impl<S> Feedback<Trace, S> for DifferentialFeedback
+where
+    S: HasClientPerfStats,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        input: &Trace,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<Trace, S>,
+        OT: ObserversTuple<Trace, S>,
+    {
+        if !self.is_enabled() {
+            return Ok(false);
+        }
+
+        let (reference, others) = self.backends.split_first().unwrap();
+        for other in others {
+            let result = run_differential(input, reference.as_ref(), other.as_ref());
+            if result.is_divergence() {
+                info!("Found differential: {:?}", result);
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+thread_local! {
+    /// OpenSSL states which the agents reached during the last execution of the harness
+    static REACHED_STATES: RefCell<Vec<String>> = RefCell::new(Vec::new());
+}
+
+/// Records the states which the agents reached during the current execution of the harness
+pub fn record_reached_states(states: Vec<String>) {
+    REACHED_STATES.with(|cell| *cell.borrow_mut() = states);
+}
+
+pub(crate) fn take_reached_states() -> Vec<String> {
+    REACHED_STATES.with(|cell| cell.replace(Vec::new()))
+}
+
+/// All OpenSSL states which have been reached while fuzzing
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReachedStatesMetadata {
+    pub states: HashSet<String>,
+}
+
+libafl::impl_serdeany!(ReachedStatesMetadata);
+
+/// Reports a trace as interesting if one of its agents reaches an OpenSSL state, as described by
+/// `SSL_state_string_long`, which has not been observed before. This complements the edge coverage
+/// with protocol specific feedback.
+pub struct StateFeedback {}
+
+impl StateFeedback {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Named for StateFeedback {
+    fn name(&self) -> &str {
+        "StateFeedback"
+    }
+}
+
+impl<S> Feedback<Trace, S> for StateFeedback
+where
+    S: HasClientPerfStats + HasMetadata,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &Trace,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<Trace, S>,
+        OT: ObserversTuple<Trace, S>,
+    {
+        collect_fork_report();
+        let reached_states = take_reached_states();
+
+        if !state.has_metadata::<ReachedStatesMetadata>() {
+            state.add_metadata(ReachedStatesMetadata::default());
+        }
+        let metadata = state
+            .metadata_mut()
+            .get_mut::<ReachedStatesMetadata>()
+            .unwrap();
+
+        let mut interesting = false;
+        for reached_state in reached_states {
+            if metadata.states.insert(reached_state.clone()) {
+                debug!("Reached new state: {}", reached_state);
+                interesting = true;
+            }
+        }
+        Ok(interesting)
+    }
+}
+
+thread_local! {
+    /// Alerts which the agents sent during the last execution of the harness
+    static SENT_ALERTS: RefCell<Vec<(AgentName, AlertDescription)>> = RefCell::new(Vec::new());
+}
+
+/// Records the alerts which the agents sent during the current execution of the harness
+pub fn record_sent_alerts(alerts: Vec<(AgentName, AlertDescription)>) {
+    SENT_ALERTS.with(|cell| *cell.borrow_mut() = alerts);
+}
+
+pub(crate) fn take_sent_alerts() -> Vec<(AgentName, AlertDescription)> {
+    SENT_ALERTS.with(|cell| cell.replace(Vec::new()))
+}
+
+/// All combinations of sending agent and alert description which have been observed while fuzzing
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AlertCombinationsMetadata {
+    pub combinations: HashSet<(AgentName, u8)>,
+}
+
+libafl::impl_serdeany!(AlertCombinationsMetadata);
+
+/// Reports a trace as interesting if one of its agents sends an alert which this agent has not
+/// sent before. This steers the fuzzer towards unusual error paths of the protocol instead of
+/// finding the same `handshake_failure` over and over.
+pub struct AlertNoveltyFeedback {}
+
+impl AlertNoveltyFeedback {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Named for AlertNoveltyFeedback {
+    fn name(&self) -> &str {
+        "AlertNoveltyFeedback"
+    }
+}
+
+impl<S> Feedback<Trace, S> for AlertNoveltyFeedback
+where
+    S: HasClientPerfStats + HasMetadata,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &Trace,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<Trace, S>,
+        OT: ObserversTuple<Trace, S>,
+    {
+        collect_fork_report();
+        let sent_alerts = take_sent_alerts();
+
+        if !state.has_metadata::<AlertCombinationsMetadata>() {
+            state.add_metadata(AlertCombinationsMetadata::default());
+        }
+        let metadata = state
+            .metadata_mut()
+            .get_mut::<AlertCombinationsMetadata>()
+            .unwrap();
+
+        let mut interesting = false;
+        for (agent, alert) in sent_alerts {
+            if metadata.combinations.insert((agent, alert.get_u8())) {
+                debug!("Agent {} sent new alert: {:?}", agent, alert);
+                interesting = true;
+            }
+        }
+        Ok(interesting)
+    }
+}
+
+/// The canonical hashes of all traces in the corpus
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CanonicalHashesMetadata {
+    pub hashes: HashSet<u64>,
+}
+
+libafl::impl_serdeany!(CanonicalHashesMetadata);
+
+/// Deduplicates the corpus. It reports a trace as interesting only if no trace with the same
+/// [`Trace::canonical_hash`] has been reported before. Recipes which differ only in the ids of
+/// their variables are therefore stored once. It is meant to be combined with other feedbacks
+/// through `feedback_and_fast`, such that only otherwise interesting traces are recorded.
+///
+/// Executions with byte havoc are never discarded: they run an unmutated corpus entry, whose
+/// hash is already known, but the havoc seed leads to a different execution.
+pub struct DedupFeedback {}
+
+impl DedupFeedback {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Named for DedupFeedback {
+    fn name(&self) -> &str {
+        "DedupFeedback"
+    }
+}
+
+impl<S> Feedback<Trace, S> for DedupFeedback
+where
+    S: HasClientPerfStats + HasMetadata,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        input: &Trace,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<Trace, S>,
+        OT: ObserversTuple<Trace, S>,
+    {
+        if byte_havoc_seed().is_some() {
+            return Ok(true);
+        }
+
+        if !state.has_metadata::<CanonicalHashesMetadata>() {
+            state.add_metadata(CanonicalHashesMetadata::default());
+        }
+        let metadata = state
+            .metadata_mut()
+            .get_mut::<CanonicalHashesMetadata>()
+            .unwrap();
+
+        let new = metadata.hashes.insert(input.canonical_hash());
+        if !new {
+            debug!("Discarding duplicate trace");
+        }
+        Ok(new)
+    }
+}
+
+/// Attaches the [`MutationLog`] of the last mutation to new corpus entries and solutions. Inputs
+/// which are executed with byte havoc are not mutated, they carry the [`ByteHavocMetadata`]
+/// instead. Logs without a parent are not attached, as they can not be replayed. It never reports
+/// a trace as interesting on its own.
+pub struct MutationLogFeedback {}
+
+impl MutationLogFeedback {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Named for MutationLogFeedback {
+    fn name(&self) -> &str {
+        "MutationLogFeedback"
+    }
+}
+
+impl<S> Feedback<Trace, S> for MutationLogFeedback
+where
+    S: HasClientPerfStats + HasMetadata,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &Trace,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<Trace, S>,
+        OT: ObserversTuple<Trace, S>,
+    {
+        Ok(false)
+    }
+
+    fn append_metadata(
+        &mut self,
+        state: &mut S,
+        testcase: &mut Testcase<Trace>,
+    ) -> Result<(), Error> {
+        if let Some(seed) = byte_havoc_seed() {
+            testcase.add_metadata(ByteHavocMetadata { seed });
+        } else if let Some(log) = state.metadata().get::<MutationLog>() {
+            if log.parent.is_some() {
+                testcase.add_metadata(log.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    /// Errors which OpenSSL reported during the last execution of the harness
+    static OPENSSL_ERRORS: RefCell<Vec<String>> = RefCell::new(Vec::new());
+}
+
+/// Records the errors which OpenSSL reported during the current execution of the harness, see
+/// [`crate::trace::TraceContext::openssl_errors`]
+pub fn record_openssl_errors(errors: Vec<String>) {
+    OPENSSL_ERRORS.with(|cell| *cell.borrow_mut() = errors);
+}
+
+/// The errors of the last execution. Unlike the states and alerts they are not taken, because
+/// both the feedback and the objective attach them.
+pub(crate) fn openssl_errors() -> Vec<String> {
+    OPENSSL_ERRORS.with(|cell| cell.borrow().clone())
+}
+
+/// Errors which OpenSSL reported while executing a testcase, in the format
+/// `library:function:reason`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OpenSSLErrorsMetadata {
+    pub errors: Vec<String>,
+}
+
+libafl::impl_serdeany!(OpenSSLErrorsMetadata);
+
+/// Attaches the errors which OpenSSL reported during the execution as [`OpenSSLErrorsMetadata`]
+/// to interesting testcases. It never reports a trace as interesting on its own.
+pub struct OpenSSLErrorsFeedback {
+    errors: Vec<String>,
+}
+
+impl OpenSSLErrorsFeedback {
+    pub fn new() -> Self {
+        Self { errors: vec![] }
+    }
+}
+
+impl Named for OpenSSLErrorsFeedback {
+    fn name(&self) -> &str {
+        "OpenSSLErrorsFeedback"
+    }
+}
+
+impl<S> Feedback<Trace, S> for OpenSSLErrorsFeedback
+where
+    S: HasClientPerfStats + HasMetadata,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &Trace,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<Trace, S>,
+        OT: ObserversTuple<Trace, S>,
+    {
+        collect_fork_report();
+        self.errors = openssl_errors();
+        Ok(false)
+    }
+
+    fn append_metadata(
+        &mut self,
+        _state: &mut S,
+        testcase: &mut Testcase<Trace>,
+    ) -> Result<(), Error> {
+        if !self.errors.is_empty() {
+            testcase.add_metadata(OpenSSLErrorsMetadata {
+                errors: std::mem::take(&mut self.errors),
+            });
+        }
+        Ok(())
+    }
+}
@@ -1,12 +1,225 @@
-use libafl::executors::ExitKind;
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use libafl::executors::{Executor, ExitKind, HasObservers};
+use libafl::inputs::Input;
+use libafl::observers::ObserversTuple;
+use nix::libc;
+use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+use nix::unistd::alarm;
 use rand::Rng;
+use rustls::msgs::codec::Codec;
+use rustls::msgs::enums::AlertDescription;
+use serde::{Deserialize, Serialize};
 
+use crate::agent::AgentName;
 use crate::error::Error;
+use crate::fuzzer::byte_havoc::{byte_havoc_seed, havoc_bytes};
+use crate::fuzzer::crash::{
+    fork_timed_out, record_fork_status, take_sanitizer_report, CrashSignature, SanitizerReport,
+    CRASH_SIGNATURE_FRAMES,
+};
+use crate::fuzzer::feedbacks::{
+    openssl_errors, record_openssl_errors, record_reached_states, record_sent_alerts,
+    take_reached_states, take_sent_alerts,
+};
 use crate::fuzzer::stats_observer::*;
+use crate::fuzzer::EDGES_MAP;
 use crate::trace::{Action, Trace, TraceContext};
 
+/// Executions in a forked child are killed after this duration, like in-process executions are
+/// interrupted by the `TimeoutExecutor`
+pub const FORK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Size of the shared memory through which a forked child hands its [`ForkReport`] to the parent.
+/// The first four bytes hold the length of the serialized report, zero if there is none.
+pub const FORK_REPORT_SIZE: usize = 1 << 20;
+
+thread_local! {
+    /// Shared memory for the [`ForkReport`]s, see [`set_fork_report_map`]. A forked child only
+    /// consists of the thread which forked it, therefore it inherits the map of that thread.
+    static FORK_REPORT_MAP: Cell<*mut u8> = Cell::new(std::ptr::null_mut());
+}
+
+/// How the execution of a forked child ended
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ForkStatus {
+    /// The child was killed after [`FORK_TIMEOUT`]
+    TimedOut,
+    Finished,
+    /// The child was terminated by a signal. If it was killed by a signal which it can not handle,
+    /// e.g. `SIGKILL`, then the signature is empty.
+    Crashed(CrashSignature, Option<SanitizerReport>),
+}
+
+/// Everything which the feedbacks of the parent need from the execution of a forked child. The
+/// state of the child is lost once it exits, therefore it writes the report to shared memory.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForkReport {
+    pub status: ForkStatus,
+    pub reached_states: Vec<String>,
+    pub sent_alerts: Vec<(AgentName, u8)>,
+    pub openssl_errors: Vec<String>,
+    pub stats: Vec<StatsValue>,
+}
+
+impl ForkReport {
+    fn new(status: ForkStatus) -> Self {
+        Self {
+            status,
+            reached_states: vec![],
+            sent_alerts: vec![],
+            openssl_errors: vec![],
+            stats: vec![],
+        }
+    }
+}
+
+/// Sets the shared memory through which the children which the current thread forks report to it,
+/// see [`fork_harness`]. The `map` must be at least [`FORK_REPORT_SIZE`] bytes large and must be
+/// mapped before the children are forked.
+pub fn set_fork_report_map(map: &'static mut [u8]) {
+    assert!(map.len() >= FORK_REPORT_SIZE);
+    map[..4].copy_from_slice(&0u32.to_le_bytes());
+    FORK_REPORT_MAP.with(|cell| cell.set(map.as_mut_ptr()));
+}
+
+fn fork_report_map() -> Option<&'static mut [u8]> {
+    let map = FORK_REPORT_MAP.with(|cell| cell.get());
+    if map.is_null() {
+        None
+    } else {
+        Some(unsafe { std::slice::from_raw_parts_mut(map, FORK_REPORT_SIZE) })
+    }
+}
+
+fn serialize_fork_report(report: &ForkReport) -> Vec<u8> {
+    match postcard::to_allocvec(report) {
+        Ok(serialized) if serialized.len() <= FORK_REPORT_SIZE - 4 => serialized,
+        // Keep at least the status if the states or stats do not fit
+        _ => postcard::to_allocvec(&ForkReport::new(report.status.clone())).unwrap(),
+    }
+}
+
+fn write_fork_report(report: &ForkReport) {
+    if let Some(map) = fork_report_map() {
+        write_serialized_fork_report(map, &serialize_fork_report(report));
+    }
+}
+
+/// Only copies memory, therefore it can be called from signal handlers
+fn write_serialized_fork_report(map: &mut [u8], serialized: &[u8]) {
+    map[4..4 + serialized.len()].copy_from_slice(serialized);
+    map[..4].copy_from_slice(&(serialized.len() as u32).to_le_bytes());
+}
+
+/// Everything the signal handlers of a forked child need, see [`fork_harness`]. It is prepared
+/// before the execution starts, such that the handlers neither allocate nor serialize where
+/// possible.
+struct ForkSignalContext {
+    /// Shared map to which the coverage of the child is copied
+    edges: *mut u8,
+    edges_len: usize,
+    /// Shared map for the [`ForkReport`], see [`set_fork_report_map`]
+    report_map: Option<&'static mut [u8]>,
+    /// Serialized report of a child which timed out
+    timeout_report: Vec<u8>,
+}
+
+/// Context of the signal handlers of the current forked child. A child only consists of a single
+/// thread, therefore the handlers can not race with [`fork_harness`].
+static mut FORK_SIGNAL_CONTEXT: Option<ForkSignalContext> = None;
+
+/// Copies the coverage of the child to the shared map which the observer of the parent reads
+fn copy_fork_coverage() {
+    if let Some(context) = unsafe { FORK_SIGNAL_CONTEXT.as_ref() } {
+        unsafe {
+            std::ptr::copy_nonoverlapping(EDGES_MAP.as_ptr(), context.edges, context.edges_len);
+        }
+    }
+}
+
+/// Reads the report of the last forked child, if there is one which has not been collected yet.
+/// The reached states, sent alerts and errors are recorded like [`harness`] does for in-process
+/// executions, the stats of the child replace the ones of the parent and the status is handed to
+/// [`crate::fuzzer::crash::CrashSignatureFeedback`]. Does nothing for in-process executions.
+pub(crate) fn collect_fork_report() {
+    let map = match fork_report_map() {
+        Some(map) => map,
+        None => return,
+    };
+
+    let length = u32::from_le_bytes([map[0], map[1], map[2], map[3]]) as usize;
+    if length == 0 || length > FORK_REPORT_SIZE - 4 {
+        return;
+    }
+    map[..4].copy_from_slice(&0u32.to_le_bytes());
+
+    let report = match postcard::from_bytes::<ForkReport>(&map[4..4 + length]) {
+        Ok(report) => report,
+        Err(err) => {
+            warn!("Failed to read the report of a forked child: {}", err);
+            return;
+        }
+    };
+
+    record_reached_states(report.reached_states);
+    record_sent_alerts(
+        report
+            .sent_alerts
+            .into_iter()
+            .filter_map(|(agent, alert)| Some((agent, AlertDescription::read_bytes(&[alert])?)))
+            .collect(),
+    );
+    record_openssl_errors(report.openssl_errors);
+    if !report.stats.is_empty() {
+        restore_stats(report.stats);
+    }
+    record_fork_status(report.status);
+}
+
+/// Signals which terminate a crashing child, see [`on_child_crash`]
+const CRASH_SIGNALS: [Signal; 5] = [
+    Signal::SIGABRT,
+    Signal::SIGSEGV,
+    Signal::SIGBUS,
+    Signal::SIGILL,
+    Signal::SIGFPE,
+];
+
+/// Reports the coverage and the crash site of a forked child. The handler is reset before it runs,
+/// therefore the child terminates through the default action once the handler returns and the
+/// parent observes the crash. The crash site is only known now, therefore capturing and
+/// serializing its signature allocates.
+extern "C" fn on_child_crash(_signal: libc::c_int) {
+    copy_fork_coverage();
+    write_fork_report(&ForkReport::new(ForkStatus::Crashed(
+        CrashSignature::capture(CRASH_SIGNATURE_FRAMES),
+        take_sanitizer_report(),
+    )));
+}
+
+/// Reports the coverage of a forked child which timed out along with the precomputed report. The
+/// handler is reset before it runs, therefore raising the signal again terminates the child once
+/// the handler returns.
+extern "C" fn on_child_timeout(signal: libc::c_int) {
+    copy_fork_coverage();
+    if let Some(context) = unsafe { FORK_SIGNAL_CONTEXT.as_mut() } {
+        if let Some(map) = context.report_map.as_mut() {
+            write_serialized_fork_report(map, &context.timeout_report);
+        }
+    }
+    unsafe {
+        libc::raise(signal);
+    }
+}
+
 pub fn harness(input: &Trace) -> ExitKind {
     let mut ctx = TraceContext::new();
+    if let Some(seed) = byte_havoc_seed() {
+        ctx.enable_byte_havoc(seed, havoc_bytes);
+    }
 
     TRACE_LENGTH.update(input.steps.len());
 
@@ -15,7 +228,7 @@ pub fn harness(input: &Trace) -> ExitKind {
             Action::Input(input) => {
                 TERM_SIZE.update(input.recipe.size());
             }
-            Action::Output(_) => {}
+            Action::Output(_) | Action::Delay(_) => {}
         }
     }
 
@@ -32,14 +245,149 @@ pub fn harness(input: &Trace) -> ExitKind {
                 warn!("{} claims: {:?}", msg, claims);
                 std::process::abort()
             }
+            // Expectations are only checked by Trace::execute_checked
+            Error::Expectation(_) => {}
         }
 
         trace!("{}", err);
     }
 
+    record_reached_states(
+        ctx.agent_states()
+            .into_iter()
+            .map(|state| state.to_string())
+            .collect(),
+    );
+    record_sent_alerts(ctx.all_sent_alerts());
+    let errors = ctx.openssl_errors();
+    for error in &errors {
+        OPENSSL_ERRORS.record(error.clone());
+    }
+    record_openssl_errors(errors);
+    for typ in ctx.message_types() {
+        MESSAGE_TYPES.record(*typ);
+    }
+
     ExitKind::Ok
 }
 
+/// Harness for executions in a forked child, see
+/// [`ExecutorConfig::Fork`](crate::fuzzer::ExecutorConfig::Fork). The coverage of the child is
+/// lost once it exits, therefore it is copied to the shared map `edges` which the observer of the
+/// parent reads, also if the child crashes or times out. Everything else the feedbacks need is
+/// handed over through a [`ForkReport`]. The child is killed after [`FORK_TIMEOUT`], see
+/// [`ForkTimeoutExecutor`].
+pub fn fork_harness(input: &Trace, edges: &mut [u8]) -> ExitKind {
+    let coverage = unsafe { &mut EDGES_MAP[0..edges.len()] };
+    // The observer of the parent only resets the shared map
+    coverage.iter_mut().for_each(|edge| *edge = 0);
+
+    // Stays in place if the child is killed by a signal which it can not handle
+    write_fork_report(&ForkReport::new(ForkStatus::Crashed(
+        CrashSignature::new(vec![]),
+        None,
+    )));
+    unsafe {
+        FORK_SIGNAL_CONTEXT = Some(ForkSignalContext {
+            edges: edges.as_mut_ptr(),
+            edges_len: edges.len(),
+            report_map: fork_report_map(),
+            timeout_report: serialize_fork_report(&ForkReport::new(ForkStatus::TimedOut)),
+        });
+    }
+
+    let on_crash = SigAction::new(
+        SigHandler::Handler(on_child_crash),
+        SaFlags::SA_RESETHAND,
+        SigSet::empty(),
+    );
+    for signal in &CRASH_SIGNALS {
+        if let Err(err) = unsafe { sigaction(*signal, &on_crash) } {
+            warn!("Failed to handle {:?} in the forked child: {}", signal, err);
+        }
+    }
+    let on_timeout = SigAction::new(
+        SigHandler::Handler(on_child_timeout),
+        SaFlags::SA_RESETHAND,
+        SigSet::empty(),
+    );
+    if let Err(err) = unsafe { sigaction(Signal::SIGALRM, &on_timeout) } {
+        warn!("Failed to handle SIGALRM in the forked child: {}", err);
+    }
+    alarm::set(u32::try_from(FORK_TIMEOUT.as_secs()).unwrap_or(u32::MAX));
+
+    let exit_kind = harness(input);
+
+    alarm::cancel();
+    write_fork_report(&ForkReport {
+        status: ForkStatus::Finished,
+        reached_states: take_reached_states(),
+        sent_alerts: take_sent_alerts()
+            .into_iter()
+            .map(|(agent, alert)| (agent, alert.get_u8()))
+            .collect(),
+        openssl_errors: openssl_errors(),
+        stats: snapshot_stats(),
+    });
+
+    copy_fork_coverage();
+    exit_kind
+}
+
+/// Wraps an [`InProcessForkExecutor`](libafl::executors::inprocess::InProcessForkExecutor) like
+/// the [`TimeoutExecutor`](libafl::executors::TimeoutExecutor) wraps an in-process executor. The
+/// fork executor reports every child which was terminated by a signal as a crash, including the
+/// ones which were killed after [`FORK_TIMEOUT`]. These are reported as [`ExitKind::Timeout`]
+/// instead, such that the `TimeoutFeedback` treats them like timeouts of in-process executions.
+pub struct ForkTimeoutExecutor<E> {
+    executor: E,
+}
+
+impl<E> ForkTimeoutExecutor<E> {
+    pub fn new(executor: E) -> Self {
+        Self { executor }
+    }
+}
+
+impl<E, EM, I, S, Z> Executor<EM, I, S, Z> for ForkTimeoutExecutor<E>
+where
+    E: Executor<EM, I, S, Z>,
+    I: Input,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, libafl::Error> {
+        let exit_kind = self.executor.run_target(fuzzer, state, mgr, input)?;
+
+        if let ExitKind::Crash = exit_kind {
+            collect_fork_report();
+            if fork_timed_out() {
+                return Ok(ExitKind::Timeout);
+            }
+        }
+        Ok(exit_kind)
+    }
+}
+
+impl<E, I, OT, S> HasObservers<I, OT, S> for ForkTimeoutExecutor<E>
+where
+    E: HasObservers<I, OT, S>,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    fn observers(&self) -> &OT {
+        self.executor.observers()
+    }
+
+    fn observers_mut(&mut self) -> &mut OT {
+        self.executor.observers_mut()
+    }
+}
+
 #[allow(unused)]
 pub fn dummy_harness(_input: &Trace) -> ExitKind {
     let mut rng = rand::thread_rng();
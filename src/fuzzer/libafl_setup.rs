@@ -1,5 +1,6 @@
 use core::time::Duration;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use itertools::Itertools;
 use libafl::bolts::shmem::{ShMemProvider, StdShMemProvider};
@@ -8,23 +9,42 @@ use libafl::events::{HasEventManagerId, LlmpRestartingEventManager};
 use libafl::{
     bolts::{rands::StdRand, tuples::tuple_list},
     corpus::{
-        Corpus, InMemoryCorpus, IndexesLenTimeMinimizerCorpusScheduler, OnDiskCorpus,
+        ondisk::OnDiskMetadataFormat, Corpus, InMemoryCorpus,
+        IndexesLenTimeMinimizerCorpusScheduler, OnDiskCorpus, PowerQueueCorpusScheduler,
         QueueCorpusScheduler,
     },
-    executors::{inprocess::InProcessExecutor, TimeoutExecutor},
-    feedback_or,
-    feedbacks::{CrashFeedback, MapFeedbackState, MaxMapFeedback, TimeFeedback, TimeoutFeedback},
-    fuzzer::{Fuzzer, StdFuzzer},
+    executors::{
+        inprocess::{InProcessExecutor, InProcessForkExecutor},
+        TimeoutExecutor,
+    },
+    feedback_and_fast, feedback_or,
+    feedbacks::{MapFeedbackState, MaxMapFeedback, TimeFeedback, TimeoutFeedback},
+    fuzzer::{Evaluator, Fuzzer, StdFuzzer},
     observers::{HitcountsMapObserver, StdMapObserver, TimeObserver},
+    stages::{
+        power::{PowerMutationalStage, PowerSchedule},
+        CalibrationStage,
+    },
     state::{HasCorpus, StdState},
     Error,
 };
 
+use crate::differential::available_backends;
+use crate::fuzzer::budget::{supervise, CampaignBudget};
+use crate::fuzzer::byte_havoc::ByteHavocStage;
+use crate::fuzzer::crash::{capture_sanitizer_reports, CrashSignatureFeedback};
+use crate::fuzzer::feedbacks::{
+    AlertNoveltyFeedback, DedupFeedback, DifferentialFeedback, MutationLogFeedback,
+    OpenSSLErrorsFeedback, StateFeedback,
+};
 use crate::fuzzer::mutations::trace_mutations;
 use crate::fuzzer::mutations::util::TermConstraints;
+use crate::fuzzer::sharding::CorpusShard;
 use crate::fuzzer::stages::{PuffinMutationalStage, PuffinScheduledMutator};
-use crate::fuzzer::stats::PuffinStats;
+use crate::fuzzer::stats::{PuffinStats, StatsFormat};
 use crate::fuzzer::stats_observer::StatsStage;
+use crate::fuzzer::trace_generator::TraceGenerator;
+use crate::trace::Trace;
 
 use crate::openssl_binding::make_deterministic;
 
@@ -32,11 +52,18 @@ use super::harness;
 use super::{EDGES_MAP, MAX_EDGES_NUM};
 use libafl::bolts::os::parse_core_bind_arg;
 use libafl::corpus::RandCorpusScheduler;
+use libafl::inputs::Input;
 
 /// Default value, how many iterations each stage gets, as an upper bound
 /// It may randomly continue earlier. Each iteration works on a different Input from the corpus
 pub static MAX_ITERATIONS_PER_STAGE: u64 = 256;
+/// Default value, how many iterations each stage gets at least as an upper bound, once the
+/// coverage plateaued. It equals [`MAX_ITERATIONS_PER_STAGE`], such that the budget is fixed
+/// unless a lower bound is configured.
+pub static MIN_ITERATIONS_PER_STAGE: u64 = 256;
 pub static MAX_MUTATIONS_PER_ITERATION: u64 = 16;
+/// Upper bound of executions with byte havoc per stage, see [`ByteHavocStage`]
+pub static MAX_BYTE_HAVOC_ITERATIONS_PER_STAGE: u64 = 16;
 pub static MAX_TRACE_LENGTH: usize = 15;
 pub static MIN_TRACE_LENGTH: usize = 5;
 
@@ -48,18 +75,166 @@ pub static MIN_TERM_SIZE: usize = 0;
 /// Above this term size we no longer mutate.
 pub static MAX_TERM_SIZE: usize = 300;
 
+/// How many random traces are generated if the initial corpus is empty
+pub static GENERATED_INITIAL_INPUTS: usize = 64;
+
+/// Policy which decides which testcase of the corpus is fuzzed next.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SchedulerConfig {
+    /// Favors small and fast testcases which cover all edges seen so far and schedules them in a
+    /// queue.
+    Queue,
+    /// Picks testcases uniformly at random.
+    Rand,
+    /// AFL-style power schedule which assigns more energy to testcases hitting rare edges. The
+    /// energy replaces the iteration bounds of the mutational stage.
+    PowerSchedule,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        #[cfg(not(feature = "no-minimizer"))]
+        {
+            SchedulerConfig::Queue
+        }
+        #[cfg(feature = "no-minimizer")]
+        {
+            SchedulerConfig::Rand
+        }
+    }
+}
+
+impl FromStr for SchedulerConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queue" => Ok(SchedulerConfig::Queue),
+            "rand" => Ok(SchedulerConfig::Rand),
+            "power" => Ok(SchedulerConfig::PowerSchedule),
+            _ => Err(format!(
+                "Unknown scheduler {}. Expected queue, rand or power.",
+                s
+            )),
+        }
+    }
+}
+
+/// How the harness is executed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExecutorConfig {
+    /// Executes the harness in the process of the client. This is fast, but a crash of the PUT
+    /// takes down the client and it has to be restarted.
+    InProcess,
+    /// Executes the harness in a forked child of the client. A crash only takes down the child,
+    /// therefore the client keeps its state. The child hands its coverage and a
+    /// [`ForkReport`](harness::ForkReport) to the client through shared memory. Children which
+    /// exceed [`FORK_TIMEOUT`](harness::FORK_TIMEOUT) are killed and reported as timeouts, like
+    /// in-process executions.
+    Fork,
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> Self {
+        ExecutorConfig::InProcess
+    }
+}
+
+impl FromStr for ExecutorConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inprocess" => Ok(ExecutorConfig::InProcess),
+            "fork" => Ok(ExecutorConfig::Fork),
+            _ => Err(format!(
+                "Unknown executor {}. Expected inprocess or fork.",
+                s
+            )),
+        }
+    }
+}
+
+/// Configuration of a fuzzing campaign
+#[derive(Clone, Debug)]
+pub struct FuzzerConfig {
+    /// Cores to use during fuzzing, e.g. `0-3` or `0,2`
+    pub core_definition: String,
+    pub stats_file: PathBuf,
+    /// How the stats are reported while fuzzing, text log lines by default
+    pub stats_format: StatsFormat,
+    pub on_disk_corpus: PathBuf,
+    /// Directory from which the initial corpus is loaded
+    pub corpus_dir: PathBuf,
+    pub objective_dir: PathBuf,
+    pub broker_port: u16,
+    pub max_iters: Option<u64>,
+    /// Seed for all clients. If not set, then each client uses its id as seed.
+    pub static_seed: Option<u64>,
+    pub scheduler: SchedulerConfig,
+    pub executor: ExecutorConfig,
+    /// Whether traces on which two backends diverge are reported as solutions. Only takes effect
+    /// if more than one backend is available, see [`available_backends`].
+    pub differential: bool,
+    /// Amount of shards the initial corpus is split into, see [`CorpusShard`]. If not set, then
+    /// each client imports the whole corpus.
+    pub corpus_shards: Option<usize>,
+    /// How many neighbouring shards each client imports additionally
+    pub shard_overlap: usize,
+    /// Bounds of the iteration budget of the mutational stage. The budget shrinks while no new
+    /// coverage is found and grows again once it is.
+    pub min_iterations_per_stage: u64,
+    pub max_iterations_per_stage: u64,
+    /// Relative weights of the mutators, keyed by their short name, see
+    /// [`PuffinScheduledMutator::with_weights`]. If empty, then each mutator is equally likely.
+    pub mutator_weights: Vec<(String, f64)>,
+    /// Stops the campaign after this many executions, summed over all clients
+    pub max_total_executions: Option<u64>,
+    /// Stops the campaign after it ran for this long
+    pub max_wallclock: Option<Duration>,
+}
+
 /// Starts the fuzzing loop
-pub fn start(
-    core_definition: String,
-    stats_file: PathBuf,
-    on_disk_corpus: PathBuf,
-    corpus_dir: PathBuf,
-    objective_dir: PathBuf,
-    broker_port: u16,
-    max_iters: Option<u64>,
-    static_seed: Option<u64>,
-) {
+pub fn start(config: FuzzerConfig) {
+    let FuzzerConfig {
+        core_definition,
+        stats_file,
+        stats_format,
+        on_disk_corpus,
+        corpus_dir,
+        objective_dir,
+        broker_port,
+        max_iters,
+        static_seed,
+        scheduler,
+        executor,
+        differential,
+        corpus_shards,
+        shard_overlap,
+        min_iterations_per_stage,
+        max_iterations_per_stage,
+        mutator_weights,
+        max_total_executions,
+        max_wallclock,
+    } = config;
+
     info!("Running on {} cores", core_definition);
+    info!("Using {:?} scheduler", scheduler);
+    if differential && available_backends().len() < 2 {
+        warn!("Differential executions need more than one backend, ignoring them");
+    }
+    info!("Using {:?} executor", executor);
+    if let Some(corpus_shards) = corpus_shards {
+        info!(
+            "Splitting the corpus into {} shards with an overlap of {}",
+            corpus_shards, shard_overlap
+        );
+    }
+
+    let budget = CampaignBudget::new(max_total_executions, max_wallclock);
+    if budget.is_limited() {
+        info!("Using budget {:?}", budget);
+    }
 
     make_deterministic();
     let shmem_provider = StdShMemProvider::new().expect("Failed to init shared memory");
@@ -69,8 +244,10 @@ pub fn start(
             info!("{}", s);
         },
         stats_file.clone(),
+        stats_format,
     )
-    .unwrap();
+    .unwrap()
+    .with_budget(budget);
 
     /*    let stats = MultiStats::new(
         |s| {
@@ -82,34 +259,100 @@ pub fn start(
         |state: Option<StdState<_, _, _, _, _>>,
          mut restarting_mgr: LlmpRestartingEventManager<_, _, _, _>| {
             info!("We're a client, let's fuzz :)");
+            capture_sanitizer_reports();
+
+            // Forked children write their coverage and reports to shared memory, see
+            // harness::fork_harness
+            let mut fork_shmem = match executor {
+                ExecutorConfig::InProcess => None,
+                ExecutorConfig::Fork => {
+                    let mut provider = StdShMemProvider::new()?;
+                    let edges = provider.new_map(unsafe { MAX_EDGES_NUM })?;
+                    let mut report = provider.new_map(harness::FORK_REPORT_SIZE)?;
+                    harness::set_fork_report_map(unsafe {
+                        std::slice::from_raw_parts_mut(
+                            report.map_mut().as_mut_ptr(),
+                            harness::FORK_REPORT_SIZE,
+                        )
+                    });
+                    Some((provider, edges, report))
+                }
+            };
+            let edges_map: &'static mut [u8] = match &mut fork_shmem {
+                Some((_, edges, _)) => unsafe {
+                    std::slice::from_raw_parts_mut(edges.map_mut().as_mut_ptr(), MAX_EDGES_NUM)
+                },
+                None => unsafe { &mut EDGES_MAP[0..MAX_EDGES_NUM] },
+            };
+            let shared_edges = edges_map.as_mut_ptr();
 
-            let edges_observer = HitcountsMapObserver::new(StdMapObserver::new("edges", unsafe {
-                &mut EDGES_MAP[0..MAX_EDGES_NUM]
-            }));
+            let edges_observer = HitcountsMapObserver::new(StdMapObserver::new("edges", edges_map));
             let time_observer = TimeObserver::new("time");
 
             let edges_feedback_state = MapFeedbackState::with_observer(&edges_observer);
 
             #[cfg(feature = "no-minimizer")]
-            let feedback = feedback_or!(MaxMapFeedback::new_tracking(
-                &edges_feedback_state,
-                &edges_observer,
-                false,  // [TODO] [LH] Why are track_index and track_novelties are false?
-                false
-            ));
+            let feedback = feedback_and_fast!(
+                feedback_or!(
+                    MaxMapFeedback::new_tracking(
+                        &edges_feedback_state,
+                        &edges_observer,
+                        false, // [TODO] [LH] Why are track_index and track_novelties are false?
+                        false
+                    ),
+                    // Rewards reaching new handshake states of OpenSSL
+                    StateFeedback::new(),
+                    // Rewards alerts which an agent has not sent before
+                    AlertNoveltyFeedback::new(),
+                    // Attaches the applied mutations
+                    MutationLogFeedback::new(),
+                    // Attaches the errors which OpenSSL reported
+                    OpenSSLErrorsFeedback::new()
+                ),
+                // Does not store structurally equal traces twice
+                DedupFeedback::new()
+            );
 
             #[cfg(not(feature = "no-minimizer"))]
-            let feedback = feedback_or!(
-                // New maximization map feedback linked to the edges observer and the feedback state
-                // `track_indexes` needed because of IndexesLenTimeMinimizerCorpusScheduler
-                MaxMapFeedback::new_tracking(&edges_feedback_state, &edges_observer, true, false),
-                // Time feedback, this one does not need a feedback state
-                // needed for IndexesLenTimeMinimizerCorpusScheduler
-                TimeFeedback::new_with_observer(&time_observer)
+            let feedback = feedback_and_fast!(
+                feedback_or!(
+                    // New maximization map feedback linked to the edges observer and the feedback state
+                    // `track_indexes` needed because of IndexesLenTimeMinimizerCorpusScheduler
+                    MaxMapFeedback::new_tracking(
+                        &edges_feedback_state,
+                        &edges_observer,
+                        true,
+                        false
+                    ),
+                    // Time feedback, this one does not need a feedback state
+                    // needed for IndexesLenTimeMinimizerCorpusScheduler
+                    TimeFeedback::new_with_observer(&time_observer),
+                    // Rewards reaching new handshake states of OpenSSL
+                    StateFeedback::new(),
+                    // Rewards alerts which an agent has not sent before
+                    AlertNoveltyFeedback::new(),
+                    // Attaches the applied mutations
+                    MutationLogFeedback::new(),
+                    // Attaches the errors which OpenSSL reported
+                    OpenSSLErrorsFeedback::new()
+                ),
+                // Does not store structurally equal traces twice
+                DedupFeedback::new()
             );
 
             // A feedback to choose if an input is a solution or not
-            let objective = feedback_or!(CrashFeedback::new(), TimeoutFeedback::new());
+            let differential_feedback = if differential {
+                DifferentialFeedback::new(available_backends())
+            } else {
+                DifferentialFeedback::disabled()
+            };
+            let objective = feedback_or!(
+                CrashSignatureFeedback::new(),
+                TimeoutFeedback::new(),
+                differential_feedback,
+                MutationLogFeedback::new(),
+                OpenSSLErrorsFeedback::new()
+            );
             // [LH] [TODO] Why not using feedback_or_fast?
 
             let sender_id = restarting_mgr.mgr_id();
@@ -131,7 +374,12 @@ pub fn start(
                             OnDiskCorpus::new(on_disk_corpus.clone()).unwrap()
                         }
                     },
-                    OnDiskCorpus::new(objective_dir.clone()).unwrap(),
+                    // The metadata contains the crash signature and the mutation log
+                    OnDiskCorpus::new_save_meta(
+                        objective_dir.clone(),
+                        Some(OnDiskMetadataFormat::JsonPretty),
+                    )
+                    .unwrap(),
                     // They are the data related to the feedbacks that you want to persist in the State.
                     tuple_list!(edges_feedback_state),
                 )
@@ -143,90 +391,239 @@ pub fn start(
                 TermConstraints {
                     min_term_size: MIN_TERM_SIZE,
                     max_term_size: MAX_TERM_SIZE,
+                    max_trace_steps: MAX_TRACE_LENGTH,
                 },
                 FRESH_ZOO_AFTER,
             );
-            let mutator = PuffinScheduledMutator::new(mutations, MAX_MUTATIONS_PER_ITERATION);
-            let mut stages = tuple_list!(
-                PuffinMutationalStage::new(mutator, MAX_ITERATIONS_PER_STAGE),
-                StatsStage::new()
-            );
+            let mutator = if mutator_weights.is_empty() {
+                PuffinScheduledMutator::new(mutations, MAX_MUTATIONS_PER_ITERATION)
+            } else {
+                PuffinScheduledMutator::with_weights(
+                    mutations,
+                    &mutator_weights,
+                    MAX_MUTATIONS_PER_ITERATION,
+                )?
+            };
 
-            // A minimization+queue policy to get testcasess from the corpus
-            #[cfg(not(feature = "no-minimizer"))]
-            let scheduler =
-                IndexesLenTimeMinimizerCorpusScheduler::new(QueueCorpusScheduler::new());
-            #[cfg(feature = "no-minimizer")]
-            let scheduler = RandCorpusScheduler::new();
-
-            let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
-
-            let mut harness_fn = &mut harness::harness;
-
-            let mut executor = TimeoutExecutor::new(
-                InProcessExecutor::new(
-                    &mut harness_fn,
-                    // hint: edges_observer is expensive to serialize (only noticeable if we add all inputs to the corpus)
-                    tuple_list!(edges_observer, time_observer),
-                    &mut fuzzer,
-                    &mut state,
-                    &mut restarting_mgr,
-                )?,
-                Duration::new(2, 0),
-            );
+            // The type of the executor depends on the configuration, therefore the fuzzing loop is
+            // shared through a macro
+            macro_rules! fuzz_with_executor {
+                ($fuzzer:ident, $stages:ident, $executor:ident) => {{
+                    // Without initial inputs we start from randomly generated traces
+                    let corpus_dir_is_empty = corpus_dir
+                        .read_dir()
+                        .map(|mut entries| entries.next().is_none())
+                        .unwrap_or(true);
 
-            // In case the corpus is empty (on first run), reset
-            if state.corpus().count() < 1 {
-                state
-                    .load_initial_inputs(
-                        &mut fuzzer,
-                        &mut executor,
-                        &mut restarting_mgr,
-                        &[corpus_dir.clone()],
-                    )
-                    .unwrap_or_else(|err| {
-                        panic!(
-                            "Failed to load initial corpus at {:?}: {}",
-                            &corpus_dir, err
-                        )
-                    });
-                println!("We imported {} inputs from disk.", state.corpus().count());
+                    // In case the corpus is empty (on first run), reset
+                    if state.corpus().count() < 1 && corpus_dir_is_empty {
+                        let mut generator = TraceGenerator::new(TermConstraints {
+                            min_term_size: MIN_TERM_SIZE,
+                            max_term_size: MAX_TERM_SIZE,
+                            max_trace_steps: MAX_TRACE_LENGTH,
+                        });
+                        state.generate_initial_inputs(
+                            &mut $fuzzer,
+                            &mut $executor,
+                            &mut generator,
+                            &mut restarting_mgr,
+                            GENERATED_INITIAL_INPUTS,
+                        )?;
+                        println!("We generated {} inputs.", state.corpus().count());
+                    } else if state.corpus().count() < 1 {
+                        if let Some(corpus_shards) = corpus_shards {
+                            // Every client only imports its own part of the corpus
+                            let shard = CorpusShard::new(
+                                sender_id.id as usize,
+                                corpus_shards,
+                                shard_overlap,
+                            );
+                            let seed_files = shard.seed_files(&corpus_dir).unwrap_or_else(|err| {
+                                panic!(
+                                    "Failed to list initial corpus at {:?}: {}",
+                                    &corpus_dir, err
+                                )
+                            });
+                            for seed_file in seed_files {
+                                let trace = Trace::from_file(&seed_file)?;
+                                $fuzzer.evaluate_input(
+                                    &mut state,
+                                    &mut $executor,
+                                    &mut restarting_mgr,
+                                    trace,
+                                )?;
+                            }
+                            println!(
+                                "We imported {} inputs from shard {} of {}.",
+                                state.corpus().count(),
+                                shard.index,
+                                shard.count
+                            );
+                        } else {
+                            state
+                                .load_initial_inputs(
+                                    &mut $fuzzer,
+                                    &mut $executor,
+                                    &mut restarting_mgr,
+                                    &[corpus_dir.clone()],
+                                )
+                                .unwrap_or_else(|err| {
+                                    panic!(
+                                        "Failed to load initial corpus at {:?}: {}",
+                                        &corpus_dir, err
+                                    )
+                                });
+                            println!("We imported {} inputs from disk.", state.corpus().count());
+                        }
+                    }
+
+                    if let Some(max_iters) = max_iters {
+                        $fuzzer.fuzz_loop_for(
+                            &mut $stages,
+                            &mut state,
+                            &mut $executor,
+                            &mut restarting_mgr,
+                            max_iters,
+                        )?;
+                    } else {
+                        $fuzzer.fuzz_loop(
+                            &mut $stages,
+                            &mut $executor,
+                            &mut state,
+                            &mut restarting_mgr,
+                        )?;
+                    }
+                }};
             }
 
-            if let Some(max_iters) = max_iters {
-                fuzzer.fuzz_loop_for(
-                    &mut stages,
-                    &mut state,
-                    &mut executor,
-                    &mut restarting_mgr,
-                    max_iters,
-                )?;
-            } else {
-                fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut restarting_mgr)?;
+            // The scheduler determines the type of the fuzzer, therefore the remaining setup is
+            // shared through a macro
+            macro_rules! fuzz_with_scheduler {
+                ($scheduler:expr, $stages:expr) => {{
+                    let mut stages = $stages;
+                    let mut fuzzer = StdFuzzer::new($scheduler, feedback, objective);
+
+                    match executor {
+                        ExecutorConfig::InProcess => {
+                            let mut harness_fn = &mut harness::harness;
+
+                            let mut executor = TimeoutExecutor::new(
+                                InProcessExecutor::new(
+                                    &mut harness_fn,
+                                    // hint: edges_observer is expensive to serialize (only noticeable if we add all inputs to the corpus)
+                                    tuple_list!(edges_observer, time_observer),
+                                    &mut fuzzer,
+                                    &mut state,
+                                    &mut restarting_mgr,
+                                )?,
+                                Duration::new(2, 0),
+                            );
+
+                            fuzz_with_executor!(fuzzer, stages, executor)
+                        }
+                        ExecutorConfig::Fork => {
+                            let (shmem_provider, _, _) = fork_shmem.as_ref().unwrap();
+                            let mut harness_fn = |input: &Trace| {
+                                harness::fork_harness(input, unsafe {
+                                    std::slice::from_raw_parts_mut(shared_edges, MAX_EDGES_NUM)
+                                })
+                            };
+
+                            let mut executor = harness::ForkTimeoutExecutor::new(
+                                InProcessForkExecutor::new(
+                                    &mut harness_fn,
+                                    tuple_list!(edges_observer, time_observer),
+                                    &mut fuzzer,
+                                    &mut state,
+                                    &mut restarting_mgr,
+                                    shmem_provider.clone(),
+                                )?,
+                            );
+
+                            fuzz_with_executor!(fuzzer, stages, executor)
+                        }
+                    }
+                }};
+            }
+
+            match scheduler {
+                SchedulerConfig::Queue => {
+                    // A minimization+queue policy to get testcasess from the corpus
+                    fuzz_with_scheduler!(
+                        IndexesLenTimeMinimizerCorpusScheduler::new(QueueCorpusScheduler::new()),
+                        tuple_list!(
+                            PuffinMutationalStage::new(
+                                mutator,
+                                min_iterations_per_stage,
+                                max_iterations_per_stage
+                            ),
+                            ByteHavocStage::new(MAX_BYTE_HAVOC_ITERATIONS_PER_STAGE),
+                            StatsStage::new()
+                        )
+                    )
+                }
+                SchedulerConfig::Rand => fuzz_with_scheduler!(
+                    RandCorpusScheduler::new(),
+                    tuple_list!(
+                        PuffinMutationalStage::new(
+                            mutator,
+                            min_iterations_per_stage,
+                            max_iterations_per_stage
+                        ),
+                        ByteHavocStage::new(MAX_BYTE_HAVOC_ITERATIONS_PER_STAGE),
+                        StatsStage::new()
+                    )
+                ),
+                SchedulerConfig::PowerSchedule => {
+                    // The calibration stage measures each new testcase and stores the metadata
+                    // the power schedule depends on. The power stage derives the iterations of
+                    // each testcase from its calibrated score.
+                    let calibration = CalibrationStage::new(&mut state, &edges_observer);
+                    let power =
+                        PowerMutationalStage::new(mutator, PowerSchedule::FAST, &edges_observer);
+                    fuzz_with_scheduler!(
+                        PowerQueueCorpusScheduler::new(),
+                        tuple_list!(
+                            calibration,
+                            power,
+                            ByteHavocStage::new(MAX_BYTE_HAVOC_ITERATIONS_PER_STAGE),
+                            StatsStage::new()
+                        )
+                    )
+                }
             }
 
             Ok(())
         };
 
-    if let Err(error) = libafl::bolts::launcher::Launcher::builder()
-        .shmem_provider(shmem_provider)
-        .configuration("launcher default".into())
-        .stats(stats)
-        .run_client(&mut run_client)
-        .cores(&parse_core_bind_arg(core_definition.as_str()).unwrap()) // possibly replace by parse_core_bind_arg
-        .broker_port(broker_port)
-        //todo where should we log the output of the harness?
-        /*.stdout_file(Some("/dev/null"))*/
-        .build()
-        .launch()
-    {
-        match error {
-            Error::ShuttingDown => {
-                // ignore
-            }
-            _ => {
-                panic!("{}", error)
+    let launch = move || {
+        if let Err(error) = libafl::bolts::launcher::Launcher::builder()
+            .shmem_provider(shmem_provider)
+            .configuration("launcher default".into())
+            .stats(stats)
+            .run_client(&mut run_client)
+            .cores(&parse_core_bind_arg(core_definition.as_str()).unwrap()) // possibly replace by parse_core_bind_arg
+            .broker_port(broker_port)
+            //todo where should we log the output of the harness?
+            /*.stdout_file(Some("/dev/null"))*/
+            .build()
+            .launch()
+        {
+            match error {
+                Error::ShuttingDown => {
+                    // ignore
+                }
+                _ => {
+                    panic!("{}", error)
+                }
             }
         }
+    };
+
+    if budget.is_limited() {
+        // The broker terminates the campaign once the budget is exhausted
+        supervise(&budget, launch);
+    } else {
+        launch();
     }
 }
@@ -1,15 +1,39 @@
 //! The fuzzer module setups the fuzzing loop. It also is responsible for gathering feedback from
 //! runs and restarting processes if they crash.
+//!
+//! Everything which depends on libafl is only built with the `fuzzer` feature. The seeds, the
+//! pcap import and the corpus statistics are always available.
 
+#[cfg(feature = "fuzzer")]
+mod budget;
+#[cfg(feature = "fuzzer")]
+pub mod byte_havoc;
+pub mod corpus_stats;
+#[cfg(feature = "fuzzer")]
+pub mod crash;
+#[cfg(feature = "fuzzer")]
+mod feedbacks;
+#[cfg(feature = "fuzzer")]
 mod harness;
+#[cfg(feature = "fuzzer")]
 mod libafl_setup;
+#[cfg(feature = "fuzzer")]
 pub mod mutations;
+pub mod pcap;
 pub mod seeds;
+pub mod sharding;
+#[cfg(feature = "fuzzer")]
 mod stats;
-#[cfg(test)]
+#[cfg(all(test, feature = "fuzzer"))]
 mod tests;
 
-pub use libafl_setup::start;
+#[cfg(feature = "fuzzer")]
+pub use libafl_setup::{
+    start, ExecutorConfig, FuzzerConfig, SchedulerConfig, MAX_ITERATIONS_PER_STAGE,
+    MIN_ITERATIONS_PER_STAGE,
+};
+#[cfg(feature = "fuzzer")]
+pub use stats::{StatsFormat, StatsOutput};
 
 // Link against correct sancov impl
 #[cfg(all(feature = "sancov_pcguard_log", feature = "sancov_libafl"))]
@@ -24,38 +48,51 @@ compile_error!(
 #[cfg(all(not(test), feature = "sancov_pcguard_log"))]
 mod sancov_pcguard_log;
 
-// Use dummy in tests
+// Use dummy in tests and if the fuzzer which would record the coverage is not built
+#[cfg(feature = "fuzzer")]
 mod macros;
-#[cfg(test)]
+#[cfg(any(
+    test,
+    all(not(feature = "fuzzer"), not(feature = "sancov_pcguard_log"))
+))]
 mod sancov_dummy;
+#[cfg(feature = "fuzzer")]
 mod stages;
-mod stats_observer;
+#[cfg(feature = "fuzzer")]
+pub(crate) mod stats_observer;
+#[cfg(feature = "fuzzer")]
 mod term_zoo;
+#[cfg(feature = "fuzzer")]
+pub mod trace_generator;
 
+#[cfg(feature = "fuzzer")]
 use crate::trace::Trace;
+#[cfg(feature = "fuzzer")]
 use libafl::inputs::{HasLen, Input};
 #[cfg(all(not(test), feature = "sancov_libafl"))]
 // This import achieves that OpenSSl compiled with -fsanitize-coverage=trace-pc-guard can link
 pub(crate) use libafl_targets::{EDGES_MAP, MAX_EDGES_NUM};
 
-#[cfg(any(test, not(feature = "sancov_libafl")))]
+#[cfg(all(feature = "fuzzer", any(test, not(feature = "sancov_libafl"))))]
 pub(crate) const EDGES_MAP_SIZE: usize = 65536;
-#[cfg(any(test, not(feature = "sancov_libafl")))]
+#[cfg(all(feature = "fuzzer", any(test, not(feature = "sancov_libafl"))))]
 pub(crate) static mut EDGES_MAP: [u8; EDGES_MAP_SIZE] = [0; EDGES_MAP_SIZE];
-#[cfg(any(test, not(feature = "sancov_libafl")))]
+#[cfg(all(feature = "fuzzer", any(test, not(feature = "sancov_libafl"))))]
 pub(crate) static mut MAX_EDGES_NUM: usize = 0;
-#[cfg(any(test, not(feature = "sancov_libafl")))]
+#[cfg(all(feature = "fuzzer", any(test, not(feature = "sancov_libafl"))))]
 pub(crate) const CMP_MAP_SIZE: usize = 65536;
-#[cfg(any(test, not(feature = "sancov_libafl")))]
+#[cfg(all(feature = "fuzzer", any(test, not(feature = "sancov_libafl"))))]
 pub(crate) static mut CMP_MAP: [u8; CMP_MAP_SIZE] = [0; CMP_MAP_SIZE];
 
 // LibAFL support
+#[cfg(feature = "fuzzer")]
 impl Input for Trace {
     fn generate_name(&self, idx: usize) -> String {
         format!("{id}.trace", id = idx)
     }
 }
 
+#[cfg(feature = "fuzzer")]
 impl HasLen for Trace {
     fn len(&self) -> usize {
         self.steps.len()
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use libafl::bolts::rands::Rand;
 use libafl::corpus::Corpus;
 use libafl::state::{HasCorpus, HasMaxSize, HasMetadata, HasRand};
@@ -14,9 +16,10 @@ use crate::fuzzer::term_zoo::generate_term_zoo;
 use crate::fuzzer::term_zoo::Zoo;
 use crate::mutator;
 use crate::term::atoms::Function;
+use crate::term::signature::Signature;
 use crate::term::{Subterms, Term};
 use crate::tls::SIGNATURE;
-use crate::trace::Trace;
+use crate::trace::{Action, Query, Trace};
 
 pub fn trace_mutations<R, C, S>(
     min_trace_length: usize,
@@ -30,7 +33,13 @@ pub fn trace_mutations<R, C, S>(
        ReplaceMatchMutator<R, S>,
        RemoveAndLiftMutator<R, S>,
        GenerateMutator<R, S>,
-       SwapMutator<R,S>
+       SwapMutator<R,S>,
+       GreaseMutator<R,S>,
+       ConstantToVariableMutator<R,S>,
+       TruncateMutator<R,S>,
+       ListReorderMutator<R,S>,
+       RecordLengthCorruptMutator<R,S>,
+       DuplicateExtensionMutator<R,S>
    )
 where
     S: HasCorpus<C, Trace> + HasMetadata + HasMaxSize + HasRand<R>,
@@ -38,13 +47,19 @@ where
     R: Rand,
 {
     tuple_list!(
-        RepeatMutator::new(max_trace_length),
+        RepeatMutator::new(max_trace_length.min(constraints.max_trace_steps)),
         SkipMutator::new(min_trace_length),
         ReplaceReuseMutator::new(constraints),
         ReplaceMatchMutator::new(constraints),
         RemoveAndLiftMutator::new(constraints),
         GenerateMutator::new(0, fresh_zoo_after, constraints, None), // Refresh zoo after 100000M mutations
-        SwapMutator::new(constraints)
+        SwapMutator::new(constraints),
+        GreaseMutator::new(constraints),
+        ConstantToVariableMutator::new(constraints),
+        TruncateMutator::new(constraints),
+        ListReorderMutator::new(constraints),
+        RecordLengthCorruptMutator::new(constraints),
+        DuplicateExtensionMutator::new(constraints)
     )
 }
 
@@ -62,12 +77,9 @@ mutator! {
         let rand = state.rand_mut();
 
         if let Some((term_a, trace_path_a)) = choose(trace, self.constraints, rand) {
-            if let Some(trace_path_b) = choose_term_path_filtered(
-                trace,
-                |term: &Term| term.get_type_shape() == term_a.get_type_shape(),
-                self.constraints,
-                rand,
-            ) {
+            if let Some(trace_path_b) =
+                choose_term_path_of_type(trace, term_a.get_type_shape(), self.constraints, rand)
+            {
                 let term_a_cloned = term_a.clone();
 
                 if let Some(term_b_mut) = find_term_mut(trace, &trace_path_b) {
@@ -128,6 +140,7 @@ mutator! {
                         rand,
                     ) {
                         let grand_subterm_cloned = grand_subterm.clone();
+                        let subterms = Arc::make_mut(subterms);
                         subterms.push(grand_subterm_cloned);
                         // move last item to the position of the item we removed
                         subterms.swap_remove(subterm_index);
@@ -169,9 +182,14 @@ mutator! {
                         },
                         rand,
                     ) {
-                        to_mutate.mutate(Term::Application(
-                            Function::new(shape.clone(), dynamic_fn.clone()), vec![]));
-                        Ok(MutationResult::Mutated)
+                        match Term::try_application(
+                            Function::new(shape.clone(), dynamic_fn.clone()), vec![]) {
+                            Ok(constant) => {
+                                to_mutate.mutate(constant);
+                                Ok(MutationResult::Mutated)
+                            }
+                            Err(_) => Ok(MutationResult::Skipped),
+                        }
                     } else {
                         Ok(MutationResult::Skipped)
                     }
@@ -213,11 +231,16 @@ mutator! {
     ) -> Result<MutationResult, Error> {
         let rand = state.rand_mut();
         if let Some(replacement) = choose_term(trace, self.constraints, rand).cloned() {
-            if let Some(to_replace) = choose_term_filtered_mut(trace, |term: &Term| {
-                term.get_type_shape() == replacement.get_type_shape()
-            }, self.constraints, rand) {
-                to_replace.mutate(replacement);
-                return Ok(MutationResult::Mutated);
+            if let Some(trace_path) = choose_term_path_of_type(
+                trace,
+                replacement.get_type_shape(),
+                self.constraints,
+                rand,
+            ) {
+                if let Some(to_replace) = find_term_mut(trace, &trace_path) {
+                    to_replace.mutate(replacement);
+                    return Ok(MutationResult::Mutated);
+                }
             }
         }
 
@@ -248,7 +271,14 @@ mutator! {
             return Ok(MutationResult::Skipped);
         }
         let remove_index = state.rand_mut().between(0, (length - 1) as u64) as usize;
-        steps.remove(remove_index);
+        let removed = steps.remove(remove_index);
+
+        // Never emit traces which read the output of the removed step
+        if trace.validate().is_err() {
+            trace.steps.insert(remove_index, removed);
+            return Ok(MutationResult::Skipped);
+        }
+
         Ok(MutationResult::Mutated)
     },
     min_trace_length: usize
@@ -279,6 +309,13 @@ mutator! {
         let insert_index = state.rand_mut().between(0, length as u64) as usize;
         let step = state.rand_mut().choose(steps).clone();
         (&mut trace.steps).insert(insert_index, step);
+
+        // Never emit traces which read outputs before they are produced
+        if trace.validate().is_err() {
+            trace.steps.remove(insert_index);
+            return Ok(MutationResult::Skipped);
+        }
+
         Ok(MutationResult::Mutated)
     },
     max_trace_length: usize
@@ -328,16 +365,299 @@ mutator! {
     zoo: Option<Zoo>
 }
 
+mutator! {
+    /// GREASE: Appends a reserved GREASE value to a list of cipher suites or extensions. Peers are
+    /// supposed to ignore these values, see [RFC 8701](https://datatracker.ietf.org/doc/html/rfc8701).
+    GreaseMutator,
+    Trace,
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        trace: &mut Trace,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+
+        let trace_path = match choose_term_path_filtered(
+            trace,
+            |term: &Term| is_greasable(term.get_type_shape()),
+            self.constraints,
+            rand,
+        ) {
+            Some(trace_path) => trace_path,
+            None => return Ok(MutationResult::Skipped),
+        };
+
+        // The GREASE value and the append function are added to the recipe
+        let recipe_size = match &trace.steps[trace_path.0].action {
+            Action::Input(input) => input.recipe.size(),
+            _ => return Ok(MutationResult::Skipped),
+        };
+
+        if let Some(to_mutate) = find_term_mut(trace, &trace_path) {
+            if let Some(greased) = append_grease(to_mutate) {
+                if recipe_size + greased.size() - to_mutate.size() > self.constraints.max_term_size
+                {
+                    return Ok(MutationResult::Skipped);
+                }
+
+                to_mutate.mutate(greased);
+                return Ok(MutationResult::Mutated);
+            }
+        }
+
+        Ok(MutationResult::Skipped)
+    },
+    constraints: TermConstraints
+}
+
+mutator! {
+    /// CONSTANT-TO-VARIABLE: Replaces a constant with a variable of the same type. The variable
+    /// reads the output of an agent which produced output before the step of the constant. This
+    /// introduces data-flow between the agents.
+    ConstantToVariableMutator,
+    Trace,
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        trace: &mut Trace,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+
+        let is_constant = |term: &Term| match term {
+            Term::Variable(_) => false,
+            Term::Application(_, subterms) => subterms.is_empty(),
+        };
+        let trace_path = match choose_term_path_filtered(trace, is_constant, self.constraints, rand) {
+            Some(trace_path) => trace_path,
+            None => return Ok(MutationResult::Skipped),
+        };
+        let typ = match find_term_mut(trace, &trace_path) {
+            Some(constant) => *constant.get_type_shape(),
+            None => return Ok(MutationResult::Skipped),
+        };
+
+        // Prefer queries which already read a value of this type in an earlier step. Their
+        // counters are valid at this step, as the knowledge of the agents only grows. Else, query
+        // any message of an agent which produced output, as long as messages can contain values of
+        // this type.
+        let mut queries = variable_queries_of_type_before(trace, &typ, trace_path.0);
+        let fallback = queries.is_empty();
+        if fallback && is_extractable(&typ) {
+            queries = trace
+                .producing_agents_before(trace_path.0)
+                .iter()
+                .map(|agent| Query {
+                    agent_name: *agent,
+                    tls_message_type: None,
+                    counter: 0,
+                })
+                .collect();
+        }
+
+        let query = match queries.choose(rand) {
+            Some(query) => *query,
+            None => return Ok(MutationResult::Skipped),
+        };
+        let variable = Signature::new_var_by_type_id(
+            typ,
+            query.agent_name,
+            query.tls_message_type,
+            query.counter,
+        );
+        let original = match find_term_mut(trace, &trace_path) {
+            Some(constant) => {
+                let original = constant.clone();
+                constant.mutate(Term::Variable(variable));
+                original
+            }
+            None => return Ok(MutationResult::Skipped),
+        };
+
+        // Never emit traces which read a value the agent did not produce
+        if fallback
+            && (trace.validate().is_err() || !is_query_satisfiable(trace, trace_path.0, typ, query))
+        {
+            if let Some(variable) = find_term_mut(trace, &trace_path) {
+                variable.mutate(original);
+            }
+            return Ok(MutationResult::Skipped);
+        }
+
+        Ok(MutationResult::Mutated)
+    },
+    constraints: TermConstraints
+}
+
+mutator! {
+    /// TRUNCATE: Truncates a byte vector or a session id to at most 15 bytes. Length fields which
+    /// are computed from the original content no longer match the encoded content.
+    TruncateMutator,
+    Trace,
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        trace: &mut Trace,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        let length = rand.below(16);
+
+        if let Some(to_mutate) = choose_term_filtered_mut(
+            trace,
+            |term: &Term| is_truncatable(term.get_type_shape()),
+            self.constraints,
+            rand,
+        ) {
+            if let Some(truncated) = truncate(to_mutate, length) {
+                to_mutate.mutate(truncated);
+                return Ok(MutationResult::Mutated);
+            }
+        }
+
+        Ok(MutationResult::Skipped)
+    },
+    constraints: TermConstraints
+}
+
+mutator! {
+    /// LIST-REORDER: Permutes the elements of a list which is built by a chain of append
+    /// functions, e.g. the extensions of a ClientHello. The set of elements stays the same, only
+    /// the order in which they are appended changes.
+    ListReorderMutator,
+    Trace,
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        trace: &mut Trace,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+
+        if let Some(to_mutate) =
+            choose_term_filtered_mut(trace, is_reorderable_list, self.constraints, rand)
+        {
+            if let Some(reordered) = reorder_list(to_mutate, rand) {
+                to_mutate.mutate(reordered);
+                return Ok(MutationResult::Mutated);
+            }
+        }
+
+        Ok(MutationResult::Skipped)
+    },
+    constraints: TermConstraints
+}
+
+mutator! {
+    /// RECORD-LENGTH-CORRUPT: Sets the length field of an encrypted record off by up to 16 bytes.
+    /// The encrypted payload keeps its size, therefore the peer reads a truncated record or waits
+    /// for bytes which never arrive.
+    RecordLengthCorruptMutator,
+    Trace,
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        trace: &mut Trace,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+        let offset = rand.below(16) + 1;
+        let shrink = rand.below(2) == 0;
+
+        // The wrapping adds the corrupting function and the offset constant
+        let max_term_size = self.constraints.max_term_size;
+        let step_indices = trace
+            .steps
+            .iter()
+            .enumerate()
+            .filter(|(_, step)| match &step.action {
+                Action::Input(input) => {
+                    is_encrypted_record(&input.recipe) && input.recipe.size() + 2 <= max_term_size
+                }
+                _ => false,
+            })
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+
+        if let Some(step_index) = step_indices.choose(rand) {
+            if let Action::Input(input) = &mut trace.steps[*step_index].action {
+                if let Some(corrupted) = corrupt_record_length(&input.recipe, offset, shrink) {
+                    input.recipe.mutate(corrupted);
+                    return Ok(MutationResult::Mutated);
+                }
+            }
+        }
+
+        Ok(MutationResult::Skipped)
+    },
+    constraints: TermConstraints
+}
+
+mutator! {
+    /// DUPLICATE-EXTENSION: Appends a second copy of an extension to the extensions of a
+    /// ClientHello or ServerHello. The record stays well-formed, but the extension type occurs
+    /// twice, which peers must reject.
+    DuplicateExtensionMutator,
+    Trace,
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        trace: &mut Trace,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let rand = state.rand_mut();
+
+        let trace_path =
+            match choose_term_path_filtered(trace, is_extension_list, self.constraints, rand) {
+                Some(trace_path) => trace_path,
+                None => return Ok(MutationResult::Skipped),
+            };
+
+        // The duplicate and the append function are added to the recipe
+        let recipe_size = match &trace.steps[trace_path.0].action {
+            Action::Input(input) => input.recipe.size(),
+            _ => return Ok(MutationResult::Skipped),
+        };
+
+        if let Some(to_mutate) = find_term_mut(trace, &trace_path) {
+            if let Some(duplicated) = duplicate_list_element(to_mutate, rand) {
+                if recipe_size + duplicated.size() - to_mutate.size()
+                    > self.constraints.max_term_size
+                {
+                    return Ok(MutationResult::Skipped);
+                }
+
+                to_mutate.mutate(duplicated);
+                return Ok(MutationResult::Mutated);
+            }
+        }
+
+        Ok(MutationResult::Skipped)
+    },
+    constraints: TermConstraints
+}
+
 pub mod util {
     use libafl::bolts::rands::Rand;
+    use rustls::msgs::enums::{AlertDescription, Compression, HandshakeType};
+    use rustls::msgs::handshake::{ClientExtension, Random, ServerExtension, SessionID};
+    use rustls::msgs::message::Message;
+    use rustls::{CipherSuite, ProtocolVersion};
 
+    use crate::term::atoms::Function;
+    use crate::term::dynamic_function::TypeShape;
+    use crate::term::signature::Signature;
     use crate::term::Term;
-    use crate::trace::{Action, Step, Trace};
+    use crate::tls::fn_impl::*;
+    use crate::trace::{Action, Query, Step, StepOutcome, Trace, TraceContext};
 
     #[derive(Copy, Clone)]
     pub struct TermConstraints {
         pub min_term_size: usize,
         pub max_term_size: usize,
+        /// Mutators and generators never produce traces with more steps
+        pub max_trace_steps: usize,
     }
 
     /// Default values which represent no constraint
@@ -346,6 +666,7 @@ pub mod util {
             Self {
                 min_term_size: 0,
                 max_term_size: 9000,
+                max_trace_steps: usize::MAX,
             }
         }
     }
@@ -465,7 +786,7 @@ pub mod util {
                         }
                     }
                 }
-                Action::Output(_) => {
+                Action::Output(_) | Action::Delay(_) => {
                     // no term -> skip
                 }
             }
@@ -485,9 +806,9 @@ pub mod util {
 
             let subterm_index = term_path.remove(0);
 
-            match term {
-                Term::Variable(_) => None,
-                Term::Application(_, subterms) => {
+            match term.subterms_mut() {
+                None => None,
+                Some(subterms) => {
                     if let Some(subterm) = subterms.get_mut(subterm_index) {
                         find_term_by_term_path_mut(subterm, term_path)
                     } else {
@@ -505,7 +826,7 @@ pub mod util {
                 Action::Input(input) => {
                     find_term_by_term_path_mut(&mut input.recipe, &mut term_path.clone())
                 }
-                Action::Output(_) => None,
+                Action::Output(_) | Action::Delay(_) => None,
             }
         } else {
             None
@@ -561,6 +882,322 @@ pub mod util {
         choose_term_path_filtered(trace, |_| true, constraints, rand)
     }
 
+    /// Chooses uniformly one of the subterms in `trace` which evaluate to `ty`
+    pub fn choose_term_path_of_type<R: Rand>(
+        trace: &Trace,
+        ty: &TypeShape,
+        constraints: TermConstraints,
+        rand: &mut R,
+    ) -> Option<TracePath> {
+        let mut candidates: Vec<TracePath> = vec![];
+
+        for (step_index, step) in trace.steps.iter().enumerate() {
+            if let Action::Input(input) = &step.action {
+                let size = input.recipe.size();
+                if size <= constraints.min_term_size || size >= constraints.max_term_size {
+                    continue;
+                }
+
+                candidates.extend(
+                    input
+                        .recipe
+                        .subterms_of_type(ty)
+                        .into_iter()
+                        .map(|term_path| (step_index, term_path)),
+                );
+            }
+        }
+
+        choose_iter(candidates, rand)
+    }
+
+    /// Returns the queries of all variables in the steps of `trace` before `step_index` which
+    /// have the type `typ`
+    pub fn variable_queries_of_type_before(
+        trace: &Trace,
+        typ: &TypeShape,
+        step_index: usize,
+    ) -> Vec<Query> {
+        let mut queries = vec![];
+
+        for step in trace.steps.iter().take(step_index) {
+            if let Action::Input(input) = &step.action {
+                for term in &input.recipe {
+                    if let Term::Variable(variable) = term {
+                        if variable.typ == *typ && !queries.contains(&variable.query) {
+                            queries.push(variable.query);
+                        }
+                    }
+                }
+            }
+        }
+
+        queries
+    }
+
+    /// Whether a variable of type `typ` can select a value with `query` in step `step_index` of
+    /// `trace`. The steps before `step_index` are executed in a fresh [`TraceContext`].
+    pub fn is_query_satisfiable(
+        trace: &Trace,
+        step_index: usize,
+        typ: TypeShape,
+        query: Query,
+    ) -> bool {
+        let mut ctx = TraceContext::new();
+        if trace.prepare(&mut ctx).is_err() {
+            return false;
+        }
+        for index in 0..step_index {
+            match ctx.execute_step(trace, index) {
+                Ok(StepOutcome::Success) => {}
+                _ => return false,
+            }
+        }
+        ctx.find_variable(typ, query).is_some()
+    }
+
+    /// Whether the knowledge extracted from messages can contain values of type `shape`, see
+    /// [`crate::variable_data::extract_knowledge`]
+    pub fn is_extractable(shape: &TypeShape) -> bool {
+        [
+            TypeShape::of::<Message>(),
+            TypeShape::of::<HandshakeType>(),
+            TypeShape::of::<AlertDescription>(),
+            TypeShape::of::<Random>(),
+            TypeShape::of::<SessionID>(),
+            TypeShape::of::<ProtocolVersion>(),
+            TypeShape::of::<CipherSuite>(),
+            TypeShape::of::<Vec<CipherSuite>>(),
+            TypeShape::of::<Compression>(),
+            TypeShape::of::<Vec<Compression>>(),
+            TypeShape::of::<ClientExtension>(),
+            TypeShape::of::<Vec<ClientExtension>>(),
+            TypeShape::of::<ServerExtension>(),
+            TypeShape::of::<Vec<ServerExtension>>(),
+            TypeShape::of::<Vec<u8>>(),
+            TypeShape::of::<u64>(),
+        ]
+        .contains(shape)
+    }
+
+    /// Whether terms of type `shape` are lists which can contain GREASE values
+    pub fn is_greasable(shape: &TypeShape) -> bool {
+        *shape == TypeShape::of::<Vec<CipherSuite>>()
+            || *shape == TypeShape::of::<Vec<ClientExtension>>()
+    }
+
+    /// Returns a term which appends a GREASE value to the `list`. Returns `None` if `list` is not a
+    /// list which can contain GREASE values.
+    pub fn append_grease(list: &Term) -> Option<Term> {
+        let shape = list.get_type_shape();
+
+        let (append, grease) = if *shape == TypeShape::of::<Vec<CipherSuite>>() {
+            (
+                Signature::new_function(&fn_append_cipher_suite),
+                Signature::new_function(&fn_grease_cipher_suite),
+            )
+        } else if *shape == TypeShape::of::<Vec<ClientExtension>>() {
+            (
+                Signature::new_function(&fn_client_extensions_append),
+                Signature::new_function(&fn_grease_extension),
+            )
+        } else {
+            return None;
+        };
+
+        let grease = Term::try_application(grease, vec![]).ok()?;
+        Term::try_application(append, vec![list.clone(), grease]).ok()
+    }
+
+    pub fn is_truncatable(shape: &TypeShape) -> bool {
+        *shape == TypeShape::of::<Vec<u8>>() || *shape == TypeShape::of::<SessionID>()
+    }
+
+    /// Returns a term which keeps the first `length` bytes of `bytes`. The `length` must be
+    /// smaller than 16, such that it can be expressed by one of the `fn_seq_*` constants.
+    pub fn truncate(bytes: &Term, length: u64) -> Option<Term> {
+        let shape = bytes.get_type_shape();
+
+        let truncate = if *shape == TypeShape::of::<Vec<u8>>() {
+            Signature::new_function(&fn_truncate_bytes)
+        } else if *shape == TypeShape::of::<SessionID>() {
+            Signature::new_function(&fn_truncate_session_id)
+        } else {
+            return None;
+        };
+
+        let length = seq_constant(length)?;
+        Term::try_application(truncate, vec![bytes.clone(), length]).ok()
+    }
+
+    /// Returns the `fn_seq_*` constant which evaluates to `value`. Returns `None` if `value` is
+    /// larger than 16.
+    pub fn seq_constant(value: u64) -> Option<Term> {
+        let seq = match value {
+            0 => Signature::new_function(&fn_seq_0),
+            1 => Signature::new_function(&fn_seq_1),
+            2 => Signature::new_function(&fn_seq_2),
+            3 => Signature::new_function(&fn_seq_3),
+            4 => Signature::new_function(&fn_seq_4),
+            5 => Signature::new_function(&fn_seq_5),
+            6 => Signature::new_function(&fn_seq_6),
+            7 => Signature::new_function(&fn_seq_7),
+            8 => Signature::new_function(&fn_seq_8),
+            9 => Signature::new_function(&fn_seq_9),
+            10 => Signature::new_function(&fn_seq_10),
+            11 => Signature::new_function(&fn_seq_11),
+            12 => Signature::new_function(&fn_seq_12),
+            13 => Signature::new_function(&fn_seq_13),
+            14 => Signature::new_function(&fn_seq_14),
+            15 => Signature::new_function(&fn_seq_15),
+            16 => Signature::new_function(&fn_seq_16),
+            _ => return None,
+        };
+
+        Term::try_application(seq, vec![]).ok()
+    }
+
+    /// Whether `term` encrypts a message into a single record, e.g. `fn_encrypt12`
+    pub fn is_encrypted_record(term: &Term) -> bool {
+        match term {
+            Term::Variable(_) => false,
+            Term::Application(function, _) => {
+                let encrypting_functions = [
+                    Signature::new_function(&fn_encrypt_handshake),
+                    Signature::new_function(&fn_encrypt_early_data),
+                    Signature::new_function(&fn_encrypt_server_handshake),
+                    Signature::new_function(&fn_encrypt_application),
+                    Signature::new_function(&fn_encrypt12),
+                    Signature::new_function(&fn_encrypt12_with_suite),
+                    Signature::new_function(&fn_encrypt12_resumption),
+                ];
+
+                encrypting_functions
+                    .iter()
+                    .any(|encrypt| encrypt.symbol().id() == function.symbol().id())
+            }
+        }
+    }
+
+    /// Returns a term which encodes the `record` with a length field which is `offset` bytes
+    /// smaller or larger than its payload. The `offset` must be between 1 and 16, such that it can
+    /// be expressed by one of the `fn_seq_*` constants.
+    pub fn corrupt_record_length(record: &Term, offset: u64, shrink: bool) -> Option<Term> {
+        let corrupt = if shrink {
+            Signature::new_function(&fn_shrink_record_length)
+        } else {
+            Signature::new_function(&fn_grow_record_length)
+        };
+
+        let offset = seq_constant(offset)?;
+        Term::try_application(corrupt, vec![record.clone(), offset]).ok()
+    }
+
+    /// Whether `function` appends an element to a list, e.g. `fn_client_extensions_append`
+    pub fn is_list_append(function: &Function) -> bool {
+        let appending_functions = [
+            Signature::new_function(&fn_client_extensions_append),
+            Signature::new_function(&fn_server_extensions_append),
+            Signature::new_function(&fn_hello_retry_extensions_append),
+            Signature::new_function(&fn_cert_req_extensions_append),
+            Signature::new_function(&fn_cert_extensions_append),
+            Signature::new_function(&fn_new_session_ticket_extensions_append),
+            Signature::new_function(&fn_append_cipher_suite),
+            Signature::new_function(&fn_append_signature_scheme),
+            Signature::new_function(&fn_append_certificate),
+            Signature::new_function(&fn_append_vec),
+            Signature::new_function(&fn_append_cert_compression_algorithm),
+            Signature::new_function(&fn_append_preshared_keys_identity),
+            Signature::new_function(&fn_append_protocol_version),
+            Signature::new_function(&fn_append_psk_key_exchange_mode),
+        ];
+
+        appending_functions
+            .iter()
+            .any(|append| append.symbol().id() == function.symbol().id())
+    }
+
+    /// Splits a chain of list appends into the innermost list and the appended elements. The
+    /// elements are returned in the order in which they are appended.
+    fn list_elements(list: &Term) -> (&Term, Vec<(&Function, &Term)>) {
+        let mut elements = vec![];
+        let mut current = list;
+
+        while let Term::Application(function, subterms) = current {
+            if !is_list_append(function) || function.name() != list.name() || subterms.len() != 2 {
+                break;
+            }
+
+            elements.push((function, &subterms[1]));
+            current = &subterms[0];
+        }
+
+        elements.reverse();
+        (current, elements)
+    }
+
+    /// Whether `term` is a chain of at least two appends of the same append function
+    pub fn is_reorderable_list(term: &Term) -> bool {
+        list_elements(term).1.len() >= 2
+    }
+
+    /// Returns a term which appends the same elements as `list` in a different order. Returns
+    /// `None` if `list` is no chain of appends or if all elements are equal.
+    pub fn reorder_list<R: Rand>(list: &Term, rand: &mut R) -> Option<Term> {
+        let (base, elements) = list_elements(list);
+        let original = elements
+            .iter()
+            .map(|(_, element)| *element)
+            .collect::<Vec<&Term>>();
+
+        let mut permuted = original.clone();
+        // Fisher-Yates shuffle
+        for i in (1..permuted.len()).rev() {
+            let j = rand.below(i as u64 + 1) as usize;
+            permuted.swap(i, j);
+        }
+
+        let unchanged = |permuted: &Vec<&Term>| {
+            permuted
+                .iter()
+                .zip(&original)
+                .all(|(a, b)| a.structurally_eq(b))
+        };
+        if unchanged(&permuted) {
+            permuted.rotate_left(1);
+            if unchanged(&permuted) {
+                return None;
+            }
+        }
+
+        let mut reordered = base.clone();
+        for ((function, _), element) in elements.iter().zip(permuted) {
+            reordered =
+                Term::try_application((*function).clone(), vec![reordered, element.clone()])
+                    .ok()?;
+        }
+        Some(reordered)
+    }
+
+    /// Whether `term` is a chain of appends which builds the extensions of a ClientHello or
+    /// ServerHello and appends at least one extension
+    pub fn is_extension_list(term: &Term) -> bool {
+        let shape = term.get_type_shape();
+
+        (*shape == TypeShape::of::<Vec<ClientExtension>>()
+            || *shape == TypeShape::of::<Vec<ServerExtension>>())
+            && !list_elements(term).1.is_empty()
+    }
+
+    /// Returns a term which appends a copy of one of the elements of `list` once more. Returns
+    /// `None` if `list` is no chain of appends.
+    pub fn duplicate_list_element<R: Rand>(list: &Term, rand: &mut R) -> Option<Term> {
+        let (_, elements) = list_elements(list);
+        let (function, element) = elements.choose(rand)?;
+
+        Term::try_application((*function).clone(), vec![list.clone(), (*element).clone()]).ok()
+    }
+
     pub fn choose_term_path_filtered<'a, R: Rand, P: Fn(&Term) -> bool + Copy>(
         trace: &Trace,
         filter: P,
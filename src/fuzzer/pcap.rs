@@ -0,0 +1,402 @@
+//! Imports captured TLS handshakes from pcap files as seed [`Trace`]s.
+//!
+//! Only the classic pcap format is supported (not pcapng). Each TCP connection of the capture
+//! becomes one trace. The records which the client sent are replayed to a server agent through
+//! `fn_new_opaque_message` recipes. The payloads are literals which are built from `fn_seq_0` to
+//! `fn_seq_15` nibbles, therefore the imported traces do not depend on any knowledge.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+
+use rustls::msgs::deframer::MessageDeframer;
+use rustls::msgs::enums::ContentType;
+use rustls::msgs::handshake::{ClientExtension, HandshakePayload};
+use rustls::msgs::message::{Message, MessagePayload, OpaqueMessage};
+use rustls::ProtocolVersion;
+
+use crate::agent::{AgentDescriptor, AgentName, TLSVersion};
+use crate::error::Error;
+use crate::term::atoms::Function;
+use crate::term::signature::Signature;
+use crate::term::{remove_prefix, Term};
+use crate::trace::{InputAction, Trace};
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b23c4d;
+const PCAP_HEADER_LEN: usize = 24;
+const PCAP_RECORD_HEADER_LEN: usize = 16;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+
+const IP_PROTOCOL_TCP: u8 = 6;
+
+/// One direction of a TCP connection, identified by source and destination address and port
+type Endpoints = (Vec<u8>, u16, Vec<u8>, u16);
+
+/// Reassembles the payload of one direction of a TCP connection
+#[derive(Default)]
+struct HalfStream {
+    next_seq: Option<u32>,
+    data: Vec<u8>,
+    /// Segments which arrived before the segments which precede them
+    pending: BTreeMap<u32, Vec<u8>>,
+}
+
+impl HalfStream {
+    fn add_segment(&mut self, seq: u32, payload: &[u8]) {
+        let next_seq = *self.next_seq.get_or_insert(seq);
+        let offset = seq.wrapping_sub(next_seq) as i32;
+
+        if offset > 0 {
+            self.pending.insert(seq, payload.to_vec());
+            return;
+        }
+
+        // Retransmissions overlap with data we already have
+        let overlap = (-offset) as usize;
+        if overlap < payload.len() {
+            self.data.extend_from_slice(&payload[overlap..]);
+            self.next_seq = Some(next_seq.wrapping_add((payload.len() - overlap) as u32));
+        }
+
+        let next_seq = self.next_seq.unwrap();
+        if let Some(payload) = self.pending.remove(&next_seq) {
+            self.add_segment(next_seq, &payload);
+        }
+    }
+}
+
+/// A TCP connection. The client is the peer which sent the first payload.
+struct Connection {
+    client: Endpoints,
+    client_stream: HalfStream,
+}
+
+/// Imports all TLS connections of the pcap at `path` as traces. Each trace replays the records of
+/// the client to a server agent. Packets which are malformed or do not belong to TLS connections
+/// are skipped with a warning. The function symbols are taken from `sig`.
+pub fn import_pcap(path: &Path, sig: &Signature) -> Result<Vec<Trace>, Error> {
+    let capture = fs::read(path)
+        .map_err(|err| Error::IO(format!("Failed to read pcap {}: {}", path.display(), err)))?;
+
+    let mut connections: Vec<Connection> = vec![];
+    let mut connection_by_endpoints: HashMap<Endpoints, usize> = HashMap::new();
+
+    for (index, packet) in read_packets(&capture)?.into_iter().enumerate() {
+        let (endpoints, seq, payload) = match packet {
+            Some(segment) => segment,
+            None => {
+                warn!("Skipping packet {} which is not a TCP segment", index);
+                continue;
+            }
+        };
+
+        if payload.is_empty() {
+            continue;
+        }
+
+        let reverse = (
+            endpoints.2.clone(),
+            endpoints.3,
+            endpoints.0.clone(),
+            endpoints.1,
+        );
+        let connection_index = match connection_by_endpoints
+            .get(&endpoints)
+            .or_else(|| connection_by_endpoints.get(&reverse))
+        {
+            Some(connection_index) => *connection_index,
+            None => {
+                connections.push(Connection {
+                    client: endpoints.clone(),
+                    client_stream: HalfStream::default(),
+                });
+                connection_by_endpoints.insert(endpoints.clone(), connections.len() - 1);
+                connections.len() - 1
+            }
+        };
+
+        let connection = &mut connections[connection_index];
+        if connection.client == endpoints {
+            connection.client_stream.add_segment(seq, payload);
+        }
+    }
+
+    let mut traces = vec![];
+    for connection in connections {
+        let records = deframe(&connection.client_stream.data);
+        if records.is_empty() {
+            warn!(
+                "Skipping connection from port {} which contains no TLS records",
+                connection.client.1
+            );
+            continue;
+        }
+
+        traces.push(records_to_trace(&records, sig)?);
+    }
+
+    Ok(traces)
+}
+
+/// Builds a trace which sends each of the `records` to a server
+fn records_to_trace(records: &[OpaqueMessage], sig: &Signature) -> Result<Trace, Error> {
+    let server = AgentName::first();
+    let tls_version = records
+        .first()
+        .map(offered_tls_version)
+        .unwrap_or(TLSVersion::V1_2);
+
+    let mut steps = vec![];
+    for record in records {
+        steps.push(InputAction::new_step(server, record_term(record, sig)?));
+    }
+
+    Ok(Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor::new_server(server, tls_version)],
+        steps,
+    })
+}
+
+/// The highest TLS version which is offered by the ClientHello in `record`
+fn offered_tls_version(record: &OpaqueMessage) -> TLSVersion {
+    if let Ok(message) = Message::try_from(record.clone()) {
+        if let MessagePayload::Handshake(handshake) = message.payload {
+            if let HandshakePayload::ClientHello(client_hello) = handshake.payload {
+                let offers_tls13 = client_hello.extensions.iter().any(|extension| {
+                    matches!(extension, ClientExtension::SupportedVersions(versions)
+                        if versions.contains(&ProtocolVersion::TLSv1_3))
+                });
+                if offers_tls13 {
+                    return TLSVersion::V1_3;
+                }
+            }
+        }
+    }
+
+    TLSVersion::V1_2
+}
+
+/// Builds a term which evaluates to `record`. Records with a content type or version for which
+/// there is no constant are decoded from their raw bytes.
+fn record_term(record: &OpaqueMessage, sig: &Signature) -> Result<Term, Error> {
+    let content_type = match record.typ {
+        ContentType::ChangeCipherSpec => "fn_content_type_change_cipher_spec",
+        ContentType::Alert => "fn_content_type_alert",
+        ContentType::Handshake => "fn_content_type_handshake",
+        ContentType::ApplicationData => "fn_content_type_application_data",
+        ContentType::Heartbeat => "fn_content_type_heartbeat",
+        _ => "",
+    };
+    let version = match record.version {
+        ProtocolVersion::TLSv1_0 => "fn_protocol_version10",
+        ProtocolVersion::TLSv1_1 => "fn_protocol_version11",
+        ProtocolVersion::TLSv1_2 => "fn_protocol_version12",
+        ProtocolVersion::TLSv1_3 => "fn_protocol_version13",
+        _ => "",
+    };
+
+    if content_type.is_empty() || version.is_empty() {
+        return Term::try_application(
+            function_by_name(sig, "fn_decode_record")?,
+            vec![bytes_term(&record.clone().encode(), sig)?],
+        );
+    }
+
+    Term::try_application(
+        function_by_name(sig, "fn_new_opaque_message")?,
+        vec![
+            Term::try_application(function_by_name(sig, content_type)?, vec![])?,
+            Term::try_application(function_by_name(sig, version)?, vec![])?,
+            bytes_term(&record.payload.0, sig)?,
+        ],
+    )
+}
+
+/// Builds a balanced term which evaluates to `bytes`. The term only consists of the functions
+/// `fn_empty_bytes_vec`, `fn_append_nibbles`, `fn_concat_bytes` and `fn_seq_0` to `fn_seq_15`.
+pub fn bytes_term(bytes: &[u8], sig: &Signature) -> Result<Term, Error> {
+    match bytes.len() {
+        0 => Term::try_application(function_by_name(sig, "fn_empty_bytes_vec")?, vec![]),
+        1 => Term::try_application(
+            function_by_name(sig, "fn_append_nibbles")?,
+            vec![
+                bytes_term(&[], sig)?,
+                nibble_term(bytes[0] >> 4, sig)?,
+                nibble_term(bytes[0] & 0xf, sig)?,
+            ],
+        ),
+        length => {
+            let (first, second) = bytes.split_at(length / 2);
+            Term::try_application(
+                function_by_name(sig, "fn_concat_bytes")?,
+                vec![bytes_term(first, sig)?, bytes_term(second, sig)?],
+            )
+        }
+    }
+}
+
+fn nibble_term(nibble: u8, sig: &Signature) -> Result<Term, Error> {
+    let name = format!("fn_seq_{}", nibble);
+    Term::try_application(function_by_name(sig, &name)?, vec![])
+}
+
+/// Looks up the function `name` of `sig`. The names of the signature are full paths, therefore
+/// they are compared without their module prefix.
+fn function_by_name(sig: &Signature, name: &str) -> Result<Function, Error> {
+    sig.functions
+        .iter()
+        .find(|(shape, _dynamic_fn)| remove_prefix(shape.name) == name)
+        .map(|(shape, dynamic_fn)| Function::new(shape.clone(), dynamic_fn.clone()))
+        .ok_or_else(|| Error::Term(format!("Signature does not contain {}", name)))
+}
+
+/// Splits `stream` into records. Data after the first malformed record is dropped.
+fn deframe(mut stream: &[u8]) -> Vec<OpaqueMessage> {
+    let mut deframer = MessageDeframer::new();
+    let mut records = vec![];
+
+    while !stream.is_empty() {
+        match deframer.read(&mut stream) {
+            Ok(0) => break,
+            Ok(_) if deframer.desynced => {
+                warn!("Skipping malformed TLS records");
+                break;
+            }
+            Ok(_) => records.extend(deframer.frames.drain(..)),
+            Err(err) => {
+                warn!("Failed to read TLS records: {}", err);
+                break;
+            }
+        }
+    }
+
+    records
+}
+
+/// Reads all packets of the `capture`. A packet is `None` if it is not a TCP segment over IPv4 or
+/// IPv6.
+fn read_packets(capture: &[u8]) -> Result<Vec<Option<(Endpoints, u32, &[u8])>>, Error> {
+    if capture.len() < PCAP_HEADER_LEN {
+        return Err(Error::IO("The pcap header is truncated".to_string()));
+    }
+
+    let magic = [capture[0], capture[1], capture[2], capture[3]];
+    let big_endian = if u32::from_le_bytes(magic) == PCAP_MAGIC_MICROS
+        || u32::from_le_bytes(magic) == PCAP_MAGIC_NANOS
+    {
+        false
+    } else if u32::from_be_bytes(magic) == PCAP_MAGIC_MICROS
+        || u32::from_be_bytes(magic) == PCAP_MAGIC_NANOS
+    {
+        true
+    } else {
+        return Err(Error::IO(
+            "Not a pcap file, pcapng is not supported".to_string(),
+        ));
+    };
+    let read_u32 = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    };
+
+    let link_type = read_u32(&capture[20..24]);
+    let mut packets = vec![];
+    let mut rest = &capture[PCAP_HEADER_LEN..];
+
+    while rest.len() >= PCAP_RECORD_HEADER_LEN {
+        let captured_length = read_u32(&rest[8..12]) as usize;
+        rest = &rest[PCAP_RECORD_HEADER_LEN..];
+
+        if captured_length > rest.len() {
+            warn!("Skipping truncated packet at the end of the pcap");
+            break;
+        }
+
+        let (frame, next) = rest.split_at(captured_length);
+        packets.push(parse_link_layer(link_type, frame));
+        rest = next;
+    }
+
+    Ok(packets)
+}
+
+fn parse_link_layer(link_type: u32, frame: &[u8]) -> Option<(Endpoints, u32, &[u8])> {
+    let ip_packet = match link_type {
+        LINKTYPE_NULL => frame.get(4..)?,
+        LINKTYPE_ETHERNET => {
+            let ether_type = u16::from_be_bytes([*frame.get(12)?, *frame.get(13)?]);
+            match ether_type {
+                0x0800 | 0x86dd => frame.get(14..)?,
+                _ => return None,
+            }
+        }
+        LINKTYPE_RAW => frame,
+        LINKTYPE_LINUX_SLL => frame.get(16..)?,
+        _ => return None,
+    };
+
+    parse_ip(ip_packet)
+}
+
+fn parse_ip(packet: &[u8]) -> Option<(Endpoints, u32, &[u8])> {
+    let (source, destination, segment) = match packet.first()? >> 4 {
+        4 => {
+            let header_length = ((packet[0] & 0xf) as usize) * 4;
+            let total_length = u16::from_be_bytes([*packet.get(2)?, *packet.get(3)?]) as usize;
+            if *packet.get(9)? != IP_PROTOCOL_TCP {
+                return None;
+            }
+            (
+                packet.get(12..16)?,
+                packet.get(16..20)?,
+                packet.get(header_length..total_length.min(packet.len()))?,
+            )
+        }
+        6 => {
+            let payload_length = u16::from_be_bytes([*packet.get(4)?, *packet.get(5)?]) as usize;
+            // Extension headers are not supported
+            if *packet.get(6)? != IP_PROTOCOL_TCP {
+                return None;
+            }
+            (
+                packet.get(8..24)?,
+                packet.get(24..40)?,
+                packet.get(40..(40 + payload_length).min(packet.len()))?,
+            )
+        }
+        _ => return None,
+    };
+
+    let source_port = u16::from_be_bytes([*segment.get(0)?, *segment.get(1)?]);
+    let destination_port = u16::from_be_bytes([*segment.get(2)?, *segment.get(3)?]);
+    let seq = u32::from_be_bytes([
+        *segment.get(4)?,
+        *segment.get(5)?,
+        *segment.get(6)?,
+        *segment.get(7)?,
+    ]);
+    let data_offset = ((*segment.get(12)? >> 4) as usize) * 4;
+    let payload = segment.get(data_offset..)?;
+
+    Some((
+        (
+            source.to_vec(),
+            source_port,
+            destination.to_vec(),
+            destination_port,
+        ),
+        seq,
+        payload,
+    ))
+}
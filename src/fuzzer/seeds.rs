@@ -3,11 +3,14 @@
 
 use rustls::internal::msgs::enums::{HandshakeType, Compression};
 
-use crate::agent::{AgentDescriptor, TLSVersion};
+use crate::agent::{AgentDescriptor, ExternalPsk, TLSVersion};
+use crate::fuzzer::pcap::bytes_term;
+use crate::openssl_binding::successful_ocsp_response;
 use crate::term;
 use crate::trace::TlsMessageType;
 use crate::trace::TlsMessageType::Handshake;
 use crate::tls::fn_impl::*;
+use crate::tls::SIGNATURE;
 use crate::{
     agent::AgentName,
     term::Term,
@@ -15,6 +18,7 @@ use crate::{
 };
 use rustls::{ProtocolVersion, CipherSuite};
 use rustls::msgs::handshake::{SessionID, Random};
+use rustls::msgs::message::Message;
 use rustls::internal::msgs::handshake::ServerExtension;
 
 pub fn seed_successful(client: AgentName, server: AgentName) -> Trace {
@@ -26,12 +30,14 @@ pub fn seed_successful(client: AgentName, server: AgentName) -> Trace {
                 tls_version: TLSVersion::V1_3,
                 server: false,
                 try_reuse: false,
+                ..AgentDescriptor::default()
             },
             AgentDescriptor {
                 name: server,
                 tls_version: TLSVersion::V1_3,
                 server: true,
                 try_reuse: false,
+                ..AgentDescriptor::default()
             },
         ],
         steps: vec![
@@ -137,12 +143,14 @@ pub fn seed_successful_mitm(client: AgentName, server: AgentName) -> Trace {
                 tls_version: TLSVersion::V1_3,
                 server: false,
                 try_reuse: false,
+                ..AgentDescriptor::default()
             },
             AgentDescriptor {
                 name: server,
                 tls_version: TLSVersion::V1_3,
                 server: true,
                 try_reuse: false,
+                ..AgentDescriptor::default()
             },
         ],
         steps: vec![
@@ -241,6 +249,229 @@ pub fn seed_successful_mitm(client: AgentName, server: AgentName) -> Trace {
     }
 }
 
+/// Forwards the ClientHello of the client split into three records to the server. OpenSSL needs to
+/// reassemble the handshake message before it is able to answer with a ServerHello.
+pub fn seed_fragmented_client_hello(client: AgentName, server: AgentName) -> Trace {
+    let client_hello = term! {
+        fn_client_hello(
+            ((client, 0)),
+            ((client, 0)),
+            ((client, 0)),
+            ((client, 0)),
+            ((client, 0)),
+            ((client, 0))
+        )
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![
+            AgentDescriptor {
+                name: client,
+                tls_version: TLSVersion::V1_3,
+                server: false,
+                try_reuse: false,
+                ..AgentDescriptor::default()
+            },
+            AgentDescriptor {
+                name: server,
+                tls_version: TLSVersion::V1_3,
+                server: true,
+                try_reuse: false,
+                ..AgentDescriptor::default()
+            },
+        ],
+        steps: vec![
+            OutputAction::new_step(client),
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_fragment(
+                        (@client_hello),
+                        (fn_fragment_length(
+                            (@client_hello),
+                            fn_seq_3
+                        ))
+                    )
+                },
+            ),
+        ],
+    }
+}
+
+/// Seed in which the server demands a key share for a different group than the client offered
+/// first. The client sends a key share for P-256, but the server only supports P-384. The attacker
+/// answers the first ClientHello with a HelloRetryRequest which selects P-384. After the second
+/// ClientHello the handshake completes like in [`seed_successful`].
+pub fn seed_hello_retry_request(client: AgentName, server: AgentName) -> Trace {
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![
+            AgentDescriptor {
+                name: client,
+                tls_version: TLSVersion::V1_3,
+                server: false,
+                groups: Some("P-256:P-384".to_string()),
+                ..AgentDescriptor::default()
+            },
+            AgentDescriptor {
+                name: server,
+                tls_version: TLSVersion::V1_3,
+                server: true,
+                groups: Some("P-384".to_string()),
+                ..AgentDescriptor::default()
+            },
+        ],
+        steps: vec![
+            OutputAction::new_step(client),
+            // First Client Hello Client -> Server
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_client_hello(
+                        ((client, 0)),
+                        ((client, 0)),
+                        ((client, 0)),
+                        ((client, 0)),
+                        ((client, 0)),
+                        ((client, 0))
+                    )
+                },
+            ),
+            // Hello Retry Request Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_hello_retry_request(
+                        fn_protocol_version12,
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::HelloRetryRequest)))]),
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::HelloRetryRequest)))]),
+                        (fn_hello_retry_extensions_append(
+                            (fn_hello_retry_extensions_append(
+                                fn_hello_retry_extensions_new,
+                                fn_supported_versions13_hello_retry_extension
+                            )),
+                            (fn_key_share_hello_retry_extension(fn_named_group_secp384r1))
+                        ))
+                    )
+                },
+            ),
+            // Second Client Hello Client -> Server
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_client_hello(
+                        ((client, 1)),
+                        ((client, 1)),
+                        ((client, 1)),
+                        ((client, 1)),
+                        ((client, 1)),
+                        ((client, 1))
+                    )
+                },
+            ),
+            // Server Hello Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_server_hello(
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]),
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]),
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]),
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]),
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]),
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))])
+                    )
+                },
+            ),
+            // Encrypted Extensions, Certificate, Certificate Verify and Finished Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_application_data(
+                        ((server, 0)[Some(TlsMessageType::ApplicationData)]/Vec<u8>)
+                    )
+                },
+            ),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_application_data(
+                        ((server, 1)[Some(TlsMessageType::ApplicationData)]/Vec<u8>)
+                    )
+                },
+            ),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_application_data(
+                        ((server, 2)[Some(TlsMessageType::ApplicationData)]/Vec<u8>)
+                    )
+                },
+            ),
+            InputAction::new_step(
+                client,
+                term! {
+                    fn_application_data(
+                        ((server, 3)[Some(TlsMessageType::ApplicationData)]/Vec<u8>)
+                    )
+                },
+            ),
+            // Finished Client -> Server
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_application_data(
+                        ((client, 0)[Some(TlsMessageType::ApplicationData)]/Vec<u8>)
+                    )
+                },
+            ),
+        ],
+    }
+}
+
+/// Like [`seed_hello_retry_request`], but the server sends the HelloRetryRequest itself without
+/// keeping state. It includes a cookie which the attacker copies into the second ClientHello. The
+/// cookie is a separate subterm, such that it can be replaced.
+pub fn seed_stateless_retry(client: AgentName, server: AgentName) -> Trace {
+    let mut trace = seed_hello_retry_request(client, server);
+    trace.descriptors[1].stateless_retry = true;
+
+    // Hello Retry Request Server -> Client
+    trace.steps[2] = InputAction::new_step(
+        client,
+        term! {
+            fn_hello_retry_request(
+                ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::HelloRetryRequest)))]),
+                ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::HelloRetryRequest)))]),
+                ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::HelloRetryRequest)))]),
+                ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::HelloRetryRequest)))])
+            )
+        },
+    );
+    // Second Client Hello Client -> Server
+    trace.steps[3] = InputAction::new_step(
+        server,
+        term! {
+            fn_client_hello(
+                ((client, 1)),
+                ((client, 1)),
+                ((client, 1)),
+                ((client, 1)),
+                ((client, 1)),
+                (fn_set_cookie_extension(
+                    ((client, 1)),
+                    (fn_get_hello_retry_cookie(
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::HelloRetryRequest)))])
+                    ))
+                ))
+            )
+        },
+    );
+
+    trace
+}
+
 pub fn seed_successful12(client: AgentName, server: AgentName) -> Trace {
     Trace {
         prior_traces: vec![],
@@ -250,12 +481,14 @@ pub fn seed_successful12(client: AgentName, server: AgentName) -> Trace {
                 tls_version: TLSVersion::V1_2,
                 server: false,
                 try_reuse: false,
+                ..AgentDescriptor::default()
             },
             AgentDescriptor {
                 name: server,
                 tls_version: TLSVersion::V1_2,
                 server: true,
                 try_reuse: false,
+                ..AgentDescriptor::default()
             },
         ],
         steps: vec![
@@ -389,6 +622,43 @@ pub fn seed_successful12(client: AgentName, server: AgentName) -> Trace {
     }
 }
 
+/// Like [`seed_successful12`], but the client requests the status of the server certificate and
+/// the server staples a successful OCSP response for its certificate. The response is a separate subterm of the
+/// CertificateStatus message, such that mutators can supply malformed responses.
+pub fn seed_ocsp_stapling12(client: AgentName, server: AgentName) -> Trace {
+    let ocsp_response = successful_ocsp_response().unwrap();
+
+    let mut trace = seed_successful12(client, server);
+    trace.descriptors[0].request_ocsp = true;
+    trace.descriptors[1].ocsp_response = Some(ocsp_response.clone());
+
+    let ocsp_response = bytes_term(&ocsp_response, &SIGNATURE).unwrap();
+
+    // Certificate Status, Server -> Client
+    trace.steps.insert(
+        4,
+        InputAction::new_step(
+            client,
+            term! {
+                fn_certificate_status((@ocsp_response))
+            },
+        ),
+    );
+
+    // Server Handshake Finished, Server -> Client. The server sent one more record before.
+    let finished_step = trace.steps.len() - 1;
+    trace.steps[finished_step] = InputAction::new_step(
+        client,
+        term! {
+            fn_opaque_message(
+                ((server, 7)[None])
+            )
+        },
+    );
+
+    trace
+}
+
 pub fn seed_successful_with_ccs(client: AgentName, server: AgentName) -> Trace {
     let mut trace = seed_successful(client, server);
 
@@ -453,7 +723,77 @@ pub fn seed_successful_with_tickets(client: AgentName, server: AgentName) -> Tra
     trace
 }
 
+/// Completes [`seed_successful`] and exchanges application data afterwards. The client sends
+/// "ping" and the server answers with "pong".
+pub fn seed_application_data(client: AgentName, server: AgentName) -> Trace {
+    let mut trace = seed_successful(client, server);
+    let ping = bytes_term(b"ping", &SIGNATURE).unwrap();
+    let pong = bytes_term(b"pong", &SIGNATURE).unwrap();
+
+    // Ping Client -> Client
+    trace.steps.push(InputAction::new_step(
+        client,
+        term! {
+            fn_plaintext_application_data((@ping))
+        },
+    ));
+    // Ping Client -> Server
+    trace.steps.push(InputAction::new_step(
+        server,
+        term! {
+            fn_application_data(
+                ((client, 1)[Some(TlsMessageType::ApplicationData)]/Vec<u8>)
+            )
+        },
+    ));
+    // Pong Server -> Server
+    trace.steps.push(InputAction::new_step(
+        server,
+        term! {
+            fn_plaintext_application_data((@pong))
+        },
+    ));
+    // Pong Server -> Client, after the two tickets of the server
+    trace.steps.push(InputAction::new_step(
+        client,
+        term! {
+            fn_application_data(
+                ((server, 6)[Some(TlsMessageType::ApplicationData)]/Vec<u8>)
+            )
+        },
+    ));
+
+    trace
+}
+
 pub fn seed_client_attacker(server: AgentName) -> (Trace) {
+    _seed_client_attacker(server, term! { fn_supported_versions13_extension })
+}
+
+/// Seed which offers the versions `[GREASE, TLS 1.3, TLS 1.2]` through the supported_versions
+/// extension. The server ignores the GREASE value and negotiates TLS 1.3 like in
+/// [`seed_client_attacker`].
+pub fn seed_client_attacker_supported_versions(server: AgentName) -> Trace {
+    _seed_client_attacker(
+        server,
+        term! {
+            fn_supported_versions_extension(
+                (fn_append_protocol_version(
+                    (fn_append_protocol_version(
+                        (fn_append_protocol_version(
+                            fn_new_protocol_versions,
+                            fn_grease_version
+                        )),
+                        fn_protocol_version13
+                    )),
+                    fn_protocol_version12
+                ))
+            )
+        },
+    )
+}
+
+fn _seed_client_attacker(server: AgentName, supported_versions_extension: Term) -> Trace {
     let client_hello = term! {
           fn_client_hello(
             fn_protocol_version12,
@@ -475,7 +815,7 @@ pub fn seed_client_attacker(server: AgentName) -> (Trace) {
                     )),
                     fn_key_share_deterministic_extension
                 )),
-                fn_supported_versions13_extension
+                (@supported_versions_extension)
             ))
         )
     };
@@ -498,6 +838,7 @@ pub fn seed_client_attacker(server: AgentName) -> (Trace) {
             tls_version: TLSVersion::V1_3,
             server: true,
             try_reuse: false,
+            ..AgentDescriptor::default()
         }],
         steps: vec![
             Step {
@@ -532,11 +873,9 @@ pub fn seed_client_attacker(server: AgentName) -> (Trace) {
     trace
 }
 
-pub fn seed_client_attacker12(server: AgentName) -> Trace {
-    _seed_client_attacker12(server).0
-}
-
-fn _seed_client_attacker12(server: AgentName) -> (Trace, Term) {
+/// Seed which sends a key share with the all-zero point, which is not on the curve secp384r1. The
+/// server has to reject the ClientHello instead of computing the ECDH shared secret.
+pub fn seed_client_attacker_zero_key_share(server: AgentName) -> Trace {
     let client_hello = term! {
           fn_client_hello(
             fn_protocol_version12,
@@ -544,76 +883,242 @@ fn _seed_client_attacker12(server: AgentName) -> (Trace, Term) {
             fn_new_session_id,
             (fn_append_cipher_suite(
                 (fn_new_cipher_suites()),
-                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
-                fn_cipher_suite12
+                fn_cipher_suite13_aes_128_gcm_sha256
             )),
             fn_compressions,
             (fn_client_extensions_append(
                 (fn_client_extensions_append(
                     (fn_client_extensions_append(
                         (fn_client_extensions_append(
-                            (fn_client_extensions_append(
-                                (fn_client_extensions_append(
-                                    fn_client_extensions_new,
-                                    fn_secp384r1_support_group_extension
-                                )),
-                                fn_signature_algorithm_extension
-                            )),
-                            fn_ec_point_formats_extension
+                            fn_client_extensions_new,
+                            fn_secp384r1_support_group_extension
                         )),
-                        fn_signed_certificate_timestamp_extension
+                        fn_signature_algorithm_extension
                     )),
-                     // Enable Renegotiation
-                    (fn_renegotiation_info_extension(fn_empty_bytes_vec))
+                    (fn_key_share_entry(
+                        fn_named_group_secp384r1,
+                        (fn_ec_point(
+                            fn_named_group_secp384r1,
+                            fn_empty_bytes_vec,
+                            fn_empty_bytes_vec
+                        ))
+                    ))
                 )),
-                // Add signature cert extension
-                fn_signature_algorithm_cert_extension
+                fn_supported_versions13_extension
             ))
         )
     };
 
-    let server_hello_transcript = term! {
-        fn_append_transcript(
-            (fn_append_transcript(
-                fn_new_transcript12,
-                (@client_hello) // ClientHello
-            )),
-            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
-        )
-    };
-
-    let certificate_transcript = term! {
-        fn_append_transcript(
-            (@server_hello_transcript),
-            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::Certificate)))]) // Certificate
-        )
-    };
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor {
+            name: server,
+            tls_version: TLSVersion::V1_3,
+            server: true,
+            try_reuse: false,
+            ..AgentDescriptor::default()
+        }],
+        steps: vec![Step {
+            agent: server,
+            action: Action::Input(InputAction {
+                recipe: client_hello,
+            }),
+        }],
+    }
+}
 
-    let server_key_exchange_transcript = term! {
-      fn_append_transcript(
-            (@certificate_transcript),
-            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]) // ServerKeyExchange
+/// Seed which sends an Encrypted Client Hello
+/// ([draft-ietf-tls-esni](https://datatracker.ietf.org/doc/draft-ietf-tls-esni/)). The outer
+/// ClientHello carries the inner ClientHello as payload of its ECH extension. The inner ClientHello
+/// is not sealed, therefore the server is expected to reject or ignore the ECH extension.
+pub fn seed_client_attacker_ech(server: AgentName) -> Trace {
+    let client_extensions = term! {
+        fn_client_extensions_append(
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        fn_client_extensions_new,
+                        fn_secp384r1_support_group_extension
+                    )),
+                    fn_signature_algorithm_extension
+                )),
+                fn_key_share_deterministic_extension
+            )),
+            fn_supported_versions13_extension
         )
     };
 
-    let server_hello_done_transcript = term! {
-      fn_append_transcript(
-            (@server_key_exchange_transcript),
-            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHelloDone)))]) // ServerHelloDone
+    let inner_client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (@client_extensions),
+                fn_encrypted_client_hello_inner_extension
+            ))
         )
     };
 
-    let client_key_exchange = term! {
-        fn_client_key_exchange(
-            (fn_new_pubkey12(
-                (fn_decode_ecdh_params(
-                    ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+    let outer_client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (@client_extensions),
+                (fn_encrypted_client_hello_extension(
+                    fn_ech_enc,
+                    (fn_encode_inner_client_hello((@inner_client_hello)))
                 ))
             ))
         )
     };
 
-    let client_key_exchange_transcript = term! {
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor {
+            name: server,
+            tls_version: TLSVersion::V1_3,
+            server: true,
+            try_reuse: false,
+            ..AgentDescriptor::default()
+        }],
+        steps: vec![InputAction::new_step(server, outer_client_hello)],
+    }
+}
+
+pub fn seed_client_attacker12(server: AgentName) -> Trace {
+    _seed_client_attacker12(server).0
+}
+
+/// Extensions of the ClientHello in [`seed_client_attacker12`]
+fn client_attacker12_extensions() -> Term {
+    client_attacker12_extensions_with_renegotiation_info(term! { fn_empty_bytes_vec })
+}
+
+/// Extensions of [`client_attacker12_extensions`] in which the renegotiation_info extension
+/// carries `renegotiated_connection`, see [RFC 5746](https://datatracker.ietf.org/doc/html/rfc5746#section-3.5)
+fn client_attacker12_extensions_with_renegotiation_info(renegotiated_connection: Term) -> Term {
+    term! {
+        fn_client_extensions_append(
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            (fn_client_extensions_append(
+                                fn_client_extensions_new,
+                                fn_secp384r1_support_group_extension
+                            )),
+                            fn_signature_algorithm_extension
+                        )),
+                        fn_ec_point_formats_extension
+                    )),
+                    fn_signed_certificate_timestamp_extension
+                )),
+                 // Enable Renegotiation
+                (fn_renegotiation_info_extension((@renegotiated_connection)))
+            )),
+            // Add signature cert extension
+            fn_signature_algorithm_cert_extension
+        )
+    }
+}
+
+fn _seed_client_attacker12(server: AgentName) -> (Trace, Term) {
+    _seed_client_attacker12_with_extensions(server, client_attacker12_extensions())
+}
+
+/// Cipher suites of the ClientHello in [`seed_client_attacker12`]
+fn client_attacker12_cipher_suites() -> Term {
+    term! {
+        fn_append_cipher_suite(
+            (fn_new_cipher_suites()),
+            // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+            fn_cipher_suite12
+        )
+    }
+}
+
+fn _seed_client_attacker12_with_extensions(
+    server: AgentName,
+    client_extensions: Term,
+) -> (Trace, Term) {
+    _seed_client_attacker12_with_hello_lists(
+        server,
+        client_attacker12_cipher_suites(),
+        client_extensions,
+    )
+}
+
+fn _seed_client_attacker12_with_hello_lists(
+    server: AgentName,
+    cipher_suites: Term,
+    client_extensions: Term,
+) -> (Trace, Term) {
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (@cipher_suites),
+            fn_compressions,
+            (@client_extensions)
+        )
+    };
+
+    let server_hello_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                fn_new_transcript12,
+                (@client_hello) // ClientHello
+            )),
+            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+        )
+    };
+
+    let certificate_transcript = term! {
+        fn_append_transcript(
+            (@server_hello_transcript),
+            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::Certificate)))]) // Certificate
+        )
+    };
+
+    let server_key_exchange_transcript = term! {
+      fn_append_transcript(
+            (@certificate_transcript),
+            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]) // ServerKeyExchange
+        )
+    };
+
+    let server_hello_done_transcript = term! {
+      fn_append_transcript(
+            (@server_key_exchange_transcript),
+            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHelloDone)))]) // ServerHelloDone
+        )
+    };
+
+    let client_key_exchange = term! {
+        fn_client_key_exchange(
+            (fn_new_pubkey12(
+                (fn_decode_ecdh_params(
+                    ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+                ))
+            ))
+        )
+    };
+
+    let client_key_exchange_transcript = term! {
       fn_append_transcript(
             (@server_hello_done_transcript),
             (@client_key_exchange)
@@ -637,6 +1142,7 @@ fn _seed_client_attacker12(server: AgentName) -> (Trace, Term) {
             tls_version: TLSVersion::V1_2,
             server: true,
             try_reuse: false,
+            ..AgentDescriptor::default()
         }],
         steps: vec![
             Step {
@@ -678,77 +1184,122 @@ fn _seed_client_attacker12(server: AgentName) -> (Trace, Term) {
     (trace, client_verify_data)
 }
 
-pub fn seed_cve_2021_3449(server: AgentName) -> Trace {
-    let (mut trace, client_verify_data) = _seed_client_attacker12(server);
+/// CCS injection ([CVE-2014-0224](https://www.openssl.org/news/secadv/20140605.txt)): Like
+/// [`seed_client_attacker12`], but the attacker sends a ChangeCipherSpec right after the
+/// ClientHello, before the ClientKeyExchange established the keys. A vulnerable server derives its
+/// keys from an empty master secret, a patched server rejects the early ChangeCipherSpec with an
+/// unexpected_message alert.
+pub fn seed_early_ccs(server: AgentName) -> Trace {
+    let mut trace = seed_client_attacker12(server);
+    trace.steps.insert(
+        1,
+        InputAction::new_step(server, term! { fn_change_cipher_spec }),
+    );
+    trace
+}
 
-    let renegotiation_client_hello = term! {
-          fn_client_hello(
-            fn_protocol_version12,
-            fn_new_random,
-            fn_new_session_id,
-            (fn_append_cipher_suite(
-                (fn_new_cipher_suites()),
-                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+/// Seed which sends GREASE values ([RFC 8701](https://datatracker.ietf.org/doc/html/rfc8701))
+/// in the cipher suites and the extensions of the ClientHello. A well-behaved server ignores them
+/// and completes the handshake like in [`seed_client_attacker12`].
+pub fn seed_client_attacker12_grease(server: AgentName) -> Trace {
+    let client_extensions = client_attacker12_extensions();
+
+    _seed_client_attacker12_with_hello_lists(
+        server,
+        term! {
+            fn_append_cipher_suite(
+                (fn_append_cipher_suite(
+                    (fn_new_cipher_suites()),
+                    fn_grease_cipher_suite
+                )),
                 fn_cipher_suite12
-            )),
-            fn_compressions,
+            )
+        },
+        term! {
+            fn_client_extensions_append(
+                (@client_extensions),
+                fn_grease_extension
+            )
+        },
+    )
+    .0
+}
+
+/// Like [`seed_client_attacker12`], but the handshake signatures and the certificate signatures
+/// are restricted to different algorithms. The ServerKeyExchange must be signed with
+/// rsa_pss_rsae_sha256 from the signature_algorithms extension, the certificate of the server is
+/// signed with rsa_pkcs1_sha256 from the signature_algorithms_cert extension.
+pub fn seed_signature_algorithms_cert12(server: AgentName) -> Trace {
+    let client_extensions = term! {
+        fn_client_extensions_append(
             (fn_client_extensions_append(
                 (fn_client_extensions_append(
                     (fn_client_extensions_append(
-                        (fn_client_extensions_append(
-                            (fn_client_extensions_append(
-                                fn_client_extensions_new,
-                                fn_secp384r1_support_group_extension
-                            )),
-                            fn_ec_point_formats_extension
-                        )),
-                        fn_signed_certificate_timestamp_extension
+                        fn_client_extensions_new,
+                        fn_secp384r1_support_group_extension
                     )),
-                     // Enable Renegotiation
-                    (fn_renegotiation_info_extension((@client_verify_data)))
+                    (fn_signature_algorithms_extension(
+                        (fn_append_signature_scheme(
+                            fn_new_signature_schemes,
+                            fn_signature_scheme_rsa_pss_rsae_sha256
+                        ))
+                    ))
                 )),
-                // Add signature cert extension
-                fn_signature_algorithm_cert_extension
-            ))
+                (fn_signature_algorithms_cert_extension(
+                    (fn_append_signature_scheme(
+                        fn_new_signature_schemes,
+                        fn_signature_scheme_rsa_pkcs1_sha256
+                    ))
+                ))
+            )),
+            fn_ec_point_formats_extension
         )
     };
 
-    trace.steps.push(Step {
-        agent: server,
-        action: Action::Input(InputAction {
-            recipe: term! {
-                fn_encrypt12(
-                    (@renegotiation_client_hello),
-                    ((server, 0)),
-                    (fn_decode_ecdh_params(
-                        ((server, 2)/Vec<u8>) // ServerECDHParams
-                    )),
-                    fn_seq_1
-                )
-            },
-        }),
-    });
+    _seed_client_attacker12_with_extensions(server, client_extensions).0
+}
 
-    /*    trace.stepSignature::push(Step {
-        agent: server,
-        action: Action::Input(InputAction {
-            recipe: term! {
-                fn_encrypt12(
-                    fn_alert_close_notify,
-                    ((server, 0)),
-                    (fn_decode_ecdh_params(
-                        ((server, 2)/Vec<u8>) // ServerECDHParams
-                    )),
-                    fn_seq_1
-                )
-            },
-        }),
-    });*/
+/// Host name which the server of [`seed_sni`] requires
+pub const SNI_SERVER_NAME: &str = "tlspuffin.test";
+
+/// Like [`seed_client_attacker12`], but the server requires the host name [`SNI_SERVER_NAME`] and
+/// the client sends it in the server_name extension
+pub fn seed_sni(server: AgentName) -> Trace {
+    let names = bytes_term(SNI_SERVER_NAME.as_bytes(), &SIGNATURE).unwrap();
+    seed_sni_with_names(
+        server,
+        term! {
+            fn_append_vec(
+                fn_empty_vec_of_vec,
+                (@names)
+            )
+        },
+    )
+}
+
+/// Like [`seed_sni`], but the client sends the host `names` instead. The server aborts with an
+/// unrecognized_name alert unless they contain [`SNI_SERVER_NAME`] only.
+pub fn seed_sni_with_names(server: AgentName, names: Term) -> Trace {
+    let extensions = client_attacker12_extensions();
+    let client_extensions = term! {
+        fn_client_extensions_append(
+            (@extensions),
+            (fn_server_name_extension((@names)))
+        )
+    };
 
+    let (mut trace, _) = _seed_client_attacker12_with_extensions(server, client_extensions);
+    trace.descriptors[0].server_name = Some(SNI_SERVER_NAME.to_string());
     trace
 }
 
-pub fn seed_heartbleed(client: AgentName, server: AgentName) -> Trace {
+/// Like [`seed_client_attacker12`], but negotiates the ECDHE-RSA `cipher_suite` instead of
+/// [`fn_cipher_suite12`]. The transcript, the master secret and the verify_data of the Finished
+/// message are computed for the hash function of the `cipher_suite`, see
+/// [`fn_finished_verify_data`].
+pub fn seed_client_attacker12_with_suite(server: AgentName, cipher_suite: Term) -> Trace {
+    let client_extensions = client_attacker12_extensions();
+
     let client_hello = term! {
           fn_client_hello(
             fn_protocol_version12,
@@ -756,39 +1307,68 @@ pub fn seed_heartbleed(client: AgentName, server: AgentName) -> Trace {
             fn_new_session_id,
             (fn_append_cipher_suite(
                 (fn_new_cipher_suites()),
-                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
-                fn_cipher_suite12
+                (@cipher_suite)
             )),
             fn_compressions,
-            (fn_client_extensions_append(
-                (fn_client_extensions_append(
-                    (fn_client_extensions_append(
-                        fn_client_extensions_new,
-                        fn_secp384r1_support_group_extension
+            (@client_extensions)
+        )
+    };
+
+    let server_ecdh_params = term! {
+        fn_decode_ecdh_params(
+            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+        )
+    };
+
+    let client_key_exchange = term! {
+        fn_client_key_exchange(
+            (fn_new_pubkey12((@server_ecdh_params)))
+        )
+    };
+
+    let transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        (fn_append_transcript(
+                            (fn_append_transcript(
+                                (fn_new_transcript_with_suite((@cipher_suite))),
+                                (@client_hello) // ClientHello
+                            )),
+                            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+                        )),
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::Certificate)))]) // Certificate
                     )),
-                    fn_ec_point_formats_extension
+                    ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]) // ServerKeyExchange
                 )),
-                fn_signed_certificate_timestamp_extension
+                ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHelloDone)))]) // ServerHelloDone
+            )),
+            (@client_key_exchange)
+        )
+    };
+
+    let client_verify_data = term! {
+        fn_finished_verify_data(
+            (@cipher_suite),
+            (@transcript),
+            (fn_master_secret12_with_suite(
+                (@cipher_suite),
+                ((server, 0)),
+                (@server_ecdh_params)
             ))
         )
     };
 
-    let trace = Trace {
+    Trace {
         prior_traces: vec![],
-        descriptors: vec![
-            AgentDescriptor {
-                name: client,
-                tls_version: TLSVersion::V1_2,
-                server: false,
-                try_reuse: false,
-            },
-            AgentDescriptor {
-                name: server,
-                tls_version: TLSVersion::V1_2,
-                server: true,
-                try_reuse: false,
-            },
-        ],
+        descriptors: vec![AgentDescriptor {
+            name: server,
+            tls_version: TLSVersion::V1_2,
+            server: true,
+            try_reuse: false,
+            ..AgentDescriptor::default()
+        }],
         steps: vec![
             Step {
                 agent: server,
@@ -796,138 +1376,1331 @@ pub fn seed_heartbleed(client: AgentName, server: AgentName) -> Trace {
                     recipe: client_hello,
                 }),
             },
-            // Send directly after client_hello such that this does not need to be encrypted
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: client_key_exchange,
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! { fn_change_cipher_spec },
+                }),
+            },
             Step {
                 agent: server,
                 action: Action::Input(InputAction {
                     recipe: term! {
-                        fn_heartbeat_fake_length(fn_empty_bytes_vec, fn_large_length)
+                        fn_encrypt12_with_suite(
+                            (fn_finished((@client_verify_data))),
+                            (@cipher_suite),
+                            ((server, 0)),
+                            (@server_ecdh_params),
+                            fn_seq_0
+                        )
                     },
                 }),
             },
         ],
-    };
-
-    trace
+    }
 }
 
-pub fn seed_freak(client: AgentName, server: AgentName) -> Trace {
-    Trace {
-        prior_traces: vec![],
-        descriptors: vec![
-            AgentDescriptor {
-                name: client,
-                tls_version: TLSVersion::V1_2,
-                server: false,
-                try_reuse: false,
-            },
-            AgentDescriptor {
-                name: server,
-                tls_version: TLSVersion::V1_2,
-                server: true,
-                try_reuse: false,
-            },
-        ],
+/// Seed which authenticates the attacker as TLS 1.2 client with the static RSA certificate. The
+/// CertificateVerify carries an RSA signature of the transcript, but announces ecdsa with sha256
+/// as hash and signature algorithm, see [`fn_certificate_verify12`]. The server must reject the
+/// signature because the algorithm does not match the key of the certificate.
+pub fn seed_certificate_verify12_mismatch(server: AgentName) -> Trace {
+    let client_extensions = client_attacker12_extensions();
+    let cipher_suites = client_attacker12_cipher_suites();
+
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (@cipher_suites),
+            fn_compressions,
+            (@client_extensions)
+        )
+    };
+
+    let client_certificate = term! {
+        fn_certificate(
+            (fn_append_certificate(
+                fn_new_certificates,
+                fn_static_rsa_certificate
+            ))
+        )
+    };
+
+    let client_key_exchange = term! {
+        fn_client_key_exchange(
+            (fn_new_pubkey12(
+                (fn_decode_ecdh_params(
+                    ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+                ))
+            ))
+        )
+    };
+
+    let transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                (fn_append_transcript(
+                    (fn_append_transcript(
+                        (fn_append_transcript(
+                            (fn_append_transcript(
+                                (fn_append_transcript(
+                                    (fn_append_transcript(
+                                        fn_new_transcript12,
+                                        (@client_hello) // ClientHello
+                                    )),
+                                    ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+                                )),
+                                ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::Certificate)))]) // Certificate
+                            )),
+                            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]) // ServerKeyExchange
+                        )),
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::CertificateRequest)))]) // CertificateRequest
+                    )),
+                    ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHelloDone)))]) // ServerHelloDone
+                )),
+                (@client_certificate)
+            )),
+            (@client_key_exchange)
+        )
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor {
+            name: server,
+            tls_version: TLSVersion::V1_2,
+            server: true,
+            try_reuse: false,
+            client_auth: true,
+            ..AgentDescriptor::default()
+        }],
         steps: vec![
-            Step {
-                agent: client,
-                action: Action::Output(OutputAction {}),
-            },
-            // Client Hello, Client -> Server
+            InputAction::new_step(server, client_hello),
+            InputAction::new_step(server, client_certificate),
+            InputAction::new_step(server, client_key_exchange),
             InputAction::new_step(
                 server,
                 term! {
-                    fn_client_hello(
-                        ((client, 0)),
-                        ((client, 0)),
-                        ((client, 0)),
-                        (fn_append_cipher_suite(
-                            (fn_new_cipher_suites()),
-                            fn_weak_export_cipher_suite
-                        )),
-                        ((client, 0)),
-                        ((client, 0))
+                    fn_certificate_verify12(
+                        fn_sig_hash_alg_ecdsa_sha256,
+                        (fn_certificate_verify12_signature((@transcript)))
                     )
                 },
             ),
-            // Server Hello, Server -> Client
-            InputAction::new_step(
-                client,
-                term! {
-                        fn_server_hello(
-                            ((server, 0)),
-                            ((server, 0)),
-                            ((server, 0)),
-                            (fn_secure_rsa_cipher_suite12),
-                            ((server, 0)),
-                            ((server, 0))
-                        )
-                },
-            ),
-            // Server Certificate, Server -> Client
+        ],
+    }
+}
+
+/// Seed which resumes a TLS 1.2 session through a session ticket
+/// ([RFC 5077](https://datatracker.ietf.org/doc/html/rfc5077)). The initial handshake requests a
+/// ticket which the ClientHello of the abbreviated handshake presents to the server.
+pub fn seed_resumption12(initial_server: AgentName, server: AgentName) -> Trace {
+    let initial_extensions = client_attacker12_extensions();
+    let (initial_handshake, _) = _seed_client_attacker12_with_extensions(
+        initial_server,
+        term! {
+            fn_client_extensions_append(
+                (@initial_extensions),
+                fn_session_ticket_request_extension
+            )
+        },
+    );
+
+    let master_secret = term! {
+        fn_master_secret12(
+            ((initial_server, 0)),
+            (fn_decode_ecdh_params(
+                ((initial_server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+            ))
+        )
+    };
+
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+                fn_cipher_suite12
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (@initial_extensions),
+                (fn_session_ticket_offer_extension(
+                    (fn_get_ticket(
+                        ((initial_server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::NewSessionTicket)))])
+                    ))
+                ))
+            ))
+        )
+    };
+
+    let server_hello_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                fn_new_transcript12,
+                (@client_hello) // ClientHello
+            )),
+            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+        )
+    };
+
+    // The server skips the key exchange and directly sends its Finished
+    let server_finished_transcript = term! {
+        fn_append_transcript(
+            (@server_hello_transcript),
+            (fn_finished(
+                (fn_server_verify_data_resumption(
+                    ((server, 0)),
+                    (@master_secret),
+                    (@server_hello_transcript)
+                ))
+            ))
+        )
+    };
+
+    let client_verify_data = term! {
+        fn_sign_transcript_resumption(
+            ((server, 0)),
+            (@master_secret),
+            (@server_finished_transcript)
+        )
+    };
+
+    let trace = Trace {
+        prior_traces: vec![initial_handshake],
+        descriptors: vec![AgentDescriptor {
+            name: server,
+            tls_version: TLSVersion::V1_2,
+            server: true,
+            try_reuse: true,
+            ..AgentDescriptor::default()
+        }],
+        steps: vec![
             Step {
-                agent: client,
+                agent: server,
                 action: Action::Input(InputAction {
-                    recipe: term! {
-                        fn_certificate(
-                            ((server, 0))
-                        )
-                    },
+                    recipe: client_hello,
                 }),
             },
-            // Server Key Exchange, Server -> Client
-            // If the KEX fails here, then no ephemeral KEX is used
             Step {
-                agent: client,
+                agent: server,
                 action: Action::Input(InputAction {
-                    recipe: term! {
-                        fn_server_key_exchange(  // check whether the client rejects this if it does not support export
-                            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>)
-                        )
-                    },
+                    recipe: term! { fn_change_cipher_spec },
                 }),
             },
-            // Server Hello Done, Server -> Client
             Step {
-                agent: client,
+                agent: server,
                 action: Action::Input(InputAction {
                     recipe: term! {
-                        fn_server_hello_done
+                        fn_encrypt12_resumption(
+                            (fn_finished((@client_verify_data))),
+                            ((server, 0)),
+                            (@master_secret),
+                            fn_seq_0
+                        )
                     },
                 }),
             },
-            // Client Key Exchange, Client -> Server
+        ],
+    };
+
+    trace
+}
+
+/// The attacker acts as a TLS 1.3 server and completes a handshake with an OpenSSL client. The
+/// server flight is built from terms, only the key share of the client is taken from its
+/// ClientHello.
+pub fn seed_client_attacker13(client: AgentName) -> Trace {
+    _seed_client_attacker13(
+        client,
+        term! { fn_signature_scheme_rsa_pss_rsae_sha256 },
+        certificate13(term! {
+            fn_append_certificate_entry(
+                fn_new_certificate_entries,
+                fn_static_rsa_certificate,
+                fn_cert_extensions_new
+            )
+        }),
+    )
+}
+
+/// Like [`seed_client_attacker13`], but the CertificateVerify announces rsa_pss_rsae_sha384 while
+/// the signature has been computed with rsa_pss_rsae_sha256. The client must reject the signature.
+pub fn seed_signature_scheme_mismatch(client: AgentName) -> Trace {
+    _seed_client_attacker13(
+        client,
+        term! { fn_signature_scheme_rsa_pss_rsae_sha384 },
+        certificate13(term! {
+            fn_append_certificate_entry(
+                fn_new_certificate_entries,
+                fn_static_rsa_certificate,
+                fn_cert_extensions_new
+            )
+        }),
+    )
+}
+
+/// Like [`seed_client_attacker13`], but the attacker sends a TLS 1.3 Certificate with an empty
+/// certificate chain. The client must abort the handshake before the CertificateVerify.
+pub fn seed_empty_certificate13(client: AgentName) -> Trace {
+    _seed_client_attacker13(
+        client,
+        term! { fn_signature_scheme_rsa_pss_rsae_sha256 },
+        certificate13(term! { fn_new_certificate_entries }),
+    )
+}
+
+/// Like [`seed_client_attacker13`], but the attacker sends the certificate as zlib compressed
+/// CompressedCertificate of [RFC 8879](https://datatracker.ietf.org/doc/html/rfc8879). The
+/// declared uncompressed length and the compressed data are independent subterms.
+///
+/// Only clients which offered zlib in the compress_certificate extension accept the message. None
+/// of the supported PUTs implements RFC 8879, therefore the seed is only checked for its encoding
+/// and not executed.
+pub fn seed_compressed_certificate13(client: AgentName) -> Trace {
+    let certificate_body = term! {
+        fn_handshake_body(
+            (fn_certificate13(
+                fn_empty_bytes_vec,
+                (fn_append_certificate_entry(
+                    fn_new_certificate_entries,
+                    fn_static_rsa_certificate,
+                    fn_cert_extensions_new
+                ))
+            ))
+        )
+    };
+
+    _seed_client_attacker13(
+        client,
+        term! { fn_signature_scheme_rsa_pss_rsae_sha256 },
+        term! {
+            fn_compressed_certificate(
+                fn_cert_compression_zlib,
+                (fn_bytes_len((@certificate_body))),
+                (fn_zlib_compress((@certificate_body)))
+            )
+        },
+    )
+}
+
+/// TLS 1.3 Certificate without request context and the chain `certificate_entries`
+fn certificate13(certificate_entries: Term) -> Term {
+    term! {
+        fn_certificate13(
+            fn_empty_bytes_vec,
+            (@certificate_entries)
+        )
+    }
+}
+
+/// `signature_scheme` is the scheme which the CertificateVerify announces. The signature always
+/// uses rsa_pss_rsae_sha256. `certificate` is the message which carries the certificate chain.
+fn _seed_client_attacker13(client: AgentName, signature_scheme: Term, certificate: Term) -> Trace {
+    let client_hello = term! {
+        (client, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ClientHello)))] / Message
+    };
+
+    let client_key_share = term! {
+        fn_get_client_key_share(
+            ((client, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ClientHello)))])
+        )
+    };
+
+    let server_hello = term! {
+          fn_server_hello13(
+            fn_new_random,
+            ((client, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ClientHello)))]), // echo the SessionID
+            fn_cipher_suite13_aes_128_gcm_sha256,
+            fn_named_group_secp384r1,
+            (fn_deterministic_public_key(fn_named_group_secp384r1)),
+            fn_supported_versions13_server_extension
+        )
+    };
+
+    let server_hello_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                fn_new_transcript,
+                (@client_hello) // ClientHello
+            )),
+            (@server_hello) // ServerHello
+        )
+    };
+
+    let encrypted_extensions = term! {
+        fn_encrypted_extensions(fn_server_extensions_new)
+    };
+
+    let encrypted_extensions_transcript = term! {
+        fn_append_transcript(
+            (@server_hello_transcript),
+            (@encrypted_extensions)
+        )
+    };
+
+    let certificate_transcript = term! {
+        fn_append_transcript(
+            (@encrypted_extensions_transcript),
+            (@certificate)
+        )
+    };
+
+    let certificate_verify = term! {
+        fn_certificate_verify(
+            (@signature_scheme),
+            (fn_certificate_verify13_signature((@certificate_transcript)))
+        )
+    };
+
+    let certificate_verify_transcript = term! {
+        fn_append_transcript(
+            (@certificate_transcript),
+            (@certificate_verify)
+        )
+    };
+
+    let server_finished = term! {
+        fn_finished(
+            (fn_server_verify_data(
+                (@certificate_verify_transcript),
+                (@server_hello_transcript),
+                (@client_key_share),
+                fn_no_psk
+            ))
+        )
+    };
+
+    let encrypt = |message: Term, sequence: Term| {
+        term! {
+            fn_encrypt_server_handshake(
+                (@message),
+                (@server_hello_transcript),
+                (@client_key_share),
+                fn_no_psk,
+                (@sequence)
+            )
+        }
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor {
+            name: client,
+            tls_version: TLSVersion::V1_3,
+            server: false,
+            try_reuse: false,
+            // The key schedule of attacking servers only supports secp384r1. Therefore, the client
+            // needs to offer a key share for it.
+            // todo https://gitlab.inria.fr/mammann/tlspuffin/-/issues/45
+            groups: Some("P-384".to_string()),
+            ..AgentDescriptor::default()
+        }],
+        steps: vec![
+            OutputAction::new_step(client),
+            InputAction::new_step(client, server_hello),
+            InputAction::new_step(client, encrypt(encrypted_extensions, term! { fn_seq_0 })),
+            InputAction::new_step(client, encrypt(certificate, term! { fn_seq_1 })),
+            InputAction::new_step(client, encrypt(certificate_verify, term! { fn_seq_2 })),
+            InputAction::new_step(client, encrypt(server_finished, term! { fn_seq_3 })),
+        ],
+    }
+}
+
+/// The attacker impersonates a TLS 1.2 server towards an OpenSSL client. The ServerKeyExchange is
+/// signed with the key of the certificate which the attacker sent before.
+pub fn seed_server_attacker12(client: AgentName) -> Trace {
+    let client_random = term! {
+        (client, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ClientHello)))] / Random
+    };
+
+    let server_hello = term! {
+          fn_server_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            ((client, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ClientHello)))]), // echo the SessionID
+            fn_cipher_suite12,
+            fn_compression,
+            fn_server_extensions_new
+        )
+    };
+
+    let certificate = term! {
+        fn_certificate(
+            (fn_append_certificate(
+                fn_new_certificates,
+                fn_static_rsa_certificate
+            ))
+        )
+    };
+
+    let server_key_exchange = term! {
+        fn_ecdhe_server_key_exchange(
+            fn_named_group_secp384r1,
+            (fn_deterministic_public_key(fn_named_group_secp384r1)),
+            (fn_server_key_exchange_signature(
+                (@client_random),
+                fn_new_random,
+                fn_named_group_secp384r1,
+                (fn_deterministic_public_key(fn_named_group_secp384r1))
+            ))
+        )
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor {
+            name: client,
+            tls_version: TLSVersion::V1_2,
+            server: false,
+            try_reuse: false,
+            ..AgentDescriptor::default()
+        }],
+        steps: vec![
+            OutputAction::new_step(client),
+            InputAction::new_step(client, server_hello),
+            InputAction::new_step(client, certificate),
+            InputAction::new_step(client, server_key_exchange),
+            InputAction::new_step(client, term! { fn_server_hello_done }),
+        ],
+    }
+}
+
+/// Seed in which an attacker answers the ClientHello of a TLS 1.3 capable client with a TLS 1.2
+/// ServerHello. The random of the ServerHello contains the downgrade sentinel, therefore the client
+/// must abort the handshake.
+pub fn seed_downgrade_sentinel(client: AgentName) -> Trace {
+    let server_hello = term! {
+          fn_server_hello(
+            fn_protocol_version12,
+            fn_downgrade_random,
+            ((client, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ClientHello)))]), // echo the SessionID
+            fn_cipher_suite12,
+            fn_compression,
+            fn_server_extensions_new
+        )
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor::new_client(client, TLSVersion::V1_3)],
+        steps: vec![
+            OutputAction::new_step(client),
+            InputAction::new_step(client, server_hello),
+        ],
+    }
+}
+
+pub fn seed_cve_2021_3449(server: AgentName) -> Trace {
+    let (mut trace, client_verify_data) = _seed_client_attacker12(server);
+
+    let renegotiation_client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+                fn_cipher_suite12
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            (fn_client_extensions_append(
+                                fn_client_extensions_new,
+                                fn_secp384r1_support_group_extension
+                            )),
+                            fn_ec_point_formats_extension
+                        )),
+                        fn_signed_certificate_timestamp_extension
+                    )),
+                     // Enable Renegotiation
+                    (fn_renegotiation_info_extension((@client_verify_data)))
+                )),
+                // Add signature cert extension
+                fn_signature_algorithm_cert_extension
+            ))
+        )
+    };
+
+    trace.steps.push(Step {
+        agent: server,
+        action: Action::Input(InputAction {
+            recipe: term! {
+                fn_encrypt12(
+                    (@renegotiation_client_hello),
+                    ((server, 0)),
+                    (fn_decode_ecdh_params(
+                        ((server, 2)/Vec<u8>) // ServerECDHParams
+                    )),
+                    fn_seq_1
+                )
+            },
+        }),
+    });
+
+    /*    trace.stepSignature::push(Step {
+        agent: server,
+        action: Action::Input(InputAction {
+            recipe: term! {
+                fn_encrypt12(
+                    fn_alert_close_notify,
+                    ((server, 0)),
+                    (fn_decode_ecdh_params(
+                        ((server, 2)/Vec<u8>) // ServerECDHParams
+                    )),
+                    fn_seq_1
+                )
+            },
+        }),
+    });*/
+
+    trace
+}
+
+/// Seed which renegotiates a TLS 1.2 session with the server. After the initial handshake of
+/// [`seed_client_attacker12`] the attacker sends an encrypted ClientHello. Its renegotiation_info
+/// extension carries the client verify_data of the initial handshake, therefore the server
+/// accepts the renegotiation, see [RFC 5746](https://datatracker.ietf.org/doc/html/rfc5746).
+pub fn seed_secure_renegotiation(server: AgentName) -> Trace {
+    let (trace, client_verify_data) = _seed_client_attacker12(server);
+    _seed_renegotiation12(server, trace, client_verify_data)
+}
+
+/// Like [`seed_secure_renegotiation`], but the renegotiation_info extension does not carry the
+/// client verify_data of the initial handshake. The server must abort with a handshake_failure
+/// alert.
+pub fn seed_renegotiation_mismatch(server: AgentName) -> Trace {
+    let (trace, _client_verify_data) = _seed_client_attacker12(server);
+    _seed_renegotiation12(server, trace, bytes_term(&[0u8; 12], &SIGNATURE).unwrap())
+}
+
+fn _seed_renegotiation12(
+    server: AgentName,
+    mut trace: Trace,
+    renegotiated_connection: Term,
+) -> Trace {
+    let client_extensions =
+        client_attacker12_extensions_with_renegotiation_info(renegotiated_connection);
+
+    let renegotiation_client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+                fn_cipher_suite12
+            )),
+            fn_compressions,
+            (@client_extensions)
+        )
+    };
+
+    trace.steps.push(Step {
+        agent: server,
+        action: Action::Input(InputAction {
+            recipe: term! {
+                fn_encrypt12(
+                    (@renegotiation_client_hello),
+                    ((server, 0)),
+                    (fn_decode_ecdh_params(
+                        ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>) // ServerECDHParams
+                    )),
+                    fn_seq_1
+                )
+            },
+        }),
+    });
+
+    trace
+}
+
+pub fn seed_heartbleed(client: AgentName, server: AgentName) -> Trace {
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+                fn_cipher_suite12
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        fn_client_extensions_new,
+                        fn_secp384r1_support_group_extension
+                    )),
+                    fn_ec_point_formats_extension
+                )),
+                fn_signed_certificate_timestamp_extension
+            ))
+        )
+    };
+
+    let trace = Trace {
+        prior_traces: vec![],
+        descriptors: vec![
+            AgentDescriptor {
+                name: client,
+                tls_version: TLSVersion::V1_2,
+                server: false,
+                try_reuse: false,
+                ..AgentDescriptor::default()
+            },
+            AgentDescriptor {
+                name: server,
+                tls_version: TLSVersion::V1_2,
+                server: true,
+                try_reuse: false,
+                ..AgentDescriptor::default()
+            },
+        ],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: client_hello,
+                }),
+            },
+            // Send directly after client_hello such that this does not need to be encrypted
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_heartbeat_fake_length(fn_empty_bytes_vec, fn_large_length)
+                    },
+                }),
+            },
+        ],
+    };
+
+    trace
+}
+
+/// Seed which announces heartbeat support and sends a heartbeat request whose declared payload
+/// length exceeds the actual payload (CVE-2014-0160).
+pub fn seed_heartbeat_oversized(server: AgentName) -> Trace {
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+                fn_cipher_suite12
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        fn_client_extensions_new,
+                        fn_secp384r1_support_group_extension
+                    )),
+                    fn_ec_point_formats_extension
+                )),
+                fn_heartbeat_extension
+            ))
+        )
+    };
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor {
+            name: server,
+            tls_version: TLSVersion::V1_2,
+            server: true,
+            try_reuse: false,
+            ..AgentDescriptor::default()
+        }],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: client_hello,
+                }),
+            },
+            // Send directly after client_hello such that this does not need to be encrypted
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_heartbeat_request(fn_empty_bytes_vec, fn_large_length)
+                    },
+                }),
+            },
+        ],
+    }
+}
+
+pub fn seed_freak(client: AgentName, server: AgentName) -> Trace {
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![
+            AgentDescriptor {
+                name: client,
+                tls_version: TLSVersion::V1_2,
+                server: false,
+                try_reuse: false,
+                ..AgentDescriptor::default()
+            },
+            AgentDescriptor {
+                name: server,
+                tls_version: TLSVersion::V1_2,
+                server: true,
+                try_reuse: false,
+                ..AgentDescriptor::default()
+            },
+        ],
+        steps: vec![
+            Step {
+                agent: client,
+                action: Action::Output(OutputAction {}),
+            },
+            // Client Hello, Client -> Server
+            InputAction::new_step(
+                server,
+                term! {
+                    fn_client_hello(
+                        ((client, 0)),
+                        ((client, 0)),
+                        ((client, 0)),
+                        (fn_append_cipher_suite(
+                            (fn_new_cipher_suites()),
+                            fn_weak_export_cipher_suite
+                        )),
+                        ((client, 0)),
+                        ((client, 0))
+                    )
+                },
+            ),
+            // Server Hello, Server -> Client
+            InputAction::new_step(
+                client,
+                term! {
+                        fn_server_hello(
+                            ((server, 0)),
+                            ((server, 0)),
+                            ((server, 0)),
+                            (fn_secure_rsa_cipher_suite12),
+                            ((server, 0)),
+                            ((server, 0))
+                        )
+                },
+            ),
+            // Server Certificate, Server -> Client
+            Step {
+                agent: client,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_certificate(
+                            ((server, 0))
+                        )
+                    },
+                }),
+            },
+            // Server Key Exchange, Server -> Client
+            // If the KEX fails here, then no ephemeral KEX is used
+            Step {
+                agent: client,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_server_key_exchange(  // check whether the client rejects this if it does not support export
+                            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>)
+                        )
+                    },
+                }),
+            },
+            // Server Hello Done, Server -> Client
+            Step {
+                agent: client,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_server_hello_done
+                    },
+                }),
+            },
+            // Client Key Exchange, Client -> Server
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_client_key_exchange(
+                             ((client, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ClientKeyExchange)))]/Vec<u8>)
+                        )
+                    },
+                }),
+            },
+            // Client Change Cipher Spec, Client -> Server
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_change_cipher_spec
+                    },
+                }),
+            },
+        ],
+    }
+}
+
+pub fn seed_session_resumption_dhe(initial_server: AgentName, server: AgentName) -> Trace {
+    let initial_handshake = seed_client_attacker(initial_server);
+
+    let new_ticket_message = term! {
+        fn_decrypt_application(
+            ((initial_server, 4)[Some(TlsMessageType::ApplicationData)]), // Ticket from last session
+            (fn_server_hello_transcript(((initial_server, 0)))),
+            (fn_server_finished_transcript(((initial_server, 0)))),
+            (fn_get_server_key_share(((initial_server, 0)))),
+            fn_no_psk,
+            fn_seq_0 // sequence restarts at 0 because we are decrypting now traffic
+        )
+    };
+
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            (fn_client_extensions_append(
+                                (fn_client_extensions_append(
+                                    fn_client_extensions_new,
+                                    fn_secp384r1_support_group_extension
+                                )),
+                                fn_signature_algorithm_extension
+                            )),
+                            fn_supported_versions13_extension
+                        )),
+                        fn_key_share_deterministic_extension
+                    )),
+                    fn_psk_exchange_mode_dhe_ke_extension
+                )),
+                // https://datatracker.ietf.org/doc/html/rfc8446#section-2.2
+                // must be last in client_hello, and initially empty until filled by fn_fill_binder
+                (fn_preshared_keys_extension_empty_binder(
+                    (@new_ticket_message)
+                ))
+            ))
+        )
+    };
+
+    let psk = term! {
+        fn_derive_psk(
+            (fn_server_hello_transcript(((initial_server, 0)))),
+            (fn_server_finished_transcript(((initial_server, 0)))),
+            (fn_client_finished_transcript(((initial_server, 0)))),
+            (fn_get_server_key_share(((initial_server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
+            (fn_get_ticket_nonce((@new_ticket_message)))
+        )
+    };
+
+    let binder = term! {
+        fn_derive_binder(
+            (@client_hello),
+            (@psk)
+        )
+    };
+
+    let full_client_hello = term! {
+        fn_fill_binder(
+            (@client_hello),
+            (@binder)
+        )
+    };
+
+    let resumption_client_finished = term! {
+        fn_finished(
+            (fn_verify_data(
+                (fn_server_finished_transcript(((server, 0)))),
+                (fn_server_hello_transcript(((server, 0)))),
+                (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
+                (fn_psk((@psk)))
+            ))
+        )
+    };
+
+    let trace = Trace {
+        prior_traces: vec![initial_handshake],
+        descriptors: vec![AgentDescriptor {
+            name: server,
+            tls_version: TLSVersion::V1_3,
+            server: true,
+            try_reuse: false,
+            ..AgentDescriptor::default()
+        }],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        @full_client_hello
+                    },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt_handshake(
+                            (@resumption_client_finished),
+                            (fn_server_hello_transcript(((server, 0)))),
+                            (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
+                            (fn_psk((@psk))),
+                            fn_seq_0  // sequence 0
+                        )
+                    },
+                }),
+            },
+        ],
+    };
+
+    trace
+}
+
+pub fn seed_session_resumption_ke(initial_server: AgentName, server: AgentName) -> Trace {
+    let initial_handshake = seed_client_attacker(initial_server);
+
+    let new_ticket_message = term! {
+        fn_decrypt_application(
+            ((initial_server, 4)[Some(TlsMessageType::ApplicationData)]), // Ticket from last session
+            (fn_server_hello_transcript(((initial_server, 0)))),
+            (fn_server_finished_transcript(((initial_server, 0)))),
+            (fn_get_server_key_share(((initial_server, 0)))),
+            fn_no_psk,
+            fn_seq_0 // sequence restarts at 0 because we are decrypting now traffic
+        )
+    };
+
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            (fn_client_extensions_append(
+                                (fn_client_extensions_append(
+                                    fn_client_extensions_new,
+                                    fn_secp384r1_support_group_extension
+                                )),
+                                fn_signature_algorithm_extension
+                            )),
+                            fn_supported_versions13_extension
+                        )),
+                        fn_key_share_deterministic_extension
+                    )),
+                    fn_psk_exchange_mode_ke_extension
+                )),
+                // https://datatracker.ietf.org/doc/html/rfc8446#section-2.2
+                // must be last in client_hello, and initially empty until filled by fn_fill_binder
+                (fn_preshared_keys_extension_empty_binder(
+                    (@new_ticket_message)
+                ))
+            ))
+        )
+    };
+
+    let psk = term! {
+        fn_derive_psk(
+            (fn_server_hello_transcript(((initial_server, 0)))),
+            (fn_server_finished_transcript(((initial_server, 0)))),
+            (fn_client_finished_transcript(((initial_server, 0)))),
+            (fn_get_server_key_share(((initial_server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
+            (fn_get_ticket_nonce((@new_ticket_message)))
+        )
+    };
+
+    let binder = term! {
+        fn_derive_binder(
+            (@client_hello),
+            (@psk)
+        )
+    };
+
+    let full_client_hello = term! {
+        fn_fill_binder(
+            (@client_hello),
+            (@binder)
+        )
+    };
+
+    let resumption_client_finished = term! {
+        fn_finished(
+            (fn_verify_data(
+                (fn_server_finished_transcript(((server, 0)))),
+                (fn_server_hello_transcript(((server, 0)))),
+                fn_no_key_share,
+                (fn_psk((@psk)))
+            ))
+        )
+    };
+
+    let trace = Trace {
+        prior_traces: vec![initial_handshake],
+        descriptors: vec![AgentDescriptor {
+            name: server,
+            tls_version: TLSVersion::V1_3,
+            server: true,
+            try_reuse: false,
+            ..AgentDescriptor::default()
+        }],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        @full_client_hello
+                    },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt_handshake(
+                            (@resumption_client_finished),
+                            (fn_server_hello_transcript(((server, 0)))),
+                            fn_no_key_share,
+                            (fn_psk((@psk))),
+                            fn_seq_0  // sequence 0
+                        )
+                    },
+                }),
+            },
+        ],
+    };
+
+    trace
+}
+
+/// Seed which contains the whole transcript in the tree. This is rather huge >300 symbols
+pub fn seed_client_attacker_full(server: AgentName) -> (Trace, Term, Term, Term) {
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            fn_client_extensions_new,
+                            fn_secp384r1_support_group_extension
+                        )),
+                        fn_signature_algorithm_extension
+                    )),
+                    fn_key_share_deterministic_extension
+                )),
+                fn_supported_versions13_extension
+            ))
+        )
+    };
+
+    let server_hello_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                fn_new_transcript,
+                (@client_hello) // ClientHello
+            )),
+            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+        )
+    };
+
+    // ((0, 1)) could be a CCS the server sends one
+
+    let encrypted_extensions = term! {
+        fn_decrypt_handshake(
+            ((server, 0)[Some(TlsMessageType::ApplicationData)]), // Encrypted Extensions
+            (@server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
+            fn_no_psk,
+            fn_seq_0  // sequence 0
+        )
+    };
+
+    let encrypted_extension_transcript = term! {
+        fn_append_transcript(
+            (@server_hello_transcript),
+            (@encrypted_extensions) // plaintext Encrypted Extensions
+        )
+    };
+
+    let server_certificate = term! {
+        fn_decrypt_handshake(
+            ((server, 1)[Some(TlsMessageType::ApplicationData)]),// Server Certificate
+            (@server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)))),
+            fn_no_psk,
+            fn_seq_1 // sequence 1
+        )
+    };
+
+    let server_certificate_transcript = term! {
+        fn_append_transcript(
+            (@encrypted_extension_transcript),
+            (@server_certificate) // plaintext Server Certificate
+        )
+    };
+
+    let server_certificate_verify = term! {
+        fn_decrypt_handshake(
+            ((server, 2)[Some(TlsMessageType::ApplicationData)]), // Server Certificate Verify
+            (@server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)))),
+            fn_no_psk,
+            fn_seq_2 // sequence 2
+        )
+    };
+
+    let server_certificate_verify_transcript = term! {
+        fn_append_transcript(
+            (@server_certificate_transcript),
+            (@server_certificate_verify) // plaintext Server Certificate Verify
+        )
+    };
+
+    let server_finished = term! {
+        fn_decrypt_handshake(
+            ((server, 3)[Some(TlsMessageType::ApplicationData)]), // Server Handshake Finished
+            (@server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)))),
+            fn_no_psk,
+            fn_seq_3 // sequence 3
+        )
+    };
+
+    let server_finished_transcript = term! {
+        fn_append_transcript(
+            (@server_certificate_verify_transcript),
+            (@server_finished) // plaintext Server Handshake Finished
+        )
+    };
+
+    let client_finished = term! {
+        fn_finished(
+            (fn_verify_data(
+                (@server_finished_transcript),
+                (@server_hello_transcript),
+                (fn_get_server_key_share(((server, 0)))),
+                fn_no_psk
+            ))
+        )
+    };
+
+    let client_finished_transcript = term! {
+        fn_append_transcript(
+            (@server_finished_transcript),
+            (@client_finished)
+        )
+    };
+
+    let trace = Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor {
+            name: server,
+            tls_version: TLSVersion::V1_3,
+            server: true,
+            try_reuse: false,
+            ..AgentDescriptor::default()
+        }],
+        steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        @client_hello
+                    },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Output(OutputAction {}),
+            },
             Step {
                 agent: server,
                 action: Action::Input(InputAction {
                     recipe: term! {
-                        fn_client_key_exchange(
-                             ((client, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ClientKeyExchange)))]/Vec<u8>)
+                        fn_encrypt_handshake(
+                            (@client_finished),
+                            (@server_hello_transcript),
+                            (fn_get_server_key_share(((server, 0)))),
+                            fn_no_psk,
+                            fn_seq_0  // sequence 0
                         )
                     },
                 }),
             },
-            // Client Change Cipher Spec, Client -> Server
             Step {
                 agent: server,
-                action: Action::Input(InputAction {
-                    recipe: term! {
-                        fn_change_cipher_spec
-                    },
-                }),
+                action: Action::Output(OutputAction {}),
             },
         ],
-    }
+    };
+
+    (
+        trace,
+        server_hello_transcript,
+        server_finished_transcript,
+        client_finished_transcript,
+    )
 }
 
-pub fn seed_session_resumption_dhe(initial_server: AgentName, server: AgentName) -> Trace {
-    let initial_handshake = seed_client_attacker(initial_server);
+/// Seed which contains the whole transcript in the tree. This is rather huge 10k symbols. It grows
+/// exponentially.
+pub fn seed_session_resumption_dhe_full(initial_server: AgentName, server: AgentName) -> Trace {
+    let (
+        initial_handshake,
+        server_hello_transcript,
+        server_finished_transcript,
+        client_finished_transcript,
+    ) = seed_client_attacker_full(initial_server);
 
     let new_ticket_message = term! {
         fn_decrypt_application(
-            ((initial_server, 4)[Some(TlsMessageType::ApplicationData)]), // Ticket from last session
-            (fn_server_hello_transcript(((initial_server, 0)))),
-            (fn_server_finished_transcript(((initial_server, 0)))),
+            ((initial_server, 4)[Some(TlsMessageType::ApplicationData)]), // Ticket?
+            (@server_hello_transcript),
+            (@server_finished_transcript),
             (fn_get_server_key_share(((initial_server, 0)))),
             fn_no_psk,
             fn_seq_0 // sequence restarts at 0 because we are decrypting now traffic
@@ -972,11 +2745,11 @@ pub fn seed_session_resumption_dhe(initial_server: AgentName, server: AgentName)
 
     let psk = term! {
         fn_derive_psk(
-            (fn_server_hello_transcript(((initial_server, 0)))),
-            (fn_server_finished_transcript(((initial_server, 0)))),
-            (fn_client_finished_transcript(((initial_server, 0)))),
-            (fn_get_server_key_share(((initial_server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
-            (fn_get_ticket_nonce((@new_ticket_message)))
+                (@server_hello_transcript),
+                (@server_finished_transcript),
+                (@client_finished_transcript),
+                (fn_get_server_key_share(((initial_server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
+                (fn_get_ticket_nonce((@new_ticket_message)))
         )
     };
 
@@ -994,11 +2767,55 @@ pub fn seed_session_resumption_dhe(initial_server: AgentName, server: AgentName)
         )
     };
 
+    let resumption_server_hello_transcript = term! {
+        fn_append_transcript(
+            (fn_append_transcript(
+                fn_new_transcript,
+                (@full_client_hello) // ClientHello
+            )),
+            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
+        )
+    };
+
+    let resumption_encrypted_extensions = term! {
+        fn_decrypt_handshake(
+            ((server, 0)[Some(TlsMessageType::ApplicationData)]), // Encrypted Extensions
+            (@resumption_server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))), //
+            (fn_psk((@psk))),
+            fn_seq_0  // sequence 0
+        )
+    };
+
+    let resumption_encrypted_extension_transcript = term! {
+        fn_append_transcript(
+            (@resumption_server_hello_transcript),
+            (@resumption_encrypted_extensions) // plaintext Encrypted Extensions
+        )
+    };
+
+    let resumption_server_finished = term! {
+        fn_decrypt_handshake(
+            ((server, 1)[Some(TlsMessageType::ApplicationData)]), // Server Handshake Finished
+            (@resumption_server_hello_transcript),
+            (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))), //
+            (fn_psk((@psk))),
+            fn_seq_1 // sequence 1
+        )
+    };
+
+    let resumption_server_finished_transcript = term! {
+        fn_append_transcript(
+            (@resumption_encrypted_extension_transcript),
+            (@resumption_server_finished) // plaintext Server Handshake Finished
+        )
+    };
+
     let resumption_client_finished = term! {
         fn_finished(
             (fn_verify_data(
-                (fn_server_finished_transcript(((server, 0)))),
-                (fn_server_hello_transcript(((server, 0)))),
+                (@resumption_server_finished_transcript),
+                (@resumption_server_hello_transcript),
                 (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
                 (fn_psk((@psk)))
             ))
@@ -1012,6 +2829,7 @@ pub fn seed_session_resumption_dhe(initial_server: AgentName, server: AgentName)
             tls_version: TLSVersion::V1_3,
             server: true,
             try_reuse: false,
+            ..AgentDescriptor::default()
         }],
         steps: vec![
             Step {
@@ -1028,7 +2846,7 @@ pub fn seed_session_resumption_dhe(initial_server: AgentName, server: AgentName)
                     recipe: term! {
                         fn_encrypt_handshake(
                             (@resumption_client_finished),
-                            (fn_server_hello_transcript(((server, 0)))),
+                            (@resumption_server_hello_transcript),
                             (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
                             (fn_psk((@psk))),
                             fn_seq_0  // sequence 0
@@ -1042,8 +2860,18 @@ pub fn seed_session_resumption_dhe(initial_server: AgentName, server: AgentName)
     trace
 }
 
-pub fn seed_session_resumption_ke(initial_server: AgentName, server: AgentName) -> Trace {
-    let initial_handshake = seed_client_attacker(initial_server);
+/// Maximum amount of early data which the servers of [`seed_zero_rtt`] accept
+pub const ZERO_RTT_MAX_EARLY_DATA: u32 = 16384;
+
+/// Seed which resumes a session and sends 0-RTT data before the handshake completes. The
+/// early data is followed by the EndOfEarlyData message which is still encrypted with the
+/// client_early_traffic_secret.
+pub fn seed_zero_rtt(initial_server: AgentName, server: AgentName) -> Trace {
+    let mut initial_handshake = seed_client_attacker(initial_server);
+    for descriptor in &mut initial_handshake.descriptors {
+        // The ticket only allows early data if the initial server accepts it
+        descriptor.max_early_data = ZERO_RTT_MAX_EARLY_DATA;
+    }
 
     let new_ticket_message = term! {
         fn_decrypt_application(
@@ -1072,16 +2900,19 @@ pub fn seed_session_resumption_ke(initial_server: AgentName, server: AgentName)
                         (fn_client_extensions_append(
                             (fn_client_extensions_append(
                                 (fn_client_extensions_append(
-                                    fn_client_extensions_new,
-                                    fn_secp384r1_support_group_extension
+                                    (fn_client_extensions_append(
+                                        fn_client_extensions_new,
+                                        fn_secp384r1_support_group_extension
+                                    )),
+                                    fn_signature_algorithm_extension
                                 )),
-                                fn_signature_algorithm_extension
+                                fn_supported_versions13_extension
                             )),
-                            fn_supported_versions13_extension
+                            fn_key_share_deterministic_extension
                         )),
-                        fn_key_share_deterministic_extension
+                        fn_psk_exchange_mode_dhe_ke_extension
                     )),
-                    fn_psk_exchange_mode_ke_extension
+                    fn_early_data_extension
                 )),
                 // https://datatracker.ietf.org/doc/html/rfc8446#section-2.2
                 // must be last in client_hello, and initially empty until filled by fn_fill_binder
@@ -1102,26 +2933,33 @@ pub fn seed_session_resumption_ke(initial_server: AgentName, server: AgentName)
         )
     };
 
-    let binder = term! {
-        fn_derive_binder(
+    let full_client_hello = term! {
+        fn_fill_binder(
             (@client_hello),
-            (@psk)
+            (fn_derive_binder(
+                (@client_hello),
+                (@psk)
+            ))
         )
     };
 
-    let full_client_hello = term! {
-        fn_fill_binder(
-            (@client_hello),
-            (@binder)
+    let client_hello_transcript = term! {
+        fn_append_transcript(
+            fn_new_transcript,
+            (@full_client_hello)
         )
     };
 
     let resumption_client_finished = term! {
         fn_finished(
             (fn_verify_data(
-                (fn_server_finished_transcript(((server, 0)))),
+                // The EndOfEarlyData message is part of the transcript
+                (fn_append_transcript(
+                    (fn_server_finished_transcript(((server, 0)))),
+                    fn_end_of_early_data
+                )),
                 (fn_server_hello_transcript(((server, 0)))),
-                fn_no_key_share,
+                (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
                 (fn_psk((@psk)))
             ))
         )
@@ -1134,6 +2972,8 @@ pub fn seed_session_resumption_ke(initial_server: AgentName, server: AgentName)
             tls_version: TLSVersion::V1_3,
             server: true,
             try_reuse: false,
+            max_early_data: ZERO_RTT_MAX_EARLY_DATA,
+            ..AgentDescriptor::default()
         }],
         steps: vec![
             Step {
@@ -1144,6 +2984,32 @@ pub fn seed_session_resumption_ke(initial_server: AgentName, server: AgentName)
                     },
                 }),
             },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt_early_data(
+                            (fn_application_data(fn_early_data_bytes_vec)),
+                            (@client_hello_transcript),
+                            (@psk),
+                            fn_seq_0  // sequence 0
+                        )
+                    },
+                }),
+            },
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_encrypt_early_data(
+                            fn_end_of_early_data,
+                            (@client_hello_transcript),
+                            (@psk),
+                            fn_seq_1  // sequence 1
+                        )
+                    },
+                }),
+            },
             Step {
                 agent: server,
                 action: Action::Input(InputAction {
@@ -1151,7 +3017,7 @@ pub fn seed_session_resumption_ke(initial_server: AgentName, server: AgentName)
                         fn_encrypt_handshake(
                             (@resumption_client_finished),
                             (fn_server_hello_transcript(((server, 0)))),
-                            fn_no_key_share,
+                            (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
                             (fn_psk((@psk))),
                             fn_seq_0  // sequence 0
                         )
@@ -1164,8 +3030,9 @@ pub fn seed_session_resumption_ke(initial_server: AgentName, server: AgentName)
     trace
 }
 
-/// Seed which contains the whole transcript in the tree. This is rather huge >300 symbols
-pub fn seed_client_attacker_full(server: AgentName) -> (Trace, Term, Term, Term) {
+/// Seed in which the client offers `h2` and `http/1.1` through ALPN. The server supports both
+/// protocols and selects one of them.
+pub fn seed_alpn_negotiation(server: AgentName) -> Trace {
     let client_hello = term! {
           fn_client_hello(
             fn_protocol_version12,
@@ -1173,7 +3040,8 @@ pub fn seed_client_attacker_full(server: AgentName) -> (Trace, Term, Term, Term)
             fn_new_session_id,
             (fn_append_cipher_suite(
                 (fn_new_cipher_suites()),
-                fn_cipher_suite13_aes_128_gcm_sha256
+                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+                fn_cipher_suite12
             )),
             fn_compressions,
             (fn_client_extensions_append(
@@ -1183,184 +3051,199 @@ pub fn seed_client_attacker_full(server: AgentName) -> (Trace, Term, Term, Term)
                             fn_client_extensions_new,
                             fn_secp384r1_support_group_extension
                         )),
-                        fn_signature_algorithm_extension
+                        fn_ec_point_formats_extension
                     )),
-                    fn_key_share_deterministic_extension
+                    fn_signature_algorithm_extension
                 )),
-                fn_supported_versions13_extension
+                (fn_alpn_extension(
+                    (fn_append_vec(
+                        (fn_append_vec(
+                            fn_empty_vec_of_vec,
+                            fn_alpn_http2
+                        )),
+                        fn_alpn_http11
+                    ))
+                ))
             ))
         )
     };
 
-    let server_hello_transcript = term! {
-        fn_append_transcript(
-            (fn_append_transcript(
-                fn_new_transcript,
-                (@client_hello) // ClientHello
-            )),
-            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
-        )
-    };
-
-    // ((0, 1)) could be a CCS the server sends one
-
-    let encrypted_extensions = term! {
-        fn_decrypt_handshake(
-            ((server, 0)[Some(TlsMessageType::ApplicationData)]), // Encrypted Extensions
-            (@server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
-            fn_no_psk,
-            fn_seq_0  // sequence 0
-        )
-    };
-
-    let encrypted_extension_transcript = term! {
-        fn_append_transcript(
-            (@server_hello_transcript),
-            (@encrypted_extensions) // plaintext Encrypted Extensions
-        )
-    };
-
-    let server_certificate = term! {
-        fn_decrypt_handshake(
-            ((server, 1)[Some(TlsMessageType::ApplicationData)]),// Server Certificate
-            (@server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)))),
-            fn_no_psk,
-            fn_seq_1 // sequence 1
-        )
-    };
-
-    let server_certificate_transcript = term! {
-        fn_append_transcript(
-            (@encrypted_extension_transcript),
-            (@server_certificate) // plaintext Server Certificate
-        )
-    };
-
-    let server_certificate_verify = term! {
-        fn_decrypt_handshake(
-            ((server, 2)[Some(TlsMessageType::ApplicationData)]), // Server Certificate Verify
-            (@server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)))),
-            fn_no_psk,
-            fn_seq_2 // sequence 2
-        )
-    };
-
-    let server_certificate_verify_transcript = term! {
-        fn_append_transcript(
-            (@server_certificate_transcript),
-            (@server_certificate_verify) // plaintext Server Certificate Verify
-        )
-    };
-
-    let server_finished = term! {
-        fn_decrypt_handshake(
-            ((server, 3)[Some(TlsMessageType::ApplicationData)]), // Server Handshake Finished
-            (@server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)))),
-            fn_no_psk,
-            fn_seq_3 // sequence 3
-        )
-    };
-
-    let server_finished_transcript = term! {
-        fn_append_transcript(
-            (@server_certificate_verify_transcript),
-            (@server_finished) // plaintext Server Handshake Finished
-        )
-    };
-
-    let client_finished = term! {
-        fn_finished(
-            (fn_verify_data(
-                (@server_finished_transcript),
-                (@server_hello_transcript),
-                (fn_get_server_key_share(((server, 0)))),
-                fn_no_psk
-            ))
-        )
-    };
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor {
+            name: server,
+            tls_version: TLSVersion::V1_2,
+            server: true,
+            try_reuse: false,
+            alpn_protocols: Some(vec![b"h2".to_vec(), b"http/1.1".to_vec()]),
+            ..AgentDescriptor::default()
+        }],
+        steps: vec![Step {
+            agent: server,
+            action: Action::Input(InputAction {
+                recipe: client_hello,
+            }),
+        }],
+    }
+}
 
-    let client_finished_transcript = term! {
-        fn_append_transcript(
-            (@server_finished_transcript),
-            (@client_finished)
+/// Client which limits the records of the server to 512 bytes through the max_fragment_length
+/// and record_size_limit extensions
+pub fn seed_max_fragment_length12(server: AgentName) -> Trace {
+    let client_hello = term! {
+          fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                (fn_new_cipher_suites()),
+                // force TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256
+                fn_cipher_suite12
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        (fn_client_extensions_append(
+                            (fn_client_extensions_append(
+                                fn_client_extensions_new,
+                                fn_secp384r1_support_group_extension
+                            )),
+                            fn_ec_point_formats_extension
+                        )),
+                        fn_signature_algorithm_extension
+                    )),
+                    (fn_max_fragment_length_extension(fn_max_fragment_length_512))
+                )),
+                (fn_record_size_limit_extension(fn_record_size_limit_512))
+            ))
         )
     };
 
-    let trace = Trace {
+    Trace {
         prior_traces: vec![],
         descriptors: vec![AgentDescriptor {
             name: server,
-            tls_version: TLSVersion::V1_3,
+            tls_version: TLSVersion::V1_2,
             server: true,
             try_reuse: false,
+            ..AgentDescriptor::default()
         }],
         steps: vec![
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: client_hello,
+                }),
+            },
+            OutputAction::new_step(server),
+        ],
+    }
+}
+
+fn default_external_psk() -> Option<ExternalPsk> {
+    Some(ExternalPsk {
+        identity: EXTERNAL_PSK_IDENTITY.to_vec(),
+        key: EXTERNAL_PSK.to_vec(),
+    })
+}
+
+/// TLS 1.3 handshake which is authenticated through an external PSK instead of a certificate
+pub fn seed_external_psk(client: AgentName, server: AgentName) -> Trace {
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![
+            AgentDescriptor {
+                name: client,
+                tls_version: TLSVersion::V1_3,
+                server: false,
+                try_reuse: false,
+                external_psk: default_external_psk(),
+                ..AgentDescriptor::default()
+            },
+            AgentDescriptor {
+                name: server,
+                tls_version: TLSVersion::V1_3,
+                server: true,
+                try_reuse: false,
+                external_psk: default_external_psk(),
+                ..AgentDescriptor::default()
+            },
+        ],
+        steps: vec![
+            OutputAction::new_step(client),
+            // Client Hello Client -> Server
             Step {
                 agent: server,
                 action: Action::Input(InputAction {
                     recipe: term! {
-                        @client_hello
+                        fn_client_hello(
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0))
+                        )
                     },
                 }),
             },
+            // Server Hello Server -> Client
             Step {
-                agent: server,
-                action: Action::Output(OutputAction {}),
+                agent: client,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_server_hello(
+                            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]/ProtocolVersion),
+                            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]/Random),
+                            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]/SessionID),
+                            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]/CipherSuite),
+                            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]/Compression),
+                            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]/Vec<ServerExtension>)
+                        )
+                    },
+                }),
             },
+            // Encrypted Extensions Server -> Client
             Step {
-                agent: server,
+                agent: client,
                 action: Action::Input(InputAction {
                     recipe: term! {
-                        fn_encrypt_handshake(
-                            (@client_finished),
-                            (@server_hello_transcript),
-                            (fn_get_server_key_share(((server, 0)))),
-                            fn_no_psk,
-                            fn_seq_0  // sequence 0
+                        fn_application_data(
+                            ((server, 0)[Some(TlsMessageType::ApplicationData)]/Vec<u8>)
+                        )
+                    },
+                }),
+            },
+            // No Certificate and CertificateVerify, Finish Server -> Client
+            Step {
+                agent: client,
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_application_data(
+                            ((server, 1)[Some(TlsMessageType::ApplicationData)]/Vec<u8>)
                         )
                     },
                 }),
             },
+            // Finished Client -> Server
             Step {
                 agent: server,
-                action: Action::Output(OutputAction {}),
+                action: Action::Input(InputAction {
+                    recipe: term! {
+                        fn_application_data(
+                            ((client, 0)[Some(TlsMessageType::ApplicationData)]/Vec<u8>)
+                        )
+                    },
+                }),
             },
         ],
-    };
-
-    (
-        trace,
-        server_hello_transcript,
-        server_finished_transcript,
-        client_finished_transcript,
-    )
+    }
 }
 
-/// Seed which contains the whole transcript in the tree. This is rather huge 10k symbols. It grows
-/// exponentially.
-pub fn seed_session_resumption_dhe_full(initial_server: AgentName, server: AgentName) -> Trace {
-    let (
-        initial_handshake,
-        server_hello_transcript,
-        server_finished_transcript,
-        client_finished_transcript,
-    ) = seed_client_attacker_full(initial_server);
-
-    let new_ticket_message = term! {
-        fn_decrypt_application(
-            ((initial_server, 4)[Some(TlsMessageType::ApplicationData)]), // Ticket?
-            (@server_hello_transcript),
-            (@server_finished_transcript),
-            (fn_get_server_key_share(((initial_server, 0)))),
-            fn_no_psk,
-            fn_seq_0 // sequence restarts at 0 because we are decrypting now traffic
-        )
-    };
-
+/// Attacking client which authenticates through an external PSK. The PSK and its identity are
+/// subterms of the recipes, therefore mutations can bind them to different values.
+pub fn seed_client_attacker_external_psk(server: AgentName) -> Trace {
     let client_hello = term! {
           fn_client_hello(
             fn_protocol_version12,
@@ -1386,111 +3269,55 @@ pub fn seed_session_resumption_dhe_full(initial_server: AgentName, server: Agent
                         )),
                         fn_key_share_deterministic_extension
                     )),
-                    fn_psk_exchange_mode_dhe_ke_extension
+                    (fn_psk_key_exchange_modes_extension(
+                        (fn_append_psk_key_exchange_mode(
+                            fn_new_psk_key_exchange_modes,
+                            fn_psk_dhe_ke_mode
+                        ))
+                    ))
                 )),
-                // https://datatracker.ietf.org/doc/html/rfc8446#section-2.2
                 // must be last in client_hello, and initially empty until filled by fn_fill_binder
-                (fn_preshared_keys_extension_empty_binder(
-                    (@new_ticket_message)
-                ))
+                (fn_preshared_keys_extension_external(fn_external_psk_identity))
             ))
         )
     };
 
-    let psk = term! {
-        fn_derive_psk(
-                (@server_hello_transcript),
-                (@server_finished_transcript),
-                (@client_finished_transcript),
-                (fn_get_server_key_share(((initial_server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
-                (fn_get_ticket_nonce((@new_ticket_message)))
-        )
-    };
-
-    let binder = term! {
-        fn_derive_binder(
-            (@client_hello),
-            (@psk)
-        )
-    };
-
     let full_client_hello = term! {
         fn_fill_binder(
             (@client_hello),
-            (@binder)
-        )
-    };
-
-    let resumption_server_hello_transcript = term! {
-        fn_append_transcript(
-            (fn_append_transcript(
-                fn_new_transcript,
-                (@full_client_hello) // ClientHello
-            )),
-            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]) // plaintext ServerHello
-        )
-    };
-
-    let resumption_encrypted_extensions = term! {
-        fn_decrypt_handshake(
-            ((server, 0)[Some(TlsMessageType::ApplicationData)]), // Encrypted Extensions
-            (@resumption_server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))), //
-            (fn_psk((@psk))),
-            fn_seq_0  // sequence 0
-        )
-    };
-
-    let resumption_encrypted_extension_transcript = term! {
-        fn_append_transcript(
-            (@resumption_server_hello_transcript),
-            (@resumption_encrypted_extensions) // plaintext Encrypted Extensions
-        )
-    };
-
-    let resumption_server_finished = term! {
-        fn_decrypt_handshake(
-            ((server, 1)[Some(TlsMessageType::ApplicationData)]), // Server Handshake Finished
-            (@resumption_server_hello_transcript),
-            (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))), //
-            (fn_psk((@psk))),
-            fn_seq_1 // sequence 1
-        )
-    };
-
-    let resumption_server_finished_transcript = term! {
-        fn_append_transcript(
-            (@resumption_encrypted_extension_transcript),
-            (@resumption_server_finished) // plaintext Server Handshake Finished
+            (fn_derive_external_binder(
+                (@client_hello),
+                fn_external_psk
+            ))
         )
     };
 
-    let resumption_client_finished = term! {
+    let client_finished = term! {
         fn_finished(
             (fn_verify_data(
-                (@resumption_server_finished_transcript),
-                (@resumption_server_hello_transcript),
+                (fn_server_finished_transcript(((server, 0)))),
+                (fn_server_hello_transcript(((server, 0)))),
                 (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
-                (fn_psk((@psk)))
+                (fn_psk(fn_external_psk))
             ))
         )
     };
 
-    let trace = Trace {
-        prior_traces: vec![initial_handshake],
+    Trace {
+        prior_traces: vec![],
         descriptors: vec![AgentDescriptor {
             name: server,
             tls_version: TLSVersion::V1_3,
             server: true,
             try_reuse: false,
+            external_psk: default_external_psk(),
+            ..AgentDescriptor::default()
         }],
         steps: vec![
             Step {
                 agent: server,
                 action: Action::Input(InputAction {
-                    recipe: term! {
-                        @full_client_hello
-                    },
+                    recipe: full_client_hello,
                 }),
             },
             Step {
@@ -1498,22 +3325,20 @@ pub fn seed_session_resumption_dhe_full(initial_server: AgentName, server: Agent
                 action: Action::Input(InputAction {
                     recipe: term! {
                         fn_encrypt_handshake(
-                            (@resumption_client_finished),
-                            (@resumption_server_hello_transcript),
+                            (@client_finished),
+                            (fn_server_hello_transcript(((server, 0)))),
                             (fn_get_server_key_share(((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))]))),
-                            (fn_psk((@psk))),
+                            (fn_psk(fn_external_psk)),
                             fn_seq_0  // sequence 0
                         )
                     },
                 }),
             },
         ],
-    };
-
-    trace
+    }
 }
 
-pub fn create_corpus() -> [(Trace, &'static str); 8] {
+pub fn create_corpus() -> [(Trace, &'static str); 19] {
     let agent_a = AgentName::first();
     let agent_b = agent_a.next();
 
@@ -1528,8 +3353,26 @@ pub fn create_corpus() -> [(Trace, &'static str); 8] {
             "seed_successful_with_tickets",
         ),
         (seed_successful12(agent_a, agent_b), "seed_successful12"),
+        (
+            seed_hello_retry_request(agent_a, agent_b),
+            "seed_hello_retry_request",
+        ),
+        (
+            seed_stateless_retry(agent_a, agent_b),
+            "seed_stateless_retry",
+        ),
         (seed_client_attacker(agent_a), "seed_client_attacker"),
+        (
+            seed_client_attacker_ech(agent_a),
+            "seed_client_attacker_ech",
+        ),
         (seed_client_attacker12(agent_a), "seed_client_attacker12"),
+        (
+            seed_client_attacker12_grease(agent_a),
+            "seed_client_attacker12_grease",
+        ),
+        (seed_resumption12(agent_a, agent_b), "seed_resumption12"),
+        (seed_client_attacker13(agent_a), "seed_client_attacker13"),
         (
             seed_session_resumption_dhe(agent_a, agent_b),
             "seed_session_resumption_dhe",
@@ -1538,5 +3381,16 @@ pub fn create_corpus() -> [(Trace, &'static str); 8] {
             seed_session_resumption_ke(agent_a, agent_b),
             "seed_session_resumption_ke",
         ),
+        (seed_zero_rtt(agent_a, agent_b), "seed_zero_rtt"),
+        (seed_alpn_negotiation(agent_a), "seed_alpn_negotiation"),
+        (
+            seed_fragmented_client_hello(agent_a, agent_b),
+            "seed_fragmented_client_hello",
+        ),
+        (seed_server_attacker12(agent_a), "seed_server_attacker12"),
+        (
+            seed_application_data(agent_a, agent_b),
+            "seed_application_data",
+        ),
     ]
 }
@@ -0,0 +1,60 @@
+//! Splits the initial corpus between the clients of the launcher. Without sharding every client
+//! imports every seed of the corpus directory and the clients spend their first stages
+//! re-discovering the same inputs. With sharding, client `i` initially only owns the seeds of
+//! shard `i`. Inputs which are found later are still shared between all clients through the
+//! event manager, and [`DedupFeedback`](crate::fuzzer::feedbacks::DedupFeedback) drops the ones
+//! whose [`Term::canonical_hash`](crate::term::Term::canonical_hash) is already known to a client.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The part of the corpus directory which a single client imports.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CorpusShard {
+    /// Index of this shard, in `0..count`
+    pub index: usize,
+    /// Total amount of shards
+    pub count: usize,
+    /// How many neighbouring shards are additionally imported. With an overlap of 0 the
+    /// shards are disjoint, with an overlap of `count - 1` every client imports every seed.
+    pub overlap: usize,
+}
+
+impl CorpusShard {
+    /// Creates the shard for the client with id `client`. Client ids larger than `count` wrap
+    /// around.
+    pub fn new(client: usize, count: usize, overlap: usize) -> Self {
+        let count = count.max(1);
+        CorpusShard {
+            index: client % count,
+            count,
+            overlap: overlap.min(count - 1),
+        }
+    }
+
+    /// Whether the `n`-th seed of the corpus directory belongs to this shard
+    pub fn owns(&self, n: usize) -> bool {
+        let owner = n % self.count;
+        (0..=self.overlap).any(|offset| (self.index + offset) % self.count == owner)
+    }
+
+    /// Lists the seed files of `corpus_dir` which belong to this shard. The files are ordered
+    /// by their name, such that all clients agree on the numbering of the seeds.
+    pub fn seed_files(&self, corpus_dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+        let mut files = Vec::new();
+        for entry in corpus_dir.read_dir()? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        Ok(files
+            .into_iter()
+            .enumerate()
+            .filter(|(n, _)| self.owns(*n))
+            .map(|(_, path)| path)
+            .collect())
+    }
+}
@@ -1,12 +1,14 @@
 use libafl::bolts::rands::Rand;
+use libafl::bolts::tuples::NamedTuple;
 use libafl::corpus::Corpus;
 use libafl::inputs::Input;
 use libafl::mutators::{
     ComposedByMutations, MutationResult, Mutator, MutatorsTuple, ScheduledMutator,
 };
 use libafl::stages::{MutationalStage, Stage};
-use libafl::state::{HasClientPerfStats, HasCorpus, HasRand};
+use libafl::state::{HasClientPerfStats, HasCorpus, HasMetadata, HasRand};
 use libafl::{Error, Evaluator};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Debug;
 use std::marker::PhantomData;
@@ -25,7 +27,7 @@ where
     mutator: M,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(C, E, EM, I, R, S, Z)>,
-    max_iterations_per_stage: u64,
+    iterations: AdaptiveIterations,
 }
 
 impl<C, E, EM, I, M, R, S, Z> MutationalStage<C, E, EM, I, M, S, Z>
@@ -50,9 +52,9 @@ where
         &mut self.mutator
     }
 
-    /// Gets the number of iterations as a random number
+    /// Gets the number of iterations as a random number below the current budget
     fn iterations(&self, state: &mut S) -> usize {
-        1 + state.rand_mut().below(self.max_iterations_per_stage) as usize
+        1 + state.rand_mut().below(self.iterations.budget()) as usize
     }
 }
 
@@ -62,7 +64,7 @@ where
     M: Mutator<I, S>,
     I: Input,
     R: Rand,
-    S: HasClientPerfStats + HasCorpus<C, I> + HasRand<R>,
+    S: HasClientPerfStats + HasCorpus<C, I> + HasRand<R> + HasMetadata,
     Z: Evaluator<E, EM, I, S>,
 {
     #[inline]
@@ -75,7 +77,17 @@ where
         manager: &mut EM,
         corpus_idx: usize,
     ) -> Result<(), Error> {
+        // Inputs are only added to the corpus if they are interesting, e.g. cover new edges
+        let corpus_size = state.corpus().count();
+        // The mutator logs the entry it mutates, see [`MutationLog::parent`]
+        state.add_metadata(MutationLog {
+            parent: Some(corpus_idx),
+            entries: vec![],
+        });
         let ret = self.perform_mutational(fuzzer, executor, state, manager, corpus_idx);
+        // Inputs which are evaluated after this stage have not been produced by its mutations
+        state.add_metadata(MutationLog::default());
+        self.iterations.record(state.corpus().count() > corpus_size);
 
         #[cfg(feature = "introspection")]
         state.introspection_stats_mut().finish_stage();
@@ -93,19 +105,92 @@ where
     S: HasClientPerfStats + HasCorpus<C, I> + HasRand<R>,
     Z: Evaluator<E, EM, I, S>,
 {
-    /// Creates a new default mutational stage
-    pub fn new(mutator: M, max_iterations_per_stage: u64) -> Self {
+    /// Creates a new default mutational stage. The iteration budget adapts to the coverage gains
+    /// within the given bounds, see [`AdaptiveIterations`].
+    pub fn new(mutator: M, min_iterations_per_stage: u64, max_iterations_per_stage: u64) -> Self {
         Self {
             mutator,
             phantom: PhantomData,
-            max_iterations_per_stage,
+            iterations: AdaptiveIterations::new(min_iterations_per_stage, max_iterations_per_stage),
         }
     }
+
+    /// The current upper bound of the iterations, see [`AdaptiveIterations::budget`]
+    pub fn budget(&self) -> u64 {
+        self.iterations.budget()
+    }
+}
+
+/// Upper bound of the iterations of a stage. The budget is halved after each run of the stage
+/// which found no new coverage and doubled after each run which did, but it never leaves the
+/// bounds `min..=max`. Inputs which plateaued are therefore left earlier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdaptiveIterations {
+    min: u64,
+    max: u64,
+    current: u64,
+}
+
+impl AdaptiveIterations {
+    /// Starts with the maximal budget. If `min` equals `max`, then the budget is static.
+    pub fn new(min: u64, max: u64) -> Self {
+        let min = min.max(1);
+        let max = max.max(min);
+        Self {
+            min,
+            max,
+            current: max,
+        }
+    }
+
+    pub fn budget(&self) -> u64 {
+        self.current
+    }
+
+    /// Adapts the budget to the outcome of the last run of the stage
+    pub fn record(&mut self, found_new_coverage: bool) {
+        self.current = if found_new_coverage {
+            self.current.saturating_mul(2).min(self.max)
+        } else {
+            (self.current / 2).max(self.min)
+        };
+    }
 }
 
 //-----------------------------
 
-/// A [`Mutator`] that schedules one of the embedded mutations on each call.
+/// A single mutation which has been applied by [`PuffinScheduledMutator`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MutationLogEntry {
+    /// Name of the mutator
+    pub mutator: String,
+    /// Seed of the random number generator right before the mutator has been applied
+    pub seed: u64,
+    pub mutated: bool,
+}
+
+/// The mutations which produced an input from the corpus entry it is based on. It is attached as
+/// metadata to new corpus entries and solutions. Applying the logged mutators with the logged
+/// seeds to the base entry reproduces the input, see [`PuffinScheduledMutator::replay`].
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MutationLog {
+    /// Id of the corpus entry which has been mutated. It is only known if the mutations have been
+    /// applied by a [`PuffinMutationalStage`].
+    pub parent: Option<usize>,
+    pub entries: Vec<MutationLogEntry>,
+}
+
+libafl::impl_serdeany!(MutationLog);
+
+/// The name of a mutator without the module path and the generics, e.g. `SkipMutator`
+pub fn short_name(name: &str) -> &str {
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+/// A [`Mutator`] that schedules one of the embedded mutations on each call. By default the
+/// mutations are chosen uniformly, see [`PuffinScheduledMutator::with_weights`] for a biased
+/// selection.
 pub struct PuffinScheduledMutator<I, MT, R, S>
 where
     I: Input,
@@ -116,6 +201,9 @@ where
     mutations: MT,
     phantom: PhantomData<(I, R, S)>,
     max_mutations_per_iteration: u64,
+    /// Relative weight of each mutation, in the order of the `mutations`. If not set, then each
+    /// mutation is equally likely.
+    weights: Option<Vec<f64>>,
 }
 
 impl<I, MT, R, S> Debug for PuffinScheduledMutator<I, MT, R, S>
@@ -138,9 +226,9 @@ where
 impl<I, MT, R, S> Mutator<I, S> for PuffinScheduledMutator<I, MT, R, S>
 where
     I: Input,
-    MT: MutatorsTuple<I, S>,
+    MT: MutatorsTuple<I, S> + NamedTuple,
     R: Rand,
-    S: HasRand<R>,
+    S: HasRand<R> + HasMetadata,
 {
     #[inline]
     fn mutate(
@@ -176,9 +264,9 @@ where
 impl<I, MT, R, S> ScheduledMutator<I, MT, S> for PuffinScheduledMutator<I, MT, R, S>
 where
     I: Input,
-    MT: MutatorsTuple<I, S>,
+    MT: MutatorsTuple<I, S> + NamedTuple,
     R: Rand,
-    S: HasRand<R>,
+    S: HasRand<R> + HasMetadata,
 {
     /// Compute the number of iterations used to apply stacked mutations
     fn iterations(&self, state: &mut S, _: &I) -> u64 {
@@ -188,7 +276,69 @@ where
     /// Get the next mutation to apply
     fn schedule(&self, state: &mut S, _: &I) -> usize {
         debug_assert!(!self.mutations().is_empty());
-        state.rand_mut().below(self.mutations().len() as u64) as usize
+        match &self.weights {
+            None => state.rand_mut().below(self.mutations().len() as u64) as usize,
+            Some(weights) => {
+                let total: f64 = weights.iter().sum();
+                let mut point = state.rand_mut().next() as f64 / u64::MAX as f64 * total;
+                let mut chosen = 0;
+                for (idx, weight) in weights.iter().enumerate() {
+                    if *weight <= 0.0 {
+                        continue;
+                    }
+                    // Rounding may leave a rest, which is attributed to the last possible choice
+                    chosen = idx;
+                    if point < *weight {
+                        break;
+                    }
+                    point -= weight;
+                }
+                chosen
+            }
+        }
+    }
+
+    /// Applies stacked mutations like the default implementation and stores a [`MutationLog`] of
+    /// the applied mutations in the metadata of the state
+    fn scheduled_mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let mut result = MutationResult::Skipped;
+        let mut log = MutationLog {
+            parent: state
+                .metadata()
+                .get::<MutationLog>()
+                .and_then(|log| log.parent),
+            entries: vec![],
+        };
+
+        let num = self.iterations(state, input);
+        for _ in 0..num {
+            let idx = self.schedule(state, input);
+
+            // Reseed, such that the decisions of the mutator only depend on the logged seed
+            let seed = state.rand_mut().next();
+            state.rand_mut().set_seed(seed);
+
+            let outcome = self
+                .mutations_mut()
+                .get_and_mutate(idx, state, input, stage_idx)?;
+            if outcome == MutationResult::Mutated {
+                result = MutationResult::Mutated;
+            }
+
+            log.entries.push(MutationLogEntry {
+                mutator: self.mutations().name(idx).unwrap_or_default().to_string(),
+                seed,
+                mutated: outcome == MutationResult::Mutated,
+            });
+        }
+
+        state.add_metadata(log);
+        Ok(result)
     }
 }
 
@@ -205,6 +355,80 @@ where
             mutations,
             phantom: PhantomData,
             max_mutations_per_iteration,
+            weights: None,
         }
     }
 }
+
+impl<I, MT, R, S> PuffinScheduledMutator<I, MT, R, S>
+where
+    I: Input,
+    MT: MutatorsTuple<I, S> + NamedTuple,
+    R: Rand,
+    S: HasRand<R>,
+{
+    /// Like [`PuffinScheduledMutator::new`], but each mutation is chosen with a probability
+    /// proportional to its weight. The `weights` are keyed by the short name of the mutators, e.g.
+    /// `SkipMutator`. Mutations which are not listed have a weight of 1, mutations with a weight
+    /// of 0 are never chosen.
+    pub fn with_weights(
+        mutations: MT,
+        weights: &[(String, f64)],
+        max_mutations_per_iteration: u64,
+    ) -> Result<Self, Error> {
+        let mut resolved = vec![1.0; mutations.len()];
+        for (name, weight) in weights {
+            let idx = (0..mutations.len())
+                .find(|idx| mutations.name(*idx).map(short_name) == Some(name.as_str()))
+                .ok_or_else(|| Error::IllegalArgument(format!("Unknown mutator {}", name)))?;
+            resolved[idx] = *weight;
+        }
+
+        if resolved
+            .iter()
+            .any(|weight| !weight.is_finite() || *weight < 0.0)
+            || resolved.iter().sum::<f64>() <= 0.0
+        {
+            return Err(Error::IllegalArgument(
+                "Weights must be finite, non-negative and not all 0".to_string(),
+            ));
+        }
+
+        Ok(PuffinScheduledMutator {
+            mutations,
+            phantom: PhantomData,
+            max_mutations_per_iteration,
+            weights: Some(resolved),
+        })
+    }
+
+    /// Applies the mutations of the `log` to `input`. Mutators are identified by their name.
+    pub fn replay(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        log: &MutationLog,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let mut result = MutationResult::Skipped;
+
+        for entry in &log.entries {
+            let idx = (0..self.mutations.len())
+                .find(|idx| self.mutations.name(*idx) == Some(entry.mutator.as_str()))
+                .ok_or_else(|| {
+                    Error::IllegalArgument(format!("Unknown mutator {}", entry.mutator))
+                })?;
+
+            state.rand_mut().set_seed(entry.seed);
+            if self
+                .mutations
+                .get_and_mutate(idx, state, input, stage_idx)?
+                == MutationResult::Mutated
+            {
+                result = MutationResult::Mutated;
+            }
+        }
+
+        Ok(result)
+    }
+}
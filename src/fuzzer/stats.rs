@@ -4,9 +4,10 @@ use core::{time, time::Duration};
 
 use std::fs::{File, OpenOptions};
 use std::io;
-use std::io::BufWriter;
+use std::io::{BufWriter, Write};
 use std::path::PathBuf;
-use std::time::SystemTime;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use libafl::stats::{PerfFeature, UserStats};
 use libafl::{
@@ -14,12 +15,127 @@ use libafl::{
     stats::{ClientStats, Stats},
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use serde_json::Serializer as JSONSerializer;
 
+use crate::fuzzer::budget::{terminate_campaign, CampaignBudget};
 use crate::fuzzer::stats_observer::{RuntimeStats, STATS};
 
+/// How the fuzzer reports its stats while fuzzing
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StatsFormat {
+    /// Human readable lines which are passed to the log
+    Text,
+    /// One [`StatsEvent`] per line, written to stdout or to a file
+    JsonLines(StatsOutput),
+}
+
+impl Default for StatsFormat {
+    fn default() -> Self {
+        StatsFormat::Text
+    }
+}
+
+impl FromStr for StatsFormat {
+    type Err = String;
+
+    /// Parses `text`, `jsonl` (stdout) or `jsonl:<path>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(StatsFormat::Text),
+            "jsonl" => Ok(StatsFormat::JsonLines(StatsOutput::Stdout)),
+            _ => match s.strip_prefix("jsonl:") {
+                Some(path) if !path.is_empty() => Ok(StatsFormat::JsonLines(StatsOutput::File(
+                    PathBuf::from(path),
+                ))),
+                _ => Err(format!(
+                    "Unknown stats format {}. Expected text, jsonl or jsonl:<path>.",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// Destination of the JSON lines stats
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum StatsOutput {
+    Stdout,
+    /// Events are appended to the file
+    File(PathBuf),
+}
+
+impl StatsOutput {
+    fn open(&self) -> Result<Box<dyn Write + Send>, io::Error> {
+        Ok(match self {
+            StatsOutput::Stdout => Box::new(io::stdout()),
+            StatsOutput::File(path) => Box::new(BufWriter::new(
+                OpenOptions::new().append(true).create(true).open(path)?,
+            )),
+        })
+    }
+}
+
+/// A single self-contained line of the JSON lines stats
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StatsEvent {
+    /// Milliseconds since the UNIX epoch
+    pub timestamp: u64,
+    /// The event which triggered the report, e.g. `Testcase` or `Stats`
+    pub event: String,
+    /// Id of the client, or `None` for the cumulative stats of all clients
+    pub client: Option<u32>,
+    pub executions: u64,
+    pub corpus_size: u64,
+    pub objectives: u64,
+    /// Covered edges, only known per client
+    pub edges: Option<u64>,
+    pub exec_per_sec: u64,
+}
+
+impl StatsEvent {
+    pub fn now(event: &str, client: Option<u32>) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0);
+
+        StatsEvent {
+            timestamp,
+            event: event.to_string(),
+            client,
+            executions: 0,
+            corpus_size: 0,
+            objectives: 0,
+            edges: None,
+            exec_per_sec: 0,
+        }
+    }
+}
+
+/// Writes [`StatsEvent`]s as JSON lines to `W`
+pub struct JsonLinesEmitter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesEmitter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn emit(&mut self, event: &StatsEvent) -> Result<(), io::Error> {
+        serde_json::to_writer(&mut self.writer, event)?;
+        self.writer.write_all(b"\n")?;
+        // Flush every line such that readers never observe partial objects
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
 /// Tracking stats during fuzzing and display both per-client and cumulative info.
 pub struct PuffinStats<F>
 where
@@ -31,6 +147,10 @@ where
     log_count: u64,
     stats_file: PathBuf,
     serializer: JSONSerializer<BufWriter<File>>,
+    format: StatsFormat,
+    /// Set if the `format` is [`StatsFormat::JsonLines`]
+    emitter: Option<JsonLinesEmitter<Box<dyn Write + Send>>>,
+    budget: CampaignBudget,
 }
 
 impl<F> Clone for PuffinStats<F>
@@ -50,10 +170,22 @@ where
                     .open(&self.stats_file)
                     .unwrap(),
             )),
+            format: self.format.clone(),
+            emitter: open_emitter(&self.format).unwrap(),
+            budget: self.budget,
         }
     }
 }
 
+fn open_emitter(
+    format: &StatsFormat,
+) -> Result<Option<JsonLinesEmitter<Box<dyn Write + Send>>>, io::Error> {
+    match format {
+        StatsFormat::Text => Ok(None),
+        StatsFormat::JsonLines(output) => Ok(Some(JsonLinesEmitter::new(output.open()?))),
+    }
+}
+
 impl<F> PuffinStats<F>
 where
     F: FnMut(String),
@@ -117,12 +249,20 @@ where
             "[{}] (CLIENT) corpus: {}, obj: {}, execs: {}, exec/sec: {}",
             event_msg, corpus_size, objective_size, total_execs, exec_sec
         );
+        let mut event = StatsEvent {
+            executions: total_execs,
+            corpus_size,
+            objectives: objective_size,
+            exec_per_sec: exec_sec,
+            ..StatsEvent::now(event_msg, Some(sender_id))
+        };
 
         // log edges
         let coverage = if let Some(edges) = client.user_stats.get("edges") {
             fmt += &format!(", {}: {}", "edges", edges);
 
             if let UserStats::Ratio(a, b) = edges {
+                event.edges = Some(*a);
                 Some(CoverageStatistics {
                     discovered: *a,
                     max: *b,
@@ -134,7 +274,7 @@ where
             None
         };
 
-        (self.print_fn)(fmt);
+        self.report(fmt, &event);
 
         ClientStatistics {
             id: sender_id,
@@ -165,7 +305,51 @@ where
             total_execs,
             self.execs_per_sec()
         );
-        (self.print_fn)(global_fmt);
+        let event = StatsEvent {
+            executions: total_execs,
+            corpus_size: self.corpus_size(),
+            objectives: self.objective_size(),
+            exec_per_sec: self.execs_per_sec(),
+            ..StatsEvent::now(event_msg, None)
+        };
+        self.report(global_fmt, &event);
+    }
+
+    /// Reports the final summary of the campaign and flushes the stats file
+    fn finish(&mut self, event_msg: &String, elapsed: Duration) {
+        info!(
+            "Budget exhausted after {} executions in {}s, corpus: {}, obj: {}",
+            self.total_execs(),
+            elapsed.as_secs(),
+            self.corpus_size(),
+            self.objective_size()
+        );
+        self.global(event_msg);
+
+        match OpenOptions::new().append(true).open(&self.stats_file) {
+            Ok(file) => {
+                let serializer = std::mem::replace(
+                    &mut self.serializer,
+                    JSONSerializer::new(BufWriter::new(file)),
+                );
+                if let Err(err) = serializer.into_inner().flush() {
+                    warn!("Failed to flush stats: {}", err);
+                }
+            }
+            Err(err) => warn!("Failed to flush stats: {}", err),
+        }
+    }
+
+    /// Passes the text to the `print_fn`, or emits the `event` if JSON lines are enabled
+    fn report(&mut self, text: String, event: &StatsEvent) {
+        match &mut self.emitter {
+            Some(emitter) => {
+                if let Err(err) = emitter.emit(event) {
+                    warn!("Failed to emit stats: {}", err);
+                }
+            }
+            None => (self.print_fn)(text),
+        }
     }
 }
 
@@ -237,6 +421,9 @@ struct TraceStatistics {
     min_term_size: Option<u64>,
     max_term_size: Option<u64>,
     mean_term_size: Option<u64>,
+
+    message_types: Option<u64>,
+    openssl_errors: Option<u64>,
 }
 
 impl IntrospectFeatures {
@@ -346,6 +533,8 @@ impl TraceStatistics {
             min_term_size: None,
             max_term_size: None,
             mean_term_size: None,
+            message_types: None,
+            openssl_errors: None,
         };
 
         // Sum for all TraceLength and TermSize
@@ -373,6 +562,16 @@ impl TraceStatistics {
                         .mean_term_size
                         .insert(get_number(user_stats, &(mmm.name.to_owned() + "-mean")));
                 }
+                RuntimeStats::MessageTypes(observer) => {
+                    trace_stats
+                        .message_types
+                        .insert(get_number(user_stats, observer.name));
+                }
+                RuntimeStats::OpenSSLErrors(observer) => {
+                    trace_stats
+                        .openssl_errors
+                        .insert(get_number(user_stats, observer.name));
+                }
                 _ => {}
             }
         }
@@ -401,6 +600,13 @@ where
     }
 
     fn display(&mut self, event_msg: String, sender_id: u32) {
+        let elapsed = current_time().saturating_sub(self.start_time);
+        if self.budget.is_exhausted(self.total_execs(), elapsed) {
+            self.finish(&event_msg, elapsed);
+            terminate_campaign();
+            return;
+        }
+
         self.log_count += 1;
 
         if self.log_count % 100 != 0 {
@@ -416,7 +622,7 @@ impl<F> PuffinStats<F>
 where
     F: FnMut(String),
 {
-    pub fn new(print_fn: F, stats_file: PathBuf) -> Result<Self, io::Error> {
+    pub fn new(print_fn: F, stats_file: PathBuf, format: StatsFormat) -> Result<Self, io::Error> {
         let writer = JSONSerializer::new(BufWriter::new(
             OpenOptions::new()
                 .append(true)
@@ -430,6 +636,15 @@ where
             log_count: 0,
             stats_file,
             serializer: writer,
+            emitter: open_emitter(&format)?,
+            format,
+            budget: CampaignBudget::default(),
         })
     }
+
+    /// Terminates the campaign once the `budget` is used up
+    pub fn with_budget(mut self, budget: CampaignBudget) -> Self {
+        self.budget = budget;
+        self
+    }
 }
@@ -1,4 +1,6 @@
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 use libafl::events::{Event, EventFirer};
 use libafl::inputs::Input;
@@ -10,6 +12,9 @@ use libafl::bolts::rands::Rand;
 
 use libafl::corpus::Corpus;
 use libafl::stages::Stage;
+use once_cell::sync::Lazy;
+use rustls::msgs::enums::HandshakeType;
+use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
 pub enum RuntimeStats {
@@ -22,6 +27,8 @@ pub enum RuntimeStats {
     ExtractionError(&'static Counter),
     TraceLength(&'static MinMaxMean),
     TermSize(&'static MinMaxMean),
+    MessageTypes(&'static MessageTypeObserver),
+    OpenSSLErrors(&'static ErrorObserver),
 }
 
 impl RuntimeStats {
@@ -39,10 +46,88 @@ impl RuntimeStats {
             RuntimeStats::ExtractionError(inner) => inner.fire(consume),
             RuntimeStats::TraceLength(inner) => inner.fire(consume),
             RuntimeStats::TermSize(inner) => inner.fire(consume),
+            RuntimeStats::MessageTypes(inner) => inner.fire(consume),
+            RuntimeStats::OpenSSLErrors(inner) => inner.fire(consume),
+        }
+    }
+
+    fn snapshot(&self) -> StatsValue {
+        match self {
+            RuntimeStats::FnError(inner)
+            | RuntimeStats::TermError(inner)
+            | RuntimeStats::OpenSSLError(inner)
+            | RuntimeStats::IOError(inner)
+            | RuntimeStats::AgentError(inner)
+            | RuntimeStats::StreamError(inner)
+            | RuntimeStats::ExtractionError(inner) => {
+                StatsValue::Counter(inner.counter.load(Ordering::SeqCst))
+            }
+            RuntimeStats::TraceLength(inner) | RuntimeStats::TermSize(inner) => {
+                StatsValue::MinMaxMean(inner.snapshot())
+            }
+            RuntimeStats::MessageTypes(inner) => StatsValue::MessageTypes([
+                inner.seen[0].load(Ordering::SeqCst),
+                inner.seen[1].load(Ordering::SeqCst),
+                inner.seen[2].load(Ordering::SeqCst),
+                inner.seen[3].load(Ordering::SeqCst),
+            ]),
+            RuntimeStats::OpenSSLErrors(inner) => StatsValue::Errors(inner.errors()),
+        }
+    }
+
+    fn restore(&self, value: StatsValue) {
+        match (self, value) {
+            (RuntimeStats::FnError(inner), StatsValue::Counter(counter))
+            | (RuntimeStats::TermError(inner), StatsValue::Counter(counter))
+            | (RuntimeStats::OpenSSLError(inner), StatsValue::Counter(counter))
+            | (RuntimeStats::IOError(inner), StatsValue::Counter(counter))
+            | (RuntimeStats::AgentError(inner), StatsValue::Counter(counter))
+            | (RuntimeStats::StreamError(inner), StatsValue::Counter(counter))
+            | (RuntimeStats::ExtractionError(inner), StatsValue::Counter(counter)) => {
+                inner.counter.store(counter, Ordering::SeqCst)
+            }
+            (RuntimeStats::TraceLength(inner), StatsValue::MinMaxMean(values))
+            | (RuntimeStats::TermSize(inner), StatsValue::MinMaxMean(values)) => {
+                inner.restore(values)
+            }
+            (RuntimeStats::MessageTypes(inner), StatsValue::MessageTypes(seen)) => {
+                for (bits, value) in inner.seen.iter().zip(seen.iter()) {
+                    bits.store(*value, Ordering::SeqCst);
+                }
+            }
+            (RuntimeStats::OpenSSLErrors(inner), StatsValue::Errors(errors)) => {
+                *inner.errors.lock().unwrap() = errors.into_iter().collect();
+            }
+            (_, value) => warn!("Ignoring stats value {:?} of a different kind", value),
         }
     }
 }
 
+/// Value of one of the [`STATS`], such that the stats can be handed from a forked child to its
+/// parent, see [`snapshot_stats`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum StatsValue {
+    Counter(usize),
+    /// Minimum, maximum and mean, each if it has been set
+    MinMaxMean([Option<usize>; 3]),
+    MessageTypes([u64; 4]),
+    Errors(Vec<String>),
+}
+
+/// Takes a snapshot of all [`STATS`]
+pub fn snapshot_stats() -> Vec<StatsValue> {
+    STATS.iter().map(RuntimeStats::snapshot).collect()
+}
+
+/// Overwrites all [`STATS`] with a snapshot of [`snapshot_stats`]. A forked child starts with the
+/// stats of its parent, therefore the snapshot of the child replaces the stats of the parent
+/// without losing values.
+pub fn restore_stats(values: Vec<StatsValue>) {
+    for (stat, value) in STATS.iter().zip(values) {
+        stat.restore(value);
+    }
+}
+
 // Fn(FnError),
 pub static FN_ERROR: Counter = Counter::new("fn");
 // Term(String),
@@ -62,7 +147,14 @@ pub static TRACE_LENGTH: MinMaxMean = MinMaxMean::new("trace-length");
 
 pub static TERM_SIZE: MinMaxMean = MinMaxMean::new("term-size");
 
-pub static STATS: [RuntimeStats; 9] = [
+pub static MESSAGE_TYPES: MessageTypeObserver = MessageTypeObserver::new("msg-types");
+
+pub static OPENSSL_ERRORS: ErrorObserver = ErrorObserver {
+    name: "ssl-errors",
+    errors: Lazy::new(|| Mutex::new(HashSet::new())),
+};
+
+pub static STATS: [RuntimeStats; 11] = [
     RuntimeStats::FnError(&FN_ERROR),
     RuntimeStats::TermError(&TERM),
     RuntimeStats::OpenSSLError(&OPENSSL),
@@ -72,6 +164,8 @@ pub static STATS: [RuntimeStats; 9] = [
     RuntimeStats::ExtractionError(&EXTRACTION),
     RuntimeStats::TraceLength(&TRACE_LENGTH),
     RuntimeStats::TermSize(&TERM_SIZE),
+    RuntimeStats::MessageTypes(&MESSAGE_TYPES),
+    RuntimeStats::OpenSSLErrors(&OPENSSL_ERRORS),
 ];
 
 pub trait Fire: Sync {
@@ -161,6 +255,35 @@ impl MinMaxMean {
         self.min_set.fetch_or(true, Ordering::SeqCst);
         self.min.fetch_min(value, Ordering::SeqCst);
     }
+
+    fn snapshot(&self) -> [Option<usize>; 3] {
+        let load = |set: &AtomicBool, value: &AtomicUsize| {
+            if set.load(Ordering::SeqCst) {
+                Some(value.load(Ordering::SeqCst))
+            } else {
+                None
+            }
+        };
+
+        [
+            load(&self.min_set, &self.min),
+            load(&self.max_set, &self.max),
+            load(&self.mean_set, &self.mean),
+        ]
+    }
+
+    fn restore(&self, [min, max, mean]: [Option<usize>; 3]) {
+        let store = |set: &AtomicBool, value: &AtomicUsize, restored: Option<usize>| {
+            set.store(restored.is_some(), Ordering::SeqCst);
+            if let Some(restored) = restored {
+                value.store(restored, Ordering::SeqCst);
+            }
+        };
+
+        store(&self.min_set, &self.min, min);
+        store(&self.max_set, &self.max, max);
+        store(&self.mean_set, &self.mean, mean);
+    }
 }
 
 impl Fire for MinMaxMean {
@@ -190,6 +313,93 @@ impl Fire for MinMaxMean {
     }
 }
 
+/// Records the distinct [`HandshakeType`]s of all messages which agents sent or received during
+/// the run. The number of types is a coarse protocol-level coverage metric.
+pub struct MessageTypeObserver {
+    pub name: &'static str,
+    /// Bitset of the codes of the recorded types
+    seen: [AtomicU64; 4],
+}
+
+impl MessageTypeObserver {
+    const fn new(name: &'static str) -> MessageTypeObserver {
+        Self {
+            name,
+            seen: [
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+                AtomicU64::new(0),
+            ],
+        }
+    }
+
+    pub fn record(&self, typ: HandshakeType) {
+        let code = typ.get_u8() as usize;
+        self.seen[code / 64].fetch_or(1 << (code % 64), Ordering::SeqCst);
+    }
+
+    pub fn has_seen(&self, typ: HandshakeType) -> bool {
+        let code = typ.get_u8() as usize;
+        self.seen[code / 64].load(Ordering::SeqCst) & (1 << (code % 64)) != 0
+    }
+
+    /// Number of distinct types which have been recorded
+    pub fn count(&self) -> usize {
+        self.seen
+            .iter()
+            .map(|bits| bits.load(Ordering::SeqCst).count_ones() as usize)
+            .sum()
+    }
+}
+
+impl Fire for MessageTypeObserver {
+    fn fire(
+        &self,
+        consume: &mut dyn FnMut(String, UserStats) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        consume(
+            self.name.to_string(),
+            UserStats::Number(self.count() as u64),
+        )
+    }
+}
+
+/// Records the distinct errors which have been reported during the run, e.g. the entries of the
+/// error queue of OpenSSL in the format `library:function:reason`
+pub struct ErrorObserver {
+    pub name: &'static str,
+    errors: Lazy<Mutex<HashSet<String>>>,
+}
+
+impl ErrorObserver {
+    pub fn record(&self, error: String) {
+        self.errors.lock().unwrap().insert(error);
+    }
+
+    /// The errors which have been recorded so far
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Number of distinct errors which have been recorded
+    pub fn count(&self) -> usize {
+        self.errors.lock().unwrap().len()
+    }
+}
+
+impl Fire for ErrorObserver {
+    fn fire(
+        &self,
+        consume: &mut dyn FnMut(String, UserStats) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        consume(
+            self.name.to_string(),
+            UserStats::Number(self.count() as u64),
+        )
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct StatsStage<C, E, EM, I, R, S, Z>
 where
@@ -70,8 +70,5 @@ fn generate_term<R: Rand>(
         }
     }
 
-    Some(Term::Application(
-        Function::new(shape.clone(), dynamic_fn.clone()),
-        subterms,
-    ))
+    Term::try_application(Function::new(shape.clone(), dynamic_fn.clone()), subterms).ok()
 }
@@ -1,36 +1,110 @@
 use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
 
 use itertools::Itertools;
 use libafl::bolts::rands::StdRand;
-use libafl::corpus::InMemoryCorpus;
+use libafl::bolts::shmem::{ShMemProvider, StdShMemProvider};
+use libafl::bolts::tuples::{tuple_list, Named};
+use libafl::corpus::{
+    Corpus, InMemoryCorpus, IndexesLenTimeMinimizerCorpusScheduler, PowerQueueCorpusScheduler,
+    QueueCorpusScheduler, RandCorpusScheduler, Testcase,
+};
+use libafl::events::{EventFirer, NopEventManager};
+use libafl::executors::inprocess::{InProcessExecutor, InProcessForkExecutor};
+use libafl::executors::ExitKind;
+use libafl::feedbacks::{
+    Feedback, MapFeedbackState, MaxMapFeedback, TimeFeedback, TimeoutFeedback,
+};
+use libafl::fuzzer::{Evaluator, Fuzzer, StdFuzzer};
+use libafl::inputs::Input;
 use libafl::mutators::{MutationResult, Mutator};
-use libafl::state::StdState;
+use libafl::observers::{HitcountsMapObserver, ObserversTuple, StdMapObserver, TimeObserver};
+use libafl::stages::power::{PowerMutationalStage, PowerSchedule};
+use libafl::stages::{CalibrationStage, Stage};
+use libafl::state::{HasClientPerfStats, HasCorpus, HasMetadata, HasSolutions, StdState};
+use libafl::{feedback_and_fast, feedback_or};
 use openssl::rand::rand_bytes;
+use rustls::msgs::codec::Codec;
+use rustls::msgs::deframer::MessageDeframer;
+use rustls::msgs::enums::{AlertDescription, HandshakeType};
+use rustls::msgs::handshake::HandshakePayload;
+use rustls::msgs::message::{Message, MessagePayload, OpaqueMessage};
 
 use crate::agent::{AgentDescriptor, AgentName, TLSVersion};
-use crate::fuzzer::mutations::util::{TermConstraints, TracePath};
+use crate::differential::{available_backends, Backend, OpenSSLBackend};
+use crate::fuzzer::budget::{supervise, CampaignBudget};
+use crate::fuzzer::byte_havoc::{havoc_bytes, set_byte_havoc_seed, ByteHavocMetadata};
+use crate::fuzzer::corpus_stats::corpus_stats;
+use crate::fuzzer::crash::{
+    is_ignored_frame, CrashSignature, CrashSignatureFeedback, SanitizerReport,
+    CRASH_SIGNATURE_FRAMES,
+};
+use crate::fuzzer::feedbacks::{
+    AlertCombinationsMetadata, AlertNoveltyFeedback, DedupFeedback, DifferentialFeedback,
+    MutationLogFeedback, OpenSSLErrorsFeedback, OpenSSLErrorsMetadata, ReachedStatesMetadata,
+    StateFeedback,
+};
+use crate::fuzzer::harness::{
+    fork_harness, harness, set_fork_report_map, ForkTimeoutExecutor, FORK_REPORT_SIZE, FORK_TIMEOUT,
+};
+use crate::fuzzer::mutations::trace_mutations;
+use crate::fuzzer::mutations::util::{is_list_append, TermConstraints, TracePath};
 use crate::fuzzer::mutations::{
-    RemoveAndLiftMutator, RepeatMutator, ReplaceMatchMutator, ReplaceReuseMutator, SkipMutator,
-    SwapMutator,
+    ConstantToVariableMutator, DuplicateExtensionMutator, GreaseMutator, ListReorderMutator,
+    RecordLengthCorruptMutator, RemoveAndLiftMutator, RepeatMutator, ReplaceMatchMutator,
+    ReplaceReuseMutator, SkipMutator, SwapMutator, TruncateMutator,
 };
+use crate::fuzzer::pcap::import_pcap;
 use crate::fuzzer::seeds::*;
+use crate::fuzzer::sharding::CorpusShard;
+use crate::fuzzer::stages::{
+    short_name, AdaptiveIterations, MutationLog, PuffinMutationalStage, PuffinScheduledMutator,
+};
+use crate::fuzzer::stats::{JsonLinesEmitter, StatsEvent, StatsFormat, StatsOutput};
+use crate::fuzzer::stats_observer::{MESSAGE_TYPES, OPENSSL_ERRORS};
 use crate::fuzzer::term_zoo::generate_term_zoo;
-use crate::openssl_binding::make_deterministic;
+use crate::fuzzer::trace_generator::generate_random_trace;
+use crate::fuzzer::{
+    start, ExecutorConfig, FuzzerConfig, SchedulerConfig, MAX_EDGES_NUM, MAX_ITERATIONS_PER_STAGE,
+    MIN_ITERATIONS_PER_STAGE,
+};
+use crate::openssl_binding::DeterministicRng;
+use crate::term;
 use crate::term::dynamic_function::DescribableFunction;
+use crate::term::signature::Signature;
 use crate::term::Term;
 use crate::tls::fn_impl::*;
 use crate::tls::SIGNATURE;
-use crate::trace::{Action, InputAction, Step, Trace, TraceContext};
+use crate::trace::{Action, InputAction, OutputAction, Step, Trace, TraceContext};
 
 #[cfg(feature = "deterministic")]
 #[test]
 fn test_openssl_no_randomness() {
-    make_deterministic(); // his affects also other tests, which is fine as we generally prefer deterministic tests
+    let _guard = DeterministicRng::enable();
     let mut buf1 = [0; 2];
     rand_bytes(&mut buf1).unwrap();
     assert_eq!(buf1, [70, 100]);
 }
 
+#[cfg(feature = "deterministic")]
+#[test]
+fn test_deterministic_rng_reverts() {
+    use crate::openssl_binding::RAND_get_rand_method;
+
+    // Hold the lock throughout, such that concurrent tests can not change the engine in between
+    let lock = DeterministicRng::lock();
+    let previous = unsafe { RAND_get_rand_method() };
+    let guard = DeterministicRng::enable_locked(lock);
+    let mut buf = [0; 2];
+    rand_bytes(&mut buf).unwrap();
+    assert_eq!(buf, [70, 100]);
+
+    // The guard restored the engine which was used before
+    let _lock = guard.disable();
+    assert_eq!(unsafe { RAND_get_rand_method() }, previous);
+}
+
 /// Checks whether repeat can repeat the last step
 #[test]
 fn test_repeat_mutator() {
@@ -68,6 +142,30 @@ fn test_repeat_mutator() {
     }
 }
 
+#[test]
+fn test_repeat_mutator_max_trace_steps() {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let server = AgentName::first();
+
+    let steps = seed_client_attacker12(server).steps.len();
+    let constraints = TermConstraints {
+        max_trace_steps: steps,
+        ..TermConstraints::default()
+    };
+    // The RepeatMutator is the first of the mutations
+    let mut mutator =
+        trace_mutations::<StdRand, InMemoryCorpus<Trace>, _>(1, 15, constraints, 100000).0;
+
+    for _ in 0..100 {
+        let mut trace = seed_client_attacker12(server);
+        let result = mutator.mutate(&mut state, &mut trace, 0).unwrap();
+        assert!(matches!(result, MutationResult::Skipped));
+        assert_eq!(trace.steps.len(), steps);
+    }
+}
+
 #[test]
 fn test_replace_match_mutator() {
     let rand = StdRand::with_seed(1235);
@@ -93,7 +191,32 @@ fn test_replace_match_mutator() {
                         }
                     }
                 },
-                Action::Output(_) => {}
+                Action::Output(_) | Action::Delay(_) => {}
+            }
+        }
+    }
+}
+
+#[test]
+fn test_replace_match_cve() {
+    // Should swap out a `fn_seq_0` node for another sequence number constant
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let server = AgentName::first();
+
+    let mut mutator = ReplaceMatchMutator::new(TermConstraints::default());
+
+    let seq_0_count = |trace: &Trace| trace.count_functions_by_name(fn_seq_0.name());
+
+    loop {
+        let mut trace = seed_client_attacker12(server);
+        let before = seq_0_count(&trace);
+        assert!(before > 0);
+
+        if let MutationResult::Mutated = mutator.mutate(&mut state, &mut trace, 0).unwrap() {
+            if seq_0_count(&trace) < before {
+                break;
             }
         }
     }
@@ -159,6 +282,869 @@ fn test_replace_reuse_mutator() {
     }
 }
 
+#[test]
+fn test_grease_mutator() {
+    let rand = StdRand::with_seed(45);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let server = AgentName::first();
+    let mut mutator = GreaseMutator::new(TermConstraints::default());
+
+    let mut trace = seed_client_attacker12(server);
+    let result = mutator.mutate(&mut state, &mut trace, 0).unwrap();
+    assert!(matches!(result, MutationResult::Mutated));
+
+    let greased = trace.count_functions_by_name(fn_grease_cipher_suite.name())
+        + trace.count_functions_by_name(fn_grease_extension.name());
+    assert_eq!(greased, 1);
+
+    let mut trace = seed_client_attacker12(server);
+    trace.steps.truncate(1);
+    let size = match &trace.steps[0].action {
+        Action::Input(input) => input.recipe.size(),
+        _ => panic!("Expected an input step"),
+    };
+
+    // The recipe already has the maximum size, therefore no GREASE value can be added
+    let mut mutator = GreaseMutator::new(TermConstraints {
+        max_term_size: size,
+        ..TermConstraints::default()
+    });
+    for _ in 0..20 {
+        let result = mutator.mutate(&mut state, &mut trace, 0).unwrap();
+        assert!(matches!(result, MutationResult::Skipped));
+    }
+}
+
+#[test]
+fn test_is_list_append() {
+    assert!(is_list_append(&Signature::new_function(
+        &fn_client_extensions_append
+    )));
+    assert!(is_list_append(&Signature::new_function(
+        &fn_append_cipher_suite
+    )));
+    // Appends to a transcript or to bytes, which are not lists
+    assert!(!is_list_append(&Signature::new_function(
+        &fn_append_transcript
+    )));
+    assert!(!is_list_append(&Signature::new_function(
+        &fn_append_nibbles
+    )));
+}
+
+#[test]
+fn test_list_reorder_mutator() {
+    let rand = StdRand::with_seed(45);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let server = AgentName::first();
+    let mut mutator = ListReorderMutator::new(TermConstraints::default());
+
+    // Only the ClientHello
+    let client_hello_trace = || {
+        let mut trace = seed_client_attacker12(server);
+        trace.steps.truncate(1);
+        trace
+    };
+    let extension_types = |trace: &Trace| match &trace.steps[0].action {
+        Action::Input(input) => {
+            let message = input
+                .recipe
+                .evaluate(&TraceContext::new())
+                .unwrap()
+                .downcast::<Message>()
+                .unwrap();
+            match message.payload {
+                MessagePayload::Handshake(handshake) => match handshake.payload {
+                    HandshakePayload::ClientHello(payload) => payload
+                        .extensions
+                        .iter()
+                        .map(|extension| extension.get_type().get_u16())
+                        .collect::<Vec<u16>>(),
+                    _ => panic!("Expected a ClientHello"),
+                },
+                _ => panic!("Expected a handshake message"),
+            }
+        }
+        _ => panic!("Expected an input step"),
+    };
+
+    let original = extension_types(&client_hello_trace());
+
+    loop {
+        let mut trace = client_hello_trace();
+        let result = mutator.mutate(&mut state, &mut trace, 0).unwrap();
+        assert!(matches!(result, MutationResult::Mutated));
+
+        let reordered = extension_types(&trace);
+        if reordered == original {
+            // a different list, e.g. the cipher suites, was reordered
+            continue;
+        }
+
+        // Same multiset of extensions in a different order
+        assert_eq!(
+            reordered.iter().sorted().collect::<Vec<_>>(),
+            original.iter().sorted().collect::<Vec<_>>()
+        );
+        break;
+    }
+}
+
+#[test]
+fn test_record_length_corrupt_mutator() {
+    let rand = StdRand::with_seed(45);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let server = AgentName::first();
+    let mut mutator = RecordLengthCorruptMutator::new(TermConstraints::default());
+
+    let original = seed_client_attacker12(server);
+    let mut trace = original.clone();
+    let result = mutator.mutate(&mut state, &mut trace, 0).unwrap();
+    assert!(matches!(result, MutationResult::Mutated));
+
+    // Only the encrypted Finished is wrapped, the encrypted record itself stays untouched
+    for (step, original_step) in trace.steps.iter().zip(original.steps.iter()) {
+        match (&step.action, &original_step.action) {
+            (Action::Input(input), Action::Input(original_input))
+                if original_input.recipe.name() == fn_encrypt12.name() =>
+            {
+                assert!(input.recipe.name().ends_with("_record_length"));
+                match &input.recipe {
+                    Term::Application(_, subterms) => {
+                        assert_eq!(subterms[0].to_string(), original_input.recipe.to_string())
+                    }
+                    Term::Variable(_) => panic!("Expected an application"),
+                }
+            }
+            (Action::Input(input), Action::Input(original_input)) => {
+                assert_eq!(input.recipe.to_string(), original_input.recipe.to_string())
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_duplicate_extension_mutator() {
+    let rand = StdRand::with_seed(45);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let server = AgentName::first();
+    let mut mutator = DuplicateExtensionMutator::new(TermConstraints::default());
+
+    // Only the ClientHello
+    let mut trace = seed_client_attacker12(server);
+    trace.steps.truncate(1);
+    let original_size = match &trace.steps[0].action {
+        Action::Input(input) => input.recipe.size(),
+        _ => panic!("Expected an input step"),
+    };
+
+    let result = mutator.mutate(&mut state, &mut trace, 0).unwrap();
+    assert!(matches!(result, MutationResult::Mutated));
+
+    let extension_types = match &trace.steps[0].action {
+        Action::Input(input) => {
+            assert!(input.recipe.size() > original_size);
+            let message = input
+                .recipe
+                .evaluate(&TraceContext::new())
+                .unwrap()
+                .downcast::<Message>()
+                .unwrap();
+            match message.payload {
+                MessagePayload::Handshake(handshake) => match handshake.payload {
+                    HandshakePayload::ClientHello(payload) => payload
+                        .extensions
+                        .iter()
+                        .map(|extension| extension.get_type().get_u16())
+                        .collect::<Vec<u16>>(),
+                    _ => panic!("Expected a ClientHello"),
+                },
+                _ => panic!("Expected a handshake message"),
+            }
+        }
+        _ => panic!("Expected an input step"),
+    };
+    assert_eq!(
+        extension_types.iter().unique().count() + 1,
+        extension_types.len()
+    );
+
+    // The server rejects the duplicated extension
+    let _guard = DeterministicRng::enable();
+    let mut ctx = TraceContext::new();
+    let _ = trace.execute(&mut ctx);
+    let alerts = ctx.sent_alerts(server).unwrap();
+    assert!(
+        alerts.contains(&AlertDescription::IllegalParameter),
+        "Unexpected alerts {:?}",
+        alerts
+    );
+}
+
+#[test]
+fn test_duplicate_extension_mutator_constraints() {
+    let rand = StdRand::with_seed(45);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let server = AgentName::first();
+
+    let mut trace = seed_client_attacker12(server);
+    trace.steps.truncate(1);
+    let size = match &trace.steps[0].action {
+        Action::Input(input) => input.recipe.size(),
+        _ => panic!("Expected an input step"),
+    };
+
+    // The recipe already has the maximum size, therefore no extension can be added
+    let mut mutator = DuplicateExtensionMutator::new(TermConstraints {
+        max_term_size: size,
+        ..TermConstraints::default()
+    });
+    for _ in 0..20 {
+        let result = mutator.mutate(&mut state, &mut trace, 0).unwrap();
+        assert!(matches!(result, MutationResult::Skipped));
+    }
+}
+
+#[test]
+fn test_truncate_mutator() {
+    let rand = StdRand::with_seed(45);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let server = AgentName::first();
+    let mut mutator = TruncateMutator::new(TermConstraints::default());
+
+    // Only the ClientHello, which contains a session id of 32 bytes
+    let client_hello_trace = || {
+        let mut trace = seed_client_attacker12(server);
+        trace.steps.truncate(1);
+        trace
+    };
+
+    // The mutator picks between the session id and the other byte vectors of the ClientHello
+    let mut truncated_session_id = false;
+    for _ in 0..100 {
+        let mut trace = client_hello_trace();
+        let result = mutator.mutate(&mut state, &mut trace, 0).unwrap();
+        assert!(matches!(result, MutationResult::Mutated));
+
+        if trace.count_functions_by_name(fn_truncate_session_id.name()) == 0 {
+            continue;
+        }
+
+        let client_hello = match &trace.steps[0].action {
+            Action::Input(input) => input
+                .recipe
+                .evaluate(&TraceContext::new())
+                .unwrap()
+                .downcast::<Message>()
+                .unwrap(),
+            _ => panic!("Expected an input step"),
+        };
+        match client_hello.payload {
+            MessagePayload::Handshake(handshake) => match handshake.payload {
+                HandshakePayload::ClientHello(payload) => {
+                    // Length byte followed by the truncated id
+                    assert!(payload.session_id.get_encoding().len() < 1 + 16);
+                }
+                _ => panic!("Expected a ClientHello"),
+            },
+            _ => panic!("Expected a handshake message"),
+        }
+        truncated_session_id = true;
+        break;
+    }
+    assert!(truncated_session_id);
+
+    // Traces without byte vectors are skipped
+    let mut trace = client_hello_trace();
+    if let Action::Input(input) = &mut trace.steps[0].action {
+        input.recipe = Term::try_application(Signature::new_function(&fn_seq_0), vec![]).unwrap();
+    }
+    let result = mutator.mutate(&mut state, &mut trace, 0).unwrap();
+    assert!(matches!(result, MutationResult::Skipped));
+}
+
+#[test]
+fn test_constant_to_variable_mutator() {
+    let rand = StdRand::with_seed(45);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let server = AgentName::first();
+    let mut mutator = ConstantToVariableMutator::new(TermConstraints::default());
+
+    fn count_variables(trace: &Trace) -> usize {
+        trace
+            .steps
+            .iter()
+            .filter_map(|step| match &step.action {
+                Action::Input(input) => Some(input),
+                _ => None,
+            })
+            .map(|input| {
+                input
+                    .recipe
+                    .into_iter()
+                    .filter(|term| matches!(term, Term::Variable(_)))
+                    .count()
+            })
+            .sum()
+    }
+
+    let mut mutated = 0;
+    for _ in 0..1000 {
+        let mut trace = seed_client_attacker12(server);
+        let before = count_variables(&trace);
+
+        if let MutationResult::Mutated = mutator.mutate(&mut state, &mut trace, 0).unwrap() {
+            assert!(trace.validate().is_ok());
+            assert_eq!(count_variables(&trace), before + 1);
+
+            // The agents may reject the new value, but the variable always finds a value
+            if let Err(crate::error::Error::Term(msg)) = trace.execute(&mut TraceContext::new()) {
+                panic!("Mutated trace is not executable: {}\n{}", msg, trace);
+            }
+
+            mutated += 1;
+            if mutated == 10 {
+                break;
+            }
+        }
+    }
+    assert!(mutated > 0);
+
+    // Messages never contain a transcript, therefore the constant is not replaced
+    let client = server.next();
+    let mut trace = Trace {
+        descriptors: vec![],
+        steps: vec![
+            OutputAction::new_step(client),
+            Step {
+                agent: server,
+                action: Action::Input(InputAction {
+                    recipe: term! { fn_new_transcript12 },
+                }),
+            },
+        ],
+        prior_traces: vec![],
+    };
+    for _ in 0..100 {
+        let result = mutator.mutate(&mut state, &mut trace, 0).unwrap();
+        assert!(matches!(result, MutationResult::Skipped));
+    }
+}
+
+#[test]
+fn test_mutation_log() {
+    let rand = StdRand::with_seed(45);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let server = AgentName::first();
+    let new_mutator = || {
+        PuffinScheduledMutator::new(
+            trace_mutations(1, 15, TermConstraints::default(), 100000),
+            16,
+        )
+    };
+
+    let mut replayed_log = false;
+    for _ in 0..100 {
+        // Stub of the mutational stage, which logs the corpus entry it mutates
+        state.add_metadata(MutationLog {
+            parent: Some(7),
+            entries: vec![],
+        });
+        let mut mutator = new_mutator();
+        let mut trace = seed_client_attacker12(server);
+        mutator.mutate(&mut state, &mut trace, 0).unwrap();
+
+        let log = state.metadata().get::<MutationLog>().unwrap().clone();
+        assert_eq!(log.parent, Some(7));
+        if log.entries.len() < 2 {
+            continue;
+        }
+        assert!(log.entries.iter().all(|entry| !entry.mutator.is_empty()));
+
+        // Applying the logged mutators in order reproduces the trace
+        let mut replayed = seed_client_attacker12(server);
+        new_mutator()
+            .replay(&mut state, &mut replayed, &log, 0)
+            .unwrap();
+        assert_eq!(format!("{}", replayed), format!("{}", trace));
+        replayed_log = true;
+        break;
+    }
+    assert!(replayed_log);
+}
+
+#[test]
+fn test_adaptive_iterations() {
+    let mut iterations = AdaptiveIterations::new(16, 256);
+    assert_eq!(iterations.budget(), 256);
+
+    // Stub of the edges observer: the amount of new edges reported after each run of the stage
+    let new_edges_per_run = [0, 0, 0, 0, 0, 0, 0];
+    let mut budgets = vec![];
+    for new_edges in new_edges_per_run.iter() {
+        iterations.record(*new_edges > 0);
+        budgets.push(iterations.budget());
+    }
+
+    // The budget shrinks while coverage plateaus, but not below the minimum
+    assert!(budgets.windows(2).all(|pair| pair[1] <= pair[0]));
+    assert!(budgets[0] < 256);
+    assert_eq!(*budgets.last().unwrap(), 16);
+
+    // New coverage raises the budget again, but not above the maximum
+    iterations.record(true);
+    assert_eq!(iterations.budget(), 32);
+    for _ in 0..10 {
+        iterations.record(true);
+    }
+    assert_eq!(iterations.budget(), 256);
+
+    // Equal bounds keep the budget static
+    let mut fixed = AdaptiveIterations::new(64, 64);
+    fixed.record(false);
+    assert_eq!(fixed.budget(), 64);
+
+    // By default the budget is fixed
+    let mut default = AdaptiveIterations::new(MIN_ITERATIONS_PER_STAGE, MAX_ITERATIONS_PER_STAGE);
+    default.record(false);
+    assert_eq!(default.budget(), MAX_ITERATIONS_PER_STAGE);
+}
+
+/// The stage spends fewer iterations on an input while its mutations find no new coverage
+#[test]
+fn test_mutational_stage_reduces_iterations() {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let mut manager = NopEventManager::new();
+    // Stub of the edges observer: the feedback never reports new coverage
+    let mut fuzzer = StdFuzzer::new(
+        QueueCorpusScheduler::new(),
+        MutationLogFeedback::new(),
+        CrashSignatureFeedback::new(),
+    );
+
+    let mut harness_fn = |_input: &Trace| ExitKind::Ok;
+    let mut executor = InProcessForkExecutor::new(
+        &mut harness_fn,
+        tuple_list!(),
+        &mut fuzzer,
+        &mut state,
+        &mut manager,
+        StdShMemProvider::new().unwrap(),
+    )
+    .unwrap();
+
+    let server = AgentName::first();
+    let corpus_idx = state
+        .corpus_mut()
+        .add(Testcase::new(seed_client_attacker12(server)))
+        .unwrap();
+
+    let mut stage = PuffinMutationalStage::new(
+        PuffinScheduledMutator::new(
+            trace_mutations(1, 15, TermConstraints::default(), 100000),
+            16,
+        ),
+        4,
+        64,
+    );
+    assert_eq!(stage.budget(), 64);
+
+    let mut budgets = vec![];
+    for _ in 0..6 {
+        stage
+            .perform(
+                &mut fuzzer,
+                &mut executor,
+                &mut state,
+                &mut manager,
+                corpus_idx,
+            )
+            .unwrap();
+        budgets.push(stage.budget());
+    }
+
+    assert_eq!(budgets, vec![32, 16, 8, 4, 4, 4]);
+    assert_eq!(state.corpus().count(), 1);
+}
+
+#[test]
+fn test_campaign_budget() {
+    let unlimited = CampaignBudget::default();
+    assert!(!unlimited.is_limited());
+    assert!(!unlimited.is_exhausted(u64::MAX, Duration::from_secs(u64::MAX)));
+
+    let budget = CampaignBudget::new(Some(100), Some(Duration::from_secs(60)));
+    assert!(budget.is_limited());
+    assert!(!budget.is_exhausted(99, Duration::from_secs(59)));
+    assert!(budget.is_exhausted(100, Duration::from_secs(0)));
+    assert!(budget.is_exhausted(0, Duration::from_secs(60)));
+}
+
+/// The supervisor returns as soon as the campaign exits on its own, long before the wallclock
+/// budget runs out
+#[test]
+fn test_supervise_returns_after_campaign() {
+    let budget = CampaignBudget::new(None, Some(Duration::from_secs(60)));
+
+    let started = Instant::now();
+    supervise(&budget, || std::thread::sleep(Duration::from_millis(100)));
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+/// Runs a whole campaign and therefore takes up to 45 seconds and needs the broker port 1339
+#[test]
+#[ignore]
+fn test_start_returns_after_budget() {
+    let campaign_dir =
+        std::env::temp_dir().join(format!("tlspuffin-budget-{}", std::process::id()));
+    let max_wallclock = Duration::from_secs(30);
+
+    let started = Instant::now();
+    start(FuzzerConfig {
+        core_definition: "0".to_string(),
+        stats_file: campaign_dir.join("stats.json"),
+        stats_format: StatsFormat::Text,
+        on_disk_corpus: campaign_dir.join("corpus"),
+        // Does not exist, therefore the initial corpus is generated
+        corpus_dir: campaign_dir.join("seeds"),
+        objective_dir: campaign_dir.join("crashes"),
+        broker_port: 1339,
+        max_iters: None,
+        static_seed: Some(42),
+        scheduler: SchedulerConfig::Queue,
+        executor: ExecutorConfig::InProcess,
+        differential: false,
+        corpus_shards: None,
+        shard_overlap: 0,
+        min_iterations_per_stage: MIN_ITERATIONS_PER_STAGE,
+        max_iterations_per_stage: MAX_ITERATIONS_PER_STAGE,
+        mutator_weights: vec![],
+        max_total_executions: Some(100),
+        max_wallclock: Some(max_wallclock),
+    });
+
+    // The supervisor kills the campaign at the latest after a grace period
+    assert!(started.elapsed() < max_wallclock + Duration::from_secs(15));
+    let _ = std::fs::remove_dir_all(&campaign_dir);
+}
+
+#[test]
+fn test_executor_config() {
+    assert_eq!("inprocess".parse(), Ok(ExecutorConfig::InProcess));
+    assert_eq!("fork".parse(), Ok(ExecutorConfig::Fork));
+    assert!("forkserver".parse::<ExecutorConfig>().is_err());
+    assert_eq!(ExecutorConfig::default(), ExecutorConfig::InProcess);
+}
+
+/// A crash in a forked child is recorded as a solution, while the fuzzer itself keeps running
+#[test]
+fn test_fork_executor_records_crash() {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let mut manager = NopEventManager::new();
+    let mut fuzzer = StdFuzzer::new(
+        QueueCorpusScheduler::new(),
+        StateFeedback::new(),
+        CrashSignatureFeedback::new(),
+    );
+
+    let client = AgentName::first();
+    let server = client.next();
+    let crashing = seed_client_attacker12(server);
+    let crashing_hash = crashing.canonical_hash();
+
+    // Aborts like the harness does for a violated security claim
+    let mut harness_fn = |input: &Trace| {
+        if input.canonical_hash() == crashing_hash {
+            std::process::abort();
+        }
+        harness(input)
+    };
+    let mut executor = InProcessForkExecutor::new(
+        &mut harness_fn,
+        tuple_list!(),
+        &mut fuzzer,
+        &mut state,
+        &mut manager,
+        StdShMemProvider::new().unwrap(),
+    )
+    .unwrap();
+
+    fuzzer
+        .evaluate_input(&mut state, &mut executor, &mut manager, crashing)
+        .unwrap();
+    assert_eq!(state.solutions().count(), 1);
+
+    fuzzer
+        .evaluate_input(
+            &mut state,
+            &mut executor,
+            &mut manager,
+            seed_successful(client, server),
+        )
+        .unwrap();
+    assert_eq!(state.solutions().count(), 1);
+}
+
+/// Aborts within a forked child, after the harness has finished
+#[inline(never)]
+fn abort_in_child() {
+    std::process::abort();
+}
+
+/// Forked children hand their reached states, crash sites and timeouts to the parent
+#[test]
+fn test_fork_harness_reports_to_parent() {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let mut manager = NopEventManager::new();
+    let mut fuzzer = StdFuzzer::new(
+        QueueCorpusScheduler::new(),
+        StateFeedback::new(),
+        feedback_or!(CrashSignatureFeedback::new(), TimeoutFeedback::new()),
+    );
+
+    let mut shmem_provider = StdShMemProvider::new().unwrap();
+    let report_map = Box::leak(Box::new(shmem_provider.new_map(FORK_REPORT_SIZE).unwrap()));
+    set_fork_report_map(unsafe {
+        std::slice::from_raw_parts_mut(report_map.map_mut().as_mut_ptr(), FORK_REPORT_SIZE)
+    });
+
+    let client = AgentName::first();
+    let server = client.next();
+    let successful = seed_successful(client, server);
+    let crashing = seed_client_attacker12(server);
+    let crashing_hash = crashing.canonical_hash();
+    let mut hanging = seed_client_attacker12(server);
+    hanging
+        .steps
+        .insert(0, Step::new_delay(server, FORK_TIMEOUT * 2));
+    let mut killed = seed_client_attacker12(server);
+    killed
+        .steps
+        .insert(0, Step::new_delay(server, FORK_TIMEOUT / 2));
+    let killed_hash = killed.canonical_hash();
+
+    let mut edges = vec![0; unsafe { MAX_EDGES_NUM }];
+    let mut harness_fn = |input: &Trace| {
+        if input.canonical_hash() == killed_hash {
+            // Killed by a signal which the child can not handle, while it executes the delay
+            std::thread::spawn(|| {
+                std::thread::sleep(FORK_TIMEOUT / 4);
+                nix::sys::signal::kill(nix::unistd::Pid::this(), nix::sys::signal::SIGKILL)
+                    .unwrap();
+            });
+        }
+        let exit_kind = fork_harness(input, &mut edges);
+        if input.canonical_hash() == crashing_hash {
+            abort_in_child();
+        }
+        exit_kind
+    };
+    let mut executor = ForkTimeoutExecutor::new(
+        InProcessForkExecutor::new(
+            &mut harness_fn,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut manager,
+            shmem_provider.clone(),
+        )
+        .unwrap(),
+    );
+
+    fuzzer
+        .evaluate_input(&mut state, &mut executor, &mut manager, successful)
+        .unwrap();
+    let reached_states = &state
+        .metadata()
+        .get::<ReachedStatesMetadata>()
+        .unwrap()
+        .states;
+    assert!(!reached_states.is_empty());
+
+    // The signature is captured on the stack of the child
+    fuzzer
+        .evaluate_input(&mut state, &mut executor, &mut manager, crashing)
+        .unwrap();
+    assert_eq!(state.solutions().count(), 1);
+    let solution = state.solutions().get(0).unwrap().borrow();
+    let signature = solution.metadata().get::<CrashSignature>().unwrap();
+    assert!(signature
+        .top_frames
+        .iter()
+        .any(|frame| frame.contains("abort_in_child")));
+    drop(solution);
+
+    // Killed by a signal, which is a crash without a known site
+    fuzzer
+        .evaluate_input(&mut state, &mut executor, &mut manager, killed)
+        .unwrap();
+    assert_eq!(state.solutions().count(), 2);
+    let solution = state.solutions().get(1).unwrap().borrow();
+    let signature = solution.metadata().get::<CrashSignature>().unwrap();
+    assert!(signature.top_frames.is_empty());
+    drop(solution);
+
+    // Killed after the timeout, which is reported like a timeout of an in-process execution
+    fuzzer
+        .evaluate_input(&mut state, &mut executor, &mut manager, hanging)
+        .unwrap();
+    assert_eq!(state.solutions().count(), 3);
+    let solution = state.solutions().get(2).unwrap().borrow();
+    assert!(solution.metadata().get::<CrashSignature>().is_none());
+}
+
+#[test]
+fn test_weighted_mutator() {
+    let rand = StdRand::with_seed(45);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let server = AgentName::first();
+
+    // Mutators are keyed by name
+    let unknown = PuffinScheduledMutator::with_weights(
+        trace_mutations(1, 15, TermConstraints::default(), 100000),
+        &[("FlipMutator".to_string(), 1.0)],
+        16,
+    );
+    assert!(unknown.is_err());
+
+    // The SkipMutator is never chosen, the others keep the default weight
+    let mut mutator = PuffinScheduledMutator::with_weights(
+        trace_mutations(1, 15, TermConstraints::default(), 100000),
+        &[("SkipMutator".to_string(), 0.0)],
+        16,
+    )
+    .unwrap();
+
+    let mut chosen = HashSet::new();
+    for _ in 0..100 {
+        let mut trace = seed_client_attacker12(server);
+        mutator.mutate(&mut state, &mut trace, 0).unwrap();
+
+        let log = state.metadata().get::<MutationLog>().unwrap();
+        chosen.extend(log.entries.iter().map(|entry| entry.mutator.clone()));
+    }
+
+    assert!(!chosen.iter().any(|name| short_name(name) == "SkipMutator"));
+    assert!(chosen.len() > 1);
+}
+
+/// The observer is shared by all tests, therefore only the inclusion of types is checked
+#[cfg(feature = "tls13")] // require version which supports TLS 1.3
+#[test]
+fn test_message_type_observer() {
+    let client = AgentName::first();
+    let server = client.next();
+
+    harness(&seed_successful(client, server));
+    // All later handshake messages of TLS 1.3 are encrypted
+    assert!(MESSAGE_TYPES.has_seen(HandshakeType::ClientHello));
+    assert!(MESSAGE_TYPES.has_seen(HandshakeType::ServerHello));
+
+    harness(&seed_successful12(client, server));
+    assert!(MESSAGE_TYPES.has_seen(HandshakeType::Certificate));
+    assert!(MESSAGE_TYPES.has_seen(HandshakeType::ServerKeyExchange));
+    assert!(MESSAGE_TYPES.has_seen(HandshakeType::ServerHelloDone));
+    assert!(MESSAGE_TYPES.has_seen(HandshakeType::ClientKeyExchange));
+    assert!(MESSAGE_TYPES.count() >= 6);
+}
+
+/// A TLS 1.2 server rejects a ClientHello which only supports TLS 1.3
+#[test]
+fn test_openssl_errors_feedback() {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let mut manager = NopEventManager::new();
+    let mut feedback = OpenSSLErrorsFeedback::new();
+
+    let server = AgentName::first();
+    let mut trace = seed_client_attacker(server);
+    trace.descriptors[0].tls_version = TLSVersion::V1_2;
+    trace.steps.truncate(1);
+    let is_unsupported_protocol = |error: &String| {
+        error.starts_with("SSL routines:") && error.ends_with(":unsupported protocol")
+    };
+
+    let mut ctx = TraceContext::new();
+    assert!(matches!(
+        trace.execute(&mut ctx),
+        Err(crate::error::Error::OpenSSL(_))
+    ));
+    assert!(ctx.openssl_errors().iter().any(&is_unsupported_protocol));
+
+    harness(&trace);
+    // The feedback only attaches the errors
+    assert!(!feedback
+        .is_interesting(&mut state, &mut manager, &trace, &(), &ExitKind::Ok)
+        .unwrap());
+    let mut testcase = Testcase::new(trace.clone());
+    feedback.append_metadata(&mut state, &mut testcase).unwrap();
+    let errors = &testcase
+        .metadata()
+        .get::<OpenSSLErrorsMetadata>()
+        .unwrap()
+        .errors;
+    assert!(errors.iter().any(&is_unsupported_protocol));
+    // The stats only keep the distinct errors of all executions
+    assert!(OPENSSL_ERRORS.errors().iter().any(&is_unsupported_protocol));
+}
+
+/// Pretends to be a backend which fails before any agent sends a message
+struct FailingBackend;
+
+impl Backend for FailingBackend {
+    fn name(&self) -> &str {
+        "failing"
+    }
+
+    fn execute(&self, _trace: &Trace) -> (TraceContext, Result<(), crate::error::Error>) {
+        (
+            TraceContext::new(),
+            Err(crate::error::Error::Agent("Not implemented".to_string())),
+        )
+    }
+}
+
+/// The differential objective only reports traces if there is a second backend to compare with
+#[test]
+fn test_differential_feedback() {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let mut manager = NopEventManager::new();
+    let client = AgentName::first();
+    let trace = seed_successful12(client, client.next());
+
+    let mut feedback = DifferentialFeedback::new(available_backends());
+    assert!(!feedback.is_enabled());
+    assert!(!feedback
+        .is_interesting(&mut state, &mut manager, &trace, &(), &ExitKind::Ok)
+        .unwrap());
+
+    let mut feedback =
+        DifferentialFeedback::new(vec![Box::new(OpenSSLBackend), Box::new(FailingBackend)]);
+    assert!(feedback.is_enabled());
+    assert!(feedback
+        .is_interesting(&mut state, &mut manager, &trace, &(), &ExitKind::Ok)
+        .unwrap());
+}
+
 #[test]
 fn test_skip_mutator() {
     let rand = StdRand::with_seed(45);
@@ -193,7 +1179,7 @@ fn test_swap_mutator() {
         let is_last_not_encrypt = if let Some(last) = trace.steps.iter().last() {
             match &last.action {
                 Action::Input(input) => Some(input.recipe.name() != fn_encrypt12.name()),
-                Action::Output(_) => None,
+                Action::Output(_) | Action::Delay(_) => None,
             }
         } else {
             None
@@ -202,7 +1188,7 @@ fn test_swap_mutator() {
         let is_first_not_ch = if let Some(first) = trace.steps.iter().nth(0) {
             match &first.action {
                 Action::Input(input) => Some(input.recipe.name() != fn_client_hello.name()),
-                Action::Output(_) => None,
+                Action::Output(_) | Action::Delay(_) => None,
             }
         } else {
             None
@@ -355,7 +1341,7 @@ fn test_corpus_term_size() {
                     .iter()
                     .map(|step| match &step.action {
                         Action::Input(input) => input.recipe.size(),
-                        Action::Output(_) => 0,
+                        Action::Output(_) | Action::Delay(_) => 0,
                     })
                     .sum::<usize>(),
             )
@@ -365,6 +1351,522 @@ fn test_corpus_term_size() {
     println!("{:?}", trace_term_sizes);
 }
 
+#[test]
+fn test_scheduler_config() {
+    assert_eq!("queue".parse(), Ok(SchedulerConfig::Queue));
+    assert_eq!("rand".parse(), Ok(SchedulerConfig::Rand));
+    assert_eq!("power".parse(), Ok(SchedulerConfig::PowerSchedule));
+    assert!("minimizer".parse::<SchedulerConfig>().is_err());
+
+    #[cfg(not(feature = "no-minimizer"))]
+    assert_eq!(SchedulerConfig::default(), SchedulerConfig::Queue);
+}
+
+/// The fuzzer initializes and runs a few iterations with each scheduler, like
+/// [`crate::fuzzer::start`] sets them up
+#[test]
+fn test_schedulers_run() {
+    macro_rules! run_with_scheduler {
+        ($scheduler:expr, |$state:ident, $observer:ident, $mutator:ident| $stages:expr) => {{
+            let edges: &'static mut [u8] = Box::leak(vec![0; 64].into_boxed_slice());
+            let edges_ptr = edges.as_mut_ptr();
+            let $observer = HitcountsMapObserver::new(StdMapObserver::new("edges", edges));
+            let edges_feedback_state = MapFeedbackState::with_observer(&$observer);
+            let time_observer = TimeObserver::new("time");
+            let feedback = feedback_or!(
+                MaxMapFeedback::new_tracking(&edges_feedback_state, &$observer, true, false),
+                TimeFeedback::new_with_observer(&time_observer)
+            );
+
+            let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+            let mut $state = StdState::new(
+                StdRand::with_seed(1235),
+                corpus,
+                InMemoryCorpus::new(),
+                tuple_list!(edges_feedback_state),
+            );
+            let $mutator = PuffinScheduledMutator::new(
+                trace_mutations(1, 15, TermConstraints::default(), 100000),
+                4,
+            );
+            let mut stages = $stages;
+
+            let mut manager = NopEventManager::new();
+            let mut fuzzer = StdFuzzer::new($scheduler, feedback, CrashSignatureFeedback::new());
+            // Stub of the harness which covers a single edge
+            let mut harness_fn = |_input: &Trace| {
+                unsafe { *edges_ptr = 1 };
+                ExitKind::Ok
+            };
+            let mut executor = InProcessExecutor::new(
+                &mut harness_fn,
+                tuple_list!($observer, time_observer),
+                &mut fuzzer,
+                &mut $state,
+                &mut manager,
+            )
+            .unwrap();
+
+            fuzzer
+                .evaluate_input(
+                    &mut $state,
+                    &mut executor,
+                    &mut manager,
+                    seed_client_attacker12(AgentName::first()),
+                )
+                .unwrap();
+            assert_eq!($state.corpus().count(), 1);
+
+            fuzzer
+                .fuzz_loop_for(&mut stages, &mut $state, &mut executor, &mut manager, 3)
+                .unwrap();
+        }};
+    }
+
+    run_with_scheduler!(
+        IndexesLenTimeMinimizerCorpusScheduler::new(QueueCorpusScheduler::new()),
+        |state, observer, mutator| tuple_list!(PuffinMutationalStage::new(mutator, 4, 4))
+    );
+    run_with_scheduler!(RandCorpusScheduler::new(), |state, observer, mutator| {
+        tuple_list!(PuffinMutationalStage::new(mutator, 4, 4))
+    });
+    run_with_scheduler!(
+        PowerQueueCorpusScheduler::new(),
+        |state, observer, mutator| {
+            tuple_list!(
+                CalibrationStage::new(&mut state, &observer),
+                PowerMutationalStage::new(mutator, PowerSchedule::FAST, &observer)
+            )
+        }
+    );
+}
+
+#[test]
+fn test_corpus_shards_are_disjoint() {
+    let corpus_dir = std::env::temp_dir().join("tlspuffin_test_shards");
+    std::fs::create_dir_all(&corpus_dir).unwrap();
+    let corpus = create_corpus();
+    for (trace, name) in corpus.iter() {
+        let buffer = postcard::to_allocvec(trace).unwrap();
+        std::fs::write(corpus_dir.join(format!("{}.trace", name)), buffer).unwrap();
+    }
+
+    // Two simulated cores, each importing its own shard
+    let first = CorpusShard::new(0, 2, 0).seed_files(&corpus_dir).unwrap();
+    let second = CorpusShard::new(1, 2, 0).seed_files(&corpus_dir).unwrap();
+    let everything = CorpusShard::new(1, 2, 1).seed_files(&corpus_dir).unwrap();
+
+    for seed_file in first.iter().chain(second.iter()) {
+        assert!(Trace::from_file(seed_file).is_ok());
+    }
+    std::fs::remove_dir_all(&corpus_dir).unwrap();
+
+    assert!(first.iter().all(|seed_file| !second.contains(seed_file)));
+    assert_eq!(first.len() + second.len(), corpus.len());
+    assert_eq!(everything.len(), corpus.len());
+}
+
+#[test]
+fn test_corpus_stats() {
+    let client = AgentName::first();
+    let server = client.next();
+    let traces = vec![
+        seed_successful(client, server),
+        seed_client_attacker12(server),
+    ];
+
+    let stats = corpus_stats(&traces);
+
+    assert_eq!(stats.traces, 2);
+    // The ClientHello of seed_client_attacker12 is also part of the transcript of its Finished
+    assert_eq!(stats.function_counts["fn_client_hello"], 3);
+    assert_eq!(stats.function_counts["fn_server_hello"], 1);
+    assert_eq!(stats.function_counts["fn_application_data"], 5);
+    assert_eq!(stats.function_counts["fn_encrypt12"], 1);
+    assert_eq!(stats.step_counts[&4], 1);
+    assert_eq!(stats.step_counts.values().sum::<usize>(), stats.traces);
+    // The ClientHello of seed_successful has six variables as arguments
+    assert!(stats.recipe_sizes[&7] >= 1);
+    assert!(stats.recipe_depths[&2] >= 1);
+    let unused = stats.unused_functions();
+    assert!(unused.iter().any(|name| name == "fn_heartbeat"));
+    assert!(!unused.iter().any(|name| name == "fn_client_hello"));
+}
+
+#[test]
+fn test_json_lines_stats() {
+    let events = vec![
+        StatsEvent {
+            executions: 1000,
+            corpus_size: 19,
+            objectives: 0,
+            exec_per_sec: 250,
+            ..StatsEvent::now("Stats", None)
+        },
+        StatsEvent {
+            executions: 500,
+            corpus_size: 20,
+            objectives: 1,
+            edges: Some(1337),
+            exec_per_sec: 125,
+            ..StatsEvent::now("Testcase", Some(1))
+        },
+        StatsEvent::now("Objective", Some(2)),
+    ];
+
+    let mut emitter = JsonLinesEmitter::new(Vec::new());
+    for event in &events {
+        emitter.emit(event).unwrap();
+    }
+    let output = String::from_utf8(emitter.into_inner()).unwrap();
+
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), events.len());
+    for (line, event) in lines.iter().zip(events.iter()) {
+        let value: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert!(value.is_object());
+        assert!(value["timestamp"].as_u64().unwrap() > 0);
+
+        let parsed: StatsEvent = serde_json::from_str(line).unwrap();
+        assert_eq!(&parsed, event);
+    }
+
+    assert_eq!(
+        "jsonl:stats.jsonl".parse::<StatsFormat>(),
+        Ok(StatsFormat::JsonLines(StatsOutput::File(
+            "stats.jsonl".into()
+        )))
+    );
+    assert_eq!("text".parse::<StatsFormat>(), Ok(StatsFormat::Text));
+    assert!("json".parse::<StatsFormat>().is_err());
+}
+
+#[test]
+fn test_generate_random_trace() {
+    let mut rand = StdRand::with_seed(45);
+    let constraints = TermConstraints {
+        min_term_size: 0,
+        max_term_size: 50,
+        max_trace_steps: 3,
+    };
+
+    for _ in 0..100 {
+        let trace = generate_random_trace(&SIGNATURE, &mut rand, constraints);
+
+        assert!(!trace.steps.is_empty());
+        assert!(trace.steps.len() <= constraints.max_trace_steps);
+        trace.validate().unwrap();
+
+        for step in &trace.steps {
+            match &step.action {
+                Action::Input(input) => {
+                    input.recipe.type_check().unwrap();
+                    assert!(input.recipe.size() <= constraints.max_term_size);
+                }
+                Action::Output(_) | Action::Delay(_) => {
+                    panic!("generated traces only contain inputs")
+                }
+            }
+        }
+    }
+}
+
+/// Stands in for a crash site which is reached by different inputs
+#[inline(never)]
+fn crash_site(trace: &Trace) -> CrashSignature {
+    assert!(!trace.steps.is_empty());
+    CrashSignature::capture(CRASH_SIGNATURE_FRAMES)
+}
+
+#[test]
+fn test_crash_signature_same_site() {
+    let client = AgentName::first();
+    let server = client.next();
+
+    let signature_a = crash_site(&seed_successful(client, server));
+    let signature_b = crash_site(&seed_client_attacker12(server));
+
+    assert!(!signature_a.top_frames.is_empty());
+    assert_eq!(signature_a.hash, signature_b.hash);
+    assert_ne!(
+        signature_a.hash,
+        CrashSignature::capture(CRASH_SIGNATURE_FRAMES).hash
+    );
+}
+
+/// Stands in for the crash handler of libafl, which runs the objective on the stack of the crash
+#[inline(never)]
+fn crash_handler(trace: Trace) -> Testcase<Trace> {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let mut manager = NopEventManager::new();
+    let mut feedback = CrashSignatureFeedback::new();
+
+    assert!(feedback
+        .is_interesting(&mut state, &mut manager, &trace, &(), &ExitKind::Crash)
+        .unwrap());
+    let mut testcase = Testcase::new(trace);
+    feedback.append_metadata(&mut state, &mut testcase).unwrap();
+    testcase
+}
+
+#[test]
+fn test_crash_signature_skips_handler_frames() {
+    assert!(is_ignored_frame(
+        "<alloc::boxed::Box<F> as core::ops::function::FnOnce<A>>::call_once"
+    ));
+    assert!(is_ignored_frame(
+        "<<libafl::executors::inprocess::InProcessExecutor<H, I, OT, S> as libafl::executors::Executor<EM, I, S, Z>>::run_target as core::ops::function::FnMut<A>>::call_mut"
+    ));
+    assert!(!is_ignored_frame(
+        "<rustls::msgs::handshake::HandshakeMessagePayload as rustls::msgs::codec::Codec>::read"
+    ));
+
+    let testcase = crash_handler(seed_client_attacker12(AgentName::first()));
+    let signature = testcase.metadata().get::<CrashSignature>().unwrap();
+
+    // The feedback itself is called as `<CrashSignatureFeedback as Feedback<I, S>>::is_interesting`
+    assert!(!signature
+        .top_frames
+        .iter()
+        .any(|frame| frame.contains("CrashSignatureFeedback")));
+    assert!(signature.top_frames[0].contains("crash_handler"));
+}
+
+#[test]
+fn test_parse_sanitizer_report() {
+    let asan = "\
+=================================================================
+==4211==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x619000009f80 at pc 0x55d4c0 bp 0x7ffd sp 0x7ffc
+READ of size 65535 at 0x619000009f80 thread T0
+    #0 0x55d4c0 in __asan_memcpy (/tlspuffin+0x4c0)
+    #1 0x55d4c1 in tls1_process_heartbeat ssl/t1_lib.c:2586:3
+    #2 0x55d4c2 in ssl3_read_bytes ssl/s3_pkt.c:1092:4
+
+0x619000009f80 is located 0 bytes to the right of 1024-byte region
+allocated by thread T0 here:
+    #0 0x55d4d0 in malloc (/tlspuffin+0x4d0)
+";
+    assert_eq!(
+        SanitizerReport::parse(asan),
+        Some(SanitizerReport {
+            kind: "heap-buffer-overflow".to_string(),
+            address: Some(0x619000009f80),
+            stack: vec![
+                "__asan_memcpy (/tlspuffin+0x4c0)".to_string(),
+                "tls1_process_heartbeat ssl/t1_lib.c:2586:3".to_string(),
+                "ssl3_read_bytes ssl/s3_pkt.c:1092:4".to_string(),
+            ],
+        })
+    );
+
+    let double_free =
+        "==4211==ERROR: AddressSanitizer: attempting double-free on 0x602000000010 in thread T0:";
+    let report = SanitizerReport::parse(double_free).unwrap();
+    assert_eq!(report.kind, "double-free");
+    assert_eq!(report.address, Some(0x602000000010));
+    assert!(report.stack.is_empty());
+
+    let ubsan = "ssl/t1_lib.c:123:5: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'";
+    let report = SanitizerReport::parse(ubsan).unwrap();
+    assert_eq!(report.kind, "signed-integer-overflow");
+    assert_eq!(report.address, None);
+
+    assert_eq!(SanitizerReport::parse("thread 'main' panicked"), None);
+}
+
+/// Builds an Ethernet frame which carries a TCP segment from port 50000 to port 443
+fn tcp_frame(client_to_server: bool, seq: u32, payload: &[u8]) -> Vec<u8> {
+    let (source, destination) = if client_to_server {
+        ([10, 0, 0, 1], [10, 0, 0, 2])
+    } else {
+        ([10, 0, 0, 2], [10, 0, 0, 1])
+    };
+    let (source_port, destination_port) = if client_to_server {
+        (50000u16, 443u16)
+    } else {
+        (443u16, 50000u16)
+    };
+
+    let mut frame = vec![0; 12];
+    frame.extend_from_slice(&[0x08, 0x00]);
+    frame.extend_from_slice(&[0x45, 0]);
+    frame.extend_from_slice(&((20 + 20 + payload.len()) as u16).to_be_bytes());
+    frame.extend_from_slice(&[0, 0, 0, 0, 64, 6, 0, 0]);
+    frame.extend_from_slice(&source);
+    frame.extend_from_slice(&destination);
+    frame.extend_from_slice(&source_port.to_be_bytes());
+    frame.extend_from_slice(&destination_port.to_be_bytes());
+    frame.extend_from_slice(&seq.to_be_bytes());
+    frame.extend_from_slice(&[0, 0, 0, 0, 0x50, 0x18, 0xff, 0xff, 0, 0, 0, 0]);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// The shipped initial corpus stays loadable, i.e. functions which it references are neither
+/// renamed nor change their arity
+#[test]
+fn test_load_corpus() {
+    let corpus_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus");
+    let mut count = 0;
+    for entry in std::fs::read_dir(corpus_dir).unwrap() {
+        let path = entry.unwrap().path();
+        let trace = Trace::from_file(&path)
+            .unwrap_or_else(|err| panic!("Failed to load {}: {}", path.display(), err));
+        assert!(!trace.steps.is_empty());
+        count += 1;
+    }
+    assert!(count > 0);
+}
+
+#[test]
+fn test_import_pcap() {
+    let _guard = DeterministicRng::enable();
+    let server = AgentName::first();
+    let trace = seed_client_attacker12(server);
+    let ctx = TraceContext::new();
+    let client_hello = match &trace.steps[0].action {
+        Action::Input(input) => input.recipe.evaluate(&ctx).unwrap(),
+        Action::Output(_) | Action::Delay(_) => panic!("expected the ClientHello"),
+    };
+    let client_hello = client_hello.as_ref().downcast_ref::<Message>().unwrap();
+    let record = OpaqueMessage::from(client_hello.clone()).encode();
+    let (first, second) = record.split_at(record.len() / 2);
+
+    let mut udp_frame = tcp_frame(true, 0, b"not tcp");
+    udp_frame[23] = 17;
+    let frames = vec![
+        tcp_frame(true, 1000, &[]), // SYN
+        udp_frame,
+        // Out of order and retransmitted segments of the ClientHello
+        tcp_frame(true, 1000 + first.len() as u32, second),
+        tcp_frame(true, 1000, first),
+        tcp_frame(true, 1000, first),
+        tcp_frame(false, 5000, &[21, 3, 3, 0, 2, 2, 40]),
+    ];
+
+    let mut capture = vec![];
+    capture.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    capture.extend_from_slice(&[2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    capture.extend_from_slice(&65535u32.to_le_bytes());
+    capture.extend_from_slice(&1u32.to_le_bytes());
+    for frame in frames {
+        capture.extend_from_slice(&[0; 8]);
+        capture.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        capture.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        capture.extend_from_slice(&frame);
+    }
+
+    let path = std::env::temp_dir().join("tlspuffin_test_import.pcap");
+    std::fs::write(&path, capture).unwrap();
+    let traces = import_pcap(&path, &SIGNATURE).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(traces.len(), 1);
+    let trace = &traces[0];
+    assert_eq!(trace.steps.len(), 1);
+    assert!(trace.validate().is_ok());
+
+    // The reassembled record is replayed byte by byte
+    let replayed = match &trace.steps[0].action {
+        Action::Input(input) => input.recipe.evaluate(&ctx).unwrap(),
+        Action::Output(_) | Action::Delay(_) => panic!("expected the replayed ClientHello"),
+    };
+    let replayed = replayed.as_ref().downcast_ref::<OpaqueMessage>().unwrap();
+    assert_eq!(replayed.clone().encode(), record);
+
+    let mut ctx = TraceContext::new();
+    trace.execute(&mut ctx).unwrap();
+    // The server answered the replayed ClientHello
+    assert!(!ctx.knowledge().is_empty());
+}
+
+#[test]
+fn test_byte_havoc_client_hello() {
+    let server = AgentName::first();
+    let trace = seed_client_attacker12(server);
+    let ctx = TraceContext::new();
+    let client_hello = match &trace.steps[0].action {
+        Action::Input(input) => input.recipe.evaluate(&ctx).unwrap(),
+        Action::Output(_) | Action::Delay(_) => panic!("expected the ClientHello"),
+    };
+    let client_hello = client_hello.as_ref().downcast_ref::<Message>().unwrap();
+    let bytes = OpaqueMessage::from(client_hello.clone()).encode();
+
+    assert_eq!(havoc_bytes(&bytes, 42), havoc_bytes(&bytes, 42));
+
+    let decode_errors = (0..100)
+        .filter(|seed| {
+            let mutated = havoc_bytes(&bytes, *seed);
+            let mut deframer = MessageDeframer::new();
+            if deframer.read(&mut mutated.as_slice()).is_err() || deframer.desynced {
+                return true;
+            }
+            deframer
+                .frames
+                .into_iter()
+                .any(|frame| Message::try_from(frame).is_err())
+        })
+        .count();
+
+    assert!(decode_errors > 0);
+}
+
+#[test]
+fn test_byte_havoc_replay() {
+    let _guard = DeterministicRng::enable();
+    let server = AgentName::first();
+    let trace = seed_client_attacker12(server);
+
+    let execute = || {
+        let mut ctx = TraceContext::new();
+        ctx.enable_byte_havoc(42, havoc_bytes);
+        let _ = trace.execute(&mut ctx);
+        ctx.byte_havoc_log().clone()
+    };
+
+    let log = execute();
+    assert!(!log.is_empty());
+    assert_eq!(log[0].mutated, havoc_bytes(&log[0].original, log[0].seed));
+    assert_eq!(log, execute());
+}
+
+#[test]
+fn test_byte_havoc_metadata() {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    state.add_metadata(MutationLog {
+        parent: Some(0),
+        entries: vec![],
+    });
+    let mut feedback = MutationLogFeedback::new();
+    let trace = seed_client_attacker12(AgentName::first());
+
+    // The seed is required to replay the execution of the unmutated trace
+    set_byte_havoc_seed(Some(42));
+    let mut testcase = Testcase::new(trace.clone());
+    feedback.append_metadata(&mut state, &mut testcase).unwrap();
+    set_byte_havoc_seed(None);
+    assert_eq!(
+        testcase.metadata().get::<ByteHavocMetadata>().unwrap().seed,
+        42
+    );
+    assert!(testcase.metadata().get::<MutationLog>().is_none());
+
+    let mut testcase = Testcase::new(trace.clone());
+    feedback.append_metadata(&mut state, &mut testcase).unwrap();
+    assert!(testcase.metadata().get::<ByteHavocMetadata>().is_none());
+    assert!(testcase.metadata().get::<MutationLog>().is_some());
+
+    // The mutational stage clears the log after it finished, such that it does not go stale
+    state.add_metadata(MutationLog::default());
+    let mut testcase = Testcase::new(trace);
+    feedback.append_metadata(&mut state, &mut testcase).unwrap();
+    assert!(testcase.metadata().get::<MutationLog>().is_none());
+}
+
 mod util {
     use crate::agent::{AgentDescriptor, AgentName, TLSVersion};
     use crate::graphviz::write_graphviz;
@@ -420,6 +1922,7 @@ mod util {
                     tls_version: TLSVersion::V1_2,
                     server: true,
                     try_reuse: false,
+                    ..AgentDescriptor::default()
                 }],
                 steps: vec![Step {
                     agent: server,
@@ -435,7 +1938,7 @@ mod util {
                 .iter()
                 .map(|step| match &step.action {
                     Action::Input(input) => input.recipe.count_functions_by_name(find_name),
-                    Action::Output(_) => 0,
+                    Action::Output(_) | Action::Delay(_) => 0,
                 })
                 .sum::<u16>()
         }
@@ -464,3 +1967,192 @@ mod util {
         }
     }
 }
+
+/// A trace is only interesting for the [`StateFeedback`] if it reaches a state of OpenSSL which
+/// no previous trace reached.
+#[test]
+fn test_state_feedback_novel_state() {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let mut manager = NopEventManager::new();
+    let mut feedback = StateFeedback::new();
+
+    let client = AgentName::first();
+    let server = client.next();
+
+    let successful = seed_successful(client, server);
+    let mut client_hello_only = successful.clone();
+    client_hello_only.steps.truncate(2);
+
+    let mut evaluate = |trace: &Trace| {
+        harness(trace);
+        let interesting = feedback
+            .is_interesting(&mut state, &mut manager, trace, &(), &ExitKind::Ok)
+            .unwrap();
+        if interesting {
+            state.corpus_mut().add(Testcase::new(trace.clone())).unwrap();
+        }
+        interesting
+    };
+
+    assert!(evaluate(&client_hello_only));
+    assert!(!evaluate(&client_hello_only));
+    // Only the complete handshake reaches the final state
+    assert!(evaluate(&successful));
+    assert!(!evaluate(&successful));
+
+    assert_eq!(state.corpus().count(), 2);
+    let reached = &state
+        .metadata()
+        .get::<ReachedStatesMetadata>()
+        .unwrap()
+        .states;
+    assert!(reached.contains("SSL negotiation finished successfully"));
+}
+
+#[test]
+fn test_alert_novelty_feedback() {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let mut manager = NopEventManager::new();
+    let mut feedback = AlertNoveltyFeedback::new();
+
+    let agent = AgentName::first();
+
+    // The server sends a handshake_failure because it supports none of the cipher suites
+    let mut handshake_failure = seed_client_attacker12(agent);
+    handshake_failure.steps = vec![Step {
+        agent,
+        action: Action::Input(InputAction {
+            recipe: term! {
+                  fn_client_hello(
+                    fn_protocol_version12,
+                    fn_new_random,
+                    fn_new_session_id,
+                    (fn_append_cipher_suite(
+                        (fn_new_cipher_suites()),
+                        fn_weak_export_cipher_suite
+                    )),
+                    fn_compressions,
+                    fn_client_extensions_new
+                )
+            },
+        }),
+    }];
+    // The client sends an illegal_parameter because of the downgrade sentinel
+    let illegal_parameter = seed_downgrade_sentinel(agent);
+
+    let mut evaluate = |trace: &Trace| {
+        harness(trace);
+        feedback
+            .is_interesting(&mut state, &mut manager, trace, &(), &ExitKind::Ok)
+            .unwrap()
+    };
+
+    assert!(evaluate(&handshake_failure));
+    // The same alert again is not interesting
+    assert!(!evaluate(&handshake_failure));
+    assert!(evaluate(&illegal_parameter));
+    assert!(!evaluate(&illegal_parameter));
+
+    let combinations = &state
+        .metadata()
+        .get::<AlertCombinationsMetadata>()
+        .unwrap()
+        .combinations;
+    assert!(combinations.contains(&(agent, AlertDescription::HandshakeFailure.get_u8())));
+    assert!(combinations.contains(&(agent, AlertDescription::IllegalParameter.get_u8())));
+}
+
+#[test]
+fn test_dedup_feedback() {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let mut manager = NopEventManager::new();
+    let mut feedback = DedupFeedback::new();
+
+    let server = AgentName::first();
+    let trace = seed_client_attacker12(server);
+    let mut is_interesting = |trace: &Trace| {
+        feedback
+            .is_interesting(&mut state, &mut manager, trace, &(), &ExitKind::Ok)
+            .unwrap()
+    };
+
+    assert!(is_interesting(&trace));
+    // Cloning changes the ids of the variables but not the structure
+    assert!(!is_interesting(&trace.clone()));
+    assert!(is_interesting(&seed_client_attacker(server)));
+}
+
+/// Stub of the coverage feedbacks, which reports every execution as new coverage
+struct NewCoverageFeedback {}
+
+impl Named for NewCoverageFeedback {
+    fn name(&self) -> &str {
+        "NewCoverageFeedback"
+    }
+}
+
+impl<S> Feedback<Trace, S> for NewCoverageFeedback
+where
+    S: HasClientPerfStats,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &Trace,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, libafl::Error>
+    where
+        EM: EventFirer<Trace, S>,
+        OT: ObserversTuple<Trace, S>,
+    {
+        Ok(true)
+    }
+}
+
+/// Byte havoc re-executes corpus entries, which must not be discarded as duplicates
+#[test]
+fn test_dedup_keeps_byte_havoc_coverage() {
+    let rand = StdRand::with_seed(1235);
+    let corpus: InMemoryCorpus<Trace> = InMemoryCorpus::new();
+    let mut state = StdState::new(rand, corpus, InMemoryCorpus::new(), ());
+    let mut manager = NopEventManager::new();
+    let mut fuzzer = StdFuzzer::new(
+        QueueCorpusScheduler::new(),
+        feedback_and_fast!(NewCoverageFeedback {}, DedupFeedback::new()),
+        CrashSignatureFeedback::new(),
+    );
+
+    let mut harness_fn = |_input: &Trace| ExitKind::Ok;
+    let mut executor = InProcessForkExecutor::new(
+        &mut harness_fn,
+        tuple_list!(),
+        &mut fuzzer,
+        &mut state,
+        &mut manager,
+        StdShMemProvider::new().unwrap(),
+    )
+    .unwrap();
+
+    let trace = seed_client_attacker12(AgentName::first());
+    fuzzer
+        .evaluate_input(&mut state, &mut executor, &mut manager, trace.clone())
+        .unwrap();
+    fuzzer
+        .evaluate_input(&mut state, &mut executor, &mut manager, trace.clone())
+        .unwrap();
+    assert_eq!(state.corpus().count(), 1);
+
+    set_byte_havoc_seed(Some(42));
+    let result = fuzzer.evaluate_input(&mut state, &mut executor, &mut manager, trace);
+    set_byte_havoc_seed(None);
+    result.unwrap();
+    assert_eq!(state.corpus().count(), 2);
+}
@@ -0,0 +1,186 @@
+//! Generates random [`Trace`]s from a [`Signature`]. The recipes of the generated traces are
+//! well-typed: Functions are only applied to subterms whose return type matches the required
+//! argument type. The generator is used to seed the fuzzer if no initial corpus is available.
+
+use libafl::bolts::rands::Rand;
+use libafl::generators::Generator;
+use libafl::Error;
+use rustls::msgs::message::Message;
+
+use crate::agent::{AgentDescriptor, AgentName, TLSVersion};
+use crate::fuzzer::mutations::util::{Choosable, TermConstraints};
+use crate::term::atoms::Function;
+use crate::term::dynamic_function::TypeShape;
+use crate::term::signature::{FunctionDefinition, Signature};
+use crate::term::Term;
+use crate::tls::SIGNATURE;
+use crate::trace::{AgentClaimer, InputAction, Trace};
+
+const MAX_DEPTH: u16 = 8; // how deep terms we allow max
+const MAX_TRIES: u16 = 100; // How often we want to try to generate a recipe before skipping a step
+const MAX_STEPS: u64 = 4; // How many input steps a generated trace has at most
+
+/// Generates a random trace with a single server agent. Each step of the trace inputs a randomly
+/// generated [`Message`] to the server. The recipes respect the `constraints` and always pass
+/// [`Term::type_check`]. The trace has at most `constraints.max_trace_steps` steps.
+///
+/// Variables are only used for types for which the signature does not contain a constant. They
+/// always reference messages of the server. Therefore, variables are only used after the first
+/// step, as the server produced no output before.
+pub fn generate_random_trace<R: Rand>(
+    signature: &Signature,
+    rand: &mut R,
+    constraints: TermConstraints,
+) -> Trace {
+    let server = AgentName::first();
+    let tls_version = if rand.below(2) == 0 {
+        TLSVersion::V1_2
+    } else {
+        TLSVersion::V1_3
+    };
+
+    let mut steps = Vec::new();
+    let step_count = ((1 + rand.below(MAX_STEPS)) as usize).min(constraints.max_trace_steps);
+    for _ in 0..step_count {
+        let allow_variables = !steps.is_empty();
+        let recipe = (0..MAX_TRIES).find_map(|_| {
+            generate_recipe(
+                signature,
+                TypeShape::of::<Message>(),
+                server,
+                allow_variables,
+                constraints,
+                rand,
+            )
+        });
+
+        if let Some(recipe) = recipe {
+            steps.push(InputAction::new_step(server, recipe));
+        }
+    }
+
+    Trace {
+        prior_traces: vec![],
+        descriptors: vec![AgentDescriptor {
+            name: server,
+            tls_version,
+            server: true,
+            ..AgentDescriptor::default()
+        }],
+        steps,
+    }
+}
+
+fn generate_recipe<R: Rand>(
+    signature: &Signature,
+    typ: TypeShape,
+    agent: AgentName,
+    allow_variables: bool,
+    constraints: TermConstraints,
+    rand: &mut R,
+) -> Option<Term> {
+    let mut budget = constraints.max_term_size;
+    let term = generate_term(
+        signature,
+        typ,
+        agent,
+        allow_variables,
+        MAX_DEPTH,
+        &mut budget,
+        rand,
+    )?;
+
+    if term.size() < constraints.min_term_size {
+        return None;
+    }
+
+    Some(term)
+}
+
+/// Generates a term of type `typ` with at most `budget` symbols. The `budget` is decreased by the
+/// size of the generated term.
+fn generate_term<R: Rand>(
+    signature: &Signature,
+    typ: TypeShape,
+    agent: AgentName,
+    allow_variables: bool,
+    depth: u16,
+    budget: &mut usize,
+    rand: &mut R,
+) -> Option<Term> {
+    if *budget == 0 {
+        return None;
+    }
+
+    let possibilities: &Vec<FunctionDefinition> = signature.functions_by_typ.get(&typ)?;
+
+    // Every argument needs at least one symbol. Once the depth or size is exhausted we bottom out
+    // at constants.
+    let remaining = *budget - 1;
+    let choice = if depth == 0 {
+        possibilities.choose_filtered(|(shape, _)| shape.is_constant(), rand)
+    } else {
+        possibilities.choose_filtered(|(shape, _)| shape.argument_types.len() <= remaining, rand)
+    };
+
+    let (shape, dynamic_fn) = match choice {
+        Some(choice) => choice,
+        None => {
+            // Agent claimers are always available, other variables only if the agent has already
+            // produced output
+            return if allow_variables || typ == TypeShape::of::<AgentClaimer>() {
+                *budget -= 1;
+                Some(Term::Variable(Signature::new_var_by_type_id(
+                    typ, agent, None, 0,
+                )))
+            } else {
+                None
+            };
+        }
+    };
+
+    *budget -= 1;
+
+    let mut subterms = Vec::with_capacity(shape.argument_types.len());
+    for argument_type in &shape.argument_types {
+        subterms.push(generate_term(
+            signature,
+            *argument_type,
+            agent,
+            allow_variables,
+            depth - 1,
+            budget,
+            rand,
+        )?);
+    }
+
+    Term::try_application(Function::new(shape.clone(), dynamic_fn.clone()), subterms).ok()
+}
+
+/// [`Generator`] which seeds the fuzzer with traces from [`generate_random_trace`]
+pub struct TraceGenerator {
+    constraints: TermConstraints,
+}
+
+impl TraceGenerator {
+    pub fn new(constraints: TermConstraints) -> Self {
+        Self { constraints }
+    }
+}
+
+impl<R> Generator<Trace, R> for TraceGenerator
+where
+    R: Rand,
+{
+    fn generate(&mut self, rand: &mut R) -> Result<Trace, Error> {
+        Ok(generate_random_trace(&SIGNATURE, rand, self.constraints))
+    }
+
+    fn generate_dummy(&self) -> Trace {
+        Trace {
+            prior_traces: vec![],
+            descriptors: vec![],
+            steps: vec![],
+        }
+    }
+}
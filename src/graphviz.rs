@@ -26,6 +26,10 @@ const COLOR: &'static str = "#00000000";
 const COLOR_LEAVES: &'static str = "#00000000";
 const SHOW_LABELS: bool = false;
 
+/// Depth after which the subterms of a term are not rendered anymore. Mutated terms can become
+/// arbitrarily deep, which would produce graphs which are neither readable nor renderable.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
 pub fn write_graphviz(output: &str, format: &str, dot_script: &str) -> Result<(), io::Error> {
     let mut child = Command::new("dot")
         .args(&["-o", output, "-T", format])
@@ -82,6 +86,19 @@ impl Trace {
                     i,
                     label=(if SHOW_LABELS { subgraph_name.as_str() } else {""}),
                 ),
+                Action::Delay(duration) => format!(
+                    "subgraph cluster{} \
+                    {{ \
+                        peripheries=0;\
+                        label=\"{label}\";\
+                        delay_{} [label=\"delay {:?}\",shape=\"box\",style=\"dashed\",fontname=\"{font}\"];\
+                    }}",
+                    i,
+                    i,
+                    duration,
+                    label=(if SHOW_LABELS { subgraph_name.as_str() } else {""}),
+                    font=FONT,
+                ),
             };
 
             subgraphs.push(subgraph);
@@ -119,40 +136,72 @@ impl Term {
         )
     }
 
+    /// Collects the nodes and edges of `root`. The terms are traversed using an explicit stack
+    /// such that deep terms can not overflow the call stack. Subterms deeper than `max_depth`
+    /// are replaced by a single truncation marker.
     fn collect_statements(
-        term: &Term,
+        root: &Term,
         tree_mode: bool,
         cluster_id: usize,
+        max_depth: usize,
         statements: &mut Vec<String>,
     ) {
-        match term {
-            Term::Variable(variable) => {
-                statements.push(format!(
-                    "{} {} [fontname=\"{}\"];",
-                    term.unique_id(tree_mode, cluster_id),
-                    Self::node_attributes(variable, COLOR_LEAVES, SHAPE_LEAVES),
-                    FONT
-                ));
-            }
-            Term::Application(func, subterms) => {
-                statements.push(format!(
-                    "{} {} [fontname=\"{}\"];",
-                    term.unique_id(tree_mode, cluster_id),
-                    Self::node_attributes(
-                        remove_fn_prefix(&remove_prefix(func.name())),
-                        if func.arity() == 0 { COLOR_LEAVES } else { COLOR },
-                        if func.arity() == 0 { SHAPE_LEAVES} else { SHAPE }
-                    ),
-                    FONT
-                ));
-
-                for subterm in subterms {
+        let mut stack: Vec<(&Term, usize)> = vec![(root, 0)];
+
+        while let Some((term, depth)) = stack.pop() {
+            match term {
+                Term::Variable(variable) => {
                     statements.push(format!(
-                        "{} -> {};",
+                        "{} {} [fontname=\"{}\"];",
                         term.unique_id(tree_mode, cluster_id),
-                        subterm.unique_id(tree_mode, cluster_id)
+                        Self::node_attributes(variable, COLOR_LEAVES, SHAPE_LEAVES),
+                        FONT
                     ));
-                    Self::collect_statements(subterm, tree_mode, cluster_id, statements);
+                }
+                Term::Application(func, subterms) => {
+                    statements.push(format!(
+                        "{} {} [fontname=\"{}\"];",
+                        term.unique_id(tree_mode, cluster_id),
+                        Self::node_attributes(
+                            remove_fn_prefix(&remove_prefix(func.name())),
+                            if func.arity() == 0 { COLOR_LEAVES } else { COLOR },
+                            if func.arity() == 0 { SHAPE_LEAVES} else { SHAPE }
+                        ),
+                        FONT
+                    ));
+
+                    if subterms.is_empty() {
+                        continue;
+                    }
+
+                    if depth >= max_depth {
+                        let marker_id = format!("t_{}", term.unique_id(tree_mode, cluster_id));
+                        statements.push(format!(
+                            "{} {} [fontname=\"{}\"];",
+                            marker_id,
+                            Self::node_attributes("...", COLOR_LEAVES, SHAPE_LEAVES),
+                            FONT
+                        ));
+                        statements.push(format!(
+                            "{} -> {};",
+                            term.unique_id(tree_mode, cluster_id),
+                            marker_id
+                        ));
+                        continue;
+                    }
+
+                    for subterm in subterms.iter() {
+                        statements.push(format!(
+                            "{} -> {};",
+                            term.unique_id(tree_mode, cluster_id),
+                            subterm.unique_id(tree_mode, cluster_id)
+                        ));
+                    }
+
+                    // reversed such that the first subterm is visited first
+                    for subterm in subterms.iter().rev() {
+                        stack.push((subterm, depth + 1));
+                    }
                 }
             }
         }
@@ -162,8 +211,20 @@ impl Term {
     /// clusters or nodes outside of this subgraph. Therefore, only trees are generated. If it is
     /// false, then graphs are rendered.
     pub fn dot_subgraph(&self, tree_mode: bool, cluster_id: usize, label: &str) -> String {
+        self.dot_subgraph_max_depth(tree_mode, cluster_id, label, DEFAULT_MAX_DEPTH)
+    }
+
+    /// Like [`Term::dot_subgraph`], but subterms deeper than `max_depth` are replaced by a
+    /// truncation marker.
+    pub fn dot_subgraph_max_depth(
+        &self,
+        tree_mode: bool,
+        cluster_id: usize,
+        label: &str,
+        max_depth: usize,
+    ) -> String {
         let mut statements = Vec::new();
-        Self::collect_statements(self, tree_mode, cluster_id, &mut statements);
+        Self::collect_statements(self, tree_mode, cluster_id, max_depth, &mut statements);
         format!(
             "subgraph cluster{} \
             {{ \
@@ -182,8 +243,13 @@ impl Term {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use crate::agent::AgentName;
     use crate::fuzzer::seeds::seed_client_attacker12;
+    use crate::term;
+    use crate::trace::Step;
+    use crate::tls::fn_impl::*;
 
     #[test]
     fn test_dot_graph() {
@@ -191,4 +257,39 @@ mod tests {
         let trace = seed_client_attacker12(server);
         println!("{}", trace.dot_graph(true));
     }
+
+    #[test]
+    fn test_dot_graph_delay() {
+        let server = AgentName::first();
+        let mut trace = seed_client_attacker12(server);
+        trace
+            .steps
+            .insert(1, Step::new_delay(server, Duration::from_millis(10)));
+
+        let graph = trace.dot_graph(true);
+        assert!(graph.contains("delay_1 [label=\"delay 10ms\""));
+    }
+
+    #[test]
+    fn test_dot_graph_deep_term() {
+        let mut deep = term! { fn_new_cipher_suites };
+        for _ in 0..10_000 {
+            deep = term! {
+                fn_append_cipher_suite(
+                    (@deep),
+                    fn_cipher_suite12
+                )
+            };
+        }
+
+        let graph = deep.dot_subgraph_max_depth(true, 0, "deep", 64);
+        assert!(graph.contains("label=\"...\""));
+        assert_eq!(graph.matches("append_cipher_suite").count(), 65);
+
+        let graph = deep.dot_subgraph_max_depth(true, 0, "deep", usize::MAX);
+        assert_eq!(graph.matches("append_cipher_suite").count(), 10_000);
+
+        // The drop glue of terms is recursive and not subject of this test
+        std::mem::forget(deep);
+    }
 }
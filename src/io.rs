@@ -16,6 +16,10 @@
 //! Bob can then read the data from his *inbound channel* and put data in his *outbound channel*.
 //! If Bob is an [`Agent`], which has an underlying *OpenSSLStream* then OpenSSL may write into the
 //! *outbound channel* of Bob.
+//!
+//! Kernel TLS offload (KTLS) is not supported. OpenSSL only hands the record layer to the kernel
+//! if it is connected to a socket, but the channels are memory buffers, see
+//! [`AgentDescriptor::ktls`].
 
 use std::cell::RefCell;
 use std::convert::TryFrom;
@@ -26,24 +30,56 @@ use std::{
 };
 
 use foreign_types_shared::ForeignTypeRef;
-use openssl::ssl::SslStream;
-use rustls::msgs::message::OpaqueMessage;
+use openssl::ssl::{SslRef, SslStream};
+use rustls::msgs::base::Payload;
+use rustls::msgs::codec::{Codec, Reader};
+use rustls::msgs::enums::{AlertDescription, ContentType};
+use rustls::msgs::message::{MessagePayload, OpaqueMessage};
 use rustls::msgs::{deframer::MessageDeframer, message::Message};
+use rustls::ProtocolVersion;
 use security_claims::Claim;
 #[cfg(feature = "claims")]
 use security_claims::{deregister_claimer, register_claimer};
 
-use crate::agent::{AgentDescriptor, AgentName, TLSVersion};
+use crate::agent::{AgentDescriptor, AgentName};
 use crate::debug::debug_opaque_message_with_info;
 use crate::error::Error;
 use crate::openssl_binding;
+use crate::openssl_binding::{BackendCapabilities, KeyLog, BACKEND_CAPABILITIES};
 use crate::trace::VecClaimer;
 
 pub trait Stream: std::io::Read + std::io::Write {
     fn add_to_inbound(&mut self, result: &OpaqueMessage);
 
+    /// Adds raw bytes to the inbound channel. The bytes are not required to form valid records.
+    fn add_bytes_to_inbound(&mut self, bytes: &[u8]);
+
+    /// Adds several records to the inbound channel, e.g. the fragments of a single message
+    fn add_all_to_inbound(&mut self, results: &[OpaqueMessage]) {
+        for result in results {
+            self.add_to_inbound(result);
+        }
+    }
+
     /// Takes a single TLS message from the outbound channel
     fn take_message_from_outbound(&mut self) -> Result<Option<MessageResult>, Error>;
+
+    /// Takes all TLS messages which are currently in the outbound channel, in the order in which
+    /// they have been written
+    fn take_all_messages_from_outbound(&mut self) -> Result<Vec<MessageResult>, Error>;
+}
+
+/// Maximum length of the fragment of a record, see
+/// [RFC 5246 6.2.3](https://datatracker.ietf.org/doc/html/rfc5246#section-6.2.3)
+const MAX_RECORD_PAYLOAD: usize = 16384 + 2048;
+
+/// Outcome of deframing the first record of the outbound channel
+enum DeframedRecord {
+    /// A complete record and the amount of bytes it occupies in the channel
+    Complete(OpaqueMessage, usize),
+    /// The channel only holds a prefix of a record, e.g. because the rest has not been written
+    /// yet
+    Incomplete,
 }
 
 /// Describes in- or outbound channels of an [`crate::agent::Agent`]. Each [`crate::agent::Agent`] can send and receive data.
@@ -60,48 +96,146 @@ pub type Channel = io::Cursor<Vec<u8>>;
 ///
 /// **Note: There need to be two separate buffer! Else for example a TLS socket would read and write
 /// into the same buffer**
+#[derive(Clone)]
 pub struct MemoryStream {
     inbound: Channel,
     outbound: Channel,
+    /// Plaintext alerts which have been written to the outbound channel
+    sent_alerts: Vec<AlertDescription>,
+    /// Plaintext alerts which have been added to the inbound channel
+    received_alerts: Vec<AlertDescription>,
+    /// Errors which OpenSSL reported for failed operations, see
+    /// [`openssl_binding::error_strings`]
+    openssl_errors: Vec<String>,
+}
+
+/// Registration of a [`VecClaimer`] for the claims of a single SSL object. Dropping the guard
+/// deregisters the claimer, therefore the C callback never outlives the stream it reports for and
+/// each SSL object has at most one registered claimer.
+struct ClaimerRegistration {
+    #[cfg(feature = "claims")]
+    ssl: *const std::ffi::c_void,
+    claimer: Rc<RefCell<VecClaimer>>,
+    agent_name: AgentName,
+}
+
+impl ClaimerRegistration {
+    fn register(
+        ssl: &SslRef,
+        claimer: Rc<RefCell<VecClaimer>>,
+        agent_name: AgentName,
+    ) -> ClaimerRegistration {
+        #[cfg(feature = "claims")]
+        {
+            let claimer = claimer.clone();
+            register_claimer(ssl.as_ptr().cast(), move |claim: Claim| {
+                (*claimer).borrow_mut().claim(agent_name, claim)
+            });
+        }
+        #[cfg(not(feature = "claims"))]
+        let _ = ssl;
+
+        ClaimerRegistration {
+            #[cfg(feature = "claims")]
+            ssl: ssl.as_ptr().cast(),
+            claimer,
+            agent_name,
+        }
+    }
+}
+
+impl Drop for ClaimerRegistration {
+    fn drop(&mut self) {
+        #[cfg(feature = "claims")]
+        deregister_claimer(self.ssl.cast());
+    }
 }
 
 /// A MemoryStream which wraps an SslStream.
 pub struct OpenSSLStream {
+    /// Declared before the `openssl_stream` such that the claimer is deregistered before the SSL
+    /// object is freed. It is only `None` while the claimer is replaced.
+    claimer_registration: Option<ClaimerRegistration>,
     openssl_stream: SslStream<MemoryStream>,
+    /// Whether this is a server which still waits for 0-RTT data
+    reading_early_data: bool,
+    /// 0-RTT data which has been received so far
+    early_data: Vec<u8>,
+    server_accepts_early_data: bool,
+    /// Decrypted application data which has been received after the handshake
+    application_data: Vec<u8>,
+    /// Whether this is a server which still waits for a ClientHello with a valid cookie
+    awaiting_cookie: bool,
+    server_stateless_retry: bool,
+    /// Receives the secrets of the connection, see [`AgentDescriptor::key_log`]
+    key_log: Option<KeyLog>,
 }
 
 impl OpenSSLStream {
     pub fn new(
-        server: bool,
-        tls_version: &TLSVersion,
-        agent_name: AgentName,
+        descriptor: &AgentDescriptor,
         claimer: Rc<RefCell<VecClaimer>>,
     ) -> Result<Self, Error> {
         let memory_stream = MemoryStream::new();
-        let openssl_stream = if server {
-            //let (cert, pkey) = openssl_binding::generate_cert();
-            let (cert, pkey) = openssl_binding::static_rsa_cert()?;
-            openssl_binding::create_openssl_server(memory_stream, &cert, &pkey, tls_version)?
+        let key_log = match &descriptor.key_log {
+            Some(path) => Some(KeyLog::open(path)?),
+            None => None,
+        };
+        let openssl_stream = if descriptor.server {
+            let (cert, pkey) = match &descriptor.cert {
+                Some(params) => openssl_binding::generate_cert_cached(params)?,
+                None => openssl_binding::static_rsa_cert()?,
+            };
+            openssl_binding::create_openssl_server(
+                memory_stream,
+                &cert,
+                &pkey,
+                descriptor,
+                key_log.as_ref(),
+            )?
         } else {
-            openssl_binding::create_openssl_client(memory_stream, tls_version)?
+            openssl_binding::create_openssl_client(memory_stream, descriptor, key_log.as_ref())?
         };
 
-        let mut stream = OpenSSLStream { openssl_stream };
-        stream.register_claimer(claimer, agent_name);
-        Ok(stream)
+        let server_accepts_early_data = descriptor.server && descriptor.max_early_data > 0;
+        let server_stateless_retry = descriptor.server && descriptor.stateless_retry;
+        let claimer_registration = Some(ClaimerRegistration::register(
+            openssl_stream.ssl(),
+            claimer,
+            descriptor.name,
+        ));
+        Ok(OpenSSLStream {
+            claimer_registration,
+            openssl_stream,
+            reading_early_data: server_accepts_early_data,
+            early_data: vec![],
+            server_accepts_early_data,
+            application_data: vec![],
+            awaiting_cookie: server_stateless_retry,
+            server_stateless_retry,
+            key_log,
+        })
     }
 
-    fn register_claimer(&mut self, claimer: Rc<RefCell<VecClaimer>>, agent_name: AgentName) {
-        #[cfg(feature = "claims")]
-        register_claimer(
-            self.openssl_stream.ssl().as_ptr().cast(),
-            move |claim: Claim| (*claimer).borrow_mut().claim(agent_name, claim),
-        );
+    /// Replaces the registered claimer. The previous registration is dropped before the new one
+    /// is made, therefore a panic during the registration never leaves a stale claimer behind.
+    fn replace_claimer(&mut self, claimer: Rc<RefCell<VecClaimer>>, agent_name: AgentName) {
+        self.claimer_registration.take();
+        self.claimer_registration = Some(ClaimerRegistration::register(
+            self.openssl_stream.ssl(),
+            claimer,
+            agent_name,
+        ));
     }
 
-    fn deregister_claimer(&mut self) {
-        #[cfg(feature = "claims")]
-        deregister_claimer(self.openssl_stream.ssl().as_ptr().cast());
+    /// Version string of the linked library, e.g. `OpenSSL 1.1.1k  25 Mar 2021`
+    pub fn backend_version() -> &'static str {
+        openssl_binding::openssl_version()
+    }
+
+    /// Features which the linked library supports
+    pub fn backend_capabilities() -> BackendCapabilities {
+        BACKEND_CAPABILITIES
     }
 
     pub fn describe_state(&self) -> &'static str {
@@ -113,25 +247,167 @@ impl OpenSSLStream {
         self.openssl_stream.ssl().state_string_long()
     }
 
+    /// Returns information about the connection which has been negotiated so far.
+    pub fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            alpn_protocol: openssl_binding::selected_alpn_protocol(&self.openssl_stream),
+            session_reused: self.openssl_stream.ssl().session_reused(),
+            version: self.openssl_stream.ssl().version_str().to_string(),
+            ocsp_response: openssl_binding::ocsp_response(&self.openssl_stream),
+            ktls: false,
+        }
+    }
+
+    /// Exports `out_len` bytes of keying material for the `label` and optional `context`. This
+    /// is only possible after the handshake completed.
+    pub fn export_keying_material(
+        &self,
+        label: &[u8],
+        context: Option<&[u8]>,
+        out_len: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let label = std::str::from_utf8(label)
+            .map_err(|err| Error::Stream(format!("Exporter label is not UTF-8: {}", err)))?;
+        openssl_binding::export_keying_material(&self.openssl_stream, label, context, out_len)
+    }
+
+    /// Returns the master secret of the current session, if the backend exposes it
+    pub fn master_secret(&self) -> Option<Vec<u8>> {
+        openssl_binding::master_secret(&self.openssl_stream)
+    }
+
+    /// Whether the 0-RTT data of the peer has been accepted
+    pub fn early_data_status(&self) -> EarlyDataStatus {
+        openssl_binding::early_data_status(&self.openssl_stream)
+    }
+
+    /// 0-RTT data which this server received so far
+    pub fn received_early_data(&self) -> &Vec<u8> {
+        &self.early_data
+    }
+
+    /// Whether the handshake of this agent completed
+    pub fn is_handshake_complete(&self) -> bool {
+        openssl_binding::is_handshake_complete(&self.openssl_stream)
+    }
+
+    /// Encrypts `data` with the keys of the established connection and writes the records to the
+    /// outbound channel
+    pub fn write_application_data(&mut self, data: &[u8]) -> Result<(), Error> {
+        if !self.is_handshake_complete() {
+            return Err(Error::Stream(
+                "Application data can only be sent after the handshake".to_string(),
+            ));
+        }
+        openssl_binding::write_application_data(&mut self.openssl_stream, data)
+    }
+
+    /// Decrypted application data which this agent received after the handshake so far
+    pub fn received_application_data(&self) -> &Vec<u8> {
+        &self.application_data
+    }
+
     pub fn next_state(&mut self) -> Result<(), Error> {
         let stream = &mut self.openssl_stream;
-        Ok(openssl_binding::do_handshake(stream)?)
+
+        if self.awaiting_cookie {
+            // The server keeps no state until the client echoes the cookie
+            let accepted = openssl_binding::stateless(stream)?;
+            self.awaiting_cookie = !accepted;
+            if !accepted {
+                return Ok(());
+            }
+        }
+
+        if self.reading_early_data {
+            // Early data has to be read before the handshake can continue
+            let finished = openssl_binding::read_early_data(stream, &mut self.early_data)?;
+            self.reading_early_data = !finished;
+            if !finished {
+                return Ok(());
+            }
+        }
+
+        openssl_binding::do_handshake(stream, &mut self.application_data)?;
+
+        // The secrets of the handshake are complete, make them available to external tools
+        if let Some(key_log) = &self.key_log {
+            if openssl_binding::is_handshake_complete(&self.openssl_stream) {
+                key_log.flush()?;
+            }
+        }
+
+        Ok(())
     }
 
     pub fn change_agent_name(&mut self, claimer: Rc<RefCell<VecClaimer>>, agent_name: AgentName) {
-        self.deregister_claimer();
-        self.register_claimer(claimer, agent_name)
+        self.replace_claimer(claimer, agent_name)
     }
 
+    /// Resets the connection such that the stream can be reused for a completely new connection.
+    /// The channels are cleared and the session of the previous connection is not resumed.
     pub fn reset(&mut self) {
         self.openssl_stream.clear();
+        openssl_binding::forget_session(&mut self.openssl_stream);
+        self.openssl_stream.get_mut().clear();
+        self.reading_early_data = self.server_accepts_early_data;
+        self.early_data.clear();
+        self.application_data.clear();
+        self.awaiting_cookie = self.server_stateless_retry;
+
+        // Clearing the SSL object may drop its claimer, therefore register it again
+        if let Some(ClaimerRegistration {
+            claimer,
+            agent_name,
+            ..
+        }) = &self.claimer_registration
+        {
+            let (claimer, agent_name) = (claimer.clone(), *agent_name);
+            self.replace_claimer(claimer, agent_name);
+        }
     }
-}
 
-#[cfg(feature = "claims")]
-impl Drop for OpenSSLStream {
-    fn drop(&mut self) {
-        self.deregister_claimer();
+    /// Alerts which this agent sent so far. Encrypted alerts are not included.
+    pub fn sent_alerts(&self) -> &Vec<AlertDescription> {
+        &self.openssl_stream.get_ref().sent_alerts
+    }
+
+    /// Alerts which this agent received so far. Encrypted alerts are not included.
+    pub fn received_alerts(&self) -> &Vec<AlertDescription> {
+        &self.openssl_stream.get_ref().received_alerts
+    }
+
+    /// Errors which OpenSSL reported for the failed operations of this agent so far, in the
+    /// format `library:function:reason`
+    pub fn openssl_errors(&self) -> &Vec<String> {
+        &self.openssl_stream.get_ref().openssl_errors
+    }
+
+    /// Starts a renegotiation of the established TLS 1.2 connection, see
+    /// [`openssl_binding::renegotiate`]
+    pub fn renegotiate(&mut self) -> Result<(), Error> {
+        openssl_binding::renegotiate(&mut self.openssl_stream)
+    }
+
+    /// See [`MemoryStream::peek_inbound`]
+    pub fn peek_inbound(&self) -> &[u8] {
+        self.openssl_stream.get_ref().peek_inbound()
+    }
+
+    /// See [`MemoryStream::peek_outbound`]
+    pub fn peek_outbound(&self) -> &[u8] {
+        self.openssl_stream.get_ref().peek_outbound()
+    }
+
+    /// Copy of the channels and alerts of this stream, see [`OpenSSLStream::restore_buffers`]
+    pub fn snapshot_buffers(&self) -> MemoryStream {
+        self.openssl_stream.get_ref().clone()
+    }
+
+    /// Replaces the channels and alerts of this stream. The state of the SSL object is not
+    /// affected, therefore bytes which it already read are not read again.
+    pub fn restore_buffers(&mut self, buffers: MemoryStream) {
+        *self.openssl_stream.get_mut() = buffers;
     }
 }
 
@@ -140,9 +416,19 @@ impl Stream for OpenSSLStream {
         self.openssl_stream.get_mut().add_to_inbound(result)
     }
 
+    fn add_bytes_to_inbound(&mut self, bytes: &[u8]) {
+        self.openssl_stream.get_mut().add_bytes_to_inbound(bytes)
+    }
+
     fn take_message_from_outbound(&mut self) -> Result<Option<MessageResult>, Error> {
         self.openssl_stream.get_mut().take_message_from_outbound()
     }
+
+    fn take_all_messages_from_outbound(&mut self) -> Result<Vec<MessageResult>, Error> {
+        self.openssl_stream
+            .get_mut()
+            .take_all_messages_from_outbound()
+    }
 }
 
 impl Read for OpenSSLStream {
@@ -166,55 +452,206 @@ impl MemoryStream {
         Self {
             inbound: io::Cursor::new(Vec::new()),
             outbound: io::Cursor::new(Vec::new()),
+            sent_alerts: vec![],
+            received_alerts: vec![],
+            openssl_errors: vec![],
+        }
+    }
+
+    /// Empties both channels and forgets the alerts and errors
+    pub fn clear(&mut self) {
+        self.inbound = io::Cursor::new(Vec::new());
+        self.outbound = io::Cursor::new(Vec::new());
+        self.sent_alerts.clear();
+        self.received_alerts.clear();
+        self.openssl_errors.clear();
+    }
+
+    pub(crate) fn record_openssl_error(&mut self, error: String) {
+        self.openssl_errors.push(error);
+    }
+
+    /// Returns the bytes of the inbound channel which have not been read yet. The channel is not
+    /// modified.
+    pub fn peek_inbound(&self) -> &[u8] {
+        let buffer = self.inbound.get_ref();
+        let position = (self.inbound.position() as usize).min(buffer.len());
+        &buffer[position..]
+    }
+
+    /// Returns the bytes of the outbound channel which have not been taken yet, see
+    /// [`Stream::take_message_from_outbound`]. The channel is not modified.
+    pub fn peek_outbound(&self) -> &[u8] {
+        self.outbound.get_ref()
+    }
+
+    /// Tries to decode `opaque_message`. The [`OpaqueMessage`] is kept even if decoding fails.
+    fn decode_message(opaque_message: OpaqueMessage) -> MessageResult {
+        let message = match Message::try_from(opaque_message.clone()) {
+            Ok(message) => Some(message),
+            Err(err) => {
+                error!("Failed to decode message! This means we maybe need to remove logical checks from rustls! {}", err);
+                None
+            }
+        };
+
+        MessageResult(message, opaque_message)
+    }
+
+    /// Deframes the first record of `buffer`. Records with an empty fragment are complete
+    /// records. Fails if the length field exceeds the maximum length of a record, because such a
+    /// record never completes.
+    fn deframe_record(buffer: &[u8]) -> Result<DeframedRecord, Error> {
+        let mut reader = Reader::init(buffer);
+        let (typ, version, length) = match (
+            ContentType::read(&mut reader),
+            ProtocolVersion::read(&mut reader),
+            u16::read(&mut reader),
+        ) {
+            (Some(typ), Some(version), Some(length)) => (typ, version, length as usize),
+            _ => return Ok(DeframedRecord::Incomplete),
+        };
+
+        if length > MAX_RECORD_PAYLOAD {
+            return Err(Error::Stream(format!(
+                "Failed to deframe binary buffer: record length {} exceeds {}",
+                length, MAX_RECORD_PAYLOAD
+            )));
+        }
+
+        match reader.take(length) {
+            Some(fragment) => Ok(DeframedRecord::Complete(
+                OpaqueMessage {
+                    typ,
+                    version,
+                    payload: Payload::new(fragment.to_vec()),
+                },
+                reader.used(),
+            )),
+            None => Ok(DeframedRecord::Incomplete),
+        }
+    }
+
+    /// Removes the first `length` bytes from the outbound channel
+    fn consume_outbound(&mut self, length: usize) {
+        let buffer = self.outbound.get_mut();
+        buffer.drain(..length);
+        let end = buffer.len() as u64;
+        self.outbound.set_position(end);
+    }
+
+    /// Decodes all plaintext alerts in `records`
+    fn decode_alerts(mut records: &[u8]) -> Vec<AlertDescription> {
+        let mut deframer = MessageDeframer::new();
+        if deframer.read(&mut records).is_err() {
+            return vec![];
         }
+
+        deframer
+            .frames
+            .into_iter()
+            .filter(|opaque_message| opaque_message.typ == ContentType::Alert)
+            .filter_map(|opaque_message| match Message::try_from(opaque_message) {
+                Ok(Message {
+                    payload: MessagePayload::Alert(alert),
+                    ..
+                }) => Some(alert.description),
+                _ => None,
+            })
+            .collect()
     }
 }
 
+/// Parameters negotiated between an [`OpenSSLStream`] and its peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// The application protocol selected through ALPN, if any.
+    pub alpn_protocol: Option<Vec<u8>>,
+    /// Whether a previous session has been resumed, e.g. through a session ticket.
+    pub session_reused: bool,
+    /// The negotiated protocol version as reported by OpenSSL, e.g. `TLSv1.3`.
+    pub version: String,
+    /// The OCSP response which the server stapled, see [`AgentDescriptor::request_ocsp`].
+    pub ocsp_response: Option<Vec<u8>>,
+    /// Whether records are encrypted or decrypted by the kernel. Never the case with memory
+    /// buffers, see [`AgentDescriptor::ktls`].
+    pub ktls: bool,
+}
+
+/// Outcome of the 0-RTT data sent by a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EarlyDataStatus {
+    NotSent,
+    Rejected,
+    Accepted,
+}
+
 pub struct MessageResult(pub Option<Message>, pub OpaqueMessage);
 
+/// A record whose length field is set explicitly. In contrast to [`OpaqueMessage::encode`], the
+/// encoding does not derive the length field from the payload. Therefore, the length field may
+/// disagree with the actual length of the payload.
+#[derive(Clone, Debug)]
+pub struct RawRecord {
+    pub typ: ContentType,
+    pub version: ProtocolVersion,
+    pub length: u16,
+    pub payload: Vec<u8>,
+}
+
+impl RawRecord {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(5 + self.payload.len());
+        self.typ.encode(&mut bytes);
+        self.version.encode(&mut bytes);
+        self.length.encode(&mut bytes);
+        bytes.extend_from_slice(&self.payload);
+        bytes
+    }
+}
+
+/// Application data which an agent sends through its established connection. The agent encrypts
+/// the data and writes the resulting records to its outbound channel.
+#[derive(Clone, Debug)]
+pub struct PlaintextApplicationData(pub Vec<u8>);
+
 impl Stream for MemoryStream {
     fn add_to_inbound(&mut self, opaque_message: &OpaqueMessage) {
-        let mut out: Vec<u8> = Vec::new();
-        out.append(&mut opaque_message.clone().encode());
-        self.inbound.get_mut().extend_from_slice(&out);
+        self.add_bytes_to_inbound(&opaque_message.clone().encode());
     }
 
-    fn take_message_from_outbound(&mut self) -> Result<Option<MessageResult>, Error> {
-        let mut deframer = MessageDeframer::new();
-        if let Ok(_) = deframer.read(&mut self.outbound.get_ref().as_slice()) {
-            let mut rest_buffer: Vec<u8> = Vec::new();
-            let mut frames = deframer.frames;
-
-            let first_message = frames.pop_front();
+    fn add_bytes_to_inbound(&mut self, bytes: &[u8]) {
+        self.received_alerts.extend(Self::decode_alerts(bytes));
+        self.inbound.get_mut().extend_from_slice(bytes);
+    }
 
-            for message in frames {
-                rest_buffer.append(&mut message.encode());
+    /// Returns `None` if the outbound channel does not hold a complete record yet. The bytes of
+    /// an incomplete record are kept until the rest of the record has been written.
+    fn take_message_from_outbound(&mut self) -> Result<Option<MessageResult>, Error> {
+        match Self::deframe_record(self.outbound.get_ref())? {
+            DeframedRecord::Complete(opaque_message, length) => {
+                self.consume_outbound(length);
+                Ok(Some(Self::decode_message(opaque_message)))
             }
+            DeframedRecord::Incomplete => Ok(None),
+        }
+    }
 
-            self.outbound.set_position(0);
-            self.outbound.get_mut().clear();
-            self.outbound.write_all(&rest_buffer).map_err(|err| {
-                Error::Stream(format!("Failed to write into outbound buffer: {}", err))
-            })?;
-
-            if let Some(opaque_message) = first_message {
-                let message = match Message::try_from(opaque_message.clone()) {
-                    Ok(message) => Some(message),
-                    Err(err) => {
-                        error!("Failed to decode message! This means we maybe need to remove logical checks from rustls! {}", err);
-                        None
-                    }
-                };
-
-                Ok(Some(MessageResult(message, opaque_message)))
-            } else {
-                // no message to return
-                Ok(None)
-            }
-        } else {
-            // Unable to deframe
-            Err(Error::Stream("Failed to deframe binary buffer".to_string()))
+    /// Like [`Stream::take_message_from_outbound`], trailing bytes of an incomplete record are
+    /// kept in the outbound channel
+    fn take_all_messages_from_outbound(&mut self) -> Result<Vec<MessageResult>, Error> {
+        let mut messages = Vec::new();
+        let mut consumed = 0;
+
+        while let DeframedRecord::Complete(opaque_message, length) =
+            Self::deframe_record(&self.outbound.get_ref()[consumed..])?
+        {
+            consumed += length;
+            messages.push(Self::decode_message(opaque_message));
         }
+
+        self.consume_outbound(consumed);
+        Ok(messages)
     }
 }
 
@@ -239,6 +676,9 @@ impl Read for MemoryStream {
 
 impl Write for MemoryStream {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Alerts are recorded as soon as they are written. A fatal alert usually makes the
+        // execution of a trace fail before the outbound channel is read again.
+        self.sent_alerts.extend(Self::decode_alerts(buf));
         self.outbound.write(buf)
     }
 
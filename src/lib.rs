@@ -14,10 +14,12 @@
 extern crate log;
 
 pub mod agent;
+pub mod differential;
 pub mod error;
 pub mod fuzzer;
 pub mod graphviz;
 pub mod io;
+pub mod recorder;
 pub mod term;
 pub mod tls;
 pub mod trace;
@@ -28,3 +30,5 @@ mod experiment;
 mod openssl_binding;
 mod tests;
 mod violation;
+
+pub use openssl_binding::{make_deterministic, openssl_version};
@@ -5,6 +5,7 @@ extern crate log;
 
 use std::fs::File;
 use std::io::Read;
+use std::time::Duration;
 use std::{env, fs, io::Write, path::PathBuf};
 
 use clap::{crate_authors, crate_name, crate_version, value_t, App, SubCommand};
@@ -20,17 +21,22 @@ use fuzzer::seeds::create_corpus;
 use trace::TraceContext;
 
 use crate::experiment::*;
-use crate::fuzzer::start;
+use crate::fuzzer::{
+    start, ExecutorConfig, FuzzerConfig, SchedulerConfig, StatsFormat, MAX_ITERATIONS_PER_STAGE,
+    MIN_ITERATIONS_PER_STAGE,
+};
 use crate::graphviz::write_graphviz;
 
 mod agent;
 mod debug;
+mod differential;
 mod error;
 mod experiment;
 mod fuzzer;
 mod graphviz;
 mod io;
 mod openssl_binding;
+mod recorder;
 mod term;
 mod tests;
 mod tls;
@@ -71,6 +77,17 @@ fn main() {
         .args_from_usage("-s, --seed=[n] '(experimental) provide a seed for all clients'")
         .args_from_usage("-p, --port=[n] 'Port of the broker'")
         .args_from_usage("-i, --max-iters=[i] 'Maximum iterations to do'")
+        .args_from_usage("--scheduler=[s] 'Corpus scheduler to use: queue, rand or power'")
+        .args_from_usage("--executor=[e] 'Executes the harness inprocess or in a forked child (fork)'")
+        .args_from_usage("--stats-format=[f] 'Format of the stats: text, jsonl (stdout) or jsonl:<path>'")
+        .args_from_usage("--differential 'Report traces on which two backends diverge as solutions'")
+        .args_from_usage("--shards=[n] 'Splits the initial corpus into n shards which are distributed among the cores'")
+        .args_from_usage("--shard-overlap=[n] 'How many neighbouring shards each core imports additionally'")
+        .args_from_usage("--min-stage-iterations=[n] 'Lower bound of the adaptive iteration budget of the mutational stage'")
+        .args_from_usage("--max-stage-iterations=[n] 'Upper bound of the adaptive iteration budget of the mutational stage'")
+        .args_from_usage("--mutator-weight=[name=w]... 'Relative weight of a mutator, e.g. SkipMutator=0.5. Other mutators have a weight of 1'")
+        .args_from_usage("--max-executions=[n] 'Stops fuzzing after n executions summed over all cores'")
+        .args_from_usage("--max-time=[s] 'Stops fuzzing after s seconds'")
         .subcommands(vec![
             SubCommand::with_name("quick-experiment").about("Starts a new experiment and writes the results out")
                 .args_from_usage("--disk-corpus 'Use a on disk corpus'"),
@@ -96,6 +113,63 @@ fn main() {
     let port = value_t!(matches, "port", u16).unwrap_or(1337);
     let static_seed = value_t!(matches, "seed", u64).ok();
     let max_iters = value_t!(matches, "max-iters", u64).ok();
+    let scheduler = if matches.is_present("scheduler") {
+        value_t!(matches, "scheduler", SchedulerConfig).unwrap_or_else(|err| err.exit())
+    } else {
+        SchedulerConfig::default()
+    };
+    let executor = if matches.is_present("executor") {
+        value_t!(matches, "executor", ExecutorConfig).unwrap_or_else(|err| err.exit())
+    } else {
+        ExecutorConfig::default()
+    };
+    let stats_format = if matches.is_present("stats-format") {
+        value_t!(matches, "stats-format", StatsFormat).unwrap_or_else(|err| err.exit())
+    } else {
+        StatsFormat::default()
+    };
+    let differential = matches.is_present("differential");
+    let corpus_shards = value_t!(matches, "shards", usize).ok();
+    let shard_overlap = value_t!(matches, "shard-overlap", usize).unwrap_or(0);
+    let min_iterations_per_stage =
+        value_t!(matches, "min-stage-iterations", u64).unwrap_or(MIN_ITERATIONS_PER_STAGE);
+    let max_iterations_per_stage =
+        value_t!(matches, "max-stage-iterations", u64).unwrap_or(MAX_ITERATIONS_PER_STAGE);
+    let mutator_weights = matches
+        .values_of("mutator-weight")
+        .map(|values| {
+            values
+                .map(parse_mutator_weight)
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .unwrap_or_else(|| Ok(vec![]))
+        .unwrap_or_else(|err| clap::Error::value_validation_auto(err).exit());
+    let max_total_executions = value_t!(matches, "max-executions", u64).ok();
+    let max_wallclock = value_t!(matches, "max-time", u64)
+        .ok()
+        .map(Duration::from_secs);
+
+    let config = FuzzerConfig {
+        core_definition,
+        stats_file: PathBuf::from("stats.json"),
+        stats_format,
+        on_disk_corpus: PathBuf::from("disk-corpus"),
+        corpus_dir: PathBuf::from("corpus"),
+        objective_dir: PathBuf::from("crashes"),
+        broker_port: port,
+        max_iters,
+        static_seed,
+        scheduler,
+        executor,
+        differential,
+        corpus_shards,
+        shard_overlap,
+        min_iterations_per_stage,
+        max_iterations_per_stage,
+        mutator_weights,
+        max_total_executions,
+        max_wallclock,
+    };
 
     info!("{}", openssl_binding::openssl_version());
 
@@ -169,16 +243,13 @@ fn main() {
         handle.set_config(create_config(&experiment_path.join("tlspuffin-log.json")));
 
         write_experiment_markdown(&experiment_path, title, description).unwrap();
-        start(
-            core_definition,
-            experiment_path.join("stats.json"),
-            experiment_path.join("corpus"),
-            PathBuf::from("./corpus"),
-            experiment_path.join("crashes"),
-            port,
-            static_seed,
-            max_iters,
-        );
+        start(FuzzerConfig {
+            stats_file: experiment_path.join("stats.json"),
+            on_disk_corpus: experiment_path.join("corpus"),
+            corpus_dir: PathBuf::from("./corpus"),
+            objective_dir: experiment_path.join("crashes"),
+            ..config
+        });
     } else if let Some(_matches) = matches.subcommand_matches("quick-experiment") {
         let description = "No Description, because this is a quick experiment.";
         let experiments_root = PathBuf::from("experiments");
@@ -199,26 +270,25 @@ fn main() {
         handle.set_config(create_config(&experiment_path.join("tlspuffin-log.json")));
 
         write_experiment_markdown(&experiment_path, title, description).unwrap();
-        start(
-            core_definition,
-            experiment_path.join("stats.json"),
-            experiment_path.join("corpus"),
-            PathBuf::from("./corpus"),
-            experiment_path.join("crashes"),
-            port,
-            static_seed,
-            max_iters,
-        );
+        start(FuzzerConfig {
+            stats_file: experiment_path.join("stats.json"),
+            on_disk_corpus: experiment_path.join("corpus"),
+            corpus_dir: PathBuf::from("./corpus"),
+            objective_dir: experiment_path.join("crashes"),
+            ..config
+        });
     } else {
-        start(
-            core_definition,
-            PathBuf::from("stats.json"),
-            PathBuf::from("disk-corpus"),
-            PathBuf::from("corpus"),
-            PathBuf::from("crashes"),
-            port,
-            static_seed,
-            max_iters,
-        );
+        start(config);
     }
 }
+
+/// Parses the weight of a mutator in the format `name=weight`
+fn parse_mutator_weight(value: &str) -> Result<(String, f64), String> {
+    let (name, weight) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Expected name=weight but got {}", value))?;
+    let weight = weight
+        .parse::<f64>()
+        .map_err(|err| format!("Invalid weight of {}: {}", name, err))?;
+    Ok((name.to_string(), weight))
+}
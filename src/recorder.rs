@@ -0,0 +1,134 @@
+//! An in-memory log of all messages which cross the [`crate::io::Stream`]s of the agents while
+//! a trace is executed. Compared to a packet capture this is cheap and always available, which
+//! makes it useful to debug flaky traces.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use rustls::msgs::codec::{Codec, Reader};
+use rustls::msgs::enums::{ContentType, HandshakeType};
+use rustls::msgs::message::OpaqueMessage;
+
+use crate::agent::AgentName;
+
+/// Whether a message has been delivered to or sent by an agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The message has been added to the inbound channel of the agent
+    Inbound,
+    /// The message has been taken from the outbound channel of the agent
+    Outbound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub step_index: usize,
+    pub agent: AgentName,
+    pub direction: Direction,
+    /// Content type of the record. Plaintext handshake messages also include the handshake type,
+    /// e.g. `Handshake(ClientHello)`.
+    pub message_type: String,
+    pub byte_len: usize,
+    /// Time since the recording started
+    pub time: Duration,
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arrow = match self.direction {
+            Direction::Inbound => "->",
+            Direction::Outbound => "<-",
+        };
+        write!(
+            f,
+            "[{:>8.3}ms] step {} {} agent {}: {} ({} bytes)",
+            self.time.as_secs_f64() * 1000.0,
+            self.step_index,
+            arrow,
+            self.agent,
+            self.message_type,
+            self.byte_len
+        )
+    }
+}
+
+/// Collects [`TraceEvent`]s, see [`crate::trace::TraceContext::enable_recording`]
+pub struct TraceRecorder {
+    start: Instant,
+    events: Vec<TraceEvent>,
+}
+
+impl TraceRecorder {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            events: vec![],
+        }
+    }
+
+    pub fn record_message(
+        &mut self,
+        step_index: usize,
+        agent: AgentName,
+        direction: Direction,
+        message: &OpaqueMessage,
+    ) {
+        let byte_len = message.payload.0.len();
+        self.record(
+            step_index,
+            agent,
+            direction,
+            describe_message_type(message),
+            byte_len,
+        );
+    }
+
+    /// Records bytes which do not necessarily form valid records
+    pub fn record_bytes(
+        &mut self,
+        step_index: usize,
+        agent: AgentName,
+        direction: Direction,
+        bytes: &[u8],
+    ) {
+        self.record(
+            step_index,
+            agent,
+            direction,
+            "Bytes".to_string(),
+            bytes.len(),
+        );
+    }
+
+    fn record(
+        &mut self,
+        step_index: usize,
+        agent: AgentName,
+        direction: Direction,
+        message_type: String,
+        byte_len: usize,
+    ) {
+        self.events.push(TraceEvent {
+            step_index,
+            agent,
+            direction,
+            message_type,
+            byte_len,
+            time: self.start.elapsed(),
+        });
+    }
+
+    /// Returns the recorded events and clears the recording
+    pub fn take_events(&mut self) -> Vec<TraceEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+fn describe_message_type(message: &OpaqueMessage) -> String {
+    if message.typ == ContentType::Handshake {
+        if let Some(handshake_type) = HandshakeType::read(&mut Reader::init(&message.payload.0)) {
+            return format!("Handshake({:?})", handshake_type);
+        }
+    }
+    format!("{:?}", message.typ)
+}
@@ -10,6 +10,7 @@ use serde::{Deserialize, Serialize};
 use crate::term::atoms::fn_container::FnContainer;
 use crate::term::dynamic_function::{DynamicFunction, DynamicFunctionShape, TypeShape};
 use crate::term::remove_prefix;
+use crate::term::signature::Symbol;
 use crate::trace::Query;
 
 /// A variable symbol with fixed type.
@@ -71,7 +72,9 @@ impl fmt::Display for Variable {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Function {
     /// Unique ID of this function. Uniqueness is guaranteed across all[`Term`]sever created. Cloning
-    /// change this ID.
+    /// change this ID. The ID is random and therefore differs between runs, see
+    /// [`Signature::function_id`](crate::term::signature::Signature::function_id) for an ID which
+    /// is stable.
     pub unique_id: u32,
     /// ID of this function. This id stays the same during cloning.
     pub resistant_id: u32,
@@ -106,28 +109,44 @@ impl Function {
         Self {
             unique_id: random(),
             resistant_id: random(),
-            fn_container: FnContainer { shape, dynamic_fn },
+            fn_container: FnContainer {
+                symbol: Symbol::intern(shape, dynamic_fn),
+            },
+        }
+    }
+
+    /// Creates a function with fresh ids for an already interned `symbol`
+    pub fn from_symbol(symbol: Symbol) -> Self {
+        Self {
+            unique_id: random(),
+            resistant_id: random(),
+            fn_container: FnContainer { symbol },
         }
     }
 
     pub fn arity(&self) -> u16 {
-        self.fn_container.shape.arity()
+        self.shape().arity()
     }
 
     pub fn is_constant(&self) -> bool {
-        self.fn_container.shape.is_constant()
+        self.shape().is_constant()
     }
 
     pub fn name<'a>(&'a self) -> &'static str {
-        self.fn_container.shape.name
+        self.shape().name
+    }
+
+    /// The interned symbol of this function, see [`Symbol::intern`]
+    pub fn symbol(&self) -> Symbol {
+        self.fn_container.symbol
     }
 
     pub fn shape(&self) -> &DynamicFunctionShape {
-        &self.fn_container.shape
+        self.fn_container.symbol.shape()
     }
 
     pub fn dynamic_fn(&self) -> &Box<dyn DynamicFunction> {
-        &self.fn_container.dynamic_fn
+        self.fn_container.symbol.dynamic_fn()
     }
 
     pub fn change_function(
@@ -135,14 +154,13 @@ impl Function {
         shape: DynamicFunctionShape,
         dynamic_fn: Box<dyn DynamicFunction>,
     ) {
-        self.fn_container.shape = shape;
-        self.fn_container.dynamic_fn = dynamic_fn;
+        self.fn_container.symbol = Symbol::intern(shape, dynamic_fn);
     }
 }
 
 impl fmt::Display for Function {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        self.fn_container.shape.fmt(f)
+        self.shape().fmt(f)
     }
 }
 
@@ -154,7 +172,8 @@ mod fn_container {
     use serde::ser::SerializeStruct;
     use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-    use crate::term::dynamic_function::{DynamicFunction, DynamicFunctionShape, TypeShape};
+    use crate::term::dynamic_function::TypeShape;
+    use crate::term::signature::Symbol;
     use crate::tls::SIGNATURE;
 
     const NAME: &str = "name";
@@ -162,23 +181,22 @@ mod fn_container {
     const RETURN: &str = "return";
     const FIELDS: &[&str] = &[NAME, ARGUMENTS, RETURN];
 
-    #[derive(Clone, Debug)]
+    #[derive(Clone, Copy, Debug)]
     pub struct FnContainer {
-        pub shape: DynamicFunctionShape,
-        pub dynamic_fn: Box<dyn DynamicFunction>,
+        pub symbol: Symbol,
     }
 
     impl Hash for FnContainer {
         fn hash<H: Hasher>(&self, state: &mut H) {
-            self.shape.hash(state)
+            self.symbol.shape().hash(state)
         }
     }
 
     impl Eq for FnContainer {}
     impl PartialEq for FnContainer {
         fn eq(&self, other: &Self) -> bool {
-            // shape already identifies the function container
-            self.shape == other.shape
+            // symbols are interned by name, which already identifies the function container
+            self.symbol.id() == other.symbol.id()
         }
     }
 
@@ -188,9 +206,10 @@ mod fn_container {
             S: Serializer,
         {
             let mut state = serializer.serialize_struct("FnContainer", FIELDS.len())?;
-            state.serialize_field(NAME, &self.shape.name)?;
-            state.serialize_field(ARGUMENTS, &self.shape.argument_types)?;
-            state.serialize_field(RETURN, &self.shape.return_type)?;
+            let shape = self.symbol.shape();
+            state.serialize_field(NAME, &shape.name)?;
+            state.serialize_field(ARGUMENTS, &shape.argument_types)?;
+            state.serialize_field(RETURN, &shape.return_type)?;
             state.end()
         }
     }
@@ -234,8 +253,7 @@ mod fn_container {
             }
 
             Ok(FnContainer {
-                shape: shape.clone(),
-                dynamic_fn: dynamic_fn.clone(),
+                symbol: Symbol::intern(shape.clone(), dynamic_fn.clone()),
             })
         }
 
@@ -296,8 +314,7 @@ mod fn_container {
             }
 
             Ok(FnContainer {
-                shape: shape.clone(),
-                dynamic_fn: dynamic_fn.clone(),
+                symbol: Symbol::intern(shape.clone(), dynamic_fn.clone()),
             })
         }
     }
@@ -22,6 +22,15 @@
 //!     )
 //! };
 //! ```
+//!
+//! Variables can be restricted to a specific message type of the agent. For example,
+//! `((server, 0)[HandshakeType::ServerHello])` only reads the first ServerHello of the server.
+//! Executing a trace fails if no such message is available.
+//!
+//! The `term!` macro panics if too many arguments are specified and does not check the types of
+//! the arguments. Recipes which are not constructed from trusted code should be built with
+//! `try_term!`, which returns an [`crate::error::Error::Term`] instead if the arity or the types
+//! of the arguments do not match.
 
 #[macro_export]
 macro_rules! term {
@@ -45,7 +54,8 @@ macro_rules! term {
     }};
 
     //
-    // Handshake TlsMessageType with `$message_type` as `TlsMessageType`
+    // Handshake TlsMessageType with `$message_type` as `Option<TlsMessageType>`, `TlsMessageType`
+    // or `HandshakeType`, see `IntoMessageTypeFilter`
     //
     (($agent:expr, $counter:expr) [$message_type:expr] / $typ:ty $(>$req_type:expr)?) => {{
         use $crate::term::dynamic_function::TypeShape;
@@ -83,7 +93,7 @@ macro_rules! term {
             $crate::term_arg!($args > argument)
         }),*];
 
-        Term::Application(func, arguments)
+        Term::Application(func, arguments.into())
     }};
     // Shorthand for constants
     ($func:ident $(>$req_type:expr)?) => {{
@@ -91,7 +101,7 @@ macro_rules! term {
         use $crate::term::Term;
 
         let func = Signature::new_function(&$func);
-        Term::Application(func, vec![])
+        Term::Application(func, vec![].into())
     }};
 
     //
@@ -105,6 +115,43 @@ macro_rules! term {
     }};
 }
 
+/// Like `term!` but checks the arity and the argument types of all functions in the term
+#[macro_export]
+macro_rules! try_term {
+    ($func:ident ($($args:tt),*) $(>$req_type:expr)?) => {{
+        use $crate::error::Error;
+        use $crate::term::signature::Signature;
+        use $crate::term::Term;
+
+        let func = Signature::new_function(&$func);
+        #[allow(unused_assignments, unused_variables, unused_mut)]
+        let mut i = 0;
+
+        #[allow(unused_assignments)]
+        let arguments: Vec<Result<Term, Error>> = vec![$({
+            let argument = func.shape().argument_types.get(i).cloned();
+            i += 1;
+            match argument {
+                Some(argument) => $crate::try_term_arg!($args > argument),
+                None => Err(Error::Term(format!(
+                    "Too many arguments specified for function {}",
+                    func.name()
+                ))),
+            }
+        }),*];
+
+        arguments
+            .into_iter()
+            .collect::<Result<Vec<Term>, Error>>()
+            .and_then(|arguments| Term::try_application(func, arguments))
+    }};
+    // Constants and variables have no arguments
+    ($($e:tt)*) => {{
+        let term: $crate::term::Term = $crate::term!($($e)*);
+        term.type_check().map(|_| term)
+    }};
+}
+
 #[macro_export]
 macro_rules! term_arg {
     // Somehow the following rules is very important
@@ -113,3 +160,9 @@ macro_rules! term_arg {
     // ( ( $e:tt ) ) => (ast!($e));
     ($e:tt $(>$req_type:expr)?) => (term!($e $(>$req_type)?));
 }
+
+#[macro_export]
+macro_rules! try_term_arg {
+    ( ( $($e:tt)* ) $(>$req_type:expr)?) => ($crate::try_term!($($e)* $(>$req_type)?));
+    ($e:tt $(>$req_type:expr)?) => ($crate::try_term!($e $(>$req_type)?));
+}
@@ -28,11 +28,13 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+pub use self::rewrite::RewriteRule;
 pub use self::term::*;
 
 pub mod atoms;
 pub mod dynamic_function;
 pub mod macros;
+mod rewrite;
 pub mod signature;
 mod term;
 
@@ -0,0 +1,134 @@
+//! Rewriting of [`Term`]s with equational rules, e.g. to normalize recipes before they are compared.
+
+use std::sync::Arc;
+
+use crate::error::Error;
+use crate::term::atoms::Variable;
+use crate::term::Term;
+
+/// A rule which rewrites subterms matching `lhs` to `rhs`. The [`Variable`]s of the `lhs` are
+/// placeholders which match any subterm of their type. Their queries only distinguish them from
+/// each other and are never evaluated.
+#[derive(Clone, Debug)]
+pub struct RewriteRule {
+    pub lhs: Term,
+    pub rhs: Term,
+}
+
+impl RewriteRule {
+    /// Creates a rule which is only valid if both sides have the same type and all variables of
+    /// the `rhs` also occur in the `lhs`
+    pub fn new(lhs: Term, rhs: Term) -> Result<Self, Error> {
+        if lhs.get_type_shape() != rhs.get_type_shape() {
+            return Err(Error::Term(format!(
+                "Both sides of a rewrite rule must have the same type, but {} differs from {}",
+                lhs.get_type_shape(),
+                rhs.get_type_shape()
+            )));
+        }
+
+        let lhs_variables: Vec<&Variable> = lhs.variables().collect();
+        if let Some(unbound) = rhs
+            .variables()
+            .find(|variable| !lhs_variables.contains(variable))
+        {
+            return Err(Error::Term(format!(
+                "Variable {} of the right-hand side is not bound by the left-hand side",
+                unbound
+            )));
+        }
+
+        Ok(Self { lhs, rhs })
+    }
+
+    /// Applies the rule at the root of `term`
+    fn apply(&self, term: &Term) -> Option<Term> {
+        let mut bindings = vec![];
+        if match_pattern(&self.lhs, term, &mut bindings) {
+            Some(substitute(&self.rhs, &bindings))
+        } else {
+            None
+        }
+    }
+}
+
+impl Term {
+    /// Rewrites this term to a normal form with respect to `rules`. Subterms are rewritten before
+    /// the terms which contain them. The result of each rewrite is rewritten again until no rule
+    /// applies anymore, therefore the rules must not rewrite terms in a cycle.
+    pub fn rewrite(&self, rules: &[RewriteRule]) -> Term {
+        let term = match self {
+            Term::Variable(_) => self.clone(),
+            Term::Application(func, subterms) => Term::Application(
+                func.clone(),
+                Arc::new(
+                    subterms
+                        .iter()
+                        .map(|subterm| subterm.rewrite(rules))
+                        .collect(),
+                ),
+            ),
+        };
+
+        match rules.iter().find_map(|rule| rule.apply(&term)) {
+            Some(rewritten) => rewritten.rewrite(rules),
+            None => term,
+        }
+    }
+}
+
+/// Matches `term` against the `pattern` and collects the bindings of the variables of the
+/// pattern. A variable only matches subterms of its type, a variable which occurs several times
+/// only matches structurally equal subterms.
+fn match_pattern<'a>(
+    pattern: &'a Term,
+    term: &Term,
+    bindings: &mut Vec<(&'a Variable, Term)>,
+) -> bool {
+    match pattern {
+        Term::Variable(variable) => {
+            if variable.typ != *term.get_type_shape() {
+                return false;
+            }
+
+            match bindings.iter().find(|(bound, _)| *bound == variable) {
+                Some((_, bound_term)) => bound_term.structurally_eq(term),
+                None => {
+                    bindings.push((variable, term.clone()));
+                    true
+                }
+            }
+        }
+        Term::Application(func, patterns) => match term {
+            Term::Application(other_func, subterms) => {
+                func.name() == other_func.name()
+                    && patterns.len() == subterms.len()
+                    && patterns
+                        .iter()
+                        .zip(subterms.iter())
+                        .all(|(pattern, subterm)| match_pattern(pattern, subterm, bindings))
+            }
+            Term::Variable(_) => false,
+        },
+    }
+}
+
+/// Replaces the variables of `term` with the terms they are bound to
+fn substitute(term: &Term, bindings: &[(&Variable, Term)]) -> Term {
+    match term {
+        Term::Variable(variable) => bindings
+            .iter()
+            .find(|(bound, _)| *bound == variable)
+            .map(|(_, bound_term)| bound_term.clone())
+            .unwrap_or_else(|| term.clone()),
+        Term::Application(func, subterms) => Term::Application(
+            func.clone(),
+            Arc::new(
+                subterms
+                    .iter()
+                    .map(|subterm| substitute(subterm, bindings))
+                    .collect(),
+            ),
+        ),
+    }
+}
@@ -1,21 +1,121 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
+use std::sync::RwLock;
 
 use itertools::Itertools;
+use once_cell::sync::Lazy;
 
 use crate::term::{
     atoms::Variable,
     dynamic_function::{
         make_dynamic, DescribableFunction, DynamicFunction, DynamicFunctionShape, TypeShape,
     },
+    Term,
 };
 
 use super::atoms::Function;
 use crate::agent::AgentName;
-use crate::trace::{Query, TlsMessageType};
+use crate::error::Error;
+use crate::trace::{IntoMessageTypeFilter, Query};
 
 pub type FunctionDefinition = (DynamicFunctionShape, Box<dyn DynamicFunction>);
 
+/// Interned function symbol, see [`Symbol::intern`]. All [`Function`]s with the same name share a
+/// single [`FunctionDefinition`], therefore copying a symbol only copies its id and a reference.
+#[derive(Clone, Copy)]
+pub struct Symbol {
+    id: u32,
+    definition: &'static FunctionDefinition,
+}
+
+/// Symbols which have been interned so far, see [`Symbol::intern`]
+static SYMBOLS: Lazy<RwLock<HashMap<&'static str, Symbol>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+impl Symbol {
+    /// Returns the symbol of the function named like the `shape`. The first call for a name
+    /// interns the definition, further calls return the same symbol and drop their arguments.
+    /// Interned definitions live until the process exits, which is fine because there is only a
+    /// bounded amount of functions.
+    pub fn intern(shape: DynamicFunctionShape, dynamic_fn: Box<dyn DynamicFunction>) -> Symbol {
+        if let Some(symbol) = Self::lookup(shape.name) {
+            return symbol;
+        }
+
+        let mut symbols = SYMBOLS.write().unwrap();
+        let id = u32::try_from(symbols.len()).expect("too many function symbols");
+        *symbols.entry(shape.name).or_insert_with(|| Symbol {
+            id,
+            definition: Box::leak(Box::new((shape, dynamic_fn))),
+        })
+    }
+
+    /// Returns the symbol of the function `name` if it has already been interned
+    pub fn lookup(name: &str) -> Option<Symbol> {
+        SYMBOLS.read().unwrap().get(name).copied()
+    }
+
+    /// Id of this symbol, which is unique among all symbols of the process. Unlike
+    /// [`Signature::function_id`] it depends on the order in which functions are interned.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn shape(&self) -> &'static DynamicFunctionShape {
+        &self.definition.0
+    }
+
+    pub fn dynamic_fn(&self) -> &'static Box<dyn DynamicFunction> {
+        &self.definition.1
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Symbol({}, {:?})", self.id, self.shape())
+    }
+}
+
+/// Differences between two [`Signature`]s, see [`Signature::diff`]
+#[derive(Debug, Default)]
+pub struct SignatureDiff {
+    /// Names of the functions which are only part of the old signature
+    pub removed: Vec<&'static str>,
+    /// Names of the functions which are only part of the new signature
+    pub added: Vec<&'static str>,
+    /// Functions which are part of both signatures but have different argument or return types.
+    /// Each pair contains the old and the new shape.
+    pub changed: Vec<(DynamicFunctionShape, DynamicFunctionShape)>,
+}
+
+impl SignatureDiff {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty() && self.changed.is_empty()
+    }
+
+    /// Whether every term which is valid in the old signature is also valid in the new one.
+    /// Adding functions is fine, removing or changing them breaks serialized traces.
+    pub fn is_backward_compatible(&self) -> bool {
+        self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for SignatureDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for name in &self.removed {
+            writeln!(f, "- {}", name)?;
+        }
+        for name in &self.added {
+            writeln!(f, "+ {}", name)?;
+        }
+        for (old, new) in &self.changed {
+            writeln!(f, "~ {} => {}", old, new)?;
+        }
+        Ok(())
+    }
+}
+
 /// Records a universe of functions.
 /// Signatures are containers for types and function symbols. They hold references to the concrete
 /// implementations of functions and the types of variables.
@@ -65,15 +165,89 @@ impl Signature {
         }
     }
 
-    /// Create a new [`Functions`] distinct from all existing [`Functions`]s.
+    /// Returns the signature of all functions of the `tls` module, i.e. [`crate::tls::SIGNATURE`].
+    /// The functions are registered on the first call in the order in which they are declared.
+    /// Further calls return the same signature, therefore the ids of the functions are stable
+    /// regardless of the entry point which registers them first.
+    pub fn register_all_tls_functions() -> &'static Signature {
+        &crate::tls::SIGNATURE
+    }
+
+    /// Amount of functions in this signature
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Stable id of the function `name`, which is its position in the signature
+    pub fn function_id(&self, name: &str) -> Option<usize> {
+        self.functions
+            .iter()
+            .position(|(shape, _dynamic_fn)| shape.name == name)
+    }
+
+    /// Create a new [`Function`] for `f` with fresh ids.
     ///
+    /// The definition of `f` is shared through the global interner, see [`Symbol::intern`]. If `f`
+    /// has already been interned, then only a read lock on the interned symbols is taken. Else,
+    /// the shape and the dynamic function of `f` are created, the write lock is taken and the
+    /// definition is leaked such that it lives until the process exits. Therefore, terms can be
+    /// created concurrently from multiple threads.
     pub fn new_function<F: 'static, Types>(f: &'static F) -> Function
     where
         F: DescribableFunction<Types>,
     {
+        if let Some(symbol) = Symbol::lookup(f.name()) {
+            return Function::from_symbol(symbol);
+        }
+
         let (shape, dynamic_fn) = make_dynamic(f);
-        let func = Function::new(shape.clone(), dynamic_fn.clone());
-        func
+        Function::new(shape, dynamic_fn)
+    }
+
+    /// Applies a new [`Function`] for `f` to `arguments`. Returns an [`Error::Term`] instead of
+    /// panicking if the arguments do not match the arity or the argument types of `f`.
+    pub fn try_new_function<F: 'static, Types>(
+        f: &'static F,
+        arguments: Vec<Term>,
+    ) -> Result<Term, Error>
+    where
+        F: DescribableFunction<Types>,
+    {
+        Term::try_application(Self::new_function(f), arguments)
+    }
+
+    /// Compares this signature (the old one) with the `other` signature (the new one). Functions
+    /// are identified by their name.
+    pub fn diff(&self, other: &Signature) -> SignatureDiff {
+        let mut diff = SignatureDiff::default();
+
+        for (name, (shape, _)) in &self.functions_by_name {
+            match other.functions_by_name.get(name) {
+                None => diff.removed.push(*name),
+                Some((other_shape, _)) => {
+                    if shape.argument_types != other_shape.argument_types
+                        || shape.return_type != other_shape.return_type
+                    {
+                        diff.changed.push((shape.clone(), other_shape.clone()));
+                    }
+                }
+            }
+        }
+
+        for name in other.functions_by_name.keys() {
+            if !self.functions_by_name.contains_key(name) {
+                diff.added.push(*name);
+            }
+        }
+
+        diff.removed.sort_unstable();
+        diff.added.sort_unstable();
+        diff.changed.sort_by_key(|(old, _)| old.name);
+        diff
     }
 
     pub fn new_var<T: 'static>(query: Query) -> Variable {
@@ -81,15 +255,17 @@ impl Signature {
         Variable::new(type_shape, query)
     }
 
+    /// Creates a variable which reads the `counter`-th message of `agent_name` which matches the
+    /// `tls_message_type`, see [`IntoMessageTypeFilter`]
     pub fn new_var_by_type_id(
         type_shape: TypeShape,
         agent_name: AgentName,
-        tls_message_type: Option<TlsMessageType>,
+        tls_message_type: impl IntoMessageTypeFilter,
         counter: u16,
     ) -> Variable {
         let query = Query {
             agent_name,
-            tls_message_type,
+            tls_message_type: tls_message_type.into_filter(),
             counter,
         };
         Variable::new(type_shape, query)
@@ -1,11 +1,15 @@
 //! This module provides[`Term`]sas well as iterators over them.
 
+use std::collections::hash_map::DefaultHasher;
 use std::fmt::Formatter;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 use std::{any::Any, fmt};
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+use crate::agent::AgentName;
 use crate::error::Error;
 use crate::term::dynamic_function::TypeShape;
 use crate::tls::error::FnError;
@@ -26,7 +30,31 @@ pub enum Term {
     ///
     /// A `Term` that is an application of an [`Function`] with arity 0 applied to 0 `Term`s can be considered a constant.
     ///
-    Application(Function, Vec<Term>),
+    /// The subterms are reference-counted and shared between clones of a term. This makes cloning
+    /// terms cheap. Before changing subterms they are copied on write (see [`Term::subterms_mut`]).
+    ///
+    Application(Function, Arc<Vec<Term>>),
+}
+
+/// Controls how [`Term::pretty_opts`] renders a term.
+#[derive(Clone, Debug)]
+pub struct PrettyOptions {
+    /// Number of spaces per nesting level
+    pub indent: usize,
+    /// Terms which do not fit into this width are wrapped, one argument per line
+    pub max_width: usize,
+    /// Whether each node is annotated with its result type
+    pub show_types: bool,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            max_width: 100,
+            show_types: false,
+        }
+    }
 }
 
 impl fmt::Display for Term {
@@ -52,6 +80,21 @@ impl Term {
         }
     }
 
+    /// Length of the longest path from the root to a leaf. Leaves have a depth of 1.
+    pub fn depth(&self) -> usize {
+        match self {
+            Term::Variable(_) => 1,
+            Term::Application(_, ref subterms) => {
+                subterms
+                    .iter()
+                    .map(|subterm| subterm.depth())
+                    .max()
+                    .unwrap_or(0)
+                    + 1
+            }
+        }
+    }
+
     pub fn is_leaf(&self) -> bool {
         match self {
             Term::Variable(_) => {
@@ -63,6 +106,42 @@ impl Term {
         }
     }
 
+    /// Iterates over the constants and variables of this term in pre-order
+    pub fn leaves(&self) -> impl Iterator<Item = &Term> {
+        PreOrder::new(self).filter(|term| term.is_leaf())
+    }
+
+    /// Iterates over the functions of all applications in this term in pre-order, including
+    /// constants
+    pub fn operators(&self) -> impl Iterator<Item = &Function> {
+        PreOrder::new(self).filter_map(|term| match term {
+            Term::Variable(_) => None,
+            Term::Application(function, _) => Some(function),
+        })
+    }
+
+    /// Iterates over the variables of this term in pre-order
+    pub fn variables(&self) -> impl Iterator<Item = &Variable> {
+        PreOrder::new(self).filter_map(|term| match term {
+            Term::Variable(variable) => Some(variable),
+            Term::Application(_, _) => None,
+        })
+    }
+
+    /// Summarizes the knowledge which this term reads: the agent, the counter and the type of
+    /// the query of each variable in pre-order
+    pub fn reads(&self) -> Vec<(AgentName, usize, TypeShape)> {
+        self.variables()
+            .map(|variable| {
+                (
+                    variable.query.agent_name,
+                    variable.query.counter as usize,
+                    variable.typ,
+                )
+            })
+            .collect()
+    }
+
     pub fn get_type_shape(&self) -> &TypeShape {
         match self {
             Term::Variable(v) => &v.typ,
@@ -70,6 +149,31 @@ impl Term {
         }
     }
 
+    /// Returns the paths of all subterms which evaluate to `ty`, including this term itself which
+    /// has the empty path. A path lists the argument indices which lead from this term to the
+    /// subterm. The paths are in pre-order.
+    pub fn subterms_of_type(&self, ty: &TypeShape) -> Vec<Vec<usize>> {
+        let mut found = vec![];
+        let mut stack: Vec<(&Term, Vec<usize>)> = vec![(self, Vec::new())];
+
+        while let Some((term, path)) = stack.pop() {
+            if let Term::Application(_, subterms) = term {
+                // push in reverse such that the first argument is visited first
+                for (index, subterm) in subterms.iter().enumerate().rev() {
+                    let mut subterm_path = path.clone();
+                    subterm_path.push(index);
+                    stack.push((subterm, subterm_path));
+                }
+            }
+
+            if term.get_type_shape() == ty {
+                found.push(path);
+            }
+        }
+
+        found
+    }
+
     pub fn name(&self) -> &str {
         match self {
             Term::Variable(v) => v.typ.name,
@@ -77,10 +181,252 @@ impl Term {
         }
     }
 
+    /// Hash of the structure of this term. Two terms have the same hash if they apply the same
+    /// functions in the same order and their variables agree in type and query. The ids of
+    /// variables and functions, which change during cloning, are ignored. The hash only depends
+    /// on names and is therefore stable across runs.
+    pub fn canonical_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.canonical_hash_into(&mut hasher);
+        hasher.finish()
+    }
+
+    fn canonical_hash_into(&self, hasher: &mut DefaultHasher) {
+        match self {
+            Term::Variable(variable) => {
+                0u8.hash(hasher);
+                variable.typ.name.hash(hasher);
+                variable.query.hash(hasher);
+            }
+            Term::Application(function, subterms) => {
+                1u8.hash(hasher);
+                function.name().hash(hasher);
+                subterms.len().hash(hasher);
+                for subterm in subterms.iter() {
+                    subterm.canonical_hash_into(hasher);
+                }
+            }
+        }
+    }
+
+    /// Compares the structure of two terms like [`Term::canonical_hash`] does
+    pub fn structurally_eq(&self, other: &Term) -> bool {
+        match (self, other) {
+            (Term::Variable(a), Term::Variable(b)) => a.typ == b.typ && a.query == b.query,
+            (Term::Application(f, f_subterms), Term::Application(g, g_subterms)) => {
+                f.name() == g.name()
+                    && f_subterms.len() == g_subterms.len()
+                    && f_subterms
+                        .iter()
+                        .zip(g_subterms.iter())
+                        .all(|(a, b)| a.structurally_eq(b))
+            }
+            _ => false,
+        }
+    }
+
+    /// Lists the subterms in which `other` differs structurally from this term, see
+    /// [`Term::structurally_eq`]. Applications of the same function are compared argument by
+    /// argument, such that only the innermost differences are reported. If one term is an
+    /// argument of the other, then an application has been removed or inserted.
+    pub fn diff(&self, other: &Term) -> Vec<TermDiff> {
+        let mut diffs = vec![];
+        self.diff_into(other, &mut vec![], &mut diffs);
+        diffs
+    }
+
+    fn diff_into(&self, other: &Term, path: &mut Vec<usize>, diffs: &mut Vec<TermDiff>) {
+        if self.structurally_eq(other) {
+            return;
+        }
+
+        if let Some(kept) = self.argument_position(other) {
+            diffs.push(TermDiff::Removed {
+                path: path.clone(),
+                term: self.clone(),
+                kept,
+            });
+            return;
+        }
+
+        if let Some(wrapped) = other.argument_position(self) {
+            diffs.push(TermDiff::Inserted {
+                path: path.clone(),
+                term: other.clone(),
+                wrapped,
+            });
+            return;
+        }
+
+        match (self, other) {
+            (Term::Application(f, f_subterms), Term::Application(g, g_subterms))
+                if f.name() == g.name() && f_subterms.len() == g_subterms.len() =>
+            {
+                for (index, (a, b)) in f_subterms.iter().zip(g_subterms.iter()).enumerate() {
+                    path.push(index);
+                    a.diff_into(b, path, diffs);
+                    path.pop();
+                }
+            }
+            _ => diffs.push(TermDiff::Modified {
+                path: path.clone(),
+                old: self.clone(),
+                new: other.clone(),
+            }),
+        }
+    }
+
+    /// Index of the first argument of this application which is structurally equal to `argument`
+    fn argument_position(&self, argument: &Term) -> Option<usize> {
+        match self {
+            Term::Variable(_) => None,
+            Term::Application(_, subterms) => subterms
+                .iter()
+                .position(|subterm| subterm.structurally_eq(argument)),
+        }
+    }
+
+    /// Checks whether the term is well-typed. Each function must be applied to as many subterms
+    /// as it has arguments and the return type of each subterm must match the type of the
+    /// corresponding argument.
+    pub fn type_check(&self) -> Result<(), Error> {
+        match self {
+            Term::Variable(_) => Ok(()),
+            Term::Application(func, subterms) => {
+                check_arguments(func, subterms)?;
+
+                for subterm in subterms.iter() {
+                    subterm.type_check()?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Applies `func` to `subterms`. In contrast to constructing a [`Term::Application`]
+    /// directly, this fails if the number or the types of the subterms do not match the
+    /// arguments of `func`. The subterms themselves are not checked again.
+    pub fn try_application(func: Function, subterms: Vec<Term>) -> Result<Term, Error> {
+        check_arguments(&func, &subterms)?;
+        Ok(Term::Application(func, subterms.into()))
+    }
+
     pub fn mutate(&mut self, other: Term) {
         *self = other;
     }
 
+    /// Returns the subterms of this term for modification. If the subterms are shared with other
+    /// terms, then they are copied first.
+    pub fn subterms_mut(&mut self) -> Option<&mut Vec<Term>> {
+        match self {
+            Term::Variable(_) => None,
+            Term::Application(_, subterms) => Some(Arc::make_mut(subterms)),
+        }
+    }
+
+    /// Returns a copy of this term in which every [`Variable`] is replaced by the result of `f`.
+    /// Function applications stay the same.
+    pub fn map_variables(&self, f: impl Fn(&Variable) -> Variable) -> Term {
+        self.map_variables_with(&f)
+    }
+
+    fn map_variables_with(&self, f: &dyn Fn(&Variable) -> Variable) -> Term {
+        match self {
+            Term::Variable(variable) => Term::Variable(f(variable)),
+            Term::Application(func, subterms) => Term::Application(
+                func.clone(),
+                subterms
+                    .iter()
+                    .map(|subterm| subterm.map_variables_with(f))
+                    .collect::<Vec<Term>>()
+                    .into(),
+            ),
+        }
+    }
+
+    /// Returns a copy of this term in which all variables which query the knowledge of the agent
+    /// `from` query the knowledge of `to` instead
+    pub fn retarget_agent(&self, from: AgentName, to: AgentName) -> Term {
+        self.map_variables(|variable| {
+            let mut variable = variable.clone();
+            if variable.query.agent_name == from {
+                variable.query.agent_name = to;
+            }
+            variable
+        })
+    }
+
+    /// Renders the term with the default [`PrettyOptions`]
+    pub fn pretty(&self) -> String {
+        self.pretty_opts(&PrettyOptions::default())
+    }
+
+    /// Renders the term on a single line if it fits into `max_width`. Else, each argument is
+    /// rendered on its own line and indented.
+    pub fn pretty_opts(&self, opts: &PrettyOptions) -> String {
+        self.pretty_at_depth(0, opts)
+    }
+
+    fn pretty_at_depth(&self, depth: usize, opts: &PrettyOptions) -> String {
+        let indentation = " ".repeat(opts.indent * depth);
+        let flat = self.pretty_flat(opts);
+
+        match self {
+            Term::Application(func, args)
+                if !args.is_empty() && indentation.len() + flat.len() > opts.max_width =>
+            {
+                let args_str = args
+                    .iter()
+                    .map(|arg| arg.pretty_at_depth(depth + 1, opts))
+                    .join(",\n");
+                let call = format!(
+                    "{}{}(\n{}\n{})",
+                    indentation,
+                    remove_prefix(func.name()),
+                    args_str,
+                    indentation
+                );
+                self.annotate_type(call, opts)
+            }
+            _ => format!("{}{}", indentation, flat),
+        }
+    }
+
+    fn pretty_flat(&self, opts: &PrettyOptions) -> String {
+        match self {
+            Term::Variable(v) => {
+                if opts.show_types {
+                    format!("{}", v)
+                } else {
+                    format!("{}", v.query)
+                }
+            }
+            Term::Application(func, args) => {
+                let name = remove_prefix(func.name());
+                let call = if args.is_empty() {
+                    name
+                } else {
+                    let args_str = args.iter().map(|arg| arg.pretty_flat(opts)).join(", ");
+                    format!("{}({})", name, args_str)
+                };
+                self.annotate_type(call, opts)
+            }
+        }
+    }
+
+    fn annotate_type(&self, rendered: String, opts: &PrettyOptions) -> String {
+        if opts.show_types {
+            format!(
+                "{} -> {}",
+                rendered,
+                remove_prefix(self.get_type_shape().name)
+            )
+        } else {
+            rendered
+        }
+    }
+
     fn display_at_depth(&self, depth: usize) -> String {
         let tabs = "\t".repeat(depth);
         match self {
@@ -121,7 +467,7 @@ impl Term {
             }
             Term::Application(func, args) => {
                 let mut dynamic_args: Vec<Box<dyn Any>> = Vec::new();
-                for term in args {
+                for term in args.iter() {
                     match term.evaluate(context) {
                         Ok(data) => {
                             dynamic_args.push(data);
@@ -139,6 +485,132 @@ impl Term {
     }
 }
 
+/// A structural difference between two terms, see [`Term::diff`]. A path lists the argument
+/// indices which lead from the compared terms to the differing subterm.
+#[derive(Clone, Debug)]
+pub enum TermDiff {
+    /// The subterm at `path` has been replaced by a different one
+    Modified {
+        path: Vec<usize>,
+        old: Term,
+        new: Term,
+    },
+    /// The application `term` at `path` has been removed, only its argument `kept` remains
+    Removed {
+        path: Vec<usize>,
+        term: Term,
+        kept: usize,
+    },
+    /// The application `term` has been inserted at `path`, its argument `wrapped` is the
+    /// previous subterm
+    Inserted {
+        path: Vec<usize>,
+        term: Term,
+        wrapped: usize,
+    },
+}
+
+impl TermDiff {
+    pub fn path(&self) -> &Vec<usize> {
+        match self {
+            TermDiff::Modified { path, .. }
+            | TermDiff::Removed { path, .. }
+            | TermDiff::Inserted { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for TermDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Arguments of an application except the one at `index`
+        let other_arguments = |term: &Term, index: usize| match term {
+            Term::Variable(_) => String::new(),
+            Term::Application(_, subterms) => subterms
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != index)
+                .map(|(_, subterm)| subterm.pretty())
+                .join(", "),
+        };
+
+        match self {
+            TermDiff::Modified { path, old, new } => {
+                write!(f, "{:?}: {} => {}", path, old.pretty(), new.pretty())
+            }
+            TermDiff::Removed { path, term, kept } => write!(
+                f,
+                "{:?}: removed {} with {}",
+                path,
+                remove_prefix(term.name()),
+                other_arguments(term, *kept)
+            ),
+            TermDiff::Inserted {
+                path,
+                term,
+                wrapped,
+            } => write!(
+                f,
+                "{:?}: inserted {} with {}",
+                path,
+                remove_prefix(term.name()),
+                other_arguments(term, *wrapped)
+            ),
+        }
+    }
+}
+
+/// Checks that `subterms` match the number and the types of the arguments of `func`
+fn check_arguments(func: &Function, subterms: &[Term]) -> Result<(), Error> {
+    let argument_types = &func.shape().argument_types;
+
+    if argument_types.len() != subterms.len() {
+        return Err(Error::Term(format!(
+            "Function {} expects {} arguments but got {}",
+            func.name(),
+            argument_types.len(),
+            subterms.len()
+        )));
+    }
+
+    for (argument_type, subterm) in argument_types.iter().zip(subterms.iter()) {
+        if subterm.get_type_shape() != argument_type {
+            return Err(Error::Term(format!(
+                "Function {} expects an argument of type {} but got {}",
+                func.name(),
+                argument_type.name,
+                subterm.get_type_shape().name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Pre-order traversal of a term. Only the subterms which still have to be visited are kept on a
+/// stack, instead of collecting all nodes upfront like [`IntoIterator`] for [`Term`].
+struct PreOrder<'a> {
+    stack: Vec<&'a Term>,
+}
+
+impl<'a> PreOrder<'a> {
+    fn new(term: &'a Term) -> Self {
+        Self { stack: vec![term] }
+    }
+}
+
+impl<'a> Iterator for PreOrder<'a> {
+    type Item = &'a Term;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let term = self.stack.pop()?;
+        if let Term::Application(_, subterms) = term {
+            // push in reverse such that the first argument is visited first
+            self.stack.extend(subterms.iter().rev());
+        }
+        Some(term)
+    }
+}
+
 /// Having the same mutator for &'a mut Term is not possible in Rust:
 /// * https://stackoverflow.com/questions/49057270/is-there-a-way-to-iterate-over-a-mutable-tree-to-get-a-random-node
 /// * https://sachanganesh.com/programming/graph-tree-traversals-in-rust/
@@ -151,7 +623,7 @@ impl<'a> IntoIterator for &'a Term {
             match term {
                 &Term::Variable(_) => {}
                 &Term::Application(_, ref subterms) => {
-                    for subterm in subterms {
+                    for subterm in subterms.iter() {
                         append(subterm, v);
                     }
                 }
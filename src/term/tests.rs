@@ -1,16 +1,24 @@
 use std::any::{Any, TypeId};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
 
 use itertools::Itertools;
-use rustls::msgs::handshake::SessionID;
-use rustls::ProtocolVersion;
+use rustls::msgs::enums::{Compression, ContentType};
+use rustls::msgs::handshake::{ClientExtension, Random, SessionID};
+use rustls::{CipherSuite, ProtocolVersion};
 
 use crate::agent::AgentName;
-use crate::term;
+use crate::error::Error;
+use crate::fuzzer::seeds::{seed_client_attacker12, seed_successful12};
+use crate::term::dynamic_function::{make_dynamic, DescribableFunction, TypeShape};
 use crate::term::signature::Signature;
+use crate::term::{remove_prefix, PrettyOptions, RewriteRule};
 use crate::tls::fn_impl::*;
 use crate::tls::fn_impl::{fn_client_hello, fn_new_session_id};
 use crate::tls::{error::FnError, SIGNATURE};
-use crate::trace::{Knowledge, Query};
+use crate::trace::{Action, InputAction, Knowledge, Query, Trace};
+use crate::{term, try_term};
 use crate::{term::Term, trace::TraceContext};
 use ring::hmac;
 use ring::hmac::Key;
@@ -96,9 +104,10 @@ fn example() {
     let generated_term = Term::Application(
         hmac256,
         vec![
-            Term::Application(hmac256_new_key, vec![]),
+            Term::Application(hmac256_new_key, vec![].into()),
             Term::Variable(variable),
-        ],
+        ]
+        .into(),
     );
 
     println!("{}", generated_term);
@@ -154,12 +163,17 @@ fn playground() {
                     Term::Application(
                         Signature::new_function(&example_op_c),
                         vec![
-                            Term::Application(Signature::new_function(&example_op_c), vec![]),
+                            Term::Application(
+                                Signature::new_function(&example_op_c),
+                                vec![].into(),
+                            ),
                             Term::Variable(Signature::new_var::<SessionID>(query)),
-                        ],
+                        ]
+                        .into(),
                     ),
                     Term::Variable(Signature::new_var::<SessionID>(query)),
-                ],
+                ]
+                .into(),
             ),
             Term::Application(
                 Signature::new_function(&example_op_c),
@@ -168,13 +182,19 @@ fn playground() {
                         Signature::new_function(&example_op_c),
                         vec![
                             Term::Variable(Signature::new_var::<SessionID>(query)),
-                            Term::Application(Signature::new_function(&example_op_c), vec![]),
-                        ],
+                            Term::Application(
+                                Signature::new_function(&example_op_c),
+                                vec![].into(),
+                            ),
+                        ]
+                        .into(),
                     ),
                     Term::Variable(Signature::new_var::<SessionID>(query)),
-                ],
+                ]
+                .into(),
             ),
-        ],
+        ]
+        .into(),
     );
 
     println!("{}", constructed_term);
@@ -200,3 +220,606 @@ fn test_static_functions() {
             .join("\n")
     );
 }
+
+#[test]
+fn test_concurrent_term_construction() {
+    let server = AgentName::first();
+    let handles = (0..8)
+        .map(|_| thread::spawn(move || seed_client_attacker12(server)))
+        .collect::<Vec<_>>();
+    let traces = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect::<Vec<Trace>>();
+
+    let mut unique_ids: HashSet<u32> = HashSet::new();
+    let mut function_count = 0;
+
+    for trace in &traces {
+        assert_eq!(trace.steps.len(), traces[0].steps.len());
+
+        for (step, expected_step) in trace.steps.iter().zip(&traces[0].steps) {
+            if let (Action::Input(input), Action::Input(expected_input)) =
+                (&step.action, &expected_step.action)
+            {
+                assert_eq!(input.recipe, expected_input.recipe);
+
+                for term in &input.recipe {
+                    if let Term::Application(func, _) = term {
+                        // Every function symbol links to the same definition in the signature
+                        let (shape, _) = SIGNATURE.functions_by_name.get(func.name()).unwrap();
+                        assert_eq!(shape, func.shape());
+
+                        function_count += 1;
+                        unique_ids.insert(func.unique_id);
+                    }
+                }
+            }
+        }
+    }
+
+    // Unique ids are never shared between threads
+    assert_eq!(unique_ids.len(), function_count);
+}
+
+#[test]
+fn test_clone_shares_subterms() {
+    let server = AgentName::first();
+    let original = seed_client_attacker12(server);
+    let mut cloned = original.clone();
+
+    let original_recipe = match &original.steps[0].action {
+        Action::Input(input) => &input.recipe,
+        Action::Output(_) | Action::Delay(_) => panic!("first step should be an input"),
+    };
+
+    if let Action::Input(input) = &mut cloned.steps[0].action {
+        if let (Term::Application(_, original_subterms), Term::Application(_, cloned_subterms)) =
+            (original_recipe, &input.recipe)
+        {
+            assert!(Arc::ptr_eq(original_subterms, cloned_subterms));
+        }
+
+        // Changing the clone copies the subterms and leaves the original untouched
+        input.recipe.subterms_mut().unwrap().pop();
+        assert!(input.recipe.size() < original_recipe.size());
+    }
+}
+
+#[test]
+fn test_type_check() {
+    let server = AgentName::first();
+    let trace = seed_client_attacker12(server);
+
+    let mut recipe = match &trace.steps[0].action {
+        Action::Input(input) => input.recipe.clone(),
+        Action::Output(_) | Action::Delay(_) => panic!("first step should be an input"),
+    };
+    recipe.type_check().unwrap();
+
+    // The first two arguments of fn_client_hello have different types
+    recipe.subterms_mut().unwrap().swap(0, 1);
+    assert!(recipe.type_check().is_err());
+
+    recipe.subterms_mut().unwrap().truncate(1);
+    assert!(recipe.type_check().is_err());
+}
+
+#[test]
+fn test_pretty_narrow_and_wide() {
+    let server = AgentName::first();
+    let trace = seed_client_attacker12(server);
+    let client_hello = match &trace.steps[0].action {
+        Action::Input(input) => &input.recipe,
+        Action::Output(_) | Action::Delay(_) => panic!("first step should be an input"),
+    };
+
+    let wide = client_hello.pretty_opts(&PrettyOptions {
+        indent: 2,
+        max_width: usize::MAX,
+        show_types: false,
+    });
+    assert_eq!(
+        wide,
+        "fn_client_hello(fn_protocol_version12, fn_new_random, fn_new_session_id, fn_append_cipher_suite(fn_new_cipher_suites, fn_cipher_suite12), fn_compressions, fn_client_extensions_append(fn_client_extensions_append(fn_client_extensions_append(fn_client_extensions_append(fn_client_extensions_append(fn_client_extensions_append(fn_client_extensions_new, fn_secp384r1_support_group_extension), fn_signature_algorithm_extension), fn_ec_point_formats_extension), fn_signed_certificate_timestamp_extension), fn_renegotiation_info_extension(fn_empty_bytes_vec)), fn_signature_algorithm_cert_extension))"
+    );
+
+    let narrow = client_hello.pretty_opts(&PrettyOptions {
+        indent: 2,
+        max_width: 60,
+        show_types: false,
+    });
+    assert_eq!(
+        narrow,
+        r#"fn_client_hello(
+  fn_protocol_version12,
+  fn_new_random,
+  fn_new_session_id,
+  fn_append_cipher_suite(
+    fn_new_cipher_suites,
+    fn_cipher_suite12
+  ),
+  fn_compressions,
+  fn_client_extensions_append(
+    fn_client_extensions_append(
+      fn_client_extensions_append(
+        fn_client_extensions_append(
+          fn_client_extensions_append(
+            fn_client_extensions_append(
+              fn_client_extensions_new,
+              fn_secp384r1_support_group_extension
+            ),
+            fn_signature_algorithm_extension
+          ),
+          fn_ec_point_formats_extension
+        ),
+        fn_signed_certificate_timestamp_extension
+      ),
+      fn_renegotiation_info_extension(fn_empty_bytes_vec)
+    ),
+    fn_signature_algorithm_cert_extension
+  )
+)"#
+    );
+
+    let typed = client_hello.pretty_opts(&PrettyOptions {
+        show_types: true,
+        ..PrettyOptions::default()
+    });
+    assert!(typed.starts_with("fn_client_hello(\n"));
+    assert!(typed.ends_with(") -> Message"));
+}
+
+#[test]
+fn test_retarget_agent() {
+    let client = AgentName::first();
+    let server = client.next();
+    let other = server.next();
+
+    let recipe = term! {
+        fn_client_hello(
+            ((client, 0)),
+            ((server, 0)),
+            fn_new_session_id,
+            ((client, 1)),
+            ((client, 0)),
+            ((client, 0))
+        )
+    };
+
+    let retargeted = recipe.retarget_agent(client, other);
+
+    assert_eq!(retargeted.size(), recipe.size());
+    for (before, after) in (&recipe).into_iter().zip(&retargeted) {
+        match (before, after) {
+            (Term::Variable(before), Term::Variable(after)) => {
+                let expected = if before.query.agent_name == client {
+                    other
+                } else {
+                    before.query.agent_name
+                };
+                assert_eq!(after.query.agent_name, expected);
+                assert_eq!(after.query.counter, before.query.counter);
+                assert_eq!(after.query.tls_message_type, before.query.tls_message_type);
+                assert_eq!(after.typ, before.typ);
+            }
+            (Term::Application(before, _), Term::Application(after, _)) => {
+                assert_eq!(before, after);
+            }
+            _ => panic!("retargeting changed the structure of the term"),
+        }
+    }
+}
+
+#[test]
+fn test_subterms_of_type() {
+    let server = AgentName::first();
+    let trace = seed_client_attacker12(server);
+    let client_hello = match &trace.steps[0].action {
+        Action::Input(input) => &input.recipe,
+        Action::Output(_) | Action::Delay(_) => panic!("first step should be an input"),
+    };
+
+    let paths = client_hello.subterms_of_type(&TypeShape::of::<Vec<ClientExtension>>());
+
+    // six times fn_client_extensions_append and once fn_client_extensions_new
+    let expected: Vec<Vec<usize>> = (0..7)
+        .map(|depth| {
+            let mut path = vec![5];
+            path.extend(std::iter::repeat(0).take(depth));
+            path
+        })
+        .collect();
+    assert_eq!(paths, expected);
+
+    assert_eq!(
+        client_hello.subterms_of_type(client_hello.get_type_shape()),
+        vec![Vec::<usize>::new()]
+    );
+    assert!(client_hello
+        .subterms_of_type(&TypeShape::of::<Vec<u64>>())
+        .is_empty());
+}
+
+#[test]
+fn test_leaves_and_operators() {
+    let server = AgentName::first();
+    let client_hello = term! {
+        fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                fn_new_cipher_suites,
+                fn_cipher_suite12
+            )),
+            fn_compressions,
+            ((server, 0)/Vec<ClientExtension>)
+        )
+    };
+
+    let leaves = client_hello.leaves().map(|leaf| leaf.name()).collect_vec();
+    assert_eq!(
+        leaves,
+        vec![
+            fn_protocol_version12.name(),
+            fn_new_random.name(),
+            fn_new_session_id.name(),
+            fn_new_cipher_suites.name(),
+            fn_cipher_suite12.name(),
+            fn_compressions.name(),
+            TypeShape::of::<Vec<ClientExtension>>().name,
+        ]
+    );
+
+    let operators = client_hello
+        .operators()
+        .map(|function| function.name())
+        .collect_vec();
+    assert_eq!(
+        operators,
+        vec![
+            fn_client_hello.name(),
+            fn_protocol_version12.name(),
+            fn_new_random.name(),
+            fn_new_session_id.name(),
+            fn_append_cipher_suite.name(),
+            fn_new_cipher_suites.name(),
+            fn_cipher_suite12.name(),
+            fn_compressions.name(),
+        ]
+    );
+
+    // Every node is either a variable or an application
+    assert_eq!(client_hello.operators().count() + 1, client_hello.size());
+    assert_eq!(
+        client_hello
+            .operators()
+            .filter(|function| function.name() == fn_new_random.name())
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn test_variables_and_reads() {
+    let client = AgentName::first();
+    let server = client.next();
+    let trace = seed_successful12(client, server);
+
+    // The server consumes the ClientHello of the client
+    let client_hello = match &trace.steps[1].action {
+        Action::Input(input) => &input.recipe,
+        Action::Output(_) | Action::Delay(_) => panic!("second step should be an input"),
+    };
+
+    assert_eq!(client_hello.variables().count(), 6);
+    assert!(client_hello
+        .variables()
+        .all(|variable| variable.query.agent_name == client));
+
+    assert_eq!(
+        client_hello.reads(),
+        vec![
+            (client, 0, TypeShape::of::<ProtocolVersion>()),
+            (client, 0, TypeShape::of::<Random>()),
+            (client, 0, TypeShape::of::<SessionID>()),
+            (client, 0, TypeShape::of::<Vec<CipherSuite>>()),
+            (client, 0, TypeShape::of::<Vec<Compression>>()),
+            (client, 0, TypeShape::of::<Vec<ClientExtension>>()),
+        ]
+    );
+
+    // Constants read nothing
+    let constant = term! { fn_new_random };
+    assert_eq!(constant.variables().count(), 0);
+    assert!(constant.reads().is_empty());
+}
+
+#[test]
+fn test_signature_diff() {
+    let old = Signature::new(vec![
+        make_dynamic(&fn_client_hello),
+        make_dynamic(&fn_new_random),
+    ]);
+    let new = Signature::new(vec![make_dynamic(&fn_new_random)]);
+
+    let diff = old.diff(&new);
+    assert_eq!(diff.removed, vec![fn_client_hello.name()]);
+    assert!(diff.added.is_empty());
+    assert!(diff.changed.is_empty());
+    assert!(!diff.is_backward_compatible());
+
+    let diff = new.diff(&old);
+    assert_eq!(diff.added, vec![fn_client_hello.name()]);
+    assert!(diff.is_backward_compatible());
+
+    assert!(old.diff(&old).is_empty());
+
+    // Same name but a different return type
+    let (mut shape, dynamic_fn) = make_dynamic(&fn_new_random);
+    shape.return_type = TypeShape::of::<u64>();
+    let changed = Signature::new(vec![(shape, dynamic_fn)]);
+
+    let diff = new.diff(&changed);
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].0.name, fn_new_random.name());
+    assert!(!diff.is_backward_compatible());
+}
+
+#[test]
+fn test_canonical_hash() {
+    let client = AgentName::first();
+    let server = client.next();
+    let recipe = term! {
+        fn_client_hello(
+            ((client, 0)),
+            ((server, 0)),
+            fn_new_session_id,
+            ((client, 1)),
+            ((client, 0)),
+            ((client, 0))
+        )
+    };
+
+    let clone = recipe.clone();
+    let ids = |term: &Term| {
+        term.into_iter()
+            .filter_map(|subterm| match subterm {
+                Term::Variable(variable) => Some(variable.unique_id),
+                Term::Application(_, _) => None,
+            })
+            .collect_vec()
+    };
+    assert_ne!(ids(&recipe), ids(&clone));
+    assert!(recipe.structurally_eq(&clone));
+    assert_eq!(recipe.canonical_hash(), clone.canonical_hash());
+
+    let different = recipe.retarget_agent(client, server);
+    assert!(!recipe.structurally_eq(&different));
+    assert_ne!(recipe.canonical_hash(), different.canonical_hash());
+}
+
+#[test]
+fn test_too_many_arguments() {
+    let client = AgentName::first();
+
+    let result = try_term! {
+        fn_new_session_id(((client, 0)/SessionID))
+    };
+    assert!(matches!(result, Err(Error::Term(_))));
+
+    let result = Signature::try_new_function(
+        &fn_application_data,
+        vec![term! { fn_new_session_id }, term! { fn_new_session_id }],
+    );
+    assert!(matches!(result, Err(Error::Term(_))));
+
+    // Surplus arguments of nested functions are reported as well
+    let result = try_term! {
+        fn_application_data((fn_new_session_id(((client, 0)/SessionID))))
+    };
+    assert!(matches!(result, Err(Error::Term(_))));
+
+    let result = try_term! {
+        fn_application_data(((client, 0)/Vec<u8>))
+    };
+    assert!(result.is_ok());
+}
+
+#[test]
+#[should_panic(expected = "too many arguments specified for function")]
+fn test_term_rejects_too_many_arguments() {
+    let client = AgentName::first();
+    let _ = term! {
+        fn_new_session_id(((client, 0)/SessionID))
+    };
+}
+
+/// The `unique_id` of a [`Function`] changes with every clone. The stable id of a function is
+/// its [`Signature::function_id`].
+#[test]
+fn test_register_all_tls_functions() {
+    let first = Signature::register_all_tls_functions();
+    let second = Signature::register_all_tls_functions();
+    assert!(std::ptr::eq(first, second));
+
+    // Every function which is declared in the signature of the tls module is registered once, in
+    // the order of the declaration
+    let declared = include_str!("../tls/mod.rs")
+        .split("define_signature!(")
+        .nth(1)
+        .unwrap()
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("fn_"))
+        .map(|line| line.to_string())
+        .collect::<Vec<String>>();
+    let registered = second
+        .functions
+        .iter()
+        .map(|(shape, _)| remove_prefix(shape.name))
+        .collect::<Vec<String>>();
+    assert_eq!(registered, declared);
+    assert_eq!(second.len(), declared.len());
+    assert_eq!(second.functions_by_name.len(), second.len());
+
+    let session_id = term! { fn_new_session_id };
+    let cloned = session_id.clone();
+    match (&session_id, &cloned) {
+        (Term::Application(func, _), Term::Application(cloned_func, _)) => {
+            assert_ne!(func.unique_id, cloned_func.unique_id);
+
+            let expected = declared.iter().position(|name| name == "fn_new_session_id");
+            assert_eq!(first.function_id(func.name()), expected);
+            assert_eq!(second.function_id(cloned_func.name()), expected);
+        }
+        _ => panic!("expected a function application"),
+    }
+}
+
+#[test]
+fn test_rewrite_duplicate_extension() {
+    // Variables of rules are placeholders, their agent is never queried
+    let pattern = AgentName::first();
+    let remove_duplicate = RewriteRule::new(
+        term! {
+            fn_client_extensions_append(
+                (fn_client_extensions_append(((pattern, 0)), ((pattern, 1)))),
+                ((pattern, 1))
+            )
+        },
+        term! {
+            fn_client_extensions_append(((pattern, 0)), ((pattern, 1)))
+        },
+    )
+    .unwrap();
+    let remove_empty_concat = RewriteRule::new(
+        term! { fn_concat_bytes(((pattern, 0)), fn_empty_bytes_vec) },
+        term! { ((pattern, 0)/Vec<u8>) },
+    )
+    .unwrap();
+    let rules = [remove_duplicate, remove_empty_concat];
+
+    let redundant = term! {
+        fn_client_extensions_append(
+            (fn_client_extensions_append(
+                (fn_client_extensions_append(
+                    (fn_client_extensions_append(
+                        fn_client_extensions_new,
+                        fn_secp384r1_support_group_extension
+                    )),
+                    fn_signature_algorithm_extension
+                )),
+                fn_signature_algorithm_extension
+            )),
+            fn_signature_algorithm_extension
+        )
+    };
+    let normalized = term! {
+        fn_client_extensions_append(
+            (fn_client_extensions_append(
+                fn_client_extensions_new,
+                fn_secp384r1_support_group_extension
+            )),
+            fn_signature_algorithm_extension
+        )
+    };
+
+    let rewritten = redundant.rewrite(&rules);
+    assert!(rewritten.structurally_eq(&normalized));
+    assert!(rewritten.type_check().is_ok());
+    // Normal forms stay the same
+    assert!(normalized.rewrite(&rules).structurally_eq(&normalized));
+
+    // Rules must not change the type of the rewritten term
+    let bytes = term! { fn_concat_bytes(fn_empty_bytes_vec, fn_empty_bytes_vec) };
+    assert!(bytes
+        .rewrite(&rules)
+        .structurally_eq(&term! { fn_empty_bytes_vec }));
+    assert!(RewriteRule::new(
+        term! { fn_concat_bytes(((pattern, 0)), fn_empty_bytes_vec) },
+        term! { fn_client_extensions_new },
+    )
+    .is_err());
+}
+
+#[test]
+fn test_encode_to_records() {
+    let client_hello = term! {
+        fn_client_hello(
+            fn_protocol_version12,
+            fn_new_random,
+            fn_new_session_id,
+            (fn_append_cipher_suite(
+                fn_new_cipher_suites,
+                fn_cipher_suite12
+            )),
+            fn_compressions,
+            (fn_client_extensions_append(
+                fn_client_extensions_new,
+                fn_extended_master_secret_extension
+            ))
+        )
+    };
+
+    let records = InputAction {
+        recipe: client_hello,
+    }
+    .encode_to_records(&TraceContext::new())
+    .unwrap();
+
+    // Record header (5), handshake header (4), version (2), random (32), session id (1 + 32),
+    // cipher suites (2 + 2), compressions (1 + 1) and extensions (2 + 4)
+    assert_eq!(records.len(), 88);
+    assert_eq!(records[0], ContentType::Handshake.get_u8());
+    assert_eq!(&records[3..5], &[0, 83]);
+    assert_eq!(records[5], 1);
+
+    // The length field of raw records is kept, even if it disagrees with the payload
+    let raw_record = term! {
+        fn_grow_record_length((fn_application_data(fn_empty_bytes_vec)), fn_seq_1)
+    };
+    let records = InputAction { recipe: raw_record }
+        .encode_to_records(&TraceContext::new())
+        .unwrap();
+    assert_eq!(records, vec![0x17, 0x03, 0x03, 0x00, 0x01]);
+
+    // Plaintext of the agent is no record yet
+    let plaintext = term! { fn_plaintext_application_data(fn_empty_bytes_vec) };
+    assert!(InputAction { recipe: plaintext }
+        .encode_to_records(&TraceContext::new())
+        .is_err());
+}
+
+#[test]
+fn test_interned_symbols() {
+    let client_hello = Signature::new_function(&fn_client_hello);
+    let other_client_hello = Signature::new_function(&fn_client_hello);
+    let server_hello = Signature::new_function(&fn_server_hello);
+
+    assert_eq!(client_hello.symbol().id(), other_client_hello.symbol().id());
+    assert_ne!(client_hello.symbol().id(), server_hello.symbol().id());
+    // All functions share a single definition, cloning does not copy it
+    assert!(std::ptr::eq(
+        client_hello.shape(),
+        other_client_hello.shape()
+    ));
+    assert!(std::ptr::eq(
+        client_hello.shape(),
+        client_hello.clone().shape()
+    ));
+    assert_eq!(remove_prefix(client_hello.name()), "fn_client_hello");
+    assert_eq!(client_hello.arity(), 6);
+
+    // Deserialized functions resolve to the same symbol
+    let term = term! { fn_client_extensions_new };
+    let deserialized: Term = postcard::from_bytes(&postcard::to_allocvec(&term).unwrap()).unwrap();
+    match (&term, &deserialized) {
+        (Term::Application(func, _), Term::Application(deserialized_func, _)) => {
+            assert_eq!(func.symbol().id(), deserialized_func.symbol().id());
+            assert!(std::ptr::eq(func.shape(), deserialized_func.shape()));
+        }
+        _ => panic!("expected applications"),
+    }
+}
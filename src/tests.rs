@@ -1,15 +1,45 @@
 #[cfg(test)]
 pub mod seeds {
+    use std::io::{Read, Write};
+    use std::time::Duration;
+
     use nix::sys::signal::Signal;
     use nix::sys::wait::WaitStatus::{Exited, Signaled};
     use nix::sys::wait::{waitpid, WaitPidFlag};
     use nix::unistd::{fork, ForkResult};
+    use openssl::nid::Nid;
+    use openssl::ocsp::{OcspResponse, OcspResponseStatus};
+    use openssl::ssl::SslOptions;
+    use openssl::x509::X509;
     use test_env_log::test;
 
-    use crate::agent::AgentName;
-    use crate::openssl_binding::{make_deterministic, openssl_version};
-    use crate::trace::Action;
+    use crate::agent::{AgentDescriptor, AgentName, ExternalPsk, TLSVersion};
+    use crate::error::Error;
+    use crate::fuzzer::mutations::util::corrupt_record_length;
+    use crate::fuzzer::pcap::bytes_term;
+    use crate::io::{EarlyDataStatus, MemoryStream, MessageResult, OpenSSLStream, Stream};
+    use crate::openssl_binding::{
+        error_strings, make_deterministic, openssl_version, CertParams, KeyType,
+    };
+    use crate::recorder::Direction;
+    use crate::term;
+    use crate::term::TermDiff;
+    use crate::tls::fn_impl::*;
+    use crate::tls::SIGNATURE;
+    use crate::trace::{
+        Action, InputAction, OutputAction, Step, StepDiff, StepOutcome, TlsMessageType, Trace,
+        TraceExpectation,
+    };
     use crate::{fuzzer::seeds::*, trace::TraceContext};
+    use rustls::msgs::codec::{Codec, Reader};
+    use rustls::msgs::enums::{
+        AlertDescription, Compression, ContentType, HandshakeType, NamedGroup,
+    };
+    use rustls::msgs::handshake::{
+        ClientExtension, DigitallySignedStruct, HandshakePayload, ServerECDHParams, ServerExtension,
+    };
+    use rustls::msgs::message::{Message, MessagePayload, OpaqueMessage};
+    use rustls::{key, ProtocolVersion, SignatureScheme};
 
     fn expect_crash<R>(mut func: R)
     where
@@ -43,6 +73,130 @@ pub mod seeds {
         println!("{}", openssl_version());
     }
 
+    #[test]
+    fn test_peek_memory_stream() {
+        // Fatal handshake_failure alert
+        let record = [0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28];
+        let mut stream = MemoryStream::new();
+
+        stream.add_bytes_to_inbound(&record);
+        assert_eq!(stream.peek_inbound(), &record);
+        assert_eq!(stream.peek_inbound(), &record);
+
+        let mut buffer = [0u8; 3];
+        assert_eq!(stream.read(&mut buffer).unwrap(), 3);
+        assert_eq!(&buffer, &record[..3]);
+        assert_eq!(stream.peek_inbound(), &record[3..]);
+        let mut buffer = [0u8; 16];
+        assert_eq!(stream.read(&mut buffer).unwrap(), 4);
+        assert_eq!(&buffer[..4], &record[3..]);
+        assert!(stream.peek_inbound().is_empty());
+
+        stream.write_all(&record).unwrap();
+        assert_eq!(stream.peek_outbound(), &record);
+        let message = stream.take_message_from_outbound().unwrap().unwrap();
+        assert_eq!(message.1.encode(), record.to_vec());
+        assert!(stream.peek_outbound().is_empty());
+        assert!(stream.take_message_from_outbound().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_agent_descriptor_builder() {
+        let server = AgentName::first();
+        let descriptor = AgentDescriptor::builder(server)
+            .server(true)
+            .tls_version(TLSVersion::V1_2)
+            .cipher_list("ECDHE-RSA-AES128-GCM-SHA256")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            descriptor,
+            AgentDescriptor {
+                name: server,
+                server: true,
+                tls_version: TLSVersion::V1_2,
+                cipher_list: Some("ECDHE-RSA-AES128-GCM-SHA256".to_string()),
+                ..AgentDescriptor::default()
+            }
+        );
+        assert!(!descriptor.try_reuse);
+        assert_eq!(descriptor.cert, None);
+        assert_eq!(descriptor.max_early_data, 0);
+        assert!(!descriptor.client_auth);
+
+        // Options of the other role or of TLS 1.3 are contradictory
+        assert!(AgentDescriptor::builder(server)
+            .client_auth(true)
+            .build()
+            .is_err());
+        assert!(AgentDescriptor::builder(server)
+            .server(true)
+            .request_ocsp(true)
+            .build()
+            .is_err());
+        assert!(AgentDescriptor::builder(server)
+            .server(true)
+            .tls_version(TLSVersion::V1_2)
+            .max_early_data(1024)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_take_partial_record_from_outbound() {
+        // Fatal handshake_failure alert
+        let record = [0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28];
+        let mut stream = MemoryStream::new();
+
+        for (i, byte) in record.iter().enumerate() {
+            assert!(stream.take_message_from_outbound().unwrap().is_none());
+            assert_eq!(stream.peek_outbound(), &record[..i]);
+            stream.write_all(&[*byte]).unwrap();
+        }
+
+        let message = stream.take_message_from_outbound().unwrap().unwrap();
+        assert_eq!(message.1.encode(), record.to_vec());
+        assert!(stream.peek_outbound().is_empty());
+
+        // The partial second record is kept after the first one has been taken
+        stream.write_all(&record).unwrap();
+        stream.write_all(&record[..4]).unwrap();
+        let messages = stream.take_all_messages_from_outbound().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(stream.peek_outbound(), &record[..4]);
+        stream.write_all(&record[4..]).unwrap();
+        let message = stream.take_message_from_outbound().unwrap().unwrap();
+        assert_eq!(message.1.encode(), record.to_vec());
+
+        // Records with an empty fragment are complete
+        let empty_record = [0x17, 0x03, 0x03, 0x00, 0x00];
+        stream.write_all(&empty_record).unwrap();
+        let message = stream.take_message_from_outbound().unwrap().unwrap();
+        assert!(message.1.payload.0.is_empty());
+        assert!(stream.peek_outbound().is_empty());
+
+        // A record which can never complete is malformed
+        stream.write_all(&[0x17, 0x03, 0x03, 0xff, 0xff]).unwrap();
+        assert!(stream.take_message_from_outbound().is_err());
+    }
+
+    #[test]
+    fn test_backend_version() {
+        let version = OpenSSLStream::backend_version();
+        let capabilities = OpenSSLStream::backend_capabilities();
+        println!("{} {:?}", version, capabilities);
+
+        assert!(!version.is_empty());
+        if capabilities.libressl {
+            assert!(version.starts_with("LibreSSL"));
+        } else {
+            assert!(version.starts_with("OpenSSL"));
+        }
+        #[cfg(feature = "openssl111")]
+        assert!(version.contains("1.1.1"));
+    }
+
     #[cfg(all(feature = "openssl101f", feature = "asan"))]
     #[test]
     fn test_seed_hearbeat() {
@@ -57,6 +211,19 @@ pub mod seeds {
         })
     }
 
+    #[cfg(all(feature = "openssl101f", feature = "asan"))]
+    #[test]
+    fn test_seed_heartbeat_oversized() {
+        expect_crash(|| {
+            make_deterministic();
+            let mut ctx = TraceContext::new();
+            let server = AgentName::first();
+            let trace = seed_heartbeat_oversized(server);
+
+            trace.execute(&mut ctx).unwrap();
+        })
+    }
+
     #[test]
     fn test_seed_cve_2021_3449() {
         if !openssl_version().contains("1.1.1j") {
@@ -72,6 +239,181 @@ pub mod seeds {
         });
     }
 
+    #[test]
+    fn test_handshake_type_variable() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let trace = seed_client_attacker12(server);
+        trace.execute(&mut ctx).unwrap();
+
+        let server_hello = term! { ((server, 0)[HandshakeType::ServerHello]/Message) }
+            .evaluate(&ctx)
+            .unwrap()
+            .downcast::<Message>()
+            .unwrap();
+        match server_hello.payload {
+            MessagePayload::Handshake(handshake) => {
+                assert_eq!(handshake.typ, HandshakeType::ServerHello)
+            }
+            _ => panic!("Expected a handshake message"),
+        }
+
+        // The server sent no ClientHello, therefore the variable does not match the ServerHello
+        let client_hello = term! { ((server, 0)[HandshakeType::ClientHello]/Message) };
+        assert!(client_hello.evaluate(&ctx).is_err());
+
+        // Executing a trace which reads the missing message fails
+        let mut ctx = TraceContext::new();
+        let mut trace = seed_client_attacker12(server);
+        trace.steps.push(Step {
+            agent: server,
+            action: Action::Input(InputAction {
+                recipe: client_hello,
+            }),
+        });
+        assert!(matches!(trace.execute(&mut ctx), Err(Error::Term(_))));
+    }
+
+    #[cfg(feature = "openssl111")]
+    #[test]
+    fn test_seed_secure_renegotiation() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let trace = seed_secure_renegotiation(server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        // The server answered the ClientHello and is in the middle of the second handshake
+        assert!(!ctx.is_handshake_complete(server).unwrap());
+        assert!(ctx.sent_alerts(server).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "openssl111")]
+    #[test]
+    fn test_seed_renegotiation_mismatch() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let trace = seed_renegotiation_mismatch(server);
+
+        match trace.execute(&mut ctx) {
+            Err(Error::OpenSSL(stack)) => assert!(error_strings(&stack)
+                .iter()
+                .any(|error| error.contains("renegotiation mismatch"))),
+            result => panic!("Expected the renegotiation to fail, got {:?}", result),
+        }
+
+        // The alert is encrypted, but TLS 1.2 records keep their content type
+        let outbound = ctx.find_agent(server).unwrap().stream.peek_outbound();
+        assert_eq!(outbound.first(), Some(&ContentType::Alert.get_u8()));
+    }
+
+    #[test]
+    fn test_agent_by_name_not_spawned() {
+        let mut ctx = TraceContext::new();
+        let agent = AgentName::first();
+
+        match ctx.agent_by_name(agent) {
+            Err(Error::Agent(message)) => assert!(message.contains(&agent.to_string())),
+            Err(err) => panic!("Expected an agent error, got {}", err),
+            Ok(_) => panic!("Expected an agent error"),
+        }
+        assert!(matches!(ctx.agent_by_name_mut(agent), Err(Error::Agent(_))));
+    }
+
+    #[cfg(feature = "openssl111")]
+    #[test]
+    fn test_agent_renegotiation() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_successful12(client, server);
+
+        trace.execute(&mut ctx).unwrap();
+        ctx.renegotiate(client).unwrap();
+
+        let MessageResult(_, opaque_message) =
+            ctx.take_message_from_outbound(client).unwrap().unwrap();
+        assert_eq!(opaque_message.typ, ContentType::Handshake);
+    }
+
+    #[cfg(any(feature = "openssl111", feature = "openssl102u", feature = "libressl"))]
+    #[test]
+    fn test_seed_alpn_negotiation() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let trace = seed_alpn_negotiation(server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let connection_info = ctx.find_agent(server).unwrap().stream.connection_info();
+        assert_eq!(connection_info.alpn_protocol, Some(b"h2".to_vec()));
+    }
+
+    #[cfg(feature = "openssl111")]
+    #[test]
+    fn test_seed_max_fragment_length12() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        ctx.enable_recording();
+        let server = AgentName::first();
+        let trace = seed_max_fragment_length12(server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let server_records = ctx
+            .take_recording()
+            .into_iter()
+            .filter(|event| event.agent == server && event.direction == Direction::Outbound)
+            .collect::<Vec<_>>();
+        // The certificate alone exceeds the limit, therefore the flight is split up
+        assert!(server_records.len() > 4);
+        assert!(server_records.iter().all(|event| event.byte_len <= 512));
+    }
+
+    #[cfg(feature = "openssl111")]
+    #[test]
+    fn test_sent_alerts_handshake_failure() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let mut trace = seed_client_attacker12(server);
+
+        // Offer only a cipher suite which the server does not support
+        let client_hello = term! {
+              fn_client_hello(
+                fn_protocol_version12,
+                fn_new_random,
+                fn_new_session_id,
+                (fn_append_cipher_suite(
+                    (fn_new_cipher_suites()),
+                    fn_weak_export_cipher_suite
+                )),
+                fn_compressions,
+                fn_client_extensions_new
+            )
+        };
+        trace.steps = vec![Step {
+            agent: server,
+            action: Action::Input(InputAction {
+                recipe: client_hello,
+            }),
+        }];
+
+        // The server aborts the handshake, therefore the execution fails
+        assert!(trace.execute(&mut ctx).is_err());
+
+        assert_eq!(
+            ctx.sent_alerts(server).unwrap(),
+            &vec![AlertDescription::HandshakeFailure]
+        );
+        assert!(ctx.received_alerts(server).unwrap().is_empty());
+    }
+
     #[test]
     fn test_seed_client_attacker12() {
         make_deterministic();
@@ -86,102 +428,1284 @@ pub mod seeds {
         assert!(server_state.contains("SSL negotiation finished successfully"));
     }
 
-    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[test]
-    fn test_seed_client_attacker() {
+    fn test_seed_client_attacker12_with_suite() {
+        let suites = vec![
+            term! { fn_cipher_suite12 },
+            term! { fn_cipher_suite12_aes_256_gcm_sha384 },
+        ];
+
+        for cipher_suite in suites {
+            make_deterministic();
+            let mut ctx = TraceContext::new();
+            let server = AgentName::first();
+            let trace = seed_client_attacker12_with_suite(server, cipher_suite.clone());
+
+            trace.execute(&mut ctx).unwrap();
+
+            assert!(ctx.is_handshake_complete(server).unwrap());
+
+            #[cfg(feature = "openssl111")]
+            {
+                let master_secret = term! {
+                    fn_master_secret12_with_suite(
+                        (@cipher_suite),
+                        ((server, 0)),
+                        (fn_decode_ecdh_params(
+                            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>)
+                        ))
+                    )
+                }
+                .evaluate(&ctx)
+                .unwrap()
+                .downcast::<Vec<u8>>()
+                .unwrap();
+
+                assert_eq!(ctx.master_secret(server).unwrap(), Some(*master_secret));
+            }
+        }
+    }
+
+    #[test]
+    fn test_seed_client_attacker12_grease() {
         make_deterministic();
         let mut ctx = TraceContext::new();
         let server = AgentName::first();
-        let trace = seed_client_attacker(server);
+        let trace = seed_client_attacker12_grease(server);
 
         trace.execute(&mut ctx).unwrap();
 
         let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
-        println!("{}", server_state);
         assert!(server_state.contains("SSL negotiation finished successfully"));
     }
 
-    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[test]
-    fn test_seed_client_attacker_full() {
+    fn test_seed_signature_algorithms_cert12() {
         make_deterministic();
         let mut ctx = TraceContext::new();
         let server = AgentName::first();
-        let (trace, ..) = seed_client_attacker_full(server);
+        let trace = seed_signature_algorithms_cert12(server);
+
+        // Both extensions are part of the same ClientHello and share no scheme
+        let client_hello = match &trace.steps[0].action {
+            Action::Input(input) => input.recipe.evaluate(&ctx).unwrap(),
+            _ => panic!("Expected the ClientHello"),
+        };
+        let client_hello = client_hello.as_ref().downcast_ref::<Message>().unwrap();
+        match &client_hello.payload {
+            MessagePayload::Handshake(handshake) => match &handshake.payload {
+                HandshakePayload::ClientHello(payload) => {
+                    assert!(payload.extensions.iter().any(|extension| matches!(
+                        extension,
+                        ClientExtension::SignatureAlgorithms(schemes)
+                            if schemes == &vec![SignatureScheme::RSA_PSS_SHA256]
+                    )));
+                    assert!(payload.extensions.iter().any(|extension| matches!(
+                        extension,
+                        ClientExtension::SignatureAlgorithmsCert(schemes)
+                            if schemes == &vec![SignatureScheme::RSA_PKCS1_SHA256]
+                    )));
+                }
+                _ => panic!("Expected a ClientHello"),
+            },
+            _ => panic!("Expected a handshake message"),
+        }
 
         trace.execute(&mut ctx).unwrap();
 
         let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
-        println!("{}", server_state);
         assert!(server_state.contains("SSL negotiation finished successfully"));
+
+        // The ServerKeyExchange is signed with the only handshake algorithm, not with the
+        // certificate algorithm
+        let server_key_exchange = term! {
+            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::ServerKeyExchange)))]/Vec<u8>)
+        }
+        .evaluate(&ctx)
+        .unwrap();
+        let mut reader = Reader::init(
+            server_key_exchange
+                .as_ref()
+                .downcast_ref::<Vec<u8>>()
+                .unwrap(),
+        );
+        ServerECDHParams::read(&mut reader).unwrap();
+        let signature = DigitallySignedStruct::read(&mut reader).unwrap();
+        assert_eq!(signature.scheme, SignatureScheme::RSA_PSS_SHA256);
+
+        // The certificate is signed with rsa_pkcs1_sha256, which only the signature_algorithms_cert
+        // extension allows
+        let certificates = term! {
+            ((server, 0)[Some(TlsMessageType::Handshake(Some(HandshakeType::Certificate)))]/Vec<key::Certificate>)
+        }
+        .evaluate(&ctx)
+        .unwrap();
+        let certificates = certificates
+            .as_ref()
+            .downcast_ref::<Vec<key::Certificate>>()
+            .unwrap();
+        let certificate = X509::from_der(&certificates[0].0).unwrap();
+        assert_eq!(
+            certificate.signature_algorithm().object().nid(),
+            Nid::SHA256WITHRSAENCRYPTION
+        );
     }
 
-    #[cfg(all(feature = "tls13", feature = "session-resumption"))]
     #[test]
-    fn test_seed_session_resumption_dhe() {
+    fn test_snapshot_restores_buffers() {
+        let mut ctx = TraceContext::new();
+        let server = ctx
+            .new_openssl_agent(&AgentDescriptor::new_server(
+                AgentName::first(),
+                TLSVersion::V1_2,
+            ))
+            .unwrap();
+
+        ctx.add_bytes_to_inbound(server, &[1, 2, 3]).unwrap();
+        let snapshot = ctx.snapshot();
+        ctx.add_bytes_to_inbound(server, &[4, 5]).unwrap();
+        assert_eq!(
+            ctx.find_agent(server).unwrap().stream.peek_inbound(),
+            &[1, 2, 3, 4, 5]
+        );
+
+        ctx.restore(snapshot.clone()).unwrap();
+        assert_eq!(
+            ctx.find_agent(server).unwrap().stream.peek_inbound(),
+            &[1, 2, 3]
+        );
+
+        // The snapshot can be restored repeatedly, but only for agents which still exist
+        ctx.restore(snapshot.clone()).unwrap();
+        ctx.reset();
+        assert!(ctx.restore(snapshot).is_err());
+    }
+
+    #[cfg(feature = "tls13")]
+    #[test]
+    fn test_execute_step_seed_successful() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_successful(client, server);
+
+        trace.prepare(&mut ctx).unwrap();
+        let initial_server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+
+        let mut server_states = vec![];
+        let mut completed = vec![];
+        for i in 0..trace.steps.len() {
+            assert_eq!(ctx.execute_step(&trace, i).unwrap(), StepOutcome::Success);
+            server_states.push(ctx.find_agent(server).unwrap().stream.describe_state());
+            completed.push((
+                ctx.is_handshake_complete(client).unwrap(),
+                ctx.is_handshake_complete(server).unwrap(),
+            ));
+        }
+
+        // The server progresses as soon as it received the ClientHello of the second step
+        assert_ne!(server_states[1], initial_server_state);
+        // The client completes after the server Finished, the server after the client Finished
+        assert_eq!(
+            completed,
+            vec![
+                (false, false),
+                (false, false),
+                (false, false),
+                (false, false),
+                (false, false),
+                (true, false),
+                (true, true),
+            ]
+        );
+
+        assert!(ctx.execute_step(&trace, trace.steps.len()).is_err());
+    }
+
+    #[test]
+    fn test_client_hello13_requires_key_share() {
+        let ctx = TraceContext::new();
+        let cipher_suites = term! {
+            fn_append_cipher_suite(
+                fn_new_cipher_suites,
+                fn_cipher_suite13_aes_128_gcm_sha256
+            )
+        };
+        let versions_only = term! {
+            fn_client_extensions_append(
+                fn_client_extensions_new,
+                fn_supported_versions13_extension
+            )
+        };
+        let with_key_share = term! {
+            fn_client_extensions_append(
+                (@versions_only),
+                fn_key_share_deterministic_extension
+            )
+        };
+
+        let missing_key_share = term! {
+            fn_client_hello13(
+                fn_new_random,
+                fn_new_session_id,
+                (@cipher_suites),
+                (@versions_only)
+            )
+        };
+        assert!(missing_key_share.evaluate(&ctx).is_err());
+
+        let client_hello = term! {
+            fn_client_hello13(
+                fn_new_random,
+                fn_new_session_id,
+                (@cipher_suites),
+                (@with_key_share)
+            )
+        };
+        assert!(client_hello.evaluate(&ctx).is_ok());
+
+        // A TLS 1.2 ClientHello must not offer TLS 1.3
+        let client_hello12 = term! {
+            fn_client_hello12(
+                fn_new_random,
+                fn_new_session_id,
+                (@cipher_suites),
+                (@with_key_share)
+            )
+        };
+        assert!(client_hello12.evaluate(&ctx).is_err());
+    }
+
+    #[test]
+    fn test_certificate_verify12_encoding() {
+        // Unknown algorithms are encoded as they are
+        let certificate_verify =
+            fn_certificate_verify12(&vec![0xfe, 0xff], &vec![0xaa, 0xbb]).unwrap();
+        assert_eq!(
+            fn_handshake_body(&certificate_verify).unwrap(),
+            vec![0xfe, 0xff, 0x00, 0x02, 0xaa, 0xbb]
+        );
+    }
+
+    #[cfg(feature = "openssl111")]
+    #[test]
+    fn test_seed_certificate_verify12_mismatch() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let trace = seed_certificate_verify12_mismatch(server);
+
+        let _ = trace.execute(&mut ctx);
+
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        assert!(!server_state.contains("SSL negotiation finished successfully"));
+        let alerts = ctx.sent_alerts(server).unwrap();
+        assert!(
+            alerts.contains(&AlertDescription::IllegalParameter),
+            "Unexpected alerts {:?}",
+            alerts
+        );
+    }
+
+    #[cfg(feature = "openssl111")]
+    #[test]
+    fn test_seed_sni() {
+        let server = AgentName::first();
+
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        seed_sni(server).execute(&mut ctx).unwrap();
+        assert!(ctx.is_handshake_complete(server).unwrap());
+        assert!(ctx.sent_alerts(server).unwrap().is_empty());
+
+        // The server aborts if the host name is absent or differs
+        let mut absent = seed_client_attacker12(server);
+        absent.descriptors[0].server_name = Some(SNI_SERVER_NAME.to_string());
+        let other_name = bytes_term(b"other.test", &SIGNATURE).unwrap();
+        let non_utf8_name = bytes_term(&[0xff, 0xfe], &SIGNATURE).unwrap();
+        let mismatch = seed_sni_with_names(
+            server,
+            term! { fn_append_vec(fn_empty_vec_of_vec, (@other_name)) },
+        );
+        let non_utf8 = seed_sni_with_names(
+            server,
+            term! { fn_append_vec(fn_empty_vec_of_vec, (@non_utf8_name)) },
+        );
+
+        for trace in &[absent, mismatch, non_utf8] {
+            make_deterministic();
+            let mut ctx = TraceContext::new();
+            let _ = trace.execute(&mut ctx);
+
+            assert!(!ctx.is_handshake_complete(server).unwrap());
+            let alerts = ctx.sent_alerts(server).unwrap();
+            assert!(
+                alerts.contains(&AlertDescription::UnrecognisedName),
+                "Unexpected alerts {:?}",
+                alerts
+            );
+        }
+    }
+
+    #[cfg(feature = "openssl111")]
+    #[test]
+    fn test_seed_early_ccs() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let trace = seed_early_ccs(server);
+
+        let _ = trace.execute(&mut ctx);
+
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        assert!(!server_state.contains("SSL negotiation finished successfully"));
+        let alerts = ctx.sent_alerts(server).unwrap();
+        assert!(
+            alerts.contains(&AlertDescription::UnexpectedMessage),
+            "Unexpected alerts {:?}",
+            alerts
+        );
+    }
+
+    #[test]
+    fn test_seed_client_attacker_ech() {
+        let ctx = TraceContext::new();
+        let server = AgentName::first();
+        let trace = seed_client_attacker_ech(server);
+
+        let client_hello = match &trace.steps[0].action {
+            Action::Input(input) => input.recipe.evaluate(&ctx).unwrap(),
+            Action::Output(_) | Action::Delay(_) => panic!("expected the outer ClientHello"),
+        };
+        let client_hello = client_hello.as_ref().downcast_ref::<Message>().unwrap();
+        let bytes = OpaqueMessage::from(client_hello.clone()).encode();
+
+        // The ECH extension (0xfe0d) of the outer ClientHello nests the inner ClientHello, which
+        // carries the ECH extension itself
+        let ech_extensions = bytes.windows(2).filter(|window| *window == [0xfe, 0x0d]);
+        assert!(ech_extensions.count() >= 2);
+    }
+
+    #[test]
+    fn test_opaque_message_client_hello() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+
+        // ClientHello offering TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256 with secp256r1 and
+        // rsa_pkcs1_sha256
+        let mut client_hello = vec![0x01, 0x00, 0x00, 0x41, 0x03, 0x03];
+        client_hello.extend_from_slice(&[0x2a; 32]);
+        client_hello.extend_from_slice(&[
+            0x00, 0x00, 0x02, 0xc0, 0x2f, 0x01, 0x00, 0x00, 0x16, 0x00, 0x0a, 0x00, 0x04, 0x00,
+            0x02, 0x00, 0x17, 0x00, 0x0b, 0x00, 0x02, 0x01, 0x00, 0x00, 0x0d, 0x00, 0x04, 0x00,
+            0x02, 0x04, 0x01,
+        ]);
+        let payload = bytes_term(&client_hello, &SIGNATURE).unwrap();
+
+        let trace = Trace {
+            prior_traces: vec![],
+            descriptors: vec![AgentDescriptor::new_server(server, TLSVersion::V1_2)],
+            steps: vec![InputAction::new_step(
+                server,
+                term! {
+                    fn_new_opaque_message(
+                        fn_content_type_handshake,
+                        fn_protocol_version10,
+                        (@payload)
+                    )
+                },
+            )],
+        };
+
+        trace.execute(&mut ctx).unwrap();
+
+        // The server answered with its first flight
+        assert!(ctx
+            .knowledge()
+            .iter()
+            .any(|knowledge| knowledge.tls_message_type
+                == Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))));
+    }
+
+    #[test]
+    fn test_seed_resumption12() {
         make_deterministic();
         let mut ctx = TraceContext::new();
         let initial_server = AgentName::first();
         let server = initial_server.next();
-        let trace = seed_session_resumption_dhe(initial_server, server);
+        let trace = seed_resumption12(initial_server, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        assert!(ctx.session_ticket(initial_server).is_some());
+
+        let agent = ctx.find_agent(server).unwrap();
+        let server_state = agent.stream.describe_state();
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+        assert!(agent.stream.connection_info().session_reused);
+    }
+
+    #[test]
+    fn test_ssl_options_no_ticket12() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_successful12(client, server);
+        trace.descriptors[1].options = SslOptions::NO_TICKET;
+        // Stop after the client Finished, the server does not issue a ticket
+        trace.steps.truncate(9);
 
         trace.execute(&mut ctx).unwrap();
 
+        assert!(ctx.session_ticket(server).is_none());
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_fragmented_client_hello() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_fragmented_client_hello(client, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let server_hello_sent = ctx.knowledge().iter().any(|knowledge| {
+            knowledge.agent_name == server
+                && knowledge.tls_message_type
+                    == Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))
+        });
+        assert!(server_hello_sent);
+    }
+
+    #[test]
+    fn test_seed_server_attacker12() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let trace = seed_server_attacker12(client);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let client_key_exchange_sent = ctx.knowledge().iter().any(|knowledge| {
+            knowledge.agent_name == client
+                && knowledge.tls_message_type
+                    == Some(TlsMessageType::Handshake(Some(
+                        HandshakeType::ClientKeyExchange,
+                    )))
+        });
+        assert!(client_key_exchange_sent);
+        assert!(ctx.sent_alerts(client).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "openssl111")]
+    #[test]
+    fn test_corrupt_record_length_rejected() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let mut trace = seed_client_attacker12(server);
+
+        // The length field of the encrypted Finished is one byte short
+        match &mut trace.steps[3].action {
+            Action::Input(input) => {
+                input.recipe = corrupt_record_length(&input.recipe, 1, true).unwrap()
+            }
+            Action::Output(_) | Action::Delay(_) => panic!("expected the encrypted Finished"),
+        }
+
+        let _ = trace.execute(&mut ctx);
+
+        let alerts = ctx.sent_alerts(server).unwrap();
+        assert!(
+            alerts.contains(&AlertDescription::BadRecordMac)
+                || alerts.contains(&AlertDescription::DecodeError),
+            "unexpected alerts {:?}",
+            alerts
+        );
+    }
+
+    #[test]
+    fn test_seed_server_attacker12_bad_signature() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let mut trace = seed_server_attacker12(client);
+
+        // Sign with a client random which differs from the one of the client
+        let bad_signature = term! {
+            fn_server_key_exchange_signature(
+                fn_new_random,
+                fn_new_random,
+                fn_named_group_secp384r1,
+                (fn_deterministic_public_key(fn_named_group_secp384r1))
+            )
+        };
+        match &mut trace.steps[3].action {
+            Action::Input(input) => input.recipe.subterms_mut().unwrap()[2] = bad_signature,
+            Action::Output(_) | Action::Delay(_) => panic!("expected the ServerKeyExchange"),
+        }
+
+        let _ = trace.execute(&mut ctx);
+
+        assert_eq!(
+            ctx.sent_alerts(client).unwrap(),
+            &vec![AlertDescription::DecryptError]
+        );
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_signature_scheme_mismatch() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let trace = seed_signature_scheme_mismatch(client);
+
+        assert!(trace.execute(&mut ctx).is_err());
+
+        let client_state = ctx.find_agent(client).unwrap().stream.describe_state();
+        assert!(!client_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_empty_certificate13() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let trace = seed_empty_certificate13(client);
+
+        // The client aborts the handshake, therefore the execution fails
+        assert!(trace.execute(&mut ctx).is_err());
+
+        // Without a certificate there is no public key to verify the CertificateVerify with
+        assert_eq!(
+            ctx.sent_alerts(client).unwrap(),
+            &vec![AlertDescription::InternalError]
+        );
+    }
+
+    #[test]
+    fn test_seed_compressed_certificate13() {
+        let client = AgentName::first();
+        let trace = seed_compressed_certificate13(client);
+
+        // The first subterm of the encryption is the CompressedCertificate
+        let compressed_certificate = match &trace.steps[3].action {
+            Action::Input(input) => match &input.recipe {
+                crate::term::Term::Application(_, subterms) => subterms[0].clone(),
+                crate::term::Term::Variable(_) => panic!("expected the encryption"),
+            },
+            Action::Output(_) | Action::Delay(_) => panic!("expected the CompressedCertificate"),
+        };
+        let evaluated = compressed_certificate
+            .evaluate(&TraceContext::new())
+            .unwrap();
+        let body =
+            fn_handshake_body(evaluated.as_ref().downcast_ref::<Message>().unwrap()).unwrap();
+
+        let certificate = fn_certificate13(
+            &vec![],
+            &fn_append_certificate_entry(
+                &fn_new_certificate_entries().unwrap(),
+                &fn_static_rsa_certificate().unwrap(),
+                &fn_cert_extensions_new().unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let certificate_body = fn_handshake_body(&certificate).unwrap();
+
+        // zlib, the uncompressed length and the length of the compressed data
+        assert_eq!(&body[0..2], &[0, 1]);
+        let uncompressed_len = u32::from_be_bytes([0, body[2], body[3], body[4]]) as usize;
+        assert_eq!(uncompressed_len, certificate_body.len());
+        let compressed_len = u32::from_be_bytes([0, body[5], body[6], body[7]]) as usize;
+        assert_eq!(compressed_len, body.len() - 8);
+
+        let mut decompressed = vec![];
+        flate2::read::ZlibDecoder::new(&body[8..])
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, certificate_body);
+    }
+
+    #[test]
+    fn test_hello_verify_request_cookie() {
+        fn evaluate_body(term: &crate::term::Term) -> Vec<u8> {
+            let evaluated = term.evaluate(&TraceContext::new()).unwrap();
+            fn_handshake_body(evaluated.as_ref().downcast_ref::<Message>().unwrap()).unwrap()
+        }
+
+        let cookie = bytes_term(b"cookie", &SIGNATURE).unwrap();
+        let mut hello_verify_request = term! { fn_hello_verify_request((@cookie)) };
+        // DTLS 1.0 followed by the length-prefixed cookie
+        assert_eq!(
+            evaluate_body(&hello_verify_request),
+            b"\xfe\xff\x06cookie".to_vec()
+        );
+
+        // The cookie is a subterm on its own, such that mutators can replace it
+        match &mut hello_verify_request {
+            crate::term::Term::Application(_, subterms) => {
+                subterms[0] = bytes_term(b"forged", &SIGNATURE).unwrap();
+            }
+            crate::term::Term::Variable(_) => panic!("expected the HelloVerifyRequest"),
+        }
+        assert_eq!(
+            evaluate_body(&hello_verify_request),
+            b"\xfe\xff\x06forged".to_vec()
+        );
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_client_attacker13() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let trace = seed_client_attacker13(client);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let client_state = ctx.find_agent(client).unwrap().stream.describe_state();
+        println!("{}", client_state);
+        assert!(client_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_server_hello13() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let trace = seed_client_attacker13(client);
+
+        // Drive the client up to and including the term-built ServerHello
+        let mut prefix = trace.clone();
+        prefix.steps.truncate(2);
+        prefix.execute(&mut ctx).unwrap();
+        assert!(ctx.sent_alerts(client).unwrap().is_empty());
+
+        let server_hello = match &trace.steps[1].action {
+            Action::Input(input) => input
+                .recipe
+                .evaluate(&ctx)
+                .unwrap()
+                .downcast::<Message>()
+                .unwrap(),
+            _ => panic!("Expected an input step"),
+        };
+        match server_hello.payload {
+            MessagePayload::Handshake(handshake) => match handshake.payload {
+                HandshakePayload::ServerHello(payload) => {
+                    assert_eq!(payload.legacy_version, ProtocolVersion::TLSv1_2);
+                    assert_eq!(payload.compression_method, Compression::Null);
+                    assert!(payload.extensions.iter().any(|extension| matches!(
+                        extension,
+                        ServerExtension::SupportedVersions(version)
+                            if *version == ProtocolVersion::TLSv1_3
+                    )));
+                    assert!(payload.extensions.iter().any(|extension| matches!(
+                        extension,
+                        ServerExtension::KeyShare(entry) if entry.group == NamedGroup::secp384r1
+                    )));
+                }
+                _ => panic!("Expected a ServerHello"),
+            },
+            _ => panic!("Expected a handshake message"),
+        }
+
+        // The remaining server flight completes the handshake
+        let mut ctx = TraceContext::new();
+        trace.execute(&mut ctx).unwrap();
+        let connection_info = ctx.find_agent(client).unwrap().stream.connection_info();
+        assert_eq!(connection_info.version, "TLSv1.3");
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_successful_ktls() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_successful(client, server);
+        for descriptor in &mut trace.descriptors {
+            descriptor.ktls = true;
+        }
+
+        trace.execute(&mut ctx).unwrap();
+
+        assert!(ctx.is_handshake_complete(client).unwrap());
+        assert!(ctx.is_handshake_complete(server).unwrap());
+        for agent in &[client, server] {
+            // KTLS requires a socket, but agents exchange records through memory buffers
+            let connection_info = ctx.find_agent(*agent).unwrap().stream.connection_info();
+            assert!(!connection_info.ktls);
+        }
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_client_attacker() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let trace = seed_client_attacker(server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        println!("{}", server_state);
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_client_attacker_supported_versions() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let trace = seed_client_attacker_supported_versions(server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        assert!(ctx.is_handshake_complete(server).unwrap());
+        let connection_info = ctx.find_agent(server).unwrap().stream.connection_info();
+        assert_eq!(connection_info.version, "TLSv1.3");
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_client_attacker_full() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let (trace, ..) = seed_client_attacker_full(server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        println!("{}", server_state);
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(all(feature = "tls13", feature = "session-resumption"))]
+    #[test]
+    fn test_seed_session_resumption_dhe() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let initial_server = AgentName::first();
+        let server = initial_server.next();
+        let trace = seed_session_resumption_dhe(initial_server, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        println!("{}", server_state);
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(feature = "tls13")]
+    #[test]
+    fn test_seed_client_attacker_zero_key_share() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let trace = seed_client_attacker_zero_key_share(server);
+
+        let _ = trace.execute(&mut ctx);
+
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        assert!(!server_state.contains("SSL negotiation finished successfully"));
+        let alerts = ctx.sent_alerts(server).unwrap();
+        assert!(
+            alerts.contains(&AlertDescription::IllegalParameter)
+                || alerts.contains(&AlertDescription::DecodeError),
+            "Unexpected alerts {:?}",
+            alerts
+        );
+    }
+
+    #[cfg(feature = "tls13")]
+    #[test]
+    fn test_seed_external_psk() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_external_psk(client, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let client_state = ctx.find_agent(client).unwrap().stream.describe_state();
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        assert!(client_state.contains("SSL negotiation finished successfully"));
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(feature = "tls13")]
+    #[test]
+    fn test_seed_external_psk_wrong_key() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_external_psk(client, server);
+        trace.descriptors[0].external_psk = Some(ExternalPsk {
+            identity: EXTERNAL_PSK_IDENTITY.to_vec(),
+            key: vec![0x24; EXTERNAL_PSK.len()],
+        });
+
+        let _ = trace.execute(&mut ctx);
+
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        assert!(!server_state.contains("SSL negotiation finished successfully"));
+        assert_eq!(
+            ctx.sent_alerts(server).unwrap(),
+            &vec![AlertDescription::DecryptError]
+        );
+    }
+
+    #[cfg(feature = "tls13")]
+    #[test]
+    fn test_seed_client_attacker_external_psk() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let server = AgentName::first();
+        let trace = seed_client_attacker_external_psk(server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(all(feature = "tls13", feature = "session-resumption"))]
+    #[test]
+    fn test_seed_session_resumption_dhe_full() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let initial_server = AgentName::first();
+        let server = initial_server.next();
+        let trace = seed_session_resumption_dhe_full(initial_server, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        println!("{}", server_state);
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(all(feature = "tls13", feature = "session-resumption"))]
+    #[test]
+    fn test_seed_zero_rtt() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let initial_server = AgentName::first();
+        let server = initial_server.next();
+        let trace = seed_zero_rtt(initial_server, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        assert_eq!(
+            ctx.early_data_status(server).unwrap(),
+            EarlyDataStatus::Accepted
+        );
+        assert_eq!(
+            ctx.received_early_data(server).unwrap(),
+            &fn_early_data_bytes_vec().unwrap()
+        );
+
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(all(feature = "tls13", feature = "session-resumption"))]
+    #[test]
+    fn test_seed_session_resumption_ke() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let initial_server = AgentName::first();
+        let server = initial_server.next();
+        let trace = seed_session_resumption_ke(initial_server, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        println!("{}", server_state);
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_successful_recording() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        ctx.enable_recording();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_successful(client, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let recording = ctx.take_recording();
+        for event in &recording {
+            println!("{}", event);
+        }
+
+        let inbound = recording
+            .iter()
+            .filter(|event| event.direction == Direction::Inbound)
+            .map(|event| (event.step_index, event.agent, event.message_type.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inbound,
+            vec![
+                (1, server, "Handshake(ClientHello)"),
+                (2, client, "Handshake(ServerHello)"),
+                (3, client, "ApplicationData"),
+                (4, client, "ApplicationData"),
+                (5, client, "ApplicationData"),
+                (6, client, "ApplicationData"),
+                (7, server, "ApplicationData"),
+            ]
+        );
+
+        let first_outbound = recording
+            .iter()
+            .find(|event| event.direction == Direction::Outbound)
+            .unwrap();
+        assert_eq!(first_outbound.step_index, 0);
+        assert_eq!(first_outbound.agent, client);
+        assert_eq!(first_outbound.message_type, "Handshake(ClientHello)");
+        assert!(recording.iter().all(|event| event.byte_len > 0));
+        assert!(recording
+            .windows(2)
+            .all(|events| events[0].time <= events[1].time));
+
+        assert!(ctx.take_recording().is_empty());
+    }
+
+    #[cfg(feature = "openssl111")]
+    #[test]
+    fn test_key_log() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_successful(client, server);
+
+        let path = std::env::temp_dir().join(format!("tlspuffin-{}.keylog", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        trace.descriptors[0].key_log = Some(path.clone());
+
+        trace.execute(&mut ctx).unwrap();
+
+        let key_log = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(key_log
+            .lines()
+            .any(|line| line.starts_with("CLIENT_TRAFFIC_SECRET_0 ")
+                || line.starts_with("CLIENT_RANDOM ")));
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_successful() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_successful(client, server);
+        println!("{}", trace);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let client_state = ctx.find_agent(client).unwrap().stream.describe_state();
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        println!("{}", client_state);
+        println!("{}", server_state);
+        assert!(client_state.contains("SSL negotiation finished successfully"));
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_execute_report_seed_successful() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_successful(client, server);
+
+        let report = trace.execute_report(&mut ctx);
+
+        assert!(report.is_success());
+        assert_eq!(report.steps.len(), trace.steps.len());
+        assert!(report
+            .steps
+            .iter()
+            .all(|outcome| *outcome == StepOutcome::Success));
+        assert!(report.final_states[&client].contains("SSL negotiation finished successfully"));
+        assert!(report.final_states[&server].contains("SSL negotiation finished successfully"));
+        assert!(report.alerts.is_empty());
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_execute_checked_seed_successful() {
+        make_deterministic();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_successful(client, server);
+
+        let mut ctx = TraceContext::new();
+        trace
+            .execute_checked(
+                &mut ctx,
+                &[
+                    TraceExpectation::expect_finished(client),
+                    TraceExpectation::expect_finished(server),
+                    TraceExpectation::expect_state(server, "SSL negotiation finished successfully"),
+                    TraceExpectation::expect_no_alerts(client),
+                    TraceExpectation::expect_no_alerts(server),
+                ],
+            )
+            .unwrap();
+
+        let mut ctx = TraceContext::new();
+        let result = trace.execute_checked(
+            &mut ctx,
+            &[TraceExpectation::expect_alert(
+                server,
+                AlertDescription::HandshakeFailure,
+            )],
+        );
+        match result {
+            Err(Error::Expectation(mismatch)) => assert!(mismatch.contains("HandshakeFailure")),
+            _ => panic!("Expected a mismatch, got {:?}", result),
+        }
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_execute_checked_after_error() {
+        make_deterministic();
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_successful(client, server);
+        // The handshake completes, but the execution fails afterwards
+        trace.steps.push(OutputAction::new_step(server.next()));
+
+        // Expectations which hold do not hide the execution error
+        let mut ctx = TraceContext::new();
+        let result = trace.execute_checked(&mut ctx, &[TraceExpectation::expect_finished(client)]);
+        assert!(matches!(result, Err(Error::Agent(_))), "{:?}", result);
+
+        // Both the mismatch and the execution error are reported
+        let mut ctx = TraceContext::new();
+        let result = trace.execute_checked(
+            &mut ctx,
+            &[TraceExpectation::expect_alert(
+                server,
+                AlertDescription::HandshakeFailure,
+            )],
+        );
+        match result {
+            Err(Error::Expectation(mismatch)) => {
+                assert!(mismatch.contains("HandshakeFailure"));
+                assert!(mismatch.contains("Could not find agent"));
+            }
+            _ => panic!("Expected a mismatch, got {:?}", result),
+        }
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_take_all_messages_from_outbound() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_successful(client, server);
+        for descriptor in &trace.descriptors {
+            ctx.new_openssl_agent(descriptor).unwrap();
+        }
+
+        ctx.next_state(client).unwrap();
+        for MessageResult(_, opaque_message) in ctx.take_all_messages_from_outbound(client).unwrap()
+        {
+            ctx.add_to_inbound(server, &opaque_message).unwrap();
+        }
+
+        // The server flushes its whole first flight at once
+        ctx.next_state(server).unwrap();
+        let flight = ctx.take_all_messages_from_outbound(server).unwrap();
+
+        assert!(matches!(
+            flight.first(),
+            Some(MessageResult(_, opaque_message)) if opaque_message.typ == ContentType::Handshake
+        ));
+        // Certificate, CertificateVerify and Finished are the last encrypted records
+        assert!(flight.len() >= 3);
+        assert!(flight[flight.len() - 3..]
+            .iter()
+            .all(|MessageResult(_, opaque_message)| opaque_message.typ
+                == ContentType::ApplicationData));
+
+        assert!(ctx
+            .take_all_messages_from_outbound(server)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_hello_retry_request() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_hello_retry_request(client, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let client_state = ctx.find_agent(client).unwrap().stream.describe_state();
+        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+        assert!(client_state.contains("SSL negotiation finished successfully"));
+        assert!(server_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_stateless_retry() {
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_stateless_retry(client, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        let client_state = ctx.find_agent(client).unwrap().stream.describe_state();
         let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
-        println!("{}", server_state);
+        assert!(client_state.contains("SSL negotiation finished successfully"));
         assert!(server_state.contains("SSL negotiation finished successfully"));
     }
 
-    #[cfg(all(feature = "tls13", feature = "session-resumption"))]
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[test]
-    fn test_seed_session_resumption_dhe_full() {
-        make_deterministic();
+    fn test_stateless_retry_wrong_cookie() {
         let mut ctx = TraceContext::new();
-        let initial_server = AgentName::first();
-        let server = initial_server.next();
-        let trace = seed_session_resumption_dhe_full(initial_server, server);
-
-        trace.execute(&mut ctx).unwrap();
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_stateless_retry(client, server);
+
+        let wrong_cookie = bytes_term(b"wrong cookie", &SIGNATURE).unwrap();
+        trace.steps[3] = InputAction::new_step(
+            server,
+            term! {
+                fn_client_hello(
+                    ((client, 1)),
+                    ((client, 1)),
+                    ((client, 1)),
+                    ((client, 1)),
+                    ((client, 1)),
+                    (fn_set_cookie_extension(((client, 1)), (@wrong_cookie)))
+                )
+            },
+        );
 
+        assert!(matches!(trace.execute(&mut ctx), Err(Error::OpenSSL(_))));
         let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
-        println!("{}", server_state);
-        assert!(server_state.contains("SSL negotiation finished successfully"));
+        assert!(!server_state.contains("SSL negotiation finished successfully"));
     }
 
-    #[cfg(all(feature = "tls13", feature = "session-resumption"))]
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[test]
-    fn test_seed_session_resumption_ke() {
+    fn test_three_agent_pool() {
         make_deterministic();
         let mut ctx = TraceContext::new();
-        let initial_server = AgentName::first();
-        let server = initial_server.next();
-        let trace = seed_session_resumption_ke(initial_server, server);
-
+        let template = AgentDescriptor {
+            tls_version: TLSVersion::V1_3,
+            server: true,
+            ..AgentDescriptor::default()
+        };
+        let names = ctx.spawn_agent_pool(3, &template).unwrap();
+        assert_eq!(names, AgentName::iter().take(3).collect::<Vec<_>>());
+        assert_eq!(names[2], AgentName::nth(2));
+        assert_eq!(names[2], AgentName::first().next().next());
+
+        // The client talks to the first server, the second server stays untouched
+        let client = AgentName::nth(3);
+        let (server_a, server_b) = (names[0], names[1]);
+        let trace = Trace {
+            prior_traces: vec![],
+            descriptors: vec![AgentDescriptor {
+                name: client,
+                tls_version: TLSVersion::V1_3,
+                server: false,
+                ..AgentDescriptor::default()
+            }],
+            steps: vec![
+                OutputAction::new_step(client),
+                InputAction::new_step(
+                    server_a,
+                    term! {
+                        fn_client_hello(
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0)),
+                            ((client, 0))
+                        )
+                    },
+                ),
+            ],
+        };
         trace.execute(&mut ctx).unwrap();
 
-        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
-        println!("{}", server_state);
-        assert!(server_state.contains("SSL negotiation finished successfully"));
+        let state_a = ctx.find_agent(server_a).unwrap().stream.describe_state();
+        let state_b = ctx.find_agent(server_b).unwrap().stream.describe_state();
+        assert_ne!(state_a, state_b);
+
+        ctx.reset_agent_pool(&names).unwrap();
     }
 
     #[cfg(feature = "tls13")] // require version which supports TLS 1.3
     #[test]
-    fn test_seed_successful() {
+    fn test_export_keying_material() {
         make_deterministic();
         let mut ctx = TraceContext::new();
         let client = AgentName::first();
         let server = client.next();
         let trace = seed_successful(client, server);
-        println!("{}", trace);
 
         trace.execute(&mut ctx).unwrap();
 
-        let client_state = ctx.find_agent(client).unwrap().stream.describe_state();
-        let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
-        println!("{}", client_state);
-        println!("{}", server_state);
-        assert!(client_state.contains("SSL negotiation finished successfully"));
-        assert!(server_state.contains("SSL negotiation finished successfully"));
+        let label = b"EXPORTER-tlspuffin";
+        let client_material = ctx
+            .export_keying_material(client, label, Some(b"context"), 32)
+            .unwrap();
+        let server_material = ctx
+            .export_keying_material(server, label, Some(b"context"), 32)
+            .unwrap();
+        assert_eq!(client_material.len(), 32);
+        assert_eq!(client_material, server_material);
+
+        let other_material = ctx
+            .export_keying_material(client, b"EXPORTER-other", Some(b"context"), 32)
+            .unwrap();
+        assert_ne!(client_material, other_material);
     }
 
     #[cfg(feature = "tls13")] // require version which supports TLS 1.3
@@ -267,6 +1791,192 @@ pub mod seeds {
         assert!(server_state.contains("SSL negotiation finished successfully"));
     }
 
+    #[test]
+    fn test_seed_ocsp_stapling12() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_ocsp_stapling12(client, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        assert!(ctx.is_handshake_complete(client).unwrap());
+        assert!(ctx.is_handshake_complete(server).unwrap());
+        // The stapled response is exactly the one the server was configured with
+        let stapled = trace.descriptors[1].ocsp_response.clone().unwrap();
+        let connection_info = ctx.find_agent(client).unwrap().stream.connection_info();
+        assert_eq!(connection_info.ocsp_response, Some(stapled.clone()));
+        assert_eq!(
+            OcspResponse::from_der(&stapled).unwrap().status(),
+            OcspResponseStatus::SUCCESSFUL
+        );
+    }
+
+    #[test]
+    fn test_seed_ocsp_stapling12_malformed_response() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_ocsp_stapling12(client, server);
+
+        let malformed = bytes_term(b"malformed", &SIGNATURE).unwrap();
+        trace.steps[4] = InputAction::new_step(
+            client,
+            term! {
+                fn_certificate_status((@malformed))
+            },
+        );
+
+        assert!(trace.execute(&mut ctx).is_err());
+        assert!(!ctx.is_handshake_complete(client).unwrap());
+    }
+
+    #[test]
+    fn test_delay_step() {
+        let mut ctx = TraceContext::new();
+        ctx.enable_recording();
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_successful12(client, server);
+        let delay = Duration::from_millis(50);
+        // wait after the ClientHello was sent to the server
+        trace.steps.insert(2, Step::new_delay(server, delay));
+
+        trace.execute(&mut ctx).unwrap();
+
+        let recording = ctx.take_recording();
+        let inbound = recording
+            .iter()
+            .filter(|event| event.direction == Direction::Inbound)
+            .collect::<Vec<_>>();
+        assert_eq!(inbound[0].step_index, 1);
+        assert_eq!(inbound[1].step_index, 3);
+        assert!(inbound[1].time - inbound[0].time >= delay);
+        assert!(inbound
+            .windows(2)
+            .all(|events| events[0].step_index <= events[1].step_index));
+
+        let client_state = ctx.find_agent(client).unwrap().stream.describe_state();
+        assert!(client_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[test]
+    fn test_seed_successful12_generated_cert() {
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_successful12(client, server);
+        let params = CertParams::valid_for_days(KeyType::Rsa, 30);
+        for descriptor in &mut trace.descriptors {
+            descriptor.cert = Some(params.clone());
+        }
+
+        trace.execute(&mut ctx).unwrap();
+
+        let client_state = ctx.find_agent(client).unwrap().stream.describe_state();
+        assert!(client_state.contains("SSL negotiation finished successfully"));
+    }
+
+    #[test]
+    fn test_expired_cert_rejected() {
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_successful12(client, server);
+        let params = CertParams::expired(KeyType::Rsa);
+        for descriptor in &mut trace.descriptors {
+            descriptor.cert = Some(params.clone());
+        }
+
+        // The client aborts the handshake, therefore the execution fails
+        assert!(trace.execute(&mut ctx).is_err());
+
+        assert_eq!(
+            ctx.sent_alerts(client).unwrap(),
+            &vec![AlertDescription::CertificateExpired]
+        );
+    }
+
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_downgrade_sentinel() {
+        make_deterministic();
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let trace = seed_downgrade_sentinel(client);
+
+        // The client detects the downgrade, therefore the execution fails
+        assert!(trace.execute(&mut ctx).is_err());
+
+        assert_eq!(
+            ctx.sent_alerts(client).unwrap(),
+            &vec![AlertDescription::IllegalParameter]
+        );
+    }
+
+    #[test]
+    fn test_seed_successful12_reused_context() {
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_successful12(client, server);
+
+        for _ in 0..3 {
+            ctx.reset();
+            trace.execute(&mut ctx).unwrap();
+
+            let client_state = ctx.find_agent(client).unwrap().stream.describe_state();
+            let server_state = ctx.find_agent(server).unwrap().stream.describe_state();
+            assert!(client_state.contains("SSL negotiation finished successfully"));
+            assert!(server_state.contains("SSL negotiation finished successfully"));
+        }
+    }
+
+    #[cfg(feature = "claims")]
+    #[test]
+    fn test_claimer_registration() {
+        let first = AgentName::first();
+        let second = first.next();
+
+        let mut ctx = TraceContext::new();
+        seed_successful12(first, second).execute(&mut ctx).unwrap();
+        let claims_of = |ctx: &TraceContext, agent: AgentName| {
+            ctx.claimer
+                .borrow()
+                .claims()
+                .iter()
+                .filter(|(name, _)| *name == agent)
+                .count()
+        };
+        let expected = ctx.claimer.borrow().claims().len();
+        let expected_client = claims_of(&ctx, first);
+        assert!(expected_client > 0);
+
+        for i in 0..4 {
+            // Swapping the roles renames the reused agents
+            let (client, server) = if i % 2 == 0 {
+                (second, first)
+            } else {
+                (first, second)
+            };
+            ctx.reset();
+            seed_successful12(client, server).execute(&mut ctx).unwrap();
+
+            // Each claim is made exactly once and under the current name of the agent
+            assert_eq!(ctx.claimer.borrow().claims().len(), expected);
+            assert_eq!(claims_of(&ctx, client), expected_client);
+            assert_eq!(claims_of(&ctx, server), expected - expected_client);
+        }
+
+        // Dropping the context deregisters the claimers of all agents
+        drop(ctx);
+        let mut ctx = TraceContext::new();
+        seed_successful12(first, second).execute(&mut ctx).unwrap();
+        assert_eq!(ctx.claimer.borrow().claims().len(), expected);
+    }
+
     // Vulnerable up until OpenSSL 1.0.1j
     #[cfg(all(feature = "openssl101f", feature = "asan"))]
     #[test]
@@ -285,6 +1995,210 @@ pub mod seeds {
         });
     }
 
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    #[test]
+    fn test_seed_application_data() {
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_application_data(client, server);
+
+        trace.execute(&mut ctx).unwrap();
+
+        assert_eq!(ctx.received_application_data(server).unwrap(), b"ping");
+        assert_eq!(ctx.received_application_data(client).unwrap(), b"pong");
+    }
+
+    #[test]
+    fn test_application_data_before_handshake() {
+        let mut ctx = TraceContext::new();
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_application_data(client, server);
+        let ping = trace.steps[trace.steps.len() - 4].clone();
+        trace.steps = vec![ping];
+
+        assert!(matches!(trace.execute(&mut ctx), Err(Error::Agent(_))));
+    }
+
+    #[test]
+    fn test_seeds_validate() {
+        for (trace, name) in create_corpus().iter() {
+            assert!(trace.validate().is_ok(), "{} is not valid", name);
+        }
+    }
+
+    #[test]
+    fn test_concat() {
+        let server = AgentName::first();
+        let other = server.next();
+        let first = seed_client_attacker12(server);
+        let second = seed_client_attacker12(server);
+        let steps = first.steps.len() + second.steps.len();
+
+        let trace = first
+            .concat_with_agents(second, &[(server, other)])
+            .unwrap();
+
+        assert!(trace.validate().is_ok());
+        assert_eq!(trace.steps.len(), steps);
+        assert_eq!(trace.descriptors.len(), 2);
+        assert_eq!(trace.steps.last().unwrap().agent, other);
+
+        // Without renaming the second part continues the connection of the first one
+        let trace = seed_client_attacker12(server)
+            .concat(seed_client_attacker12(server))
+            .unwrap();
+        assert!(trace.validate().is_ok());
+        assert_eq!(trace.descriptors.len(), 1);
+    }
+
+    #[test]
+    fn test_concat_renumbers_queries() {
+        make_deterministic();
+        let server = AgentName::first();
+        let other = server.next();
+        let first = seed_client_attacker12(server);
+        let second = seed_client_attacker12(server);
+
+        let server_hello_counters = |trace: &Trace, agent: AgentName| -> Vec<u16> {
+            trace.steps[first.steps.len()..]
+                .iter()
+                .filter_map(|step| match &step.action {
+                    Action::Input(input) => Some(input.recipe.variables()),
+                    Action::Output(_) | Action::Delay(_) => None,
+                })
+                .flatten()
+                .filter(|variable| {
+                    variable.query.agent_name == agent
+                        && variable.query.tls_message_type
+                            == Some(TlsMessageType::Handshake(Some(HandshakeType::ServerHello)))
+                })
+                .map(|variable| variable.query.counter)
+                .collect()
+        };
+
+        // The second part reads the second ServerHello of the server
+        let trace = first.clone().concat(second.clone()).unwrap();
+        let counters = server_hello_counters(&trace, server);
+        assert!(!counters.is_empty());
+        assert!(counters.iter().all(|counter| *counter == 1));
+
+        // A renamed server has no knowledge of the first part
+        let trace = first
+            .clone()
+            .concat_with_agents(second, &[(server, other)])
+            .unwrap();
+        let counters = server_hello_counters(&trace, other);
+        assert!(!counters.is_empty());
+        assert!(counters.iter().all(|counter| *counter == 0));
+    }
+
+    #[test]
+    fn test_slice() {
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_successful(client, server);
+
+        let prefix = trace.slice(0..3).unwrap();
+        assert_eq!(prefix.steps.len(), 3);
+        assert!(prefix.validate().is_ok());
+
+        // The ServerHello of the client reads the output of the server in step #1
+        assert!(matches!(trace.slice(2..5), Err(Error::Term(_))));
+        assert!(matches!(
+            trace.slice(0..trace.steps.len() + 1),
+            Err(Error::Term(_))
+        ));
+    }
+
+    #[test]
+    fn test_diff_removed_extension() {
+        let server = AgentName::first();
+        let trace = seed_client_attacker12(server);
+        assert!(trace.diff(&seed_client_attacker12(server)).is_empty());
+
+        // Remove the ec_point_formats extension from the ClientHello
+        let mut modified = trace.clone();
+        if let Action::Input(input) = &mut modified.steps[0].action {
+            let extensions = &mut input.recipe.subterms_mut().unwrap()[5];
+            let ec_point_formats = &mut extensions.subterms_mut().unwrap()[0]
+                .subterms_mut()
+                .unwrap()[0]
+                .subterms_mut()
+                .unwrap()[0];
+            let remaining = ec_point_formats.subterms_mut().unwrap()[0].clone();
+            ec_point_formats.mutate(remaining);
+        }
+
+        let diff = trace.diff(&modified);
+        println!("{}", diff);
+        assert_eq!(diff.steps.len(), 1);
+        match &diff.steps[0] {
+            StepDiff::Modified {
+                old_index,
+                new_index,
+                terms,
+                ..
+            } => {
+                assert_eq!((*old_index, *new_index), (0, 0));
+                assert_eq!(terms.len(), 1);
+                match &terms[0] {
+                    TermDiff::Removed { path, term, kept } => {
+                        assert_eq!(path, &vec![5, 0, 0, 0]);
+                        assert_eq!(*kept, 0);
+                        assert!(term.name().ends_with("fn_client_extensions_append"));
+                    }
+                    other => panic!("Unexpected term diff {:?}", other),
+                }
+            }
+            other => panic!("Unexpected step diff {:?}", other),
+        }
+        assert!(diff
+            .to_string()
+            .contains("removed fn_client_extensions_append with fn_ec_point_formats_extension"));
+    }
+
+    #[test]
+    fn test_validate_dangling_agent() {
+        let server = AgentName::first();
+        let mut trace = seed_client_attacker12(server);
+        trace.steps.push(OutputAction::new_step(server.next()));
+
+        assert!(matches!(trace.validate(), Err(Error::Agent(_))));
+    }
+
+    #[test]
+    fn test_validate_forward_reference() {
+        let client = AgentName::first();
+        let server = client.next();
+        let mut trace = seed_successful(client, server);
+        // The ClientHello is now read before the client produced it
+        trace.steps.remove(0);
+
+        assert!(matches!(trace.validate(), Err(Error::Term(_))));
+    }
+
+    #[test]
+    fn test_step_budget_exceeded() {
+        make_deterministic();
+        let client = AgentName::first();
+        let server = client.next();
+        let trace = seed_successful12(client, server);
+
+        // Each input step is followed by an output step which yields several messages. With a
+        // multiplier of one the budget is exhausted before the handshake finishes.
+        let mut ctx = TraceContext::new();
+        ctx.set_max_steps_multiplier(1);
+        match trace.execute(&mut ctx) {
+            Err(Error::Stream(message)) => assert_eq!(message, "step budget exceeded"),
+            result => panic!("expected budget error, got {:?}", result),
+        }
+
+        let mut ctx = TraceContext::new();
+        trace.execute(&mut ctx).unwrap();
+    }
+
     #[test]
     fn test_term_sizes() {
         let client = AgentName::first();
@@ -303,13 +2217,99 @@ pub mod seeds {
                         // should be below 200, else we should increase MAX_TERM_SIZE in fuzzer setup
                         assert!(input.recipe.size() < 200);
                     }
-                    Action::Output(_) => {}
+                    Action::Output(_) | Action::Delay(_) => {}
                 }
             }
         }
     }
 }
 
+#[cfg(test)]
+pub mod differential {
+    use test_env_log::test;
+
+    use crate::agent::{AgentName, TLSVersion};
+    use crate::differential::{run_differential, Backend, DiffResult, OpenSSLBackend};
+    use crate::error::Error;
+    use crate::fuzzer::seeds::*;
+    use crate::openssl_binding::make_deterministic;
+    use crate::trace::{Trace, TraceContext};
+
+    /// Pretends to be a patched OpenSSL which only supports TLS 1.2
+    struct DowngradingBackend;
+
+    impl Backend for DowngradingBackend {
+        fn name(&self) -> &str {
+            "openssl-downgraded"
+        }
+
+        fn execute(&self, trace: &Trace) -> (TraceContext, Result<(), Error>) {
+            let mut trace = trace.clone();
+            for descriptor in &mut trace.descriptors {
+                descriptor.tls_version = TLSVersion::V1_2;
+            }
+            OpenSSLBackend.execute(&trace)
+        }
+    }
+
+    #[test]
+    fn test_differential_equal() {
+        make_deterministic();
+        let client = AgentName::first();
+        let trace = seed_successful12(client, client.next());
+
+        let result = run_differential(&trace, &OpenSSLBackend, &OpenSSLBackend);
+        assert_eq!(result, DiffResult::Equal);
+    }
+
+    /// Pretends to be a patched OpenSSL which does not support ALPN on the server side
+    struct NoAlpnBackend;
+
+    impl Backend for NoAlpnBackend {
+        fn name(&self) -> &str {
+            "openssl-no-alpn"
+        }
+
+        fn execute(&self, trace: &Trace) -> (TraceContext, Result<(), Error>) {
+            let mut trace = trace.clone();
+            for descriptor in &mut trace.descriptors {
+                descriptor.alpn_protocols = None;
+            }
+            OpenSSLBackend.execute(&trace)
+        }
+    }
+
+    #[cfg(any(feature = "openssl111", feature = "openssl102u", feature = "libressl"))]
+    #[test]
+    fn test_differential_connection_diverges() {
+        make_deterministic();
+        let server = AgentName::first();
+        let trace = seed_alpn_negotiation(server);
+
+        // The server sends the same messages, but does not select a protocol
+        let result = run_differential(&trace, &OpenSSLBackend, &NoAlpnBackend);
+        match result {
+            DiffResult::ConnectionDiverged { agent, a, b } => {
+                assert_eq!(agent, server);
+                assert_eq!(a.unwrap().alpn_protocol, Some(b"h2".to_vec()));
+                assert_eq!(b.unwrap().alpn_protocol, None);
+            }
+            result => panic!("Expected diverging connections, got {:?}", result),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tls13")] // require version which supports TLS 1.3
+    fn test_differential_diverges() {
+        make_deterministic();
+        let client = AgentName::first();
+        let trace = seed_successful(client, client.next());
+
+        let result = run_differential(&trace, &OpenSSLBackend, &DowngradingBackend);
+        assert!(result.is_divergence());
+    }
+}
+
 #[cfg(test)]
 pub mod serialization {
     use test_env_log::test;
@@ -452,6 +2452,8 @@ pub mod rustls {
     };
     use test_env_log::test;
 
+    use crate::tls::fn_impl::{fn_application_data, fn_pad_record, fn_set_record_length};
+
     #[test]
     fn test_rustls_message_stability_ch() {
         let hello_client_hex = "1603010136010001320303aa1795f64f48fcfcd0121368f88f176fe2570b07\
@@ -505,6 +2507,52 @@ pub mod rustls {
         println!("{:#?}", Message::try_from(opaque_message).unwrap());
     }
 
+    #[test]
+    fn test_heartbeat_request_keeps_declared_length() {
+        let opaque_message =
+            crate::tls::fn_impl::fn_heartbeat_request(&vec![1, 2, 3], &0x4000).unwrap();
+
+        assert_eq!(
+            opaque_message.encode(),
+            vec![0x18, 0x03, 0x03, 0x00, 0x06, 0x01, 0x40, 0x00, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_compressed_certificate_keeps_declared_length() {
+        let message =
+            crate::tls::fn_impl::fn_compressed_certificate(&1, &0xffffff, &vec![1, 2, 3]).unwrap();
+
+        assert_eq!(
+            OpaqueMessage::from(message).encode(),
+            vec![
+                0x16, 0x03, 0x03, 0x00, 0x0f, 0x19, 0x00, 0x00, 0x0b, 0x00, 0x01, 0xff, 0xff, 0xff,
+                0x00, 0x00, 0x03, 1, 2, 3
+            ]
+        );
+        // Lengths are never truncated silently
+        assert!(
+            crate::tls::fn_impl::fn_compressed_certificate(&1, &0x1000000, &vec![1, 2, 3]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_compress_certificate_extension() {
+        let algorithms = crate::tls::fn_impl::fn_append_cert_compression_algorithm(
+            &crate::tls::fn_impl::fn_new_cert_compression_algorithms().unwrap(),
+            &crate::tls::fn_impl::fn_cert_compression_zlib().unwrap(),
+        )
+        .unwrap();
+        let extension =
+            crate::tls::fn_impl::fn_compress_certificate_extension(&algorithms).unwrap();
+
+        // Extension type, length of the extension, length of the algorithms and zlib
+        assert_eq!(
+            extension.get_encoding(),
+            vec![0x00, 0x1b, 0x00, 0x03, 0x02, 0x00, 0x01]
+        );
+    }
+
     #[test]
     fn test_rustls_message_stability_ch_renegotiation() {
         // Derived from "openssl s_client -msg -connect localhost:44330" and then pressing R
@@ -703,4 +2751,28 @@ pub mod rustls {
         tls.read_to_end(&mut plaintext).unwrap();
         stdout().write_all(&plaintext).unwrap();
     }
+
+    #[test]
+    fn test_set_record_length() {
+        let message = fn_application_data(&vec![1, 2, 3, 4]).unwrap();
+
+        let shorter = fn_set_record_length(&message, &2).unwrap().encode();
+        assert_eq!(shorter, vec![0x17, 0x03, 0x03, 0x00, 0x02, 1, 2, 3, 4]);
+
+        let longer = fn_set_record_length(&message, &300).unwrap().encode();
+        assert_eq!(longer, vec![0x17, 0x03, 0x03, 0x01, 0x2c, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_pad_record() {
+        let message = fn_application_data(&vec![1, 2, 3, 4]).unwrap();
+
+        let padded = fn_pad_record(&message, &3).unwrap();
+        assert_eq!(padded.typ, ContentType::ApplicationData);
+        assert_eq!(padded.payload.0, vec![1, 2, 3, 4, 0x17, 0, 0, 0]);
+        assert_eq!(
+            padded.encode(),
+            vec![0x17, 0x03, 0x03, 0x00, 0x08, 1, 2, 3, 4, 0x17, 0, 0, 0]
+        );
+    }
 }
@@ -1,3 +1,9 @@
+use rustls::msgs::base::Payload;
+use rustls::msgs::enums::ExtensionType;
+use rustls::msgs::enums::PSKKeyExchangeMode;
+use rustls::msgs::handshake::{ClientExtension, Random, UnknownExtension};
+use rustls::{CipherSuite, ProtocolVersion};
+
 use super::error::FnError;
 
 pub fn fn_seq_0() -> Result<u64, FnError> {
@@ -57,6 +63,111 @@ pub fn fn_large_length() -> Result<u64, FnError> {
     Ok(32702) // chosen by experimenting
 }
 
+// ----
+// Record size limits, see https://datatracker.ietf.org/doc/html/rfc6066#section-4 and
+// https://datatracker.ietf.org/doc/html/rfc8449
+// ----
+
+/// Code of the max_fragment_length extension which limits records to 2^9 bytes
+pub fn fn_max_fragment_length_512() -> Result<u64, FnError> {
+    Ok(1)
+}
+/// Record size limit which is small, but above the minimum of 64 bytes
+pub fn fn_record_size_limit_512() -> Result<u64, FnError> {
+    Ok(512)
+}
+
+// ----
+// Certificate compression algorithms, see https://datatracker.ietf.org/doc/html/rfc8879
+// ----
+
+pub fn fn_cert_compression_zlib() -> Result<u64, FnError> {
+    Ok(1)
+}
+pub fn fn_cert_compression_brotli() -> Result<u64, FnError> {
+    Ok(2)
+}
+pub fn fn_cert_compression_zstd() -> Result<u64, FnError> {
+    Ok(3)
+}
+
+// ----
+// External PSKs, see https://datatracker.ietf.org/doc/html/rfc8446#section-2.2
+// ----
+
+/// Identity of the external PSK which agents share by default
+pub const EXTERNAL_PSK_IDENTITY: &[u8] = b"tlspuffin";
+/// External PSK which agents share by default
+pub const EXTERNAL_PSK: [u8; 32] = [0x42; 32];
+
+pub fn fn_external_psk_identity() -> Result<Vec<u8>, FnError> {
+    Ok(EXTERNAL_PSK_IDENTITY.to_vec())
+}
+pub fn fn_external_psk() -> Result<Vec<u8>, FnError> {
+    Ok(EXTERNAL_PSK.to_vec())
+}
+pub fn fn_psk_ke_mode() -> Result<PSKKeyExchangeMode, FnError> {
+    Ok(PSKKeyExchangeMode::PSK_KE)
+}
+pub fn fn_psk_dhe_ke_mode() -> Result<PSKKeyExchangeMode, FnError> {
+    Ok(PSKKeyExchangeMode::PSK_DHE_KE)
+}
+
+// ----
+// Hash and signature algorithm pairs of TLS 1.2, see https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.1.4.1
+// ----
+
+/// sha256 (4) with rsa (1)
+pub fn fn_sig_hash_alg_rsa_sha256() -> Result<Vec<u8>, FnError> {
+    Ok(vec![4, 1])
+}
+/// sha256 (4) with ecdsa (3)
+pub fn fn_sig_hash_alg_ecdsa_sha256() -> Result<Vec<u8>, FnError> {
+    Ok(vec![4, 3])
+}
+
 pub fn fn_empty_bytes_vec() -> Result<Vec<u8>, FnError> {
     Ok(vec![])
 }
+
+/// Used as 0-RTT data
+pub fn fn_early_data_bytes_vec() -> Result<Vec<u8>, FnError> {
+    Ok(b"GET / HTTP/1.1\r\n\r\n".to_vec())
+}
+
+// ----
+// GREASE values, see https://datatracker.ietf.org/doc/html/rfc8701
+// ----
+
+/// Reserved cipher suite which peers must ignore
+pub fn fn_grease_cipher_suite() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::Unknown(0x0A0A))
+}
+
+/// Reserved extension which peers must ignore. The body is empty.
+pub fn fn_grease_extension() -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::Unknown(UnknownExtension {
+        typ: ExtensionType::Unknown(0x1A1A),
+        payload: Payload::empty(),
+    }))
+}
+
+/// Reserved protocol version which peers must ignore
+pub fn fn_grease_version() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::Unknown(0x2A2A))
+}
+
+// ----
+// Downgrade protection, see https://datatracker.ietf.org/doc/html/rfc8446#section-4.1.3
+// ----
+
+/// Last bytes of ServerHello.random if a TLS 1.3 capable server negotiates TLS 1.2
+pub const DOWNGRADE_SENTINEL_TLS12: [u8; 8] = *b"DOWNGRD\x01";
+
+/// ServerHello.random with the TLS 1.2 downgrade sentinel. A TLS 1.3 capable client must abort
+/// the handshake with an `illegal_parameter` alert if it negotiates TLS 1.2 with this random.
+pub fn fn_downgrade_random() -> Result<Random, FnError> {
+    let mut random_data: [u8; 32] = [1; 32];
+    random_data[24..].copy_from_slice(&DOWNGRADE_SENTINEL_TLS12);
+    Ok(Random::from(random_data))
+}
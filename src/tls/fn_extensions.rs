@@ -18,7 +18,8 @@ use crate::tls::key_exchange::deterministic_key_share;
 use super::error::FnError;
 use crate::tls::fn_impl::fn_get_ticket_age_add;
 use crate::tls::fn_utils::fn_get_ticket;
-use rustls::msgs::message::Message;
+use rustls::msgs::codec::Codec;
+use rustls::msgs::message::{Message, MessagePayload};
 
 pub fn fn_client_extensions_new() -> Result<Vec<ClientExtension>, FnError> {
     Ok(vec![])
@@ -122,21 +123,48 @@ pub fn fn_new_session_ticket_extensions_append(
 //
 
 /// ServerName => 0x0000,
-pub fn fn_server_name_extension() -> Result<ClientExtension, FnError> {
-    let dns_name = "inria.fr";
-    Ok(ClientExtension::ServerName(vec![ServerName {
-        typ: ServerNameType::HostName,
-        payload: ServerNamePayload::HostName((
-            PayloadU16(dns_name.to_string().into_bytes()),
-            webpki::DnsNameRef::try_from_ascii_str(dns_name)?.to_owned(),
-        )),
-    }]))
+/// Each of the `names` is sent as host name. The list may be empty and the names do not need to be
+/// valid DNS names or UTF-8, such that servers can be tested with malformed names.
+pub fn fn_server_name_extension(names: &Vec<Vec<u8>>) -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::ServerName(
+        names
+            .iter()
+            .map(|name| {
+                let payload = match webpki::DnsNameRef::try_from_ascii(name) {
+                    Ok(dns_name) => ServerNamePayload::HostName((
+                        PayloadU16::new(name.clone()),
+                        dns_name.to_owned(),
+                    )),
+                    // rustls only represents valid DNS names, therefore encode others manually
+                    Err(_) => ServerNamePayload::Unknown(Payload::new(
+                        PayloadU16::new(name.clone()).get_encoding(),
+                    )),
+                };
+                ServerName {
+                    typ: ServerNameType::HostName,
+                    payload,
+                }
+            })
+            .collect(),
+    ))
 }
 pub fn fn_server_name_server_extension() -> Result<ServerExtension, FnError> {
     Ok(ServerExtension::ServerNameAck)
 }
 /// MaxFragmentLength => 0x0001,
-nyi_fn!();
+/// The `code` is not checked, such that also codes outside of 1 to 4 can be sent
+pub fn fn_max_fragment_length_extension(code: &u64) -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::Unknown(UnknownExtension {
+        typ: ExtensionType::MaxFragmentLength,
+        payload: Payload::new(vec![*code as u8]),
+    }))
+}
+pub fn fn_max_fragment_length_server_extension(code: &u64) -> Result<ServerExtension, FnError> {
+    Ok(ServerExtension::Unknown(UnknownExtension {
+        typ: ExtensionType::MaxFragmentLength,
+        payload: Payload::new(vec![*code as u8]),
+    }))
+}
 /// ClientCertificateUrl => 0x0002,
 nyi_fn!();
 /// TrustedCAKeys => 0x0003,
@@ -206,6 +234,12 @@ pub fn fn_signature_algorithm_extension() -> Result<ClientExtension, FnError> {
         SignatureScheme::RSA_PSS_SHA256,
     ]))
 }
+/// Announces exactly the `schemes` in the signature_algorithms extension
+pub fn fn_signature_algorithms_extension(
+    schemes: &Vec<SignatureScheme>,
+) -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::SignatureAlgorithms(schemes.clone()))
+}
 pub fn fn_signature_algorithm_cert_req_extension() -> Result<CertReqExtension, FnError> {
     Ok(CertReqExtension::SignatureAlgorithms(vec![
         SignatureScheme::RSA_PKCS1_SHA256,
@@ -215,7 +249,13 @@ pub fn fn_signature_algorithm_cert_req_extension() -> Result<CertReqExtension, F
 /// UseSRTP => 0x000e,
 nyi_fn!();
 /// Heartbeat => 0x000f,
-nyi_fn!();
+pub fn fn_heartbeat_extension() -> Result<ClientExtension, FnError> {
+    // HeartbeatMode: peer_allowed_to_send(1)
+    Ok(ClientExtension::Unknown(UnknownExtension {
+        typ: ExtensionType::Heartbeat,
+        payload: Payload::new(vec![1]),
+    }))
+}
 /// ALProtocolNegotiation => 0x0010,
 pub fn fn_empty_vec_of_vec() -> Result<Vec<Vec<u8>>, FnError> {
     Ok(vec![])
@@ -237,6 +277,15 @@ pub fn fn_al_protocol_negotiation(
             .collect(),
     ))
 }
+pub fn fn_alpn_extension(protocols: &Vec<Vec<u8>>) -> Result<ClientExtension, FnError> {
+    fn_al_protocol_negotiation(protocols)
+}
+pub fn fn_alpn_http2() -> Result<Vec<u8>, FnError> {
+    Ok(b"h2".to_vec())
+}
+pub fn fn_alpn_http11() -> Result<Vec<u8>, FnError> {
+    Ok(b"http/1.1".to_vec())
+}
 pub fn fn_al_protocol_server_negotiation(
     protocol_name_list: &Vec<Vec<u8>>,
 ) -> Result<ServerExtension, FnError> {
@@ -290,9 +339,54 @@ nyi_fn!();
 /// tls_lts => 0x001A,
 nyi_fn!();
 /// compress_certificate => 0x001B,
-nyi_fn!();
+pub fn fn_new_cert_compression_algorithms() -> Result<Vec<u64>, FnError> {
+    Ok(vec![])
+}
+pub fn fn_append_cert_compression_algorithm(
+    algorithms: &Vec<u64>,
+    algorithm: &u64,
+) -> Result<Vec<u64>, FnError> {
+    let mut new_algorithms = algorithms.clone();
+    new_algorithms.push(*algorithm);
+
+    Ok(new_algorithms)
+}
+/// Announces the certificate compression `algorithms` of [RFC 8879](https://datatracker.ietf.org/doc/html/rfc8879)
+pub fn fn_compress_certificate_extension(
+    algorithms: &Vec<u64>,
+) -> Result<ClientExtension, FnError> {
+    let mut algorithm_bytes = vec![];
+    for algorithm in algorithms {
+        (*algorithm as u16).encode(&mut algorithm_bytes);
+    }
+    let mut bytes = vec![];
+    PayloadU8::new(algorithm_bytes).encode(&mut bytes);
+
+    Ok(ClientExtension::Unknown(UnknownExtension {
+        typ: ExtensionType::Unknown(0x001b),
+        payload: Payload::new(bytes),
+    }))
+}
 /// record_size_limit => 0x001C,
-nyi_fn!();
+/// The `limit` is not checked, such that also limits below the minimum of 64 can be sent
+pub fn fn_record_size_limit_extension(limit: &u64) -> Result<ClientExtension, FnError> {
+    let mut bytes = vec![];
+    (*limit as u16).encode(&mut bytes);
+
+    Ok(ClientExtension::Unknown(UnknownExtension {
+        typ: ExtensionType::Unknown(0x001c),
+        payload: Payload::new(bytes),
+    }))
+}
+pub fn fn_record_size_limit_server_extension(limit: &u64) -> Result<ServerExtension, FnError> {
+    let mut bytes = vec![];
+    (*limit as u16).encode(&mut bytes);
+
+    Ok(ServerExtension::Unknown(UnknownExtension {
+        typ: ExtensionType::Unknown(0x001c),
+        payload: Payload::new(bytes),
+    }))
+}
 /// pwd_protect => 0x001D,
 nyi_fn!();
 /// pwd_clear => 0x001E,
@@ -310,14 +404,12 @@ pub fn fn_session_ticket_request_extension() -> Result<ClientExtension, FnError>
     Ok(ClientExtension::SessionTicketRequest)
 }
 pub fn fn_session_ticket_offer_extension(ticket: &Vec<u8>) -> Result<ClientExtension, FnError> {
-    // todo unclear where the arguments come from here, needs manual trace implementation
-    //      https://gitlab.inria.fr/mammann/tlspuffin/-/issues/65
     Ok(ClientExtension::SessionTicketOffer(Payload::new(
         ticket.clone(),
     )))
 }
 pub fn fn_session_ticket_server_extension() -> Result<ServerExtension, FnError> {
-    Ok(ServerExtension::ServerNameAck)
+    Ok(ServerExtension::SessionTicketAck)
 }
 /// TLMSP => 0x0024,
 nyi_fn!();
@@ -369,6 +461,21 @@ pub fn fn_preshared_keys_extension_empty_binder(
     )))
 }
 
+/// Offers the external PSK `identity`. The binder is empty until filled by `fn_fill_binder`.
+pub fn fn_preshared_keys_extension_external(
+    identity: &Vec<u8>,
+) -> Result<ClientExtension, FnError> {
+    let binder_len = rustls::suites::TLS13_AES_128_GCM_SHA256
+        .get_hash()
+        .output_len;
+
+    // External PSKs have no ticket age, therefore the obfuscated age is 0
+    Ok(ClientExtension::PresharedKey(PresharedKeyOffer::new(
+        PresharedKeyIdentity::new(identity.clone(), 0),
+        vec![0u8; binder_len],
+    )))
+}
+
 pub fn fn_preshared_keys_server_extension(identities: &u64) -> Result<ServerExtension, FnError> {
     Ok(ServerExtension::PresharedKey(*identities as u16))
 }
@@ -385,6 +492,25 @@ pub fn fn_early_data_server_extension() -> Result<ServerExtension, FnError> {
     Ok(ServerExtension::EarlyData)
 }
 /// SupportedVersions => 0x002b,
+pub fn fn_new_protocol_versions() -> Result<Vec<ProtocolVersion>, FnError> {
+    Ok(vec![])
+}
+pub fn fn_append_protocol_version(
+    versions: &Vec<ProtocolVersion>,
+    version: &ProtocolVersion,
+) -> Result<Vec<ProtocolVersion>, FnError> {
+    let mut new_versions = versions.clone();
+    new_versions.push(*version);
+
+    Ok(new_versions)
+}
+/// Offers the `versions` in the given order, including unknown or GREASE versions. The server
+/// selects the highest version which it supports.
+pub fn fn_supported_versions_extension(
+    versions: &Vec<ProtocolVersion>,
+) -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::SupportedVersions(versions.clone()))
+}
 pub fn fn_supported_versions12_extension() -> Result<ClientExtension, FnError> {
     Ok(ClientExtension::SupportedVersions(vec![
         ProtocolVersion::TLSv1_2,
@@ -419,6 +545,35 @@ pub fn fn_cookie_extension(cookie: &Vec<u8>) -> Result<ClientExtension, FnError>
 pub fn fn_cookie_hello_retry_extension(cookie: &Vec<u8>) -> Result<HelloRetryExtension, FnError> {
     Ok(HelloRetryExtension::Cookie(PayloadU16::new(cookie.clone())))
 }
+/// The cookie of the first cookie extension in the extensions of a HelloRetryRequest
+pub fn fn_get_hello_retry_cookie(
+    extensions: &Vec<HelloRetryExtension>,
+) -> Result<Vec<u8>, FnError> {
+    extensions
+        .iter()
+        .find_map(|extension| match extension {
+            HelloRetryExtension::Cookie(cookie) => Some(cookie.0.clone()),
+            _ => None,
+        })
+        .ok_or_else(|| FnError::Unknown("HelloRetryRequest contains no cookie".to_string()))
+}
+/// Replaces the cookie extension in `extensions` with one which contains `cookie`. The position of
+/// the extension is kept. If there is no cookie extension, then it is appended.
+pub fn fn_set_cookie_extension(
+    extensions: &Vec<ClientExtension>,
+    cookie: &Vec<u8>,
+) -> Result<Vec<ClientExtension>, FnError> {
+    let mut extensions = extensions.clone();
+    let cookie_extension = fn_cookie_extension(cookie)?;
+    match extensions
+        .iter_mut()
+        .find(|extension| extension.get_type() == ExtensionType::Cookie)
+    {
+        Some(extension) => *extension = cookie_extension,
+        None => extensions.push(cookie_extension),
+    }
+    Ok(extensions)
+}
 /// PSKKeyExchangeModes => 0x002d,
 pub fn fn_psk_exchange_mode_dhe_ke_extension() -> Result<ClientExtension, FnError> {
     Ok(ClientExtension::PresharedKeyModes(vec![
@@ -430,6 +585,22 @@ pub fn fn_psk_exchange_mode_ke_extension() -> Result<ClientExtension, FnError> {
         PSKKeyExchangeMode::PSK_KE,
     ]))
 }
+pub fn fn_new_psk_key_exchange_modes() -> Result<Vec<PSKKeyExchangeMode>, FnError> {
+    Ok(vec![])
+}
+pub fn fn_append_psk_key_exchange_mode(
+    modes: &Vec<PSKKeyExchangeMode>,
+    mode: &PSKKeyExchangeMode,
+) -> Result<Vec<PSKKeyExchangeMode>, FnError> {
+    let mut new_modes = modes.clone();
+    new_modes.push(*mode);
+    Ok(new_modes)
+}
+pub fn fn_psk_key_exchange_modes_extension(
+    modes: &Vec<PSKKeyExchangeMode>,
+) -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::PresharedKeyModes(modes.clone()))
+}
 /// TicketEarlyDataInfo => 0x002e,
 nyi_fn!();
 /// CertificateAuthorities => 0x002f,
@@ -467,6 +638,14 @@ pub fn fn_signature_algorithm_cert_extension() -> Result<ClientExtension, FnErro
         SignatureScheme::RSA_PSS_SHA256,
     ]))
 }
+/// Announces exactly the `schemes` in the signature_algorithms_cert extension. They apply to the
+/// signatures of certificates, while the signature_algorithms extension applies to the signatures
+/// of the handshake, see [RFC 8446 4.2.3](https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.3).
+pub fn fn_signature_algorithms_cert_extension(
+    schemes: &Vec<SignatureScheme>,
+) -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::SignatureAlgorithmsCert(schemes.clone()))
+}
 /// KeyShare => 0x0033,
 pub fn fn_key_share_deterministic_extension() -> Result<ClientExtension, FnError> {
     fn_key_share_extension(&deterministic_key_share(&SECP384R1)?)
@@ -486,8 +665,22 @@ pub fn fn_key_share_server_extension(key_share: &Vec<u8>) -> Result<ServerExtens
         payload: PayloadU16::new(key_share.clone()),
     }))
 }
-pub fn fn_key_share_hello_retry_extension() -> Result<HelloRetryExtension, FnError> {
-    Ok(HelloRetryExtension::KeyShare(NamedGroup::secp384r1))
+/// Key share with a single entry for `group`. The `raw_point` is sent as it is, e.g. a point
+/// which is not on the curve, see [`fn_ec_point`](crate::tls::fn_impl::fn_ec_point).
+pub fn fn_key_share_entry(
+    group: &NamedGroup,
+    raw_point: &Vec<u8>,
+) -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::KeyShare(vec![KeyShareEntry {
+        group: group.clone(),
+        payload: PayloadU16::new(raw_point.clone()),
+    }]))
+}
+/// Selects the `group` for which the client has to send a key share in its second ClientHello
+pub fn fn_key_share_hello_retry_extension(
+    group: &NamedGroup,
+) -> Result<HelloRetryExtension, FnError> {
+    Ok(HelloRetryExtension::KeyShare(group.clone()))
 }
 /// transparency_info => 0x0034,
 nyi_fn!();
@@ -514,6 +707,49 @@ pub fn fn_transport_parameters_server_extension(
 nyi_fn!();
 /// ChannelId => 0x754f,
 nyi_fn!();
+/// encrypted_client_hello => 0xfe0d,
+///
+/// The outer ECH extension: `ECHClientHelloType outer(0)`, the HPKE cipher suite
+/// HKDF-SHA256/AES-128-GCM, config id 0, the encapsulated key `enc` and the `payload`.
+pub fn fn_encrypted_client_hello_extension(
+    enc: &Vec<u8>,
+    payload: &Vec<u8>,
+) -> Result<ClientExtension, FnError> {
+    let mut bytes = vec![0];
+    0x0001u16.encode(&mut bytes); // kdf_id: HKDF-SHA256
+    0x0001u16.encode(&mut bytes); // aead_id: AES-128-GCM
+    0u8.encode(&mut bytes); // config_id
+    PayloadU16::new(enc.clone()).encode(&mut bytes);
+    PayloadU16::new(payload.clone()).encode(&mut bytes);
+
+    Ok(ClientExtension::Unknown(UnknownExtension {
+        typ: ExtensionType::Unknown(0xfe0d),
+        payload: Payload::new(bytes),
+    }))
+}
+/// The inner ECH extension `ECHClientHelloType inner(1)` which marks a ClientHelloInner
+pub fn fn_encrypted_client_hello_inner_extension() -> Result<ClientExtension, FnError> {
+    Ok(ClientExtension::Unknown(UnknownExtension {
+        typ: ExtensionType::Unknown(0xfe0d),
+        payload: Payload::new(vec![1]),
+    }))
+}
+/// Encodes the handshake message of the ClientHello `client_hello` as ECH payload. The payload is
+/// not sealed with HPKE, such that the inner ClientHello stays a subterm which can be mutated.
+pub fn fn_encode_inner_client_hello(client_hello: &Message) -> Result<Vec<u8>, FnError> {
+    match &client_hello.payload {
+        MessagePayload::Handshake(payload) if payload.typ == HandshakeType::ClientHello => {
+            Ok(payload.get_encoding())
+        }
+        _ => Err(FnError::Unknown(
+            "Only a ClientHello can be the inner ClientHello of ECH".to_string(),
+        )),
+    }
+}
+/// A fixed X25519 public key which is used as encapsulated HPKE key of ECH
+pub fn fn_ech_enc() -> Result<Vec<u8>, FnError> {
+    Ok(vec![0x42; 32])
+}
 /// RenegotiationInfo => 0xff01,
 pub fn fn_renegotiation_info_extension(data: &Vec<u8>) -> Result<ClientExtension, FnError> {
     Ok(ClientExtension::RenegotiationInfo(PayloadU8::new(
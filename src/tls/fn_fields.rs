@@ -1,15 +1,25 @@
+use openssl::hash::MessageDigest;
+use openssl::rsa::Padding;
+use openssl::sign::{RsaPssSaltlen, Signer};
+use ring::hmac;
 use rustls::hash_hs::HandshakeHash;
 use rustls::msgs::codec::Codec;
 use rustls::msgs::codec::Reader;
-use rustls::msgs::enums::{Compression, ExtensionType, NamedGroup};
+use rustls::kx::KeyExchange;
+use rustls::msgs::base::PayloadU8;
+use rustls::msgs::enums::{Compression, ContentType, ECCurveType, ExtensionType, NamedGroup};
 use rustls::msgs::handshake::{
-    HasServerExtensions, Random, ServerECDHParams, ServerExtension, SessionID,
+    ClientExtension, ECParameters, HasServerExtensions, Random, ServerECDHParams, ServerExtension,
+    SessionID,
 };
-use rustls::{CipherSuite, NoKeyLog, ProtocolVersion};
+use rustls::{CipherSuite, NoKeyLog, ProtocolVersion, SignatureScheme, ALL_KX_GROUPS};
 
 use super::error::FnError;
-use crate::tls::key_exchange::tls12_new_secrets;
-use crate::tls::key_schedule::dhe_key_schedule;
+use crate::openssl_binding::static_rsa_cert;
+use crate::tls::key_exchange::{
+    deterministic_key_share, tls12_new_secrets, tls12_resumed_secrets,
+};
+use crate::tls::key_schedule::{dhe_key_schedule, tls13_handshake_traffic_secret};
 
 pub fn fn_protocol_version13() -> Result<ProtocolVersion, FnError> {
     Ok(ProtocolVersion::TLSv1_3)
@@ -19,6 +29,35 @@ pub fn fn_protocol_version12() -> Result<ProtocolVersion, FnError> {
     Ok(ProtocolVersion::TLSv1_2)
 }
 
+/// Legacy version which clients usually set in the record of the ClientHello
+pub fn fn_protocol_version10() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::TLSv1_0)
+}
+
+pub fn fn_protocol_version11() -> Result<ProtocolVersion, FnError> {
+    Ok(ProtocolVersion::TLSv1_1)
+}
+
+pub fn fn_content_type_change_cipher_spec() -> Result<ContentType, FnError> {
+    Ok(ContentType::ChangeCipherSpec)
+}
+
+pub fn fn_content_type_alert() -> Result<ContentType, FnError> {
+    Ok(ContentType::Alert)
+}
+
+pub fn fn_content_type_handshake() -> Result<ContentType, FnError> {
+    Ok(ContentType::Handshake)
+}
+
+pub fn fn_content_type_application_data() -> Result<ContentType, FnError> {
+    Ok(ContentType::ApplicationData)
+}
+
+pub fn fn_content_type_heartbeat() -> Result<ContentType, FnError> {
+    Ok(ContentType::Heartbeat)
+}
+
 pub fn fn_new_session_id() -> Result<SessionID, FnError> {
     let mut id: Vec<u8> = Vec::from([3u8; 32]);
     id.insert(0, 32);
@@ -57,6 +96,22 @@ pub fn fn_get_server_key_share(
     }
 }
 
+/// Returns the secp384r1 key share which a client offered in its ClientHello
+pub fn fn_get_client_key_share(
+    client_extensions: &Vec<ClientExtension>,
+) -> Result<Option<Vec<u8>>, FnError> {
+    client_extensions
+        .iter()
+        .find_map(|extension| match extension {
+            ClientExtension::KeyShare(entries) => entries
+                .iter()
+                .find(|entry| entry.group == NamedGroup::secp384r1) // todo https://gitlab.inria.fr/mammann/tlspuffin/-/issues/45
+                .map(|entry| Some(entry.payload.0.clone())),
+            _ => None,
+        })
+        .ok_or(FnError::Unknown("KeyShare extension not found".to_string()))
+}
+
 pub fn fn_verify_data(
     server_finished: &HandshakeHash,
     server_hello: &HandshakeHash,
@@ -82,6 +137,150 @@ pub fn fn_verify_data(
     Ok(Vec::from(bytes.as_ref()))
 }
 
+/// Computes the verify data of the Finished message of a server. The transcript must contain all
+/// messages up to the CertificateVerify of the server.
+pub fn fn_server_verify_data(
+    server_certificate_verify: &HandshakeHash,
+    server_hello: &HandshakeHash,
+    client_key_share: &Option<Vec<u8>>,
+    psk: &Option<Vec<u8>>,
+) -> Result<Vec<u8>, FnError> {
+    let (suite, server_secret, _) = tls13_handshake_traffic_secret(
+        server_hello,
+        client_key_share,
+        psk,
+        false, // false, because we need the traffic secret of the server
+    )?;
+
+    // HKDF-Expand-Label(server_secret, "finished", "", Hash.length)
+    let hash_length = suite.get_hash().output_len as u16;
+    let label = b"tls13 finished";
+    let info: [&[u8]; 4] = [&hash_length.to_be_bytes(), &[label.len() as u8], label, &[0u8]];
+    let finished_key = hmac::Key::from(server_secret.expand(&info, suite.hkdf_algorithm.hmac_algorithm())?);
+
+    let tag = hmac::sign(
+        &finished_key,
+        &server_certificate_verify.get_current_hash_raw(),
+    );
+    Ok(Vec::from(tag.as_ref()))
+}
+
+/// Signs the `transcript` for a TLS 1.3 CertificateVerify of a server. The signature uses
+/// rsa_pss_rsae_sha256 and the static key of the certificate from [`fn_static_rsa_certificate`].
+pub fn fn_certificate_verify13_signature(transcript: &HandshakeHash) -> Result<Vec<u8>, FnError> {
+    let mut message = vec![0x20u8; 64];
+    message.extend_from_slice(b"TLS 1.3, server CertificateVerify\x00");
+    message.extend_from_slice(&transcript.get_current_hash_raw());
+
+    let sign = || {
+        let (_cert, key) = static_rsa_cert()?;
+        let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+        signer.set_rsa_padding(Padding::PKCS1_PSS)?;
+        signer.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+        signer.update(&message)?;
+        signer.sign_to_vec()
+    };
+
+    sign().map_err(|err| FnError::Unknown(err.to_string()))
+}
+
+/// Signs the `transcript` for a TLS 1.2 CertificateVerify of a client, see
+/// [RFC 5246 7.4.8](https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.8). The signature
+/// uses rsa_pkcs1_sha256 and the static key of the certificate from [`fn_static_rsa_certificate`].
+/// The transcript must use SHA-256, like the one of [`fn_new_transcript12`].
+pub fn fn_certificate_verify12_signature(transcript: &HandshakeHash) -> Result<Vec<u8>, FnError> {
+    // DigestInfo of SHA-256, see https://datatracker.ietf.org/doc/html/rfc8017#section-9.2
+    let mut digest_info = vec![
+        0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01,
+        0x05, 0x00, 0x04, 0x20,
+    ];
+    digest_info.extend_from_slice(&transcript.get_current_hash_raw());
+
+    let sign = || -> Result<Vec<u8>, openssl::error::ErrorStack> {
+        let (_cert, key) = static_rsa_cert()?;
+        let rsa = key.rsa()?;
+        let mut signature = vec![0; rsa.size() as usize];
+        let len = rsa.private_encrypt(&digest_info, &mut signature, Padding::PKCS1)?;
+        signature.truncate(len);
+        Ok(signature)
+    };
+
+    sign().map_err(|err| FnError::Unknown(err.to_string()))
+}
+
+// ----
+// seed_server_attacker12()
+// ----
+
+pub fn fn_named_group_secp384r1() -> Result<NamedGroup, FnError> {
+    Ok(NamedGroup::secp384r1)
+}
+
+/// Deterministic public key for an ECDHE key exchange on the curve `group`
+pub fn fn_deterministic_public_key(group: &NamedGroup) -> Result<Vec<u8>, FnError> {
+    let skxg = KeyExchange::choose(*group, &ALL_KX_GROUPS).ok_or(FnError::Unknown(
+        "Failed to find key exchange group".to_string(),
+    ))?;
+    deterministic_key_share(skxg)
+}
+
+/// Uncompressed encoding of the point `(x, y)` on the curve `group`, see
+/// [SEC 1 2.3.3](https://www.secg.org/sec1-v2.pdf). Coordinates which are shorter than the field
+/// size are padded with leading zeros, longer ones are kept as they are. The point is not
+/// validated, which allows sending points which are not on the curve.
+pub fn fn_ec_point(group: &NamedGroup, x: &Vec<u8>, y: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let field_size = match group {
+        NamedGroup::secp256r1 => 32,
+        NamedGroup::secp384r1 => 48,
+        NamedGroup::secp521r1 => 66,
+        _ => {
+            return Err(FnError::Unknown(format!(
+                "Points on {:?} are not supported",
+                group
+            )))
+        }
+    };
+
+    let mut point = vec![0x04];
+    for coordinate in &[x, y] {
+        point.resize(point.len() + field_size.saturating_sub(coordinate.len()), 0);
+        point.extend_from_slice(coordinate);
+    }
+    Ok(point)
+}
+
+/// Signs the ECDHE parameters of a TLS 1.2 ServerKeyExchange, see
+/// [RFC 4492 5.4](https://datatracker.ietf.org/doc/html/rfc4492#section-5.4). The signature uses
+/// rsa_pkcs1_sha256 and the static key of the certificate from [`fn_static_rsa_certificate`].
+pub fn fn_server_key_exchange_signature(
+    client_random: &Random,
+    server_random: &Random,
+    group: &NamedGroup,
+    public: &Vec<u8>,
+) -> Result<Vec<u8>, FnError> {
+    let mut message = Vec::new();
+    client_random.encode(&mut message);
+    server_random.encode(&mut message);
+    ServerECDHParams {
+        curve_params: ECParameters {
+            curve_type: ECCurveType::NamedCurve,
+            named_group: *group,
+        },
+        public: PayloadU8::new(public.clone()),
+    }
+    .encode(&mut message);
+
+    let sign = || {
+        let (_cert, key) = static_rsa_cert()?;
+        let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+        signer.set_rsa_padding(Padding::PKCS1)?;
+        signer.update(&message)?;
+        signer.sign_to_vec()
+    };
+
+    sign().map_err(|err| FnError::Unknown(err.to_string()))
+}
+
 // ----
 // seed_client_attacker12()
 // ----
@@ -97,6 +296,76 @@ pub fn fn_sign_transcript(
     Ok(secrets.client_verify_data(&vh))
 }
 
+/// Computes the verify_data of the client Finished of an abbreviated TLS 1.2 handshake
+pub fn fn_sign_transcript_resumption(
+    server_random: &Random,
+    master_secret: &Vec<u8>,
+    transcript: &HandshakeHash,
+) -> Result<Vec<u8>, FnError> {
+    let secrets = tls12_resumed_secrets(server_random, master_secret)?;
+
+    let vh = transcript.get_current_hash();
+    Ok(secrets.client_verify_data(&vh))
+}
+
+/// Computes the verify_data of the server Finished of an abbreviated TLS 1.2 handshake. The
+/// client Finished covers the server Finished.
+pub fn fn_server_verify_data_resumption(
+    server_random: &Random,
+    master_secret: &Vec<u8>,
+    transcript: &HandshakeHash,
+) -> Result<Vec<u8>, FnError> {
+    let secrets = tls12_resumed_secrets(server_random, master_secret)?;
+
+    let vh = transcript.get_current_hash();
+    Ok(secrets.server_verify_data(&vh))
+}
+
+// ----
+// Signature Schemes
+// ----
+
+pub fn fn_new_signature_schemes() -> Result<Vec<SignatureScheme>, FnError> {
+    Ok(vec![])
+}
+
+pub fn fn_append_signature_scheme(
+    schemes: &Vec<SignatureScheme>,
+    scheme: &SignatureScheme,
+) -> Result<Vec<SignatureScheme>, FnError> {
+    let mut new_schemes = schemes.clone();
+    new_schemes.push(scheme.clone());
+    Ok(new_schemes)
+}
+
+pub fn fn_signature_scheme_rsa_pkcs1_sha1() -> Result<SignatureScheme, FnError> {
+    Ok(SignatureScheme::RSA_PKCS1_SHA1)
+}
+pub fn fn_signature_scheme_rsa_pkcs1_sha256() -> Result<SignatureScheme, FnError> {
+    Ok(SignatureScheme::RSA_PKCS1_SHA256)
+}
+pub fn fn_signature_scheme_rsa_pkcs1_sha384() -> Result<SignatureScheme, FnError> {
+    Ok(SignatureScheme::RSA_PKCS1_SHA384)
+}
+pub fn fn_signature_scheme_rsa_pss_rsae_sha256() -> Result<SignatureScheme, FnError> {
+    Ok(SignatureScheme::RSA_PSS_SHA256)
+}
+pub fn fn_signature_scheme_rsa_pss_rsae_sha384() -> Result<SignatureScheme, FnError> {
+    Ok(SignatureScheme::RSA_PSS_SHA384)
+}
+pub fn fn_signature_scheme_rsa_pss_rsae_sha512() -> Result<SignatureScheme, FnError> {
+    Ok(SignatureScheme::RSA_PSS_SHA512)
+}
+pub fn fn_signature_scheme_ecdsa_secp256r1_sha256() -> Result<SignatureScheme, FnError> {
+    Ok(SignatureScheme::ECDSA_NISTP256_SHA256)
+}
+pub fn fn_signature_scheme_ecdsa_secp384r1_sha384() -> Result<SignatureScheme, FnError> {
+    Ok(SignatureScheme::ECDSA_NISTP384_SHA384)
+}
+pub fn fn_signature_scheme_ed25519() -> Result<SignatureScheme, FnError> {
+    Ok(SignatureScheme::ED25519)
+}
+
 // ----
 // Cipher Suites
 // ----
@@ -126,6 +395,10 @@ pub fn fn_cipher_suite12() -> Result<CipherSuite, FnError> {
     )
 }
 
+pub fn fn_cipher_suite12_aes_256_gcm_sha384() -> Result<CipherSuite, FnError> {
+    Ok(CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384)
+}
+
 pub fn fn_cipher_suite13_aes_128_gcm_sha256() -> Result<CipherSuite, FnError> {
     Ok(CipherSuite::TLS13_AES_128_GCM_SHA256)
 }
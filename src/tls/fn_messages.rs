@@ -10,6 +10,7 @@ use rustls::msgs::handshake::{CertificateEntry, CertificateStatus, HelloRetryExt
 
 use rustls::msgs::alert::AlertMessagePayload;
 use rustls::msgs::base::{PayloadU16, PayloadU24, PayloadU8};
+use rustls::msgs::codec::Codec;
 use rustls::{
     internal::msgs::{
         base::Payload,
@@ -22,11 +23,13 @@ use rustls::{
 };
 use HandshakePayload::EncryptedExtensions;
 
+use crate::io::PlaintextApplicationData;
 use crate::nyi_fn;
 
 use super::error::FnError;
 use rustls::msgs::message::OpaqueMessage;
 
+/// Forwards a record which has been received from another agent without decoding it
 pub fn fn_opaque_message(message: &OpaqueMessage) -> Result<OpaqueMessage, FnError> {
     Ok(message.clone())
 }
@@ -75,6 +78,12 @@ pub fn fn_application_data(data: &Vec<u8>) -> Result<Message, FnError> {
     })
 }
 
+/// In contrast to [`fn_application_data`], which wraps an already encrypted record, the `data`
+/// is sent by the agent itself. It encrypts the data with the keys of its established connection.
+pub fn fn_plaintext_application_data(data: &Vec<u8>) -> Result<PlaintextApplicationData, FnError> {
+    Ok(PlaintextApplicationData(data.clone()))
+}
+
 // ----
 // Heartbeats Message constructors
 // https://www.iana.org/assignments/tls-parameters/tls-parameters.xhtml#heartbeat-message-types
@@ -127,6 +136,24 @@ pub fn fn_heartbeat(payload: &Vec<u8>) -> Result<Message, FnError> {
     fn_heartbeat_fake_length(payload, &(payload.len() as u64))
 }
 
+/// Heartbeat request record in which the `payload_length` field is set to `declared_length`.
+/// The record is encoded manually such that the length field is never recomputed from the payload.
+pub fn fn_heartbeat_request(
+    payload: &Vec<u8>,
+    declared_length: &u64,
+) -> Result<OpaqueMessage, FnError> {
+    let mut encoded: Vec<u8> = Vec::new();
+    HeartbeatMessageType::Request.encode(&mut encoded);
+    (*declared_length as u16).encode(&mut encoded);
+    encoded.extend_from_slice(payload);
+
+    Ok(OpaqueMessage {
+        typ: ContentType::Heartbeat,
+        version: ProtocolVersion::TLSv1_2,
+        payload: Payload::new(encoded),
+    })
+}
+
 // ----
 // Handshake Message constructors
 // ----
@@ -165,6 +192,70 @@ pub fn fn_client_hello(
         }),
     })
 }
+/// TLS 1.3 ClientHello without compression. Fails if the `extensions` do not offer TLS 1.3
+/// through the supported_versions extension or contain no key_share extension, because a server
+/// can not negotiate TLS 1.3 with (EC)DHE without them.
+pub fn fn_client_hello13(
+    random: &Random,
+    session_id: &SessionID,
+    cipher_suites: &Vec<CipherSuite>,
+    extensions: &Vec<ClientExtension>,
+) -> Result<Message, FnError> {
+    if !offers_version(extensions, ProtocolVersion::TLSv1_3) {
+        return Err(FnError::Unknown(
+            "A TLS 1.3 ClientHello must offer TLS 1.3 in the supported_versions extension"
+                .to_string(),
+        ));
+    }
+    if !extensions
+        .iter()
+        .any(|extension| matches!(extension, ClientExtension::KeyShare(_)))
+    {
+        return Err(FnError::Unknown(
+            "A TLS 1.3 ClientHello must contain a key_share extension".to_string(),
+        ));
+    }
+
+    fn_client_hello(
+        &ProtocolVersion::TLSv1_2,
+        random,
+        session_id,
+        cipher_suites,
+        &vec![Compression::Null],
+        extensions,
+    )
+}
+/// TLS 1.2 ClientHello without compression. Fails if the `extensions` offer TLS 1.3 through the
+/// supported_versions extension, see [`fn_client_hello13`].
+pub fn fn_client_hello12(
+    random: &Random,
+    session_id: &SessionID,
+    cipher_suites: &Vec<CipherSuite>,
+    extensions: &Vec<ClientExtension>,
+) -> Result<Message, FnError> {
+    if offers_version(extensions, ProtocolVersion::TLSv1_3) {
+        return Err(FnError::Unknown(
+            "A TLS 1.2 ClientHello must not offer TLS 1.3 in the supported_versions extension"
+                .to_string(),
+        ));
+    }
+
+    fn_client_hello(
+        &ProtocolVersion::TLSv1_2,
+        random,
+        session_id,
+        cipher_suites,
+        &vec![Compression::Null],
+        extensions,
+    )
+}
+/// Whether the supported_versions extension in `extensions` offers `version`
+fn offers_version(extensions: &[ClientExtension], version: ProtocolVersion) -> bool {
+    extensions.iter().any(|extension| match extension {
+        ClientExtension::SupportedVersions(versions) => versions.contains(&version),
+        _ => false,
+    })
+}
 /// ServerHello => 0x02,
 pub fn fn_server_hello(
     legacy_version: &ProtocolVersion,
@@ -189,12 +280,51 @@ pub fn fn_server_hello(
         }),
     })
 }
+/// ServerHello of TLS 1.3 with the mandatory key_share and supported_versions extensions. The
+/// legacy_version is fixed to TLS 1.2 and the compression method to null, see
+/// [RFC 8446 4.1.3](https://datatracker.ietf.org/doc/html/rfc8446#section-4.1.3). The
+/// `supported_version` extension announces the actual version.
+pub fn fn_server_hello13(
+    random: &Random,
+    session_id: &SessionID,
+    cipher_suite: &CipherSuite,
+    key_share_group: &NamedGroup,
+    key_share: &Vec<u8>,
+    supported_version: &ServerExtension,
+) -> Result<Message, FnError> {
+    fn_server_hello(
+        &ProtocolVersion::TLSv1_2,
+        random,
+        session_id,
+        cipher_suite,
+        &Compression::Null,
+        &vec![
+            ServerExtension::KeyShare(KeyShareEntry {
+                group: *key_share_group,
+                payload: PayloadU16::new(key_share.clone()),
+            }),
+            supported_version.clone(),
+        ],
+    )
+}
 /// hello_verify_request_RESERVED => 0x03,
-nyi_fn!();
+/// The HelloVerifyRequest of DTLS 1.0 and 1.2 with the body version DTLS 1.0, which servers use
+/// for any DTLS version. The agents do not speak DTLS, therefore this message is only useful
+/// within records which are crafted by the attacker.
+pub fn fn_hello_verify_request(cookie: &Vec<u8>) -> Result<Message, FnError> {
+    let mut body = vec![0xfe, 0xff];
+    PayloadU8::new(cookie.clone()).encode(&mut body);
+
+    Ok(Message {
+        version: ProtocolVersion::TLSv1_2,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::HelloVerifyRequest,
+            payload: HandshakePayload::Unknown(Payload::new(body)),
+        }),
+    })
+}
 /// NewSessionTicket => 0x04,
 pub fn fn_new_session_ticket(lifetime_hint: &u64, ticket: &Vec<u8>) -> Result<Message, FnError> {
-    // todo unclear where the arguments come from here, needs manual trace implementation
-    //      https://gitlab.inria.fr/mammann/tlspuffin/-/issues/65
     Ok(Message {
         version: ProtocolVersion::TLSv1_2,
         payload: MessagePayload::Handshake(HandshakeMessagePayload {
@@ -228,8 +358,19 @@ pub fn fn_new_session_ticket13(
     })
 }
 /// EndOfEarlyData => 0x05,
-nyi_fn!();
+pub fn fn_end_of_early_data() -> Result<Message, FnError> {
+    Ok(Message {
+        version: ProtocolVersion::TLSv1_2,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::EndOfEarlyData,
+            payload: HandshakePayload::EndOfEarlyData,
+        }),
+    })
+}
 /// HelloRetryRequest => 0x06,
+///
+/// On the wire this is a ServerHello which carries the special random value of
+/// [RFC 8446, Section 4.1.3](https://datatracker.ietf.org/doc/html/rfc8446#section-4.1.3).
 pub fn fn_hello_retry_request(
     legacy_version: &ProtocolVersion,
     session_id: &SessionID,
@@ -250,6 +391,9 @@ pub fn fn_hello_retry_request(
     })
 }
 /// EncryptedExtensions => 0x08,
+///
+/// In TLS 1.3 this message is only valid within an encrypted record, see
+/// [`crate::tls::fn_utils::fn_encrypt_server_handshake`].
 pub fn fn_encrypted_extensions(
     server_extensions: &Vec<ServerExtension>,
 ) -> Result<Message, FnError> {
@@ -275,13 +419,14 @@ pub fn fn_certificate(certs: &CertificatePayload) -> Result<Message, FnError> {
         }),
     })
 }
+/// TLS 1.3 Certificate with the certificate request `context` and the certificate chain `entries`.
+/// Each entry pairs a DER certificate with its extensions, see [`fn_append_certificate_entry`].
+///
+/// [`fn_append_certificate_entry`]: crate::tls::fn_impl::fn_append_certificate_entry
 pub fn fn_certificate13(
     context: &Vec<u8>,
     entries: &Vec<CertificateEntry>,
 ) -> Result<Message, FnError> {
-    // todo unclear where the arguments come from here, needs manual trace implementation
-    //      Vec<CertificateEntry> is not possible to create
-    //      https://gitlab.inria.fr/mammann/tlspuffin/-/issues/65
     Ok(Message {
         version: ProtocolVersion::TLSv1_2,
         payload: MessagePayload::Handshake(HandshakeMessagePayload {
@@ -305,6 +450,35 @@ pub fn fn_server_key_exchange(data: &Vec<u8>) -> Result<Message, FnError> {
         }),
     })
 }
+/// ECDHE ServerKeyExchange of TLS 1.2 with the `public` point of the server on the curve
+/// `group`. The `signature` is not checked and may be invalid.
+pub fn fn_ecdhe_server_key_exchange(
+    group: &NamedGroup,
+    public: &Vec<u8>,
+    signature: &Vec<u8>,
+) -> Result<Message, FnError> {
+    Ok(Message {
+        version: ProtocolVersion::TLSv1_2,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::ServerKeyExchange,
+            payload: HandshakePayload::ServerKeyExchange(ServerKeyExchangePayload::ECDHE(
+                ECDHEServerKeyExchange {
+                    params: ServerECDHParams {
+                        curve_params: ECParameters {
+                            curve_type: ECCurveType::NamedCurve,
+                            named_group: *group,
+                        },
+                        public: PayloadU8::new(public.clone()),
+                    },
+                    dss: DigitallySignedStruct {
+                        scheme: SignatureScheme::RSA_PKCS1_SHA256,
+                        sig: PayloadU16::new(signature.clone()),
+                    },
+                },
+            )),
+        }),
+    })
+}
 /// CertificateRequest => 0x0d,
 pub fn fn_certificate_request() -> Result<Message, FnError> {
     // todo unclear where the arguments come from here, needs manual trace implementation
@@ -350,15 +524,50 @@ pub fn fn_server_hello_done() -> Result<Message, FnError> {
     })
 }
 /// CertificateVerify => 0x0f,
-pub fn fn_certificate_verify(signature: &Vec<u8>) -> Result<Message, FnError> {
-    // todo unclear where the arguments come from here, needs manual trace implementation
-    //      https://gitlab.inria.fr/mammann/tlspuffin/-/issues/65
+///
+/// The announced `scheme` is independent of the `signature`. Therefore, a signature computed with
+/// one scheme can be presented as another one.
+pub fn fn_certificate_verify(
+    scheme: &SignatureScheme,
+    signature: &Vec<u8>,
+) -> Result<Message, FnError> {
     Ok(Message {
         version: ProtocolVersion::TLSv1_2,
         payload: MessagePayload::Handshake(HandshakeMessagePayload {
             typ: HandshakeType::CertificateVerify,
             payload: HandshakePayload::CertificateVerify(DigitallySignedStruct {
-                scheme: SignatureScheme::ED25519,
+                scheme: scheme.clone(),
+                sig: PayloadU16::new(signature.clone()),
+            }),
+        }),
+    })
+}
+/// TLS 1.2 CertificateVerify in which the 2-byte hash and signature algorithm pair `sig_hash_alg`
+/// precedes the `signature`, see [RFC 5246 7.4.8](https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.8).
+/// The bytes of the pair are encoded as they are, even if they are no known algorithm or not two
+/// bytes long.
+pub fn fn_certificate_verify12(
+    sig_hash_alg: &Vec<u8>,
+    signature: &Vec<u8>,
+) -> Result<Message, FnError> {
+    let mut body = sig_hash_alg.clone();
+    PayloadU16::new(signature.clone()).encode(&mut body);
+
+    Ok(Message {
+        version: ProtocolVersion::TLSv1_2,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::CertificateVerify,
+            payload: HandshakePayload::Unknown(Payload::new(body)),
+        }),
+    })
+}
+pub fn fn_certificate_verify13(signature: &Vec<u8>) -> Result<Message, FnError> {
+    Ok(Message {
+        version: ProtocolVersion::TLSv1_2,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::CertificateVerify,
+            payload: HandshakePayload::CertificateVerify(DigitallySignedStruct {
+                scheme: SignatureScheme::RSA_PSS_SHA256,
                 sig: PayloadU16::new(signature.clone()),
             }),
         }),
@@ -418,7 +627,36 @@ pub fn fn_key_update_not_requested() -> Result<Message, FnError> {
     })
 }
 /// compressed_certificate => 0x019,
-nyi_fn!();
+///
+/// Certificate message compressed with `algorithm`, see
+/// [RFC 8879](https://datatracker.ietf.org/doc/html/rfc8879). The `uncompressed_len` is encoded as
+/// it is, even if it does not match the length of the decompressed `compressed_data`. It fails if
+/// `uncompressed_len` does not fit into the 24 bits of the length field.
+pub fn fn_compressed_certificate(
+    algorithm: &u64,
+    uncompressed_len: &u64,
+    compressed_data: &Vec<u8>,
+) -> Result<Message, FnError> {
+    if *uncompressed_len > 0xff_ffff {
+        return Err(FnError::Unknown(format!(
+            "The uncompressed length {} does not fit into 24 bits",
+            uncompressed_len
+        )));
+    }
+
+    let mut body = vec![];
+    (*algorithm as u16).encode(&mut body);
+    body.extend_from_slice(&(*uncompressed_len as u32).to_be_bytes()[1..]);
+    PayloadU24::new(compressed_data.clone()).encode(&mut body);
+
+    Ok(Message {
+        version: ProtocolVersion::TLSv1_2,
+        payload: MessagePayload::Handshake(HandshakeMessagePayload {
+            typ: HandshakeType::Unknown(0x19),
+            payload: HandshakePayload::Unknown(Payload::new(body)),
+        }),
+    })
+}
 /// ekt_key => 0x01A,
 nyi_fn!();
 /// MessageHash => 0xfe
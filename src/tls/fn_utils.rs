@@ -1,24 +1,35 @@
 use std::convert::TryFrom;
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use ring::hkdf::Prk;
+use ring::hmac;
 
 use rustls::cipher::{new_tls12, new_tls13_read, new_tls13_write};
 use rustls::hash_hs::HandshakeHash;
 use rustls::internal::msgs::enums::HandshakeType;
 use rustls::key_schedule::KeyScheduleEarly;
-use rustls::msgs::base::PayloadU8;
+use rustls::msgs::base::{Payload, PayloadU8};
 use rustls::msgs::codec::{Codec, Reader};
 use rustls::msgs::handshake::{
     CertificateEntry, CertificateExtension, HandshakeMessagePayload, HandshakePayload, Random,
-    ServerECDHParams,
+    ServerECDHParams, SessionID,
 };
 use rustls::msgs::message::{Message, MessagePayload, OpaqueMessage};
-use rustls::{key, Certificate, ProtocolVersion};
+use rustls::{key, Certificate, CipherSuite, ProtocolVersion};
 
-use crate::tls::key_exchange::{tls12_key_exchange, tls12_new_secrets};
+use crate::io::RawRecord;
+use crate::openssl_binding::static_rsa_cert;
+use crate::tls::key_exchange::{
+    supported_cipher_suite, tls12_key_exchange, tls12_new_secrets, tls12_resumed_secrets,
+    tls12_suite_secrets,
+};
 use crate::tls::key_schedule::*;
 
 use super::error::FnError;
 use rustls::msgs::alert::AlertMessagePayload;
-use rustls::msgs::enums::{AlertDescription, AlertLevel};
+use rustls::msgs::enums::{AlertDescription, AlertLevel, ContentType};
 
 // ----
 // seed_client_attacker()
@@ -104,6 +115,43 @@ pub fn fn_encrypt_handshake(
     Ok(Message::try_from(application_data.clone())?)
 }
 
+/// Encrypts a message of a client with the client_early_traffic_secret. This allows to send 0-RTT
+/// data and the EndOfEarlyData message while resuming a session with `psk`. The
+/// `client_hello` transcript contains the full ClientHello including the binder.
+pub fn fn_encrypt_early_data(
+    some_message: &Message,
+    client_hello: &HandshakeHash,
+    psk: &Vec<u8>,
+    sequence: &u64,
+) -> Result<Message, FnError> {
+    let (suite, key) = tls13_client_early_traffic_secret(client_hello, psk)?;
+    let encrypter = new_tls13_write(suite, &key);
+    let application_data = encrypter.encrypt(
+        OpaqueMessage::from(some_message.clone()).borrow(),
+        *sequence,
+    )?;
+    Ok(Message::try_from(application_data.clone())?)
+}
+
+/// Encrypts a handshake message of a server with the handshake traffic secret of the server. This
+/// allows to act as a TLS 1.3 server towards a client.
+pub fn fn_encrypt_server_handshake(
+    some_message: &Message,
+    server_hello: &HandshakeHash,
+    client_key_share: &Option<Vec<u8>>,
+    psk: &Option<Vec<u8>>,
+    sequence: &u64,
+) -> Result<Message, FnError> {
+    let (suite, key, _) =
+        tls13_handshake_traffic_secret(&server_hello, client_key_share, psk, false)?;
+    let encrypter = new_tls13_write(suite, &key);
+    let application_data = encrypter.encrypt(
+        OpaqueMessage::from(some_message.clone()).borrow(),
+        *sequence,
+    )?;
+    Ok(Message::try_from(application_data.clone())?)
+}
+
 pub fn fn_encrypt_application(
     some_message: &Message,
     server_hello_transcript: &HandshakeHash,
@@ -174,6 +222,26 @@ pub fn fn_derive_binder(full_client_hello: &Message, psk: &Vec<u8>) -> Result<Ve
     Ok(Vec::from(real_binder.as_ref()))
 }
 
+/// Binder of an external PSK, see [`fn_derive_binder`] for resumption PSKs
+pub fn fn_derive_external_binder(
+    full_client_hello: &Message,
+    psk: &Vec<u8>,
+) -> Result<Vec<u8>, FnError> {
+    let client_hello_payload: HandshakeMessagePayload = match full_client_hello.payload.clone() {
+        MessagePayload::Handshake(payload) => Some(payload),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        FnError::Unknown("Only can fill binder in HandshakeMessagePayload".to_owned())
+    })?;
+
+    let suite_hash = rustls::suites::TLS13_AES_128_GCM_SHA256.get_hash();
+    let binder_plaintext = client_hello_payload.get_encoding_for_binder_signing();
+    let handshake_hash = HandshakeHash::new().get_hash_given(suite_hash, &binder_plaintext);
+
+    tls13_external_psk_binder(psk, handshake_hash.as_ref())
+}
+
 pub fn fn_fill_binder(full_client_hello: &Message, binder: &Vec<u8>) -> Result<Message, FnError> {
     match full_client_hello.payload.clone() {
         MessagePayload::Handshake(payload) => match payload.payload {
@@ -198,6 +266,7 @@ pub fn fn_fill_binder(full_client_hello: &Message, binder: &Vec<u8>) -> Result<M
 pub fn fn_get_ticket(new_ticket: &Message) -> Result<Vec<u8>, FnError> {
     match new_ticket.payload.clone() {
         MessagePayload::Handshake(payload) => match payload.payload {
+            HandshakePayload::NewSessionTicket(payload) => Some(payload.ticket.0.clone()),
             HandshakePayload::NewSessionTicketTLS13(payload) => Some(payload.ticket.0.clone()),
             _ => None,
         },
@@ -228,6 +297,127 @@ pub fn fn_get_ticket_nonce(new_ticket: &Message) -> Result<Vec<u8>, FnError> {
     .ok_or_else(|| FnError::Unknown("Could not find ticket in message".to_owned()))
 }
 
+/// Splits the plaintext `message` into records which carry at most `max_fragment_len` bytes of
+/// payload each. The records have the content type and version of the `message`.
+pub fn fn_fragment(
+    message: &Message,
+    max_fragment_len: &u64,
+) -> Result<Vec<OpaqueMessage>, FnError> {
+    if *max_fragment_len == 0 {
+        return Err(FnError::Unknown(
+            "Unable to fragment into records of length 0".to_string(),
+        ));
+    }
+
+    let opaque = OpaqueMessage::from(message.clone());
+    let payload = opaque.payload.0;
+
+    if payload.is_empty() {
+        return Ok(vec![OpaqueMessage {
+            typ: opaque.typ,
+            version: opaque.version,
+            payload: Payload::empty(),
+        }]);
+    }
+
+    Ok(payload
+        .chunks(*max_fragment_len as usize)
+        .map(|chunk| OpaqueMessage {
+            typ: opaque.typ,
+            version: opaque.version,
+            payload: Payload::new(chunk.to_vec()),
+        })
+        .collect())
+}
+
+/// Fragment length with which [`fn_fragment`] splits `message` into `fragments` records
+pub fn fn_fragment_length(message: &Message, fragments: &u64) -> Result<u64, FnError> {
+    if *fragments == 0 {
+        return Err(FnError::Unknown(
+            "Unable to fragment into 0 records".to_string(),
+        ));
+    }
+
+    let length = OpaqueMessage::from(message.clone()).payload.0.len() as u64;
+    Ok((length + fragments - 1) / fragments)
+}
+
+/// Encodes `message` as a single record whose length field is set to `length`, regardless of the
+/// actual length of the payload
+pub fn fn_set_record_length(message: &Message, length: &u64) -> Result<RawRecord, FnError> {
+    let opaque = OpaqueMessage::from(message.clone());
+    Ok(RawRecord {
+        typ: opaque.typ,
+        version: opaque.version,
+        length: *length as u16,
+        payload: opaque.payload.0,
+    })
+}
+
+/// Encodes `message` as a single record whose length field is `offset` bytes larger than the
+/// actual length of the payload
+pub fn fn_grow_record_length(message: &Message, offset: &u64) -> Result<RawRecord, FnError> {
+    let opaque = OpaqueMessage::from(message.clone());
+    Ok(RawRecord {
+        typ: opaque.typ,
+        version: opaque.version,
+        length: (opaque.payload.0.len() as u64).wrapping_add(*offset) as u16,
+        payload: opaque.payload.0,
+    })
+}
+
+/// Encodes `message` as a single record whose length field is `offset` bytes smaller than the
+/// actual length of the payload
+pub fn fn_shrink_record_length(message: &Message, offset: &u64) -> Result<RawRecord, FnError> {
+    let opaque = OpaqueMessage::from(message.clone());
+    Ok(RawRecord {
+        typ: opaque.typ,
+        version: opaque.version,
+        length: (opaque.payload.0.len() as u64).saturating_sub(*offset) as u16,
+        payload: opaque.payload.0,
+    })
+}
+
+/// Body of the handshake `message`, i.e. its encoding without the handshake type and length
+pub fn fn_handshake_body(message: &Message) -> Result<Vec<u8>, FnError> {
+    match &message.payload {
+        MessagePayload::Handshake(payload) => Ok(payload.get_encoding()[4..].to_vec()),
+        _ => Err(FnError::Unknown(
+            "Only handshake messages have a handshake body".to_string(),
+        )),
+    }
+}
+
+/// Compresses `data` with zlib, e.g. for a CompressedCertificate
+pub fn fn_zlib_compress(data: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|_| encoder.finish())
+        .map_err(|err| FnError::Unknown(format!("Failed to compress with zlib: {}", err)))
+}
+
+/// Length of `bytes`
+pub fn fn_bytes_len(bytes: &Vec<u8>) -> Result<u64, FnError> {
+    Ok(bytes.len() as u64)
+}
+
+/// Builds the TLS 1.3 inner plaintext of `message` followed by `pad_bytes` zero bytes of padding,
+/// see [RFC 8446 5.4](https://datatracker.ietf.org/doc/html/rfc8446#section-5.4). The record is
+/// disguised as application data like encrypted TLS 1.3 records.
+pub fn fn_pad_record(message: &Message, pad_bytes: &u64) -> Result<OpaqueMessage, FnError> {
+    let opaque = OpaqueMessage::from(message.clone());
+    let mut payload = opaque.payload.0;
+    opaque.typ.encode(&mut payload);
+    payload.resize(payload.len() + *pad_bytes as usize, 0);
+
+    Ok(OpaqueMessage {
+        typ: ContentType::ApplicationData,
+        version: ProtocolVersion::TLSv1_2,
+        payload: Payload::new(payload),
+    })
+}
+
 // ----
 // seed_client_attacker12()
 // ----
@@ -268,6 +458,142 @@ pub fn fn_encrypt12(
     Ok(Message::try_from(encrypted)?)
 }
 
+/// Returns the master secret of a TLS 1.2 handshake, which allows to resume the session later on
+pub fn fn_master_secret12(
+    server_random: &Random,
+    server_ecdh_params: &ServerECDHParams,
+) -> Result<Vec<u8>, FnError> {
+    let secrets = tls12_new_secrets(server_random, server_ecdh_params)?;
+    Ok(secrets.get_master_secret())
+}
+
+/// Like [`fn_new_transcript12`], but the transcript uses the hash function of the `cipher_suite`
+pub fn fn_new_transcript_with_suite(cipher_suite: &CipherSuite) -> Result<HandshakeHash, FnError> {
+    let suite = supported_cipher_suite(cipher_suite)?;
+
+    let mut transcript = HandshakeHash::new();
+    transcript.start_hash(&suite.get_hash());
+    Ok(transcript)
+}
+
+/// Like [`fn_master_secret12`], but the master secret is derived for the `cipher_suite`
+pub fn fn_master_secret12_with_suite(
+    cipher_suite: &CipherSuite,
+    server_random: &Random,
+    server_ecdh_params: &ServerECDHParams,
+) -> Result<Vec<u8>, FnError> {
+    let suite = supported_cipher_suite(cipher_suite)?;
+    let secrets = tls12_suite_secrets(suite, server_random, server_ecdh_params)?;
+    Ok(secrets.get_master_secret())
+}
+
+/// Like [`fn_encrypt12`], but the message is encrypted with the keys of the `cipher_suite`
+pub fn fn_encrypt12_with_suite(
+    message: &Message,
+    cipher_suite: &CipherSuite,
+    server_random: &Random,
+    server_ecdh_params: &ServerECDHParams,
+    sequence: &u64,
+) -> Result<Message, FnError> {
+    let suite = supported_cipher_suite(cipher_suite)?;
+    let secrets = tls12_suite_secrets(suite, server_random, server_ecdh_params)?;
+
+    let (_decrypter, encrypter) = new_tls12(&secrets);
+    let encrypted = encrypter.encrypt(OpaqueMessage::from(message.clone()).borrow(), *sequence)?;
+    Ok(Message::try_from(encrypted)?)
+}
+
+/// Computes the verify_data of a Finished message over the `transcript` with the hash function of
+/// the `cipher_suite`:
+///
+/// * For TLS 1.3 suites the `base_key` is the handshake traffic secret of the sender, see
+///   [RFC 8446 4.4.4](https://datatracker.ietf.org/doc/html/rfc8446#section-4.4.4).
+/// * For TLS 1.2 suites the `base_key` is the master secret, and the verify_data of the client is
+///   computed, see [RFC 5246 7.4.9](https://datatracker.ietf.org/doc/html/rfc5246#section-7.4.9).
+pub fn fn_finished_verify_data(
+    cipher_suite: &CipherSuite,
+    transcript: &HandshakeHash,
+    base_key: &Vec<u8>,
+) -> Result<Vec<u8>, FnError> {
+    let suite = supported_cipher_suite(cipher_suite)?;
+    let hkdf_algorithm = suite.hkdf_algorithm;
+    let transcript_hash = transcript.get_current_hash_raw();
+
+    if is_tls13_suite(cipher_suite) {
+        // HKDF-Expand-Label(base_key, "finished", "", Hash.length)
+        let hash_length = suite.get_hash().output_len as u16;
+        let label = b"tls13 finished";
+        let info: [&[u8]; 4] = [
+            &hash_length.to_be_bytes(),
+            &[label.len() as u8],
+            label,
+            &[0u8],
+        ];
+        let finished_key = hmac::Key::from(
+            Prk::new_less_safe(hkdf_algorithm, base_key)
+                .expand(&info, hkdf_algorithm.hmac_algorithm())?,
+        );
+
+        let tag = hmac::sign(&finished_key, &transcript_hash);
+        Ok(Vec::from(tag.as_ref()))
+    } else {
+        let mut verify_data = vec![0u8; 12];
+        tls12_prf(
+            &mut verify_data,
+            hkdf_algorithm.hmac_algorithm(),
+            base_key,
+            b"client finished",
+            &transcript_hash,
+        );
+        Ok(verify_data)
+    }
+}
+
+fn is_tls13_suite(cipher_suite: &CipherSuite) -> bool {
+    matches!(
+        cipher_suite,
+        CipherSuite::TLS13_AES_128_GCM_SHA256
+            | CipherSuite::TLS13_AES_256_GCM_SHA384
+            | CipherSuite::TLS13_CHACHA20_POLY1305_SHA256
+            | CipherSuite::TLS13_AES_128_CCM_SHA256
+            | CipherSuite::TLS13_AES_128_CCM_8_SHA256
+    )
+}
+
+/// The PRF of TLS 1.2, see [RFC 5246 5](https://datatracker.ietf.org/doc/html/rfc5246#section-5)
+fn tls12_prf(out: &mut [u8], algorithm: hmac::Algorithm, secret: &[u8], label: &[u8], seed: &[u8]) {
+    let key = hmac::Key::new(algorithm, secret);
+    let mut label_seed = label.to_vec();
+    label_seed.extend_from_slice(seed);
+
+    // A(1) = HMAC(secret, label + seed)
+    let mut a = hmac::sign(&key, &label_seed);
+    for chunk in out.chunks_mut(algorithm.digest_algorithm().output_len) {
+        let mut context = hmac::Context::with_key(&key);
+        context.update(a.as_ref());
+        context.update(&label_seed);
+        let output = context.sign();
+        chunk.copy_from_slice(&output.as_ref()[..chunk.len()]);
+
+        a = hmac::sign(&key, a.as_ref());
+    }
+}
+
+/// Encrypts a message within an abbreviated TLS 1.2 handshake which resumes the session of the
+/// `master_secret`
+pub fn fn_encrypt12_resumption(
+    message: &Message,
+    server_random: &Random,
+    master_secret: &Vec<u8>,
+    sequence: &u64,
+) -> Result<Message, FnError> {
+    let secrets = tls12_resumed_secrets(server_random, master_secret)?;
+
+    let (_decrypter, encrypter) = new_tls12(&secrets);
+    let encrypted = encrypter.encrypt(OpaqueMessage::from(message.clone()).borrow(), *sequence)?;
+    Ok(Message::try_from(encrypted)?)
+}
+
 pub fn fn_new_certificate() -> Result<key::Certificate, FnError> {
     let der_cert = hex::decode(
         "308203473082022fa003020102021406f7fb1d20\
@@ -295,6 +621,14 @@ pub fn fn_new_certificate() -> Result<key::Certificate, FnError> {
     })?))
 }
 
+/// Certificate for which the private key is known, see [`crate::openssl_binding::static_rsa_cert`]
+pub fn fn_static_rsa_certificate() -> Result<key::Certificate, FnError> {
+    let der_cert = static_rsa_cert()
+        .and_then(|(cert, _key)| cert.to_der())
+        .map_err(|err| FnError::Unknown(err.to_string()))?;
+    Ok(Certificate(der_cert))
+}
+
 pub fn fn_new_certificates() -> Result<Vec<key::Certificate>, FnError> {
     Ok(vec![])
 }
@@ -326,3 +660,65 @@ pub fn fn_append_certificate_entry(
 
     Ok(new_certs)
 }
+
+// ----
+// Literal bytes, e.g. for traces imported from captures
+// ----
+
+/// Appends the byte `high * 16 + low` to `bytes`. The nibbles are usually the constants
+/// `fn_seq_0` to `fn_seq_15`.
+pub fn fn_append_nibbles(bytes: &Vec<u8>, high: &u64, low: &u64) -> Result<Vec<u8>, FnError> {
+    if *high > 0xf || *low > 0xf {
+        return Err(FnError::Unknown(format!(
+            "Nibbles {} and {} do not form a byte",
+            high, low
+        )));
+    }
+
+    let mut new_bytes = bytes.clone();
+    new_bytes.push((*high as u8) << 4 | *low as u8);
+    Ok(new_bytes)
+}
+
+pub fn fn_concat_bytes(first: &Vec<u8>, second: &Vec<u8>) -> Result<Vec<u8>, FnError> {
+    let mut new_bytes = first.clone();
+    new_bytes.extend_from_slice(second);
+    Ok(new_bytes)
+}
+
+/// Keeps the first `length` bytes. Used by the
+/// [`TruncateMutator`](crate::fuzzer::mutations::TruncateMutator).
+pub fn fn_truncate_bytes(bytes: &Vec<u8>, length: &u64) -> Result<Vec<u8>, FnError> {
+    let mut new_bytes = bytes.clone();
+    new_bytes.truncate(*length as usize);
+    Ok(new_bytes)
+}
+
+/// Keeps the first `length` bytes of the session id
+pub fn fn_truncate_session_id(session_id: &SessionID, length: &u64) -> Result<SessionID, FnError> {
+    let encoding = session_id.get_encoding();
+    // The encoding is prefixed by a single length byte
+    let mut id = encoding[1..].to_vec();
+    id.truncate(*length as usize);
+    Ok(SessionID::new(&id))
+}
+
+/// Builds a record from its fields. The `payload` is not required to be a valid message of the
+/// `content_type`, which allows to send arbitrary bytes.
+pub fn fn_new_opaque_message(
+    content_type: &ContentType,
+    version: &ProtocolVersion,
+    payload: &Vec<u8>,
+) -> Result<OpaqueMessage, FnError> {
+    Ok(OpaqueMessage {
+        typ: *content_type,
+        version: *version,
+        payload: Payload::new(payload.clone()),
+    })
+}
+
+/// Decodes a single record from `bytes`. Trailing bytes are ignored.
+pub fn fn_decode_record(bytes: &Vec<u8>) -> Result<OpaqueMessage, FnError> {
+    OpaqueMessage::read(&mut Reader::init(bytes))
+        .map_err(|err| FnError::Unknown(format!("Failed to decode record: {:?}", err)))
+}
@@ -6,10 +6,36 @@ use rustls::kx::{KeyExchange, KeyExchangeResult};
 use rustls::msgs::enums::NamedGroup;
 use rustls::msgs::handshake::{Random, ServerECDHParams};
 use rustls::suites::Tls12CipherSuite;
-use rustls::{tls12, SupportedKxGroup, ALL_KX_GROUPS};
+use rustls::{tls12, CipherSuite, SupportedCipherSuite, SupportedKxGroup, ALL_KX_GROUPS};
 
 use crate::tls::error::FnError;
 
+/// Looks up the implementation of the cipher `suite` in rustls
+pub fn supported_cipher_suite(
+    suite: &CipherSuite,
+) -> Result<&'static SupportedCipherSuite, FnError> {
+    match suite {
+        CipherSuite::TLS13_AES_128_GCM_SHA256 => Ok(&rustls::suites::TLS13_AES_128_GCM_SHA256),
+        CipherSuite::TLS13_AES_256_GCM_SHA384 => Ok(&rustls::suites::TLS13_AES_256_GCM_SHA384),
+        CipherSuite::TLS13_CHACHA20_POLY1305_SHA256 => {
+            Ok(&rustls::suites::TLS13_CHACHA20_POLY1305_SHA256)
+        }
+        CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256 => {
+            Ok(&rustls::suites::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256)
+        }
+        CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384 => {
+            Ok(&rustls::suites::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384)
+        }
+        CipherSuite::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256 => {
+            Ok(&rustls::suites::TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256)
+        }
+        _ => Err(FnError::Unknown(format!(
+            "Cipher suite {:?} is not supported",
+            suite
+        ))),
+    }
+}
+
 fn deterministic_key_exchange(skxg: &'static SupportedKxGroup) -> Result<KeyExchange, FnError> {
     let random = FixedByteRandom { byte: 42 };
     let ours = ring::agreement::EphemeralPrivateKey::generate(skxg.agreement_algorithm, &random)?;
@@ -53,12 +79,7 @@ pub fn tls12_key_exchange(
     Ok(kxd)
 }
 
-pub fn tls12_new_secrets(
-    server_random: &Random,
-    server_ecdh_params: &ServerECDHParams,
-) -> Result<ConnectionSecrets, FnError> {
-    let suite = &rustls::suites::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256; // todo https://gitlab.inria.fr/mammann/tlspuffin/-/issues/45
-
+fn tls12_randoms(server_random: &Random) -> Result<ConnectionRandoms, FnError> {
     let mut server_random_bytes = vec![0; 32];
 
     server_random.write_slice(&mut server_random_bytes);
@@ -66,11 +87,28 @@ pub fn tls12_new_secrets(
     let server_random = server_random_bytes
         .try_into()
         .map_err(|_| FnError::Unknown("Server random did not have length of 32".to_string()))?;
-    let randoms = ConnectionRandoms {
+    Ok(ConnectionRandoms {
         we_are_client: true,
         client: [1; 32], // todo https://gitlab.inria.fr/mammann/tlspuffin/-/issues/45
         server: server_random,
-    };
+    })
+}
+
+pub fn tls12_new_secrets(
+    server_random: &Random,
+    server_ecdh_params: &ServerECDHParams,
+) -> Result<ConnectionSecrets, FnError> {
+    let suite = &rustls::suites::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256; // todo https://gitlab.inria.fr/mammann/tlspuffin/-/issues/45
+    tls12_suite_secrets(suite, server_random, server_ecdh_params)
+}
+
+/// Like [`tls12_new_secrets`], but the secrets are derived for the cipher `suite`
+pub fn tls12_suite_secrets(
+    suite: &'static SupportedCipherSuite,
+    server_random: &Random,
+    server_ecdh_params: &ServerECDHParams,
+) -> Result<ConnectionSecrets, FnError> {
+    let randoms = tls12_randoms(server_random)?;
     let kxd = tls12_key_exchange(server_ecdh_params)?;
     let suite12 = Tls12CipherSuite::try_from(suite)
         .map_err(|_err| FnError::Unknown("VersionNotCompatibleError".to_string()))?;
@@ -80,6 +118,24 @@ pub fn tls12_new_secrets(
     Ok(secrets)
 }
 
+/// Derives the secrets of an abbreviated handshake which resumes a session with the
+/// `master_secret` of a previous handshake.
+pub fn tls12_resumed_secrets(
+    server_random: &Random,
+    master_secret: &Vec<u8>,
+) -> Result<ConnectionSecrets, FnError> {
+    let suite = &rustls::suites::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256; // todo https://gitlab.inria.fr/mammann/tlspuffin/-/issues/45
+
+    let randoms = tls12_randoms(server_random)?;
+    let suite12 = Tls12CipherSuite::try_from(suite)
+        .map_err(|_err| FnError::Unknown("VersionNotCompatibleError".to_string()))?;
+    Ok(ConnectionSecrets::new_resume(
+        &randoms,
+        suite12,
+        master_secret.as_slice(),
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use rustls::kx_group::SECP384R1;
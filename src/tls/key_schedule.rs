@@ -1,5 +1,6 @@
 use ring::digest;
-use ring::hkdf::Prk;
+use ring::hkdf::{Prk, Salt, HKDF_SHA256};
+use ring::hmac;
 use rustls::hash_hs::HandshakeHash;
 use rustls::key_schedule::{
     KeyScheduleEarly, KeyScheduleHandshake, KeyScheduleNonSecret,
@@ -43,6 +44,41 @@ pub fn tls13_handshake_traffic_secret(
     ))
 }
 
+/// Derives the client_early_traffic_secret from the `psk` and the transcript of the ClientHello.
+/// The key schedule of rustls does not expose this secret, therefore the derivation of
+/// [RFC 8446](https://datatracker.ietf.org/doc/html/rfc8446#section-7.1) is done manually.
+pub fn tls13_client_early_traffic_secret(
+    client_hello: &HandshakeHash,
+    psk: &Vec<u8>,
+) -> Result<(&'static SupportedCipherSuite, Prk), FnError> {
+    let suite = &rustls::suites::TLS13_AES_128_GCM_SHA256; // todo see op_cipher_suites() https://gitlab.inria.fr/mammann/tlspuffin/-/issues/45
+    let hkdf_algorithm = suite.hkdf_algorithm;
+    let hash_len = hkdf_algorithm.hmac_algorithm().digest_algorithm().output_len;
+
+    let zeroes = [0u8; digest::MAX_OUTPUT_LEN];
+    let early_secret = Salt::new(hkdf_algorithm, &zeroes[..hash_len]).extract(psk);
+
+    // HkdfLabel of Derive-Secret(early_secret, "c e traffic", ClientHello)
+    let label = b"tls13 c e traffic";
+    let context = client_hello.get_current_hash_raw();
+    let output_len = (hash_len as u16).to_be_bytes();
+    let label_len = [label.len() as u8];
+    let context_len = [context.len() as u8];
+    let info = [
+        &output_len[..],
+        &label_len[..],
+        &label[..],
+        &context_len[..],
+        &context[..],
+    ];
+
+    let secret = early_secret
+        .expand(&info, hkdf_algorithm)
+        .map_err(|_| FnError::Unknown("Failed to derive early traffic secret".to_owned()))?;
+
+    Ok((suite, Prk::from(secret)))
+}
+
 pub fn tls13_application_traffic_secret(
     server_hello: &HandshakeHash,
     server_finished: &HandshakeHash,
@@ -152,3 +188,41 @@ pub fn dhe_key_schedule(
 
     key_schedule
 }
+
+/// Binder of an external PSK for the `binder_hash` of the truncated ClientHello, see
+/// [RFC 8446](https://datatracker.ietf.org/doc/html/rfc8446#section-4.2.11.2). In contrast to
+/// resumption PSKs, the binder key is derived with the label `ext binder`. Only SHA-256 is
+/// supported, like by the legacy PSK callbacks of OpenSSL.
+pub fn tls13_external_psk_binder(psk: &[u8], binder_hash: &[u8]) -> Result<Vec<u8>, FnError> {
+    let early_secret = Salt::new(HKDF_SHA256, &[0u8; 32]).extract(psk);
+    let empty_hash = digest::digest(&digest::SHA256, &[]);
+    let binder_key = hkdf_expand_label(&early_secret, b"ext binder", empty_hash.as_ref())?;
+    let finished_key = hkdf_expand_label(
+        &Prk::new_less_safe(HKDF_SHA256, &binder_key),
+        b"finished",
+        &[],
+    )?;
+
+    let key = hmac::Key::new(hmac::HMAC_SHA256, &finished_key);
+    Ok(hmac::sign(&key, binder_hash).as_ref().to_vec())
+}
+
+/// HKDF-Expand-Label of TLS 1.3 with an output as long as a SHA-256 digest
+fn hkdf_expand_label(secret: &Prk, label: &[u8], context: &[u8]) -> Result<Vec<u8>, FnError> {
+    const LABEL_PREFIX: &[u8] = b"tls13 ";
+    let output_len = (digest::SHA256_OUTPUT_LEN as u16).to_be_bytes();
+    let label_len = [(LABEL_PREFIX.len() + label.len()) as u8];
+    let context_len = [context.len() as u8];
+    let info = [
+        &output_len[..],
+        &label_len[..],
+        LABEL_PREFIX,
+        label,
+        &context_len[..],
+        context,
+    ];
+
+    let mut output = vec![0u8; digest::SHA256_OUTPUT_LEN];
+    secret.expand(&info, HKDF_SHA256)?.fill(&mut output)?;
+    Ok(output)
+}
@@ -40,8 +40,24 @@ macro_rules! nyi_fn {
 define_signature!(
     SIGNATURE,
     // constants
+    fn_cert_compression_brotli
+    fn_cert_compression_zlib
+    fn_cert_compression_zstd
+    fn_downgrade_random
+    fn_early_data_bytes_vec
     fn_empty_bytes_vec
+    fn_external_psk
+    fn_external_psk_identity
+    fn_grease_cipher_suite
+    fn_grease_extension
+    fn_grease_version
     fn_large_length
+    fn_max_fragment_length_512
+    fn_psk_dhe_ke_mode
+    fn_psk_ke_mode
+    fn_record_size_limit_512
+    fn_sig_hash_alg_ecdsa_sha256
+    fn_sig_hash_alg_rsa_sha256
     fn_seq_0
     fn_seq_1
     fn_seq_10
@@ -68,29 +84,46 @@ define_signature!(
     fn_certificate_request13
     fn_certificate_status
     fn_certificate_verify
+    fn_certificate_verify12
+    fn_certificate_verify13
     fn_change_cipher_spec
     fn_client_hello
+    fn_client_hello12
+    fn_client_hello13
     fn_client_key_exchange
+    fn_compressed_certificate
     fn_empty_handshake_message
     fn_encrypted_extensions
+    fn_end_of_early_data
     fn_finished
+    fn_opaque_message
     fn_heartbeat
     fn_heartbeat_fake_length
+    fn_heartbeat_request
     fn_hello_request
     fn_hello_retry_request
+    fn_hello_verify_request
     fn_key_update
     fn_key_update_not_requested
     fn_message_hash
     fn_new_session_ticket
     fn_new_session_ticket13
-    fn_opaque_message
     fn_server_hello
+    fn_server_hello13
     fn_server_hello_done
+    fn_plaintext_application_data
     fn_server_key_exchange
+    fn_ecdhe_server_key_exchange
     // extensions
     fn_al_protocol_negotiation
     fn_al_protocol_server_negotiation
+    fn_alpn_extension
+    fn_alpn_http11
+    fn_alpn_http2
+    fn_append_cert_compression_algorithm
     fn_append_preshared_keys_identity
+    fn_append_protocol_version
+    fn_append_psk_key_exchange_mode
     fn_append_vec
     fn_cert_extensions_append
     fn_cert_extensions_new
@@ -99,15 +132,23 @@ define_signature!(
     fn_certificate_authorities_extension
     fn_client_extensions_append
     fn_client_extensions_new
+    fn_compress_certificate_extension
     fn_cookie_extension
     fn_cookie_hello_retry_extension
+    fn_get_hello_retry_cookie
+    fn_set_cookie_extension
     fn_derive_binder
+    fn_derive_external_binder
     fn_derive_psk
     fn_early_data_extension
     fn_early_data_new_session_ticket_extension
     fn_early_data_server_extension
     fn_ec_point_formats_extension
     fn_ec_point_formats_server_extension
+    fn_ech_enc
+    fn_encode_inner_client_hello
+    fn_encrypted_client_hello_extension
+    fn_encrypted_client_hello_inner_extension
     fn_empty_preshared_keys_identity_vec
     fn_empty_vec_of_vec
     fn_extended_master_secret_extension
@@ -116,20 +157,31 @@ define_signature!(
     fn_get_ticket
     fn_get_ticket_age_add
     fn_get_ticket_nonce
+    fn_heartbeat_extension
     fn_hello_retry_extensions_append
     fn_hello_retry_extensions_new
     fn_key_share_deterministic_extension
     fn_key_share_deterministic_server_extension
+    fn_key_share_entry
     fn_key_share_extension
     fn_key_share_hello_retry_extension
     fn_key_share_server_extension
+    fn_max_fragment_length_extension
+    fn_max_fragment_length_server_extension
+    fn_new_cert_compression_algorithms
     fn_new_preshared_key_identity
+    fn_new_protocol_versions
+    fn_new_psk_key_exchange_modes
     fn_new_session_ticket_extensions_append
     fn_new_session_ticket_extensions_new
     fn_preshared_keys_extension_empty_binder
+    fn_preshared_keys_extension_external
     fn_preshared_keys_server_extension
     fn_psk_exchange_mode_dhe_ke_extension
     fn_psk_exchange_mode_ke_extension
+    fn_psk_key_exchange_modes_extension
+    fn_record_size_limit_extension
+    fn_record_size_limit_server_extension
     fn_renegotiation_info_extension
     fn_renegotiation_info_server_extension
     fn_secp384r1_support_group_extension
@@ -143,12 +195,15 @@ define_signature!(
     fn_signature_algorithm_cert_extension
     fn_signature_algorithm_cert_req_extension
     fn_signature_algorithm_extension
+    fn_signature_algorithms_cert_extension
+    fn_signature_algorithms_extension
     fn_signed_certificate_timestamp_certificate_extension
     fn_signed_certificate_timestamp_extension
     fn_signed_certificate_timestamp_server_extension
     fn_status_request_certificate_extension
     fn_status_request_extension
     fn_status_request_server_extension
+    fn_supported_versions_extension
     fn_supported_versions12_extension
     fn_supported_versions12_hello_retry_extension
     fn_supported_versions12_server_extension
@@ -167,42 +222,95 @@ define_signature!(
     fn_unknown_server_extension
     // fields
     fn_append_cipher_suite
+    fn_append_signature_scheme
     fn_cipher_suite12
+    fn_cipher_suite12_aes_256_gcm_sha384
+    fn_content_type_alert
+    fn_content_type_application_data
+    fn_content_type_change_cipher_spec
+    fn_content_type_handshake
+    fn_content_type_heartbeat
     fn_cipher_suite13_aes_128_gcm_sha256
     fn_cipher_suite13_aes_256_gcm_sha384
     fn_cipher_suite13_aes_128_ccm_sha256
     fn_compression
     fn_compressions
+    fn_certificate_verify12_signature
+    fn_certificate_verify13_signature
+    fn_deterministic_public_key
+    fn_ec_point
+    fn_get_client_key_share
     fn_get_server_key_share
+    fn_named_group_secp384r1
     fn_new_cipher_suites
+    fn_new_signature_schemes
     fn_new_random
     fn_new_session_id
     fn_no_key_share
+    fn_protocol_version10
+    fn_protocol_version11
     fn_protocol_version12
     fn_protocol_version13
     fn_secure_rsa_cipher_suite12
+    fn_server_key_exchange_signature
+    fn_server_verify_data
+    fn_server_verify_data_resumption
     fn_sign_transcript
+    fn_sign_transcript_resumption
+    fn_signature_scheme_ecdsa_secp256r1_sha256
+    fn_signature_scheme_ecdsa_secp384r1_sha384
+    fn_signature_scheme_ed25519
+    fn_signature_scheme_rsa_pkcs1_sha1
+    fn_signature_scheme_rsa_pkcs1_sha256
+    fn_signature_scheme_rsa_pkcs1_sha384
+    fn_signature_scheme_rsa_pss_rsae_sha256
+    fn_signature_scheme_rsa_pss_rsae_sha384
+    fn_signature_scheme_rsa_pss_rsae_sha512
     fn_verify_data
     fn_weak_export_cipher_suite
     fn_weak_export_cipher_suites_remove_me
     // utils
     fn_append_certificate
     fn_append_certificate_entry
+    fn_append_nibbles
     fn_append_transcript
+    fn_bytes_len
+    fn_concat_bytes
     fn_decode_ecdh_params
+    fn_decode_record
     fn_decrypt_application
     fn_decrypt_handshake
     fn_encrypt12
+    fn_encrypt12_resumption
+    fn_encrypt12_with_suite
+    fn_encrypt_early_data
     fn_encrypt_application
     fn_encrypt_handshake
+    fn_encrypt_server_handshake
+    fn_finished_verify_data
+    fn_fragment
+    fn_fragment_length
+    fn_grow_record_length
+    fn_handshake_body
+    fn_master_secret12
+    fn_master_secret12_with_suite
     fn_new_certificate
     fn_new_certificate_entries
     fn_new_certificates
+    fn_new_opaque_message
     fn_new_pubkey12
     fn_new_transcript
     fn_new_transcript12
+    fn_new_transcript_with_suite
     fn_no_psk
+    fn_pad_record
     fn_psk
+    fn_set_record_length
+    fn_shrink_record_length
+    fn_static_rsa_certificate
+    fn_truncate_bytes
+    fn_truncate_session_id
+    fn_zlib_compress
     // transcript functions
     fn_client_finished_transcript
     fn_server_finished_transcript
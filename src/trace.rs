@@ -44,7 +44,7 @@
 //!                             Term::Variable(Signature::new_var::<Vec<CipherSuite>>(query)),
 //!                             Term::Variable(Signature::new_var::<Vec<Compression>>(query)),
 //!                             Term::Variable(Signature::new_var::<Vec<ClientExtension>>(query)),
-//!                         ],
+//!                         ].into(),
 //!                     ),
 //!                 }),
 //!             },
@@ -66,16 +66,23 @@
 
 use core::fmt;
 use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::ops::Deref;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, Range};
 use std::rc::Rc;
-use std::{any::TypeId, fmt::Formatter};
+use std::time::Duration;
+use std::{
+    any::{Any, TypeId},
+    fmt::Formatter,
+};
 
 use itertools::Itertools;
 use rustls::msgs::message::Message;
 use rustls::msgs::message::OpaqueMessage;
 use rustls::msgs::{
-    enums::{ContentType, HandshakeType},
+    enums::{AlertDescription, ContentType, HandshakeType},
     message::MessagePayload,
 };
 use security_claims::{Claim, ClaimType};
@@ -86,13 +93,17 @@ use crate::debug::{debug_message_with_info, debug_opaque_message_with_info};
 use crate::error::Error;
 #[allow(unused)] // used in docs
 use crate::io::Channel;
-use crate::io::{MessageResult, Stream};
+use crate::io::{
+    EarlyDataStatus, MemoryStream, MessageResult, PlaintextApplicationData, RawRecord, Stream,
+};
+use crate::recorder::{Direction, TraceEvent, TraceRecorder};
 use crate::term::remove_prefix;
 use crate::tls::error::FnError;
+use crate::tls::fn_impl::fn_get_ticket;
 use crate::violation::is_violation;
 use crate::{
     agent::{Agent, AgentName},
-    term::{dynamic_function::TypeShape, Term},
+    term::{dynamic_function::TypeShape, Term, TermDiff},
     variable_data::{extract_knowledge, VariableData},
 };
 
@@ -107,6 +118,31 @@ pub enum TlsMessageType {
     Heartbeat,
 }
 
+/// Converts into the [`Query::tls_message_type`] of a variable. Besides an optional
+/// [`TlsMessageType`], a [`HandshakeType`] can be used to only match handshake messages of this
+/// type, e.g. `((server, 0)[HandshakeType::ServerHello])` in the `term!` macro.
+pub trait IntoMessageTypeFilter {
+    fn into_filter(self) -> Option<TlsMessageType>;
+}
+
+impl IntoMessageTypeFilter for Option<TlsMessageType> {
+    fn into_filter(self) -> Option<TlsMessageType> {
+        self
+    }
+}
+
+impl IntoMessageTypeFilter for TlsMessageType {
+    fn into_filter(self) -> Option<TlsMessageType> {
+        Some(self)
+    }
+}
+
+impl IntoMessageTypeFilter for HandshakeType {
+    fn into_filter(self) -> Option<TlsMessageType> {
+        Some(TlsMessageType::Handshake(Some(self)))
+    }
+}
+
 impl QueryMatcher for HandshakeType {
     fn matches(&self, query: &Self) -> bool {
         query == self
@@ -257,6 +293,11 @@ impl VecClaimer {
     pub fn claim(&mut self, name: AgentName, claim: Claim) {
         self.claims.push((name, claim));
     }
+
+    /// Claims which have been made so far, in the order in which they have been made
+    pub fn claims(&self) -> &Vec<(AgentName, Claim)> {
+        &self.claims
+    }
 }
 
 /// The [`TraceContext`] contains a list of [`VariableData`], which is known as the knowledge
@@ -268,9 +309,54 @@ pub struct TraceContext {
     /// The knowledge of the attacker
     knowledge: Vec<Knowledge>,
     agents: Vec<Agent>,
+    /// Agents of previous executions which can be reused instead of spawning new ones, see
+    /// [`TraceContext::reset`]
+    spare_agents: Vec<Agent>,
     pub claimer: Rc<RefCell<VecClaimer>>,
+    /// A trace may execute at most `max_steps_multiplier * steps.len()` operations. An operation
+    /// is an executed action or a message which is taken from an outbound channel.
+    max_steps_multiplier: usize,
+    /// Operations which the currently executing trace may still execute
+    remaining_operations: usize,
+    /// If set, then all records are mutated on the byte level before they are delivered. The
+    /// seed makes the mutations reproducible.
+    byte_havoc: Option<(u64, ByteMutation)>,
+    byte_havoc_log: Vec<ByteHavocRecord>,
+    /// If set, then all messages which cross the streams of the agents are recorded
+    recorder: Option<TraceRecorder>,
+    /// Index of the step which is currently executed
+    current_step: usize,
+    /// Types of the handshake messages which have been decoded so far, see
+    /// [`TraceContext::message_types`]
+    message_types: Vec<HandshakeType>,
+}
+
+/// Buffers and claims of a [`TraceContext`] at some point of an execution, see
+/// [`TraceContext::snapshot`]
+#[derive(Clone)]
+pub struct ContextSnapshot {
+    buffers: Vec<(AgentName, MemoryStream)>,
+    claimer: VecClaimer,
+    knowledge_len: usize,
+}
+
+/// Records how the bytes of a delivery were mutated, see [`TraceContext::enable_byte_havoc`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ByteHavocRecord {
+    pub agent: AgentName,
+    /// The seed with which `original` was mutated to `mutated`
+    pub seed: u64,
+    pub original: Vec<u8>,
+    pub mutated: Vec<u8>,
 }
 
+/// Mutation of the bytes of a delivery with a seed, see [`TraceContext::enable_byte_havoc`]. The
+/// result must only depend on the bytes and the seed, else executions can not be replayed.
+pub type ByteMutation = fn(&[u8], u64) -> Vec<u8>;
+
+/// Default for [`TraceContext::set_max_steps_multiplier`]
+pub const DEFAULT_MAX_STEPS_MULTIPLIER: usize = 64;
+
 pub trait QueryMatcher {
     fn matches(&self, query: &Self) -> bool;
 }
@@ -282,14 +368,130 @@ impl TraceContext {
         Self {
             knowledge: vec![],
             agents: vec![],
+            spare_agents: vec![],
             claimer,
+            max_steps_multiplier: DEFAULT_MAX_STEPS_MULTIPLIER,
+            remaining_operations: 0,
+            byte_havoc: None,
+            byte_havoc_log: vec![],
+            recorder: None,
+            current_step: 0,
+            message_types: vec![],
+        }
+    }
+
+    /// Records every message which is added to an inbound channel or taken from an outbound
+    /// channel, see [`TraceContext::take_recording`]
+    pub fn enable_recording(&mut self) {
+        self.recorder = Some(TraceRecorder::new());
+    }
+
+    /// Returns the events which have been recorded since the last call. The recording is empty if
+    /// it has not been enabled through [`TraceContext::enable_recording`].
+    pub fn take_recording(&mut self) -> Vec<TraceEvent> {
+        self.recorder
+            .as_mut()
+            .map(|recorder| recorder.take_events())
+            .unwrap_or_default()
+    }
+
+    fn record_message(&mut self, agent: AgentName, direction: Direction, message: &OpaqueMessage) {
+        let step_index = self.current_step;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_message(step_index, agent, direction, message);
+        }
+    }
+
+    /// Records the [`HandshakeType`] of a successfully decoded message
+    fn record_message_type(&mut self, message: Option<&Message>) {
+        if let Some(Message {
+            payload: MessagePayload::Handshake(payload),
+            ..
+        }) = message
+        {
+            if !self.message_types.contains(&payload.typ) {
+                self.message_types.push(payload.typ);
+            }
+        }
+    }
+
+    /// Types of the handshake messages which have been added to an inbound channel or taken from
+    /// an outbound channel, in the order in which they have been seen first
+    pub fn message_types(&self) -> &Vec<HandshakeType> {
+        &self.message_types
+    }
+
+    /// Mutates the bytes of all records with `mutation` before they are delivered to an agent,
+    /// e.g. with the havoc mutations of [`crate::fuzzer::byte_havoc::havoc_bytes`]. The recipes
+    /// stay untouched. Executing the same trace with the same `seed` results in the same
+    /// mutations.
+    pub fn enable_byte_havoc(&mut self, seed: u64, mutation: ByteMutation) {
+        self.byte_havoc = Some((seed, mutation));
+    }
+
+    /// The deliveries which have been mutated so far, in the order they happened
+    pub fn byte_havoc_log(&self) -> &Vec<ByteHavocRecord> {
+        &self.byte_havoc_log
+    }
+
+    /// Limits the operations a trace can execute to `multiplier` times its number of steps. This
+    /// guards executions which are not supervised by a timeout, like offline replays.
+    pub fn set_max_steps_multiplier(&mut self, multiplier: usize) {
+        self.max_steps_multiplier = multiplier;
+    }
+
+    fn reset_operation_budget(&mut self, steps: usize) {
+        self.remaining_operations = self.max_steps_multiplier.saturating_mul(steps);
+    }
+
+    fn count_operation(&mut self) -> Result<(), Error> {
+        if self.remaining_operations == 0 {
+            return Err(Error::Stream("step budget exceeded".to_string()));
         }
+
+        self.remaining_operations -= 1;
+        Ok(())
     }
 
     pub fn add_knowledge(&mut self, knowledge: Knowledge) {
         self.knowledge.push(knowledge)
     }
 
+    /// The knowledge of the attacker in the order it was learned
+    pub fn knowledge(&self) -> &Vec<Knowledge> {
+        &self.knowledge
+    }
+
+    /// Captures the channels of all agents, the claims and the knowledge such that a partial
+    /// execution can be reverted with [`TraceContext::restore`].
+    ///
+    /// The states of the SSL objects of the agents are not captured, because OpenSSL can not copy
+    /// them. A restore therefore only reverts the buffer and claim layer. Agents which progressed
+    /// their handshake in the meantime stay in their new state.
+    pub fn snapshot(&self) -> ContextSnapshot {
+        ContextSnapshot {
+            buffers: self
+                .agents
+                .iter()
+                .map(|agent| (agent.descriptor.name, agent.stream.snapshot_buffers()))
+                .collect(),
+            claimer: self.claimer.borrow().clone(),
+            knowledge_len: self.knowledge.len(),
+        }
+    }
+
+    /// Reverts the channels, claims and knowledge to the `snapshot`, see
+    /// [`TraceContext::snapshot`]. Agents which have been spawned after the snapshot keep their
+    /// channels. Fails if an agent of the snapshot no longer exists.
+    pub fn restore(&mut self, snapshot: ContextSnapshot) -> Result<(), Error> {
+        for (name, buffers) in snapshot.buffers {
+            self.find_agent_mut(name)?.stream.restore_buffers(buffers);
+        }
+        *self.claimer.borrow_mut() = snapshot.claimer;
+        self.knowledge.truncate(snapshot.knowledge_len);
+        Ok(())
+    }
+
     /// Count the number of sub-messages of type [type_id] in the output message [in_step_id].
     pub fn number_matching_message(
         &self,
@@ -316,6 +518,24 @@ impl TraceContext {
         query_type_shape: TypeShape,
         query: Query,
     ) -> Option<&(dyn VariableData)> {
+        let mut possibilities = self.possibilities(query_type_shape, query);
+
+        possibilities.sort_by(|a, b| a.specificity().cmp(&b.specificity()));
+
+
+        possibilities
+            .iter()
+            .nth(query.counter as usize)
+            .map(|possibility| possibility.data.as_ref())
+    }
+
+    /// Amount of knowledge which a variable of type `query_type_shape` can select with `query`,
+    /// regardless of the counter of the `query`
+    pub fn count_possibilities(&self, query_type_shape: TypeShape, query: Query) -> usize {
+        self.possibilities(query_type_shape, query).len()
+    }
+
+    fn possibilities(&self, query_type_shape: TypeShape, query: Query) -> Vec<&Knowledge> {
         let query_type_id: TypeId = query_type_shape.into();
 
         let mut possibilities: Vec<&Knowledge> = Vec::new();
@@ -332,13 +552,7 @@ impl TraceContext {
             }
         }
 
-        possibilities.sort_by(|a, b| a.specificity().cmp(&b.specificity()));
-
-
         possibilities
-            .iter()
-            .nth(query.counter as usize)
-            .map(|possibility| possibility.data.as_ref())
     }
 
     /// Adds data to the inbound [`Channel`] of the [`Agent`] referenced by the parameter "agent".
@@ -347,15 +561,106 @@ impl TraceContext {
         agent_name: AgentName,
         message: &OpaqueMessage,
     ) -> Result<(), Error> {
+        self.record_message(agent_name, Direction::Inbound, message);
+        if message.typ == ContentType::Handshake {
+            self.record_message_type(Message::try_from(message.clone()).ok().as_ref());
+        }
+
+        if self.byte_havoc.is_some() {
+            return self.deliver_bytes(agent_name, &message.clone().encode());
+        }
+
         self.find_agent_mut(agent_name)
             .map(|agent| agent.stream.add_to_inbound(message))
     }
 
+    /// Adds raw bytes to the inbound [`Channel`] of the [`Agent`] referenced by the parameter
+    /// "agent".
+    pub fn add_bytes_to_inbound(
+        &mut self,
+        agent_name: AgentName,
+        bytes: &[u8],
+    ) -> Result<(), Error> {
+        let step_index = self.current_step;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_bytes(step_index, agent_name, Direction::Inbound, bytes);
+        }
+
+        self.deliver_bytes(agent_name, bytes)
+    }
+
+    fn deliver_bytes(&mut self, agent_name: AgentName, bytes: &[u8]) -> Result<(), Error> {
+        if let Some((seed, mutation)) = self.byte_havoc {
+            // Each delivery gets its own seed, else all deliveries are mutated in the same way
+            let seed = seed.wrapping_add(self.byte_havoc_log.len() as u64);
+            let mutated = mutation(bytes, seed);
+
+            self.find_agent_mut(agent_name)?
+                .stream
+                .add_bytes_to_inbound(&mutated);
+            self.byte_havoc_log.push(ByteHavocRecord {
+                agent: agent_name,
+                seed,
+                original: bytes.to_vec(),
+                mutated,
+            });
+            return Ok(());
+        }
+
+        self.find_agent_mut(agent_name)
+            .map(|agent| agent.stream.add_bytes_to_inbound(bytes))
+    }
+
+    /// Adds several records at once to the inbound [`Channel`] of the [`Agent`] referenced by the
+    /// parameter "agent".
+    pub fn add_all_to_inbound(
+        &mut self,
+        agent_name: AgentName,
+        messages: &[OpaqueMessage],
+    ) -> Result<(), Error> {
+        for message in messages {
+            self.record_message(agent_name, Direction::Inbound, message);
+        }
+
+        if self.byte_havoc.is_some() {
+            let bytes: Vec<u8> = messages
+                .iter()
+                .flat_map(|message| message.clone().encode())
+                .collect();
+            return self.deliver_bytes(agent_name, &bytes);
+        }
+
+        self.find_agent_mut(agent_name)
+            .map(|agent| agent.stream.add_all_to_inbound(messages))
+    }
+
     pub fn next_state(&mut self, agent_name: AgentName) -> Result<(), Error> {
         let agent = self.find_agent_mut(agent_name)?;
         Ok(agent.stream.next_state()?)
     }
 
+    /// Executes only the step `index` of `trace`, e.g. to inspect the agents between steps. The
+    /// agents must already be spawned, see [`Trace::prepare`]. A failing step is reported as
+    /// [`StepOutcome::Error`] such that the agents can still be inspected. Security claims are
+    /// only checked by [`Trace::execute`].
+    pub fn execute_step(&mut self, trace: &Trace, index: usize) -> Result<StepOutcome, Error> {
+        let step = trace.steps.get(index).ok_or_else(|| {
+            Error::Agent(format!(
+                "Can not execute step #{} of a trace with {} steps",
+                index,
+                trace.steps.len()
+            ))
+        })?;
+        self.find_agent(step.agent)?;
+
+        trace!("Executing step #{}", index);
+        self.current_step = index;
+        match Trace::execute_step(step, self) {
+            Ok(()) => Ok(StepOutcome::Success),
+            Err(err) => Ok(StepOutcome::Error(err.to_string())),
+        }
+    }
+
     /// Takes data from the outbound [`Channel`] of the [`Agent`] referenced by the parameter "agent".
     /// See [`MemoryStream::take_message_from_outbound`]
     pub fn take_message_from_outbound(
@@ -363,7 +668,29 @@ impl TraceContext {
         agent_name: AgentName,
     ) -> Result<Option<MessageResult>, Error> {
         let agent = self.find_agent_mut(agent_name)?;
-        Ok(agent.stream.take_message_from_outbound()?)
+        let result = agent.stream.take_message_from_outbound()?;
+
+        if let Some(MessageResult(message, opaque_message)) = &result {
+            self.record_message_type(message.as_ref());
+            self.record_message(agent_name, Direction::Outbound, opaque_message);
+        }
+        Ok(result)
+    }
+
+    /// Takes all data from the outbound [`Channel`] of the [`Agent`] referenced by the parameter
+    /// "agent". See [`MemoryStream::take_all_messages_from_outbound`]
+    pub fn take_all_messages_from_outbound(
+        &mut self,
+        agent_name: AgentName,
+    ) -> Result<Vec<MessageResult>, Error> {
+        let agent = self.find_agent_mut(agent_name)?;
+        let results = agent.stream.take_all_messages_from_outbound()?;
+
+        for MessageResult(message, opaque_message) in &results {
+            self.record_message_type(message.as_ref());
+            self.record_message(agent_name, Direction::Outbound, opaque_message);
+        }
+        Ok(results)
     }
 
     fn add_agent(&mut self, agent: Agent) -> AgentName {
@@ -377,23 +704,158 @@ impl TraceContext {
         return Ok(agent_name);
     }
 
+    fn agent_not_found(name: AgentName) -> Error {
+        Error::Agent(format!(
+            "Could not find agent {}. Did you forget to call spawn_agents?",
+            name
+        ))
+    }
+
     fn find_agent_mut(&mut self, name: AgentName) -> Result<&mut Agent, Error> {
         let mut iter = self.agents.iter_mut();
 
         iter.find(|agent| agent.descriptor.name == name)
-            .ok_or(Error::Agent(format!(
-                "Could not find agent {}. Did you forget to call spawn_agents?",
-                name
-            )))
+            .ok_or_else(|| Self::agent_not_found(name))
     }
 
     pub fn find_agent(&self, name: AgentName) -> Result<&Agent, Error> {
         let mut iter = self.agents.iter();
         iter.find(|agent| agent.descriptor.name == name)
-            .ok_or(Error::Agent(format!(
-                "Could not find agent {}. Did you forget to call spawn_agents?",
+            .ok_or_else(|| Self::agent_not_found(name))
+    }
+
+    /// Returns the stream of the agent `name`. Fails with [`Error::Agent`] if no such agent has
+    /// been spawned.
+    pub fn agent_by_name(&self, name: AgentName) -> Result<&dyn Stream, Error> {
+        Ok(&self.find_agent(name)?.stream)
+    }
+
+    /// Mutable variant of [`TraceContext::agent_by_name`]
+    pub fn agent_by_name_mut(&mut self, name: AgentName) -> Result<&mut dyn Stream, Error> {
+        Ok(&mut self.find_agent_mut(name)?.stream)
+    }
+
+    /// Exports keying material of the agent `name`, see
+    /// [`crate::io::OpenSSLStream::export_keying_material`]
+    pub fn export_keying_material(
+        &self,
+        name: AgentName,
+        label: &[u8],
+        context: Option<&[u8]>,
+        out_len: usize,
+    ) -> Result<Vec<u8>, Error> {
+        self.find_agent(name)?
+            .stream
+            .export_keying_material(label, context, out_len)
+    }
+
+    /// Lets the agent `name` start a renegotiation of its established TLS 1.2 connection. The
+    /// messages of the new handshake are taken by the next [`OutputAction`].
+    pub fn renegotiate(&mut self, name: AgentName) -> Result<(), Error> {
+        if !self.is_handshake_complete(name)? {
+            return Err(Error::Agent(format!(
+                "Agent {} has not completed its handshake and can not renegotiate",
+                name
+            )));
+        }
+        self.find_agent_mut(name)?.stream.renegotiate()
+    }
+
+    /// Returns the master secret of the session of the agent `name`, see
+    /// [`crate::io::OpenSSLStream::master_secret`]
+    pub fn master_secret(&self, name: AgentName) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.find_agent(name)?.stream.master_secret())
+    }
+
+    /// Returns the ticket of the first NewSessionTicket message which the server `name` issued.
+    /// A follow-up ClientHello can present the ticket to resume the session.
+    pub fn session_ticket(&self, name: AgentName) -> Option<Vec<u8>> {
+        let query = Query {
+            agent_name: name,
+            tls_message_type: Some(TlsMessageType::Handshake(Some(
+                HandshakeType::NewSessionTicket,
+            ))),
+            counter: 0,
+        };
+
+        self.find_variable(TypeShape::of::<Message>(), query)
+            .and_then(|data| data.clone_box_any().downcast::<Message>().ok())
+            .and_then(|message| fn_get_ticket(&message).ok())
+    }
+
+    /// Returns whether the server `name` accepted the 0-RTT data of its peer
+    pub fn early_data_status(&self, name: AgentName) -> Result<EarlyDataStatus, Error> {
+        Ok(self.find_agent(name)?.stream.early_data_status())
+    }
+
+    /// Returns the 0-RTT data which the server `name` received so far
+    pub fn received_early_data(&self, name: AgentName) -> Result<&Vec<u8>, Error> {
+        Ok(self.find_agent(name)?.stream.received_early_data())
+    }
+
+    /// Returns whether the agent `name` completed its handshake
+    pub fn is_handshake_complete(&self, name: AgentName) -> Result<bool, Error> {
+        Ok(self.find_agent(name)?.stream.is_handshake_complete())
+    }
+
+    /// Lets the agent `name` send `data` through its established connection. Fails if the agent
+    /// has not completed its handshake yet.
+    pub fn write_application_data(&mut self, name: AgentName, data: &[u8]) -> Result<(), Error> {
+        if !self.is_handshake_complete(name)? {
+            return Err(Error::Agent(format!(
+                "Agent {} has not completed its handshake and can not send application data",
                 name
-            )))
+            )));
+        }
+        self.find_agent_mut(name)?
+            .stream
+            .write_application_data(data)
+    }
+
+    /// Returns the decrypted application data which the agent `name` received after its handshake
+    pub fn received_application_data(&self, name: AgentName) -> Result<&Vec<u8>, Error> {
+        Ok(self.find_agent(name)?.stream.received_application_data())
+    }
+
+    /// Returns the plaintext alerts which the agent `name` sent so far.
+    pub fn sent_alerts(&self, name: AgentName) -> Result<&Vec<AlertDescription>, Error> {
+        Ok(self.find_agent(name)?.stream.sent_alerts())
+    }
+
+    /// Returns the plaintext alerts which the agent `name` received so far.
+    pub fn received_alerts(&self, name: AgentName) -> Result<&Vec<AlertDescription>, Error> {
+        Ok(self.find_agent(name)?.stream.received_alerts())
+    }
+
+    /// Returns the plaintext alerts which the agents sent so far, together with the sending agent
+    pub fn all_sent_alerts(&self) -> Vec<(AgentName, AlertDescription)> {
+        self.agents
+            .iter()
+            .flat_map(|agent| {
+                agent
+                    .stream
+                    .sent_alerts()
+                    .iter()
+                    .map(move |alert| (agent.descriptor.name, *alert))
+            })
+            .collect()
+    }
+
+    /// Returns the errors which OpenSSL reported for the failed operations of all agents so far,
+    /// see [`crate::io::OpenSSLStream::openssl_errors`]
+    pub fn openssl_errors(&self) -> Vec<String> {
+        self.agents
+            .iter()
+            .flat_map(|agent| agent.stream.openssl_errors().iter().cloned())
+            .collect()
+    }
+
+    /// Returns the OpenSSL state of each agent, see [`crate::io::OpenSSLStream::describe_state`]
+    pub fn agent_states(&self) -> Vec<&'static str> {
+        self.agents
+            .iter()
+            .map(|agent| agent.stream.describe_state())
+            .collect()
     }
 
     pub fn reset_agents(&mut self) {
@@ -401,6 +863,212 @@ impl TraceContext {
             agent.reset();
         }
     }
+
+    /// Prepares this context for the execution of another trace. The knowledge and the claims
+    /// are cleared. The agents are reset and kept, such that the next execution reuses them
+    /// instead of allocating new OpenSSL contexts.
+    pub fn reset(&mut self) {
+        self.knowledge.clear();
+        self.claimer.borrow_mut().claims.clear();
+        self.byte_havoc_log.clear();
+        self.message_types.clear();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.take_events();
+        }
+        self.remaining_operations = 0;
+        self.current_step = 0;
+
+        for mut agent in self.agents.drain(..) {
+            agent.reset();
+            self.spare_agents.push(agent);
+        }
+    }
+
+    /// Takes an agent of a previous execution which is reusable with the `descriptor`
+    fn take_spare_agent(&mut self, descriptor: &AgentDescriptor) -> Option<Agent> {
+        let position = self
+            .spare_agents
+            .iter()
+            .position(|spare| spare.descriptor.is_reusable_with(descriptor))?;
+        Some(self.spare_agents.swap_remove(position))
+    }
+
+    /// Spawns `n` agents which are configured like the `template`. Each agent gets the next name
+    /// which is not yet taken in this context. Returns the names of the spawned agents.
+    pub fn spawn_agent_pool(
+        &mut self,
+        n: usize,
+        template: &AgentDescriptor,
+    ) -> Result<Vec<AgentName>, Error> {
+        let names: Vec<AgentName> = AgentName::iter()
+            .filter(|name| self.find_agent(*name).is_err())
+            .take(n)
+            .collect();
+
+        if names.len() != n {
+            return Err(Error::Agent(format!("Unable to spawn {} more agents", n)));
+        }
+
+        for name in &names {
+            self.new_openssl_agent(&AgentDescriptor {
+                name: *name,
+                ..template.clone()
+            })?;
+        }
+
+        Ok(names)
+    }
+
+    /// Resets the streams of the agents in `names`, e.g. of a pool spawned with
+    /// [`TraceContext::spawn_agent_pool`]
+    pub fn reset_agent_pool(&mut self, names: &[AgentName]) -> Result<(), Error> {
+        for name in names {
+            self.find_agent_mut(*name)?.reset();
+        }
+        Ok(())
+    }
+}
+
+/// Declarative expectation about the agents after a [`Trace`] has been executed, see
+/// [`Trace::execute_checked`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceExpectation {
+    /// The agent completed its handshake
+    Finished(AgentName),
+    /// The long description of the OpenSSL state of the agent contains the string
+    State(AgentName, String),
+    /// The agent sent the alert
+    SentAlert(AgentName, AlertDescription),
+    /// The agent received the alert
+    ReceivedAlert(AgentName, AlertDescription),
+    /// The agent neither sent nor received any alert
+    NoAlerts(AgentName),
+}
+
+impl TraceExpectation {
+    pub fn expect_finished(agent: AgentName) -> Self {
+        TraceExpectation::Finished(agent)
+    }
+
+    pub fn expect_state(agent: AgentName, state: &str) -> Self {
+        TraceExpectation::State(agent, state.to_string())
+    }
+
+    pub fn expect_alert(agent: AgentName, alert: AlertDescription) -> Self {
+        TraceExpectation::SentAlert(agent, alert)
+    }
+
+    pub fn expect_received_alert(agent: AgentName, alert: AlertDescription) -> Self {
+        TraceExpectation::ReceivedAlert(agent, alert)
+    }
+
+    pub fn expect_no_alerts(agent: AgentName) -> Self {
+        TraceExpectation::NoAlerts(agent)
+    }
+
+    /// Returns an [`Error::Expectation`] which describes the mismatch if the expectation does not
+    /// hold in `ctx`
+    pub fn check(&self, ctx: &TraceContext) -> Result<(), Error> {
+        let mismatch = match self {
+            TraceExpectation::Finished(agent) => {
+                if ctx.is_handshake_complete(*agent)? {
+                    None
+                } else {
+                    Some(format!(
+                        "agent {} did not finish its handshake, state: {}",
+                        agent,
+                        ctx.find_agent(*agent)?.stream.describe_state()
+                    ))
+                }
+            }
+            TraceExpectation::State(agent, state) => {
+                let actual = ctx.find_agent(*agent)?.stream.describe_state();
+                if actual.contains(state.as_str()) {
+                    None
+                } else {
+                    Some(format!(
+                        "agent {} is in state \"{}\", expected \"{}\"",
+                        agent, actual, state
+                    ))
+                }
+            }
+            TraceExpectation::SentAlert(agent, alert) => {
+                let sent = ctx.sent_alerts(*agent)?;
+                if sent.contains(alert) {
+                    None
+                } else {
+                    Some(format!(
+                        "agent {} did not send {:?}, sent alerts: {:?}",
+                        agent, alert, sent
+                    ))
+                }
+            }
+            TraceExpectation::ReceivedAlert(agent, alert) => {
+                let received = ctx.received_alerts(*agent)?;
+                if received.contains(alert) {
+                    None
+                } else {
+                    Some(format!(
+                        "agent {} did not receive {:?}, received alerts: {:?}",
+                        agent, alert, received
+                    ))
+                }
+            }
+            TraceExpectation::NoAlerts(agent) => {
+                let sent = ctx.sent_alerts(*agent)?;
+                let received = ctx.received_alerts(*agent)?;
+                if sent.is_empty() && received.is_empty() {
+                    None
+                } else {
+                    Some(format!(
+                        "agent {} sent alerts {:?} and received alerts {:?}",
+                        agent, sent, received
+                    ))
+                }
+            }
+        };
+
+        match mismatch {
+            Some(mismatch) => Err(Error::Expectation(mismatch)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Outcome of a single [`Step`] of a trace execution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepOutcome {
+    Success,
+    /// The step has not been executed because an earlier step failed
+    Blocked,
+    /// The step failed, the message describes the [`Error`]
+    Error(String),
+}
+
+/// Result of [`Trace::execute_report`]
+#[derive(Debug, Clone)]
+pub struct ExecutionReport {
+    /// One outcome for each step of the trace, prior traces are not included
+    pub steps: Vec<StepOutcome>,
+    /// OpenSSL state of each agent after the execution, see [`TraceContext::agent_states`]
+    pub final_states: HashMap<AgentName, String>,
+    /// Alerts which the agents sent, see [`TraceContext::all_sent_alerts`]
+    pub alerts: Vec<(AgentName, AlertDescription)>,
+    /// The error which stopped the execution, or the violated security claim
+    pub error: Option<Error>,
+}
+
+impl ExecutionReport {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn into_result(self) -> Result<(), Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -424,6 +1092,10 @@ impl Trace {
             {
                 // rename if it already exists and we want to reuse
                 reusable.rename(ctx.claimer.clone(), descriptor.name);
+            } else if let Some(mut spare) = ctx.take_spare_agent(descriptor) {
+                // reuse an agent of a previous execution, see TraceContext::reset
+                spare.rename(ctx.claimer.clone(), descriptor.name);
+                ctx.add_agent(spare);
             } else {
                 // only spawn completely new if not yet existing
                 ctx.new_openssl_agent(&descriptor)?;
@@ -434,31 +1106,71 @@ impl Trace {
     }
 
     pub fn execute(&self, ctx: &mut TraceContext) -> Result<(), Error> {
+        self.execute_steps(ctx, &mut vec![])
+    }
+
+    /// Executes the trace like [`Trace::execute`], but reports the outcome of each step and the
+    /// final state of the agents instead of only the first error
+    pub fn execute_report(&self, ctx: &mut TraceContext) -> ExecutionReport {
+        let mut steps = Vec::with_capacity(self.steps.len());
+        let error = self.execute_steps(ctx, &mut steps).err();
+        steps.resize(self.steps.len(), StepOutcome::Blocked);
+
+        ExecutionReport {
+            steps,
+            final_states: ctx
+                .agents
+                .iter()
+                .map(|agent| {
+                    (
+                        agent.descriptor.name,
+                        agent.stream.describe_state().to_string(),
+                    )
+                })
+                .collect(),
+            alerts: ctx.all_sent_alerts(),
+            error,
+        }
+    }
+
+    /// Executes the prior traces and spawns the agents of this trace. Afterwards the steps can be
+    /// executed one by one with [`TraceContext::execute_step`].
+    pub fn prepare(&self, ctx: &mut TraceContext) -> Result<(), Error> {
         for trace in &self.prior_traces {
             trace.spawn_agents(ctx)?;
             trace.execute(ctx)?;
             ctx.reset_agents();
         }
         self.spawn_agents(ctx)?;
+        ctx.reset_operation_budget(self.steps.len());
+        Ok(())
+    }
+
+    /// Executes the steps and pushes the outcome of each executed step to `outcomes`
+    fn execute_steps(
+        &self,
+        ctx: &mut TraceContext,
+        outcomes: &mut Vec<StepOutcome>,
+    ) -> Result<(), Error> {
+        self.prepare(ctx)?;
         let steps = &self.steps;
         for i in 0..steps.len() {
             let step = &steps[i];
             trace!("Executing step #{}", i);
+            ctx.current_step = i;
 
-            step.action.execute(step, ctx)?;
-
-            // Output after each InputAction step
-            match step.action {
-                Action::Input(_) => {
-                    let output_step = &Step {
-                        agent: step.agent,
-                        action: Action::Output(OutputAction {}),
-                    };
-
-                    output_step.action.execute(output_step, ctx)?;
-                }
-                Action::Output(_) => {}
+            if let Err(err) = Self::execute_step(step, ctx) {
+                outcomes.push(StepOutcome::Error(err.to_string()));
+                return Err(err);
             }
+            outcomes.push(StepOutcome::Success);
+
+            trace!(
+                "Alerts of agent {}: sent {:?}, received {:?}",
+                step.agent,
+                ctx.sent_alerts(step.agent),
+                ctx.received_alerts(step.agent)
+            );
 
             let claims: &Vec<(AgentName, Claim)> = &ctx.claimer.deref().borrow().claims;
 
@@ -478,6 +1190,416 @@ impl Trace {
 
         Ok(())
     }
+
+    fn execute_step(step: &Step, ctx: &mut TraceContext) -> Result<(), Error> {
+        ctx.count_operation()?;
+        step.action.execute(step, ctx)?;
+
+        // Output after each InputAction step
+        match step.action {
+            Action::Input(_) => {
+                let output_step = &Step {
+                    agent: step.agent,
+                    action: Action::Output(OutputAction {}),
+                };
+
+                ctx.count_operation()?;
+                output_step.action.execute(output_step, ctx)?;
+            }
+            Action::Output(_) | Action::Delay(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Executes the trace like [`Trace::execute`] and afterwards verifies every expectation, even
+    /// if the execution failed. The first expectation which does not hold is reported as
+    /// [`Error::Expectation`], which also names the execution error if there was one. If all
+    /// expectations hold, the result of the execution is returned.
+    pub fn execute_checked(
+        &self,
+        ctx: &mut TraceContext,
+        expectations: &[TraceExpectation],
+    ) -> Result<(), Error> {
+        let execution = self.execute(ctx);
+
+        for expectation in expectations {
+            if let Err(err) = expectation.check(ctx) {
+                let mismatch = match err {
+                    Error::Expectation(mismatch) => mismatch,
+                    err => err.to_string(),
+                };
+                return Err(Error::Expectation(match &execution {
+                    Ok(()) => mismatch,
+                    Err(execution_err) => {
+                        format!(
+                            "{}, after the execution failed: {}",
+                            mismatch, execution_err
+                        )
+                    }
+                }));
+            }
+        }
+
+        execution
+    }
+
+    /// Checks whether this trace is structurally executable. Every [`Step`] must reference an
+    /// agent which is spawned by this or a prior trace. Variables in recipes must only reference
+    /// agents which already produced output in an earlier step.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.validate_with(&mut vec![], &mut vec![])
+    }
+
+    fn validate_with(
+        &self,
+        spawned_agents: &mut Vec<AgentName>,
+        producing_agents: &mut Vec<AgentName>,
+    ) -> Result<(), Error> {
+        for trace in &self.prior_traces {
+            trace.validate_with(spawned_agents, producing_agents)?;
+        }
+
+        spawned_agents.extend(self.descriptors.iter().map(|descriptor| descriptor.name));
+
+        for (i, step) in self.steps.iter().enumerate() {
+            if !spawned_agents.contains(&step.agent) {
+                return Err(Error::Agent(format!(
+                    "Step #{} references agent {} which is never spawned",
+                    i, step.agent
+                )));
+            }
+
+            if let Action::Input(input) = &step.action {
+                for variable in input.recipe.variables() {
+                    let agent = variable.query.agent_name;
+
+                    if !spawned_agents.contains(&agent) {
+                        return Err(Error::Agent(format!(
+                            "Variable {} in step #{} references agent {} which is never spawned",
+                            variable, i, agent
+                        )));
+                    }
+
+                    // The claimer of an agent is always available
+                    if variable.typ != TypeShape::of::<AgentClaimer>()
+                        && !producing_agents.contains(&agent)
+                    {
+                        return Err(Error::Term(format!(
+                            "Variable {} in step #{} is read before agent {} produced any output",
+                            variable, i, agent
+                        )));
+                    }
+                }
+            }
+
+            // Input and output steps both yield output of the agent
+            if !matches!(step.action, Action::Delay(_)) && !producing_agents.contains(&step.agent) {
+                producing_agents.push(step.agent);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Agents which produced output before the step at `step_index`, including the agents of the
+    /// prior traces. The variables of the step at `step_index` may only read from these agents.
+    pub fn producing_agents_before(&self, step_index: usize) -> Vec<AgentName> {
+        let mut producing_agents = vec![];
+
+        for trace in &self.prior_traces {
+            for agent in trace.producing_agents_before(trace.steps.len()) {
+                if !producing_agents.contains(&agent) {
+                    producing_agents.push(agent);
+                }
+            }
+        }
+
+        for step in self.steps.iter().take(step_index) {
+            if !matches!(step.action, Action::Delay(_)) && !producing_agents.contains(&step.agent) {
+                producing_agents.push(step.agent);
+            }
+        }
+
+        producing_agents
+    }
+
+    /// Appends the steps of `other` to the steps of this trace, see [`Trace::concat_with_agents`]
+    pub fn concat(self, other: Trace) -> Result<Trace, Error> {
+        self.concat_with_agents(other, &[])
+    }
+
+    /// Appends the steps of `other` to the steps of this trace. The agents of `other` are renamed
+    /// according to `mapping`, which contains pairs of old and new names. Descriptors of agents
+    /// which already exist in this trace are dropped, such that the steps of `other` continue the
+    /// connections of these agents. The prior traces of `other` are appended to the prior traces.
+    ///
+    /// Knowledge is shared between the two parts. Therefore, the counters of variables of `other`
+    /// which query an agent of this trace are increased by the amount of knowledge which this
+    /// trace already provides for the query. To count it, this trace is executed once, which
+    /// fails if the execution fails. Agents which are renamed to names which are not used in this
+    /// trace keep their knowledge separate.
+    pub fn concat_with_agents(
+        mut self,
+        other: Trace,
+        mapping: &[(AgentName, AgentName)],
+    ) -> Result<Trace, Error> {
+        let rename = |name: AgentName| {
+            mapping
+                .iter()
+                .find(|(from, _)| *from == name)
+                .map_or(name, |(_, to)| *to)
+        };
+
+        let mut ctx = TraceContext::new();
+        self.execute(&mut ctx)?;
+
+        for mut descriptor in other.descriptors {
+            descriptor.name = rename(descriptor.name);
+            if !self.descriptors.iter().any(|d| d.name == descriptor.name) {
+                self.descriptors.push(descriptor);
+            }
+        }
+
+        for step in other.steps {
+            let action = match step.action {
+                Action::Input(input) => Action::Input(InputAction {
+                    recipe: input.recipe.map_variables(|variable| {
+                        let mut variable = variable.clone();
+                        variable.query.agent_name = rename(variable.query.agent_name);
+                        let known = ctx.count_possibilities(variable.typ, variable.query);
+                        variable.query.counter += known as u16;
+                        variable
+                    }),
+                }),
+                action => action,
+            };
+            self.steps.push(Step {
+                agent: rename(step.agent),
+                action,
+            });
+        }
+
+        self.prior_traces.extend(other.prior_traces);
+        Ok(self)
+    }
+
+    /// Extracts the steps in `range`. The descriptors and prior traces stay the same. Fails if the
+    /// range is out of bounds or if a variable in the slice queries an agent which produced output
+    /// in the steps before the slice. The counters of such variables would refer to different
+    /// messages in the slice.
+    pub fn slice(&self, range: Range<usize>) -> Result<Trace, Error> {
+        let steps = self.steps.get(range.clone()).ok_or_else(|| {
+            Error::Term(format!(
+                "Slice {:?} is out of bounds of a trace with {} steps",
+                range,
+                self.steps.len()
+            ))
+        })?;
+
+        let dropped_producers: Vec<AgentName> = self.steps[..range.start]
+            .iter()
+            .filter(|step| !matches!(step.action, Action::Delay(_)))
+            .map(|step| step.agent)
+            .collect();
+
+        for (i, step) in steps.iter().enumerate() {
+            if let Action::Input(input) = &step.action {
+                for term in &input.recipe {
+                    if let Term::Variable(variable) = term {
+                        if dropped_producers.contains(&variable.query.agent_name) {
+                            return Err(Error::Term(format!(
+                                "Variable {} in step #{} references output of agent {} before the slice",
+                                variable,
+                                range.start + i,
+                                variable.query.agent_name
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Trace {
+            descriptors: self.descriptors.clone(),
+            steps: steps.to_vec(),
+            prior_traces: self.prior_traces.clone(),
+        })
+    }
+
+    /// Hash of the structure of this trace, which is based on [`Term::canonical_hash`]. Traces
+    /// which differ only in the ids of their terms have the same hash.
+    pub fn canonical_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        format!("{:?}", self.descriptors).hash(&mut hasher);
+        for step in &self.steps {
+            step.agent.hash(&mut hasher);
+            match &step.action {
+                Action::Input(input) => input.recipe.canonical_hash().hash(&mut hasher),
+                Action::Output(_) => "output".hash(&mut hasher),
+                Action::Delay(duration) => duration.hash(&mut hasher),
+            }
+        }
+        for trace in &self.prior_traces {
+            trace.canonical_hash().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Compares the steps of this trace with the ones of `other`. Steps which are structurally
+    /// equal, see [`Step::structurally_eq`], are matched along the longest common subsequence.
+    /// The remaining steps are reported as modified if they have the same kind of action, else
+    /// as removed from this trace or added in `other`. Descriptors and prior traces are ignored.
+    pub fn diff(&self, other: &Trace) -> TraceDiff {
+        let (old, new) = (&self.steps, &other.steps);
+
+        // common[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+        let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                common[i][j] = if old[i].structurally_eq(&new[j]) {
+                    common[i + 1][j + 1] + 1
+                } else {
+                    common[i + 1][j].max(common[i][j + 1])
+                };
+            }
+        }
+
+        let mut steps = vec![];
+        let mut removed = vec![];
+        let mut added = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && old[i].structurally_eq(&new[j]) {
+                TraceDiff::flush_hunk(old, new, &mut removed, &mut added, &mut steps);
+                i += 1;
+                j += 1;
+            } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+                removed.push(i);
+                i += 1;
+            } else {
+                added.push(j);
+                j += 1;
+            }
+        }
+        TraceDiff::flush_hunk(old, new, &mut removed, &mut added, &mut steps);
+
+        TraceDiff { steps }
+    }
+}
+
+/// Difference of a single step, see [`Trace::diff`]
+#[derive(Clone, Debug)]
+pub enum StepDiff {
+    /// The step at `index` of the old trace is missing in the new one
+    Removed { index: usize, step: Step },
+    /// The step at `index` of the new trace is missing in the old one
+    Added { index: usize, step: Step },
+    /// The step at `old_index` has been changed to the one at `new_index`. For input steps,
+    /// `terms` lists the differences of the recipes.
+    Modified {
+        old_index: usize,
+        new_index: usize,
+        old: Step,
+        new: Step,
+        terms: Vec<TermDiff>,
+    },
+}
+
+/// Result of [`Trace::diff`]
+#[derive(Clone, Debug)]
+pub struct TraceDiff {
+    pub steps: Vec<StepDiff>,
+}
+
+impl TraceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Pairs steps of the same kind of action in a hunk of unmatched steps as modified
+    fn flush_hunk(
+        old: &[Step],
+        new: &[Step],
+        removed: &mut Vec<usize>,
+        added: &mut Vec<usize>,
+        steps: &mut Vec<StepDiff>,
+    ) {
+        let mut added = added.drain(..).peekable();
+
+        for old_index in removed.drain(..) {
+            let old_step = &old[old_index];
+            let paired = added.next_if(|new_index| {
+                std::mem::discriminant(&old_step.action)
+                    == std::mem::discriminant(&new[*new_index].action)
+            });
+
+            steps.push(match paired {
+                Some(new_index) => {
+                    let new_step = &new[new_index];
+                    let terms = match (&old_step.action, &new_step.action) {
+                        (Action::Input(a), Action::Input(b)) => a.recipe.diff(&b.recipe),
+                        _ => vec![],
+                    };
+                    StepDiff::Modified {
+                        old_index,
+                        new_index,
+                        old: old_step.clone(),
+                        new: new_step.clone(),
+                        terms,
+                    }
+                }
+                None => StepDiff::Removed {
+                    index: old_index,
+                    step: old_step.clone(),
+                },
+            });
+        }
+
+        steps.extend(added.map(|index| StepDiff::Added {
+            index,
+            step: new[index].clone(),
+        }));
+    }
+}
+
+impl fmt::Display for TraceDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Trace diff:")?;
+        if self.is_empty() {
+            return write!(f, " no changes");
+        }
+
+        for step in &self.steps {
+            match step {
+                StepDiff::Removed { index, step } => {
+                    write!(f, "\n- step #{}: {} -> {}", index, step.agent, step.action)?
+                }
+                StepDiff::Added { index, step } => {
+                    write!(f, "\n+ step #{}: {} -> {}", index, step.agent, step.action)?
+                }
+                StepDiff::Modified {
+                    old_index,
+                    new_index,
+                    old,
+                    new,
+                    terms,
+                } => {
+                    write!(f, "\n~ step #{} => step #{}:", old_index, new_index)?;
+                    if old.agent != new.agent {
+                        write!(f, "\n    agent {} => {}", old.agent, new.agent)?;
+                    }
+                    if terms.is_empty() {
+                        write!(f, "\n    {} => {}", old.action, new.action)?;
+                    }
+                    for term in terms {
+                        write!(f, "\n    {}", term)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Debug for Trace {
@@ -502,10 +1624,32 @@ pub struct Step {
     pub action: Action,
 }
 
+impl Step {
+    /// Compares the agents and actions of two steps. Recipes are compared with
+    /// [`Term::structurally_eq`].
+    pub fn structurally_eq(&self, other: &Step) -> bool {
+        self.agent == other.agent
+            && match (&self.action, &other.action) {
+                (Action::Input(a), Action::Input(b)) => a.recipe.structurally_eq(&b.recipe),
+                (Action::Output(_), Action::Output(_)) => true,
+                (Action::Delay(a), Action::Delay(b)) => a == b,
+                _ => false,
+            }
+    }
+
+    /// Step in which the `agent` waits for `duration` before the next step is executed
+    pub fn new_delay(agent: AgentName, duration: Duration) -> Step {
+        Step {
+            agent,
+            action: Action::Delay(duration),
+        }
+    }
+}
+
 /// There are two action types [`OutputAction`] and [`InputAction`] differ.
 /// Both actions drive the internal state machine of an [`Agent`] forward by calling `next_state()`.
 /// The [`OutputAction`] first forwards the state machine and then extracts knowledge from the
-/// TLS messages produced by the underlying stream by calling  `take_message_from_outbound(...)`.
+/// TLS messages produced by the underlying stream by calling  `take_all_messages_from_outbound(...)`.
 /// The [`InputAction`] evaluates the recipe term and injects the newly produced message
 /// into the *inbound channel* of the [`Agent`] referenced through the corresponding [`Step`]s
 /// by calling `add_to_inbound(...)` and then drives the state machine forward.
@@ -515,6 +1659,9 @@ pub struct Step {
 pub enum Action {
     Input(InputAction),
     Output(OutputAction),
+    /// Pauses the execution of the trace. This is needed for states which are left after a
+    /// timeout, e.g. retransmissions in DTLS.
+    Delay(Duration),
 }
 
 impl Action {
@@ -522,6 +1669,10 @@ impl Action {
         match self {
             Action::Input(input) => input.input(step, ctx),
             Action::Output(output) => output.output(step, ctx),
+            Action::Delay(duration) => {
+                std::thread::sleep(*duration);
+                Ok(())
+            }
         }
     }
 }
@@ -531,12 +1682,13 @@ impl fmt::Display for Action {
         match self {
             Action::Input(input) => write!(f, "{}", input),
             Action::Output(output) => write!(f, "{}", output),
+            Action::Delay(duration) => write!(f, "Delay: {:?}", duration),
         }
     }
 }
 
 /// The [`OutputAction`] first forwards the state machine and then extracts knowledge from the
-/// TLS messages produced by the underlying stream by calling  `take_message_from_outbound(...)`.
+/// TLS messages produced by the underlying stream by calling  `take_all_messages_from_outbound(...)`.
 /// An output action is automatically called after each input step.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct OutputAction {}
@@ -552,10 +1704,8 @@ impl OutputAction {
     fn output(&self, step: &Step, ctx: &mut TraceContext) -> Result<(), Error> {
         ctx.next_state(step.agent)?;
 
-        while let Some(MessageResult(message_o, opaque_message)) =
-            ctx.take_message_from_outbound(step.agent)?
-        {
-            let message_result = MessageResult(message_o, opaque_message);
+        for message_result in ctx.take_all_messages_from_outbound(step.agent)? {
+            ctx.count_operation()?;
             let MessageResult(message, opaque_message) = &message_result;
             let tls_message_type = Some(TlsMessageType::try_from(&message_result)?);
 
@@ -621,8 +1771,8 @@ pub struct InputAction {
     pub recipe: Term,
 }
 
-/// Processes messages in the inbound channel. Uses the recipe field to evaluate to a rustls Message
-/// or a MultiMessage.
+/// Processes messages in the inbound channel. Uses the recipe field to evaluate to a rustls Message,
+/// a list of records (e.g. fragments of a single message) or a MultiMessage.
 impl InputAction {
     pub fn new_step(agent: AgentName, recipe: Term) -> Step {
         Step {
@@ -635,24 +1785,84 @@ impl InputAction {
         // message controlled by the attacker
         let evaluated = self.recipe.evaluate(ctx)?;
 
-        if let Some(msg) = evaluated.as_ref().downcast_ref::<Message>() {
-            debug_message_with_info(format!("Input message").as_str(), msg);
+        match Delivery::of(evaluated.as_ref())? {
+            Delivery::Record(opaque_message) => ctx.add_to_inbound(step.agent, &opaque_message)?,
+            Delivery::Records(opaque_messages) => {
+                ctx.add_all_to_inbound(step.agent, opaque_messages)?
+            }
+            Delivery::Bytes(bytes) => ctx.add_bytes_to_inbound(step.agent, &bytes)?,
+            // The agent itself sends the data to its peer
+            Delivery::Plaintext(data) => ctx.write_application_data(step.agent, data)?,
+        }
+
+        ctx.next_state(step.agent)
+    }
 
-            ctx.add_to_inbound(step.agent, &OpaqueMessage::from(msg.clone()))?;
-        } else if let Some(opaque_message) = evaluated.as_ref().downcast_ref::<OpaqueMessage>() {
+    /// Evaluates the recipe and returns the bytes which this action would add to the inbound
+    /// channel of an agent, without delivering them. Variables are resolved from the knowledge of
+    /// `ctx`. Recipes of [`PlaintextApplicationData`] are rejected, because an agent encrypts them
+    /// to records only when it sends them.
+    pub fn encode_to_records(&self, ctx: &TraceContext) -> Result<Vec<u8>, Error> {
+        let evaluated = self.recipe.evaluate(ctx)?;
+
+        match Delivery::of(evaluated.as_ref())? {
+            Delivery::Record(opaque_message) => Ok(opaque_message.encode()),
+            Delivery::Records(opaque_messages) => Ok(opaque_messages
+                .iter()
+                .flat_map(|opaque_message| opaque_message.clone().encode())
+                .collect()),
+            Delivery::Bytes(bytes) => Ok(bytes),
+            Delivery::Plaintext(_) => Err(Error::Term(format!(
+                "Recipe of type {} is encrypted by the agent and does not encode to records",
+                self.recipe.get_type_shape()
+            ))),
+        }
+    }
+}
+
+/// What the evaluated recipe of an [`InputAction`] hands to an agent
+enum Delivery<'a> {
+    Record(OpaqueMessage),
+    /// Records which are added at once, e.g. the fragments of a single message
+    Records(&'a [OpaqueMessage]),
+    /// Bytes which are added as they are, e.g. a [`RawRecord`] whose length field disagrees with
+    /// its payload
+    Bytes(Vec<u8>),
+    /// Data which the agent itself encrypts and sends to its peer
+    Plaintext(&'a [u8]),
+}
+
+impl<'a> Delivery<'a> {
+    fn of(evaluated: &'a dyn Any) -> Result<Self, Error> {
+        if let Some(msg) = evaluated.downcast_ref::<Message>() {
+            debug_message_with_info(format!("Input message").as_str(), msg);
+            Ok(Delivery::Record(OpaqueMessage::from(msg.clone())))
+        } else if let Some(opaque_message) = evaluated.downcast_ref::<OpaqueMessage>() {
             debug_opaque_message_with_info(
                 format!("Input opaque message").as_str(),
                 opaque_message,
             );
-            ctx.add_to_inbound(step.agent, opaque_message)?;
+            Ok(Delivery::Record(opaque_message.clone()))
+        } else if let Some(opaque_messages) = evaluated.downcast_ref::<Vec<OpaqueMessage>>() {
+            for opaque_message in opaque_messages {
+                debug_opaque_message_with_info(
+                    format!("Input opaque message").as_str(),
+                    opaque_message,
+                );
+            }
+            Ok(Delivery::Records(opaque_messages))
+        } else if let Some(raw_record) = evaluated.downcast_ref::<RawRecord>() {
+            debug!("Input raw record: {:?}", raw_record);
+            Ok(Delivery::Bytes(raw_record.encode()))
+        } else if let Some(plaintext) = evaluated.downcast_ref::<PlaintextApplicationData>() {
+            debug!("Input plaintext application data: {:?}", plaintext);
+            Ok(Delivery::Plaintext(&plaintext.0))
         } else {
-            return Err(FnError::Unknown(String::from(
-                "Recipe is not a `Message`, `OpaqueMessage` or `MultiMessage`!",
+            Err(FnError::Unknown(String::from(
+                "Recipe is not a `Message`, `OpaqueMessage`, `Vec<OpaqueMessage>`, `RawRecord`, `PlaintextApplicationData` or `MultiMessage`!",
             ))
-            .into());
+            .into())
         }
-
-        ctx.next_state(step.agent)
     }
 }
 
@@ -8,7 +8,7 @@ use rustls::msgs::handshake::ServerKeyExchangePayload;
 use rustls::{
     internal::msgs::{
         enums::Compression,
-        handshake::{ClientExtension, HandshakePayload, ServerExtension},
+        handshake::{ClientExtension, HandshakePayload, HelloRetryExtension, ServerExtension},
         message::{Message, MessagePayload},
     },
     CipherSuite,
@@ -116,6 +116,25 @@ pub fn extract_knowledge(message: &Message) -> Result<Vec<Box<dyn VariableData>>
                         .chain(server_extensions)
                         .collect::<Vec<Box<dyn VariableData>>>()
                 }
+                HandshakePayload::HelloRetryRequest(hrr) => {
+                    let vars: Vec<Box<dyn VariableData>> = vec![
+                        Box::new(message.clone()),
+                        hs.typ.clone_box(),
+                        Box::new(hrr.session_id.clone()),
+                        Box::new(hrr.cipher_suite.clone()),
+                        Box::new(hrr.legacy_version.clone()),
+                        Box::new(hrr.extensions.clone()),
+                    ];
+
+                    let hello_retry_extensions =
+                        hrr.extensions.iter().map(|extension: &HelloRetryExtension| {
+                            Box::new(extension.clone()) as Box<dyn VariableData>
+                        });
+
+                    vars.into_iter()
+                        .chain(hello_retry_extensions)
+                        .collect::<Vec<Box<dyn VariableData>>>()
+                }
                 HandshakePayload::Certificate(c) => {
                     vec![Box::new(message.clone()), Box::new(c.clone())]
                 }
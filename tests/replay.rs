@@ -0,0 +1,37 @@
+use std::{env, fs, process::Command};
+
+use tlspuffin::{agent::AgentName, fuzzer::seeds::seed_successful};
+
+#[test]
+#[cfg(feature = "tls13")] // require version which supports TLS 1.3
+fn test_replay_seed_successful() {
+    let client = AgentName::first();
+    let server = client.next();
+    let trace = seed_successful(client, server);
+
+    let path = env::temp_dir().join(format!(
+        "replay-seed-successful-{}.trace",
+        std::process::id()
+    ));
+    fs::write(&path, postcard::to_allocvec(&trace).unwrap()).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_replay"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "replay failed: {}", stdout);
+    assert!(stdout.contains("trace executed successfully"));
+}
+
+#[test]
+fn test_replay_missing_file() {
+    let status = Command::new(env!("CARGO_BIN_EXE_replay"))
+        .arg(env::temp_dir().join("replay-does-not-exist.trace"))
+        .status()
+        .unwrap();
+
+    assert_eq!(status.code(), Some(1));
+}
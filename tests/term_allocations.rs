@@ -0,0 +1,69 @@
+//! Measures the allocations of copying terms. This is a separate test binary, because it replaces
+//! the global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::mem::size_of;
+
+use tlspuffin::agent::AgentName;
+use tlspuffin::fuzzer::seeds::seed_client_attacker12;
+use tlspuffin::term::signature::Signature;
+use tlspuffin::term::Term;
+use tlspuffin::tls::fn_impl::fn_client_hello;
+use tlspuffin::trace::Action;
+
+/// Counts the bytes which are allocated by the current thread, such that concurrent tests do not
+/// disturb the measurement
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = Cell::new(0);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATED.try_with(|allocated| allocated.set(allocated.get() + layout.size()));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Returns the value of `f` along with the amount of bytes it allocated
+fn allocated_by<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATED.with(Cell::get);
+    let value = f();
+    (value, ALLOCATED.with(Cell::get) - before)
+}
+
+#[test]
+fn test_function_clone_does_not_allocate() {
+    let function = Signature::new_function(&fn_client_hello);
+
+    let (copy, allocated) = allocated_by(|| function.clone());
+    assert_eq!(copy, function);
+    // The definition is interned, a copy only shares the symbol
+    assert_eq!(allocated, 0);
+}
+
+#[test]
+fn test_term_deep_clone_allocations() {
+    let client_hello = match &seed_client_attacker12(AgentName::first()).steps[0].action {
+        Action::Input(input) => input.recipe.clone(),
+        Action::Output(_) | Action::Delay(_) => panic!("expected the ClientHello"),
+    };
+
+    // Rewriting without rules copies every subterm
+    let (copy, allocated) = allocated_by(|| client_hello.rewrite(&[]));
+    assert_eq!(copy.size(), client_hello.size());
+
+    // Each subterm only allocates the shared list of its subterms and its slot in the list of its
+    // parent. Without interning, each function would additionally copy its definition.
+    let per_subterm = size_of::<Term>() + 2 * size_of::<usize>() + size_of::<Vec<Term>>();
+    assert!(allocated <= copy.size() * per_subterm);
+}